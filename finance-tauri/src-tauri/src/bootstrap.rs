@@ -13,7 +13,16 @@ use sqlx::SqlitePool;
 /// runs exactly once and only newer ones run on an existing database.
 ///
 /// Append-only: never edit or reorder an existing entry once it has shipped — add a new one.
-const MIGRATIONS: &[&str] = &[include_str!("migrations/0001_initial.sql")];
+const MIGRATIONS: &[&str] = &[
+    include_str!("migrations/0001_initial.sql"),
+    include_str!("migrations/0002_goals.sql"),
+    include_str!("migrations/0003_rrule.sql"),
+    include_str!("migrations/0004_business_days.sql"),
+    include_str!("migrations/0005_occurrences.sql"),
+    include_str!("migrations/0006_schedule_links.sql"),
+    include_str!("migrations/0007_amount_estimates.sql"),
+    include_str!("migrations/0008_notifications.sql"),
+];
 
 /// Open (creating if needed) the SQLite database at `db_path` and bring its schema up to
 /// date, returning a ready-to-use connection pool.
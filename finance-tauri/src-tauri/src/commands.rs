@@ -9,19 +9,24 @@
 //! arguments (`req`) are deserialized by serde using the struct's own field names, so the
 //! frontend sends snake_case bodies for those.
 
-use sqlx::SqlitePool;
+use chrono::{Local, NaiveDateTime};
+use sqlx::{SqliteConnection, SqlitePool};
 use tauri::State;
 
 use crate::db;
 use crate::error::AppError;
+use crate::holidays::Holidays;
 use crate::models::{
-    Account, Category, CategoryTypes, GetAccount, GetScheduledTransaction, InitialData,
-    NewScheduledTransaction, NewTransactionData, NewTransferData, PatchCategory,
-    PatchScheduledTransaction, PatchTransaction, PatchTransfer, PostCategory,
-    PostScheduledTransaction, PostScheduledTransactionPay, PostTransaction, PostTransfer,
-    ScheduledTransaction, ScheduledTransactionKinds, Transaction, TransactionTransferJoined,
-    Transfer,
+    Account, Category, CategoryTypes, DueScheduledTransaction, GetAccount, GetGoal,
+    GetScheduledTransaction, Goal, GoalProgress, InitialData, NewScheduledOccurrence,
+    NewScheduledTransaction, NewTransactionData, NewTransferData, NotificationSettings,
+    OccurrenceOutcomes, PatchCategory, PatchGoal, PatchScheduledTransaction, PatchTransaction,
+    PatchTransfer, PostCategory, PostGoal, PostGoalContribution, PostScheduledTransaction,
+    PostScheduledTransactionPay, PostScheduledTransactionSnooze, PostTransaction, PostTransfer,
+    ScheduledEntry, ScheduledOccurrence, ScheduledTransaction, ScheduledTransactionKinds,
+    Transaction, TransactionTransferJoined, Transfer, UpcomingOccurrence,
 };
+use crate::recurrence::RRule;
 use crate::service;
 use crate::state::AppState;
 
@@ -185,6 +190,8 @@ pub async fn create_transaction(
         date: req.date,
         account: account_id,
         category: req.category,
+        scheduled_transaction_id: None,
+        occurrence: None,
     };
 
     db::transactions::insert(&state.pool, &data).await
@@ -244,6 +251,8 @@ pub async fn update_transaction(
         date: req.date,
         account: req.account,
         category: req.category,
+        scheduled_transaction_id: None,
+        occurrence: None,
     };
 
     db::transactions::update(&state.pool, transaction_id, &data).await
@@ -280,6 +289,8 @@ pub async fn create_transfer(
         value: req.value,
         description: req.description,
         date: req.date,
+        scheduled_transaction_id: None,
+        occurrence: None,
     };
 
     db::transfers::insert(&state.pool, &data).await
@@ -310,6 +321,8 @@ pub async fn update_transfer(
         value: req.value,
         description: req.description,
         date: req.date,
+        scheduled_transaction_id: None,
+        occurrence: None,
     };
 
     db::transfers::update(&state.pool, transfer_id, &data).await
@@ -334,7 +347,7 @@ pub async fn create_scheduled_transaction(
     state: State<'_, AppState>,
     req: PostScheduledTransaction,
 ) -> Result<GetScheduledTransaction, AppError> {
-    let new = build_new_scheduled(&state.pool, &req)
+    let new = build_new_scheduled(&state.pool, &state.holidays, &req)
         .await?
         .ok_or(AppError::BadRequest)?;
 
@@ -350,6 +363,46 @@ pub async fn get_scheduled_transactions(
     service::all_scheduled_enriched(&state.pool).await
 }
 
+/// The occurrences an unsaved schedule would have (at most `count`, 12 by default, none due
+/// after `until`), validated as creating it would be.
+#[tauri::command]
+#[specta::specta]
+pub async fn preview_scheduled_transaction(
+    state: State<'_, AppState>,
+    req: PostScheduledTransaction,
+    count: Option<i32>,
+    until: Option<NaiveDateTime>,
+) -> Result<Vec<UpcomingOccurrence>, AppError> {
+    let new = build_new_scheduled(&state.pool, &state.holidays, &req)
+        .await?
+        .ok_or(AppError::BadRequest)?;
+
+    let st = service::scheduled_transaction_from(&new);
+    service::upcoming_occurrences(
+        &st,
+        &state.holidays,
+        service::estimated_value(&state.pool, &st).await?,
+        count.unwrap_or(service::PREVIEW_OCCURRENCES),
+        until,
+    )
+}
+
+/// Schedules that ran to completion, kept so their history and entries can be audited.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_finished_scheduled_transactions(
+    state: State<'_, AppState>,
+) -> Result<Vec<GetScheduledTransaction>, AppError> {
+    let finished = db::scheduled_transactions::get_finished(&state.pool).await?;
+    let mut result = Vec::with_capacity(finished.len());
+
+    for st in &finished {
+        result.push(service::enrich_scheduled(&state.pool, st).await?);
+    }
+
+    Ok(result)
+}
+
 #[tauri::command]
 #[specta::specta]
 pub async fn get_scheduled_transaction(
@@ -370,7 +423,7 @@ pub async fn update_scheduled_transaction(
     // Ensure the scheduled transaction exists (404 otherwise).
     db::scheduled_transactions::get(&state.pool, scheduled_transaction_id).await?;
 
-    let new = build_new_scheduled(&state.pool, &req)
+    let new = build_new_scheduled(&state.pool, &state.holidays, &req)
         .await?
         .ok_or(AppError::BadRequest)?;
 
@@ -395,7 +448,206 @@ pub async fn pay_scheduled_transaction(
     scheduled_transaction_id: i32,
     req: PostScheduledTransactionPay,
 ) -> Result<ScheduledTransaction, AppError> {
-    pay_scheduled_impl(&state.pool, scheduled_transaction_id, &req).await
+    pay_scheduled_impl(&state.pool, &state.holidays, scheduled_transaction_id, &req).await
+}
+
+/// Move past the current occurrence without booking anything.
+#[tauri::command]
+#[specta::specta]
+pub async fn skip_scheduled_transaction(
+    state: State<'_, AppState>,
+    scheduled_transaction_id: i32,
+) -> Result<ScheduledTransaction, AppError> {
+    skip_scheduled_impl(&state.pool, &state.holidays, scheduled_transaction_id).await
+}
+
+/// Move the current due date to a later date. The following occurrences keep their dates.
+#[tauri::command]
+#[specta::specta]
+pub async fn snooze_scheduled_transaction(
+    state: State<'_, AppState>,
+    scheduled_transaction_id: i32,
+    req: PostScheduledTransactionSnooze,
+) -> Result<ScheduledTransaction, AppError> {
+    snooze_scheduled_impl(&state.pool, scheduled_transaction_id, &req).await
+}
+
+/// What happened to the schedule's occurrences so far, oldest first.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_scheduled_transaction_history(
+    state: State<'_, AppState>,
+    scheduled_transaction_id: i32,
+) -> Result<Vec<ScheduledOccurrence>, AppError> {
+    db::scheduled_transactions::get(&state.pool, scheduled_transaction_id).await?;
+    db::scheduled_occurrences::get_by_scheduled(&state.pool, scheduled_transaction_id).await
+}
+
+/// The schedule's next occurrences, starting with the one currently due (at most `count`, 12 by
+/// default, none due after `until`).
+#[tauri::command]
+#[specta::specta]
+pub async fn get_scheduled_transaction_occurrences(
+    state: State<'_, AppState>,
+    scheduled_transaction_id: i32,
+    count: Option<i32>,
+    until: Option<NaiveDateTime>,
+) -> Result<Vec<UpcomingOccurrence>, AppError> {
+    let st = db::scheduled_transactions::get(&state.pool, scheduled_transaction_id).await?;
+    service::upcoming_occurrences(
+        &st,
+        &state.holidays,
+        service::estimated_value(&state.pool, &st).await?,
+        count.unwrap_or(service::PREVIEW_OCCURRENCES),
+        until,
+    )
+}
+
+/// The transactions and transfers booked by paying the schedule, by occurrence.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_scheduled_transaction_entries(
+    state: State<'_, AppState>,
+    scheduled_transaction_id: i32,
+) -> Result<Vec<ScheduledEntry>, AppError> {
+    db::scheduled_transactions::get(&state.pool, scheduled_transaction_id).await?;
+    db::scheduled_transactions::get_entries(&state.pool, scheduled_transaction_id).await
+}
+
+// ---------------------------------------------------------------------------------------
+// Goals
+// ---------------------------------------------------------------------------------------
+
+#[tauri::command]
+#[specta::specta]
+pub async fn create_goal(state: State<'_, AppState>, req: PostGoal) -> Result<GetGoal, AppError> {
+    service::validate_goal(&state.pool, &req).await?;
+
+    let goal = db::goals::insert(&state.pool, &req, Local::now().naive_local()).await?;
+    service::goal_to_dto(&state.pool, goal).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_goals(state: State<'_, AppState>) -> Result<Vec<GetGoal>, AppError> {
+    let goals = db::goals::get_all(&state.pool).await?;
+    let mut result = Vec::with_capacity(goals.len());
+
+    for goal in goals {
+        result.push(service::goal_to_dto(&state.pool, goal).await?);
+    }
+
+    Ok(result)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_goal(state: State<'_, AppState>, goal_id: i32) -> Result<GetGoal, AppError> {
+    let goal = db::goals::get(&state.pool, goal_id).await?;
+    service::goal_to_dto(&state.pool, goal).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn update_goal(
+    state: State<'_, AppState>,
+    goal_id: i32,
+    req: PatchGoal,
+) -> Result<GetGoal, AppError> {
+    db::goals::get(&state.pool, goal_id).await?;
+    service::validate_goal(&state.pool, &req).await?;
+
+    let goal = db::goals::update(&state.pool, goal_id, &req).await?;
+    service::goal_to_dto(&state.pool, goal).await
+}
+
+/// Delete a goal along with its contribution schedule, if any. The linked accounts are kept.
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_goal(state: State<'_, AppState>, goal_id: i32) -> Result<Goal, AppError> {
+    let goal = db::goals::delete(&state.pool, goal_id).await?;
+
+    if let Some(scheduled_transaction_id) = goal.scheduled_transaction_id {
+        db::scheduled_transactions::delete(&state.pool, scheduled_transaction_id).await?;
+    }
+
+    Ok(goal)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_goal_progress(
+    state: State<'_, AppState>,
+    goal_id: i32,
+) -> Result<GoalProgress, AppError> {
+    let goal = db::goals::get(&state.pool, goal_id).await?;
+    service::goal_progress(&state.pool, &goal, Local::now().date_naive()).await
+}
+
+/// Create (or replace) the goal's recurring monthly contribution transfer.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_goal_contribution(
+    state: State<'_, AppState>,
+    goal_id: i32,
+    req: PostGoalContribution,
+) -> Result<GetGoal, AppError> {
+    let goal = db::goals::get(&state.pool, goal_id).await?;
+    service::set_goal_contribution(&state.pool, &goal, &req, Local::now().date_naive()).await
+}
+
+/// Stop the goal's contribution by deleting its scheduled transfer, if any.
+#[tauri::command]
+#[specta::specta]
+pub async fn delete_goal_contribution(
+    state: State<'_, AppState>,
+    goal_id: i32,
+) -> Result<GetGoal, AppError> {
+    let goal = db::goals::get(&state.pool, goal_id).await?;
+    if let Some(scheduled_transaction_id) = goal.scheduled_transaction_id {
+        db::scheduled_transactions::delete(&state.pool, scheduled_transaction_id).await?;
+    }
+
+    let goal = db::goals::set_scheduled(&state.pool, goal_id, None).await?;
+    service::goal_to_dto(&state.pool, goal).await
+}
+
+// ---------------------------------------------------------------------------------------
+// Notifications
+// ---------------------------------------------------------------------------------------
+
+/// The scheduled transactions that are overdue, due today or due within the notification lead
+/// time — what the scheduled list badge counts. The same list is pushed to the frontend as the
+/// `dueScheduledTransactions` event after every background check.
+#[tauri::command]
+#[specta::specta]
+pub async fn get_due_scheduled_transactions(
+    state: State<'_, AppState>,
+) -> Result<Vec<DueScheduledTransaction>, AppError> {
+    let settings = db::notification_settings::get(&state.pool).await?;
+    service::due_scheduled(&state.pool, Local::now().date_naive(), settings.lead_days).await
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_notification_settings(
+    state: State<'_, AppState>,
+) -> Result<NotificationSettings, AppError> {
+    db::notification_settings::get(&state.pool).await
+}
+
+/// Turn the notifications on or off and set their lead time (0 to 30 days, `BadRequest`
+/// otherwise). The background check picks the new settings up on its next run.
+#[tauri::command]
+#[specta::specta]
+pub async fn update_notification_settings(
+    state: State<'_, AppState>,
+    req: NotificationSettings,
+) -> Result<NotificationSettings, AppError> {
+    if !(0..=service::MAX_LEAD_DAYS).contains(&req.lead_days) {
+        return Err(AppError::BadRequest);
+    }
+    db::notification_settings::update(&state.pool, &req).await
 }
 
 // ---------------------------------------------------------------------------------------
@@ -410,24 +662,33 @@ enum Materialize {
 
 /// What happens to the schedule itself once it has been paid.
 enum Advance {
-    /// One-off, or the last occurrence of a finite repeat — remove the schedule.
-    Remove,
+    /// One-off, or the last occurrence of a finite repeat — mark the schedule finished, with
+    /// its count moved past the last occurrence.
+    Finish(Option<i32>),
     /// Repeating — bump the count and next date to the following occurrence.
     Next(NewScheduledTransaction),
+    /// Partially paid — the occurrence stays due as it is.
+    Keep,
 }
 
-/// Materialise a scheduled transaction into a real transaction/transfer, then either delete
-/// the schedule (one-off / finished) or advance it to its next occurrence.
+/// Materialise a scheduled transaction into a real transaction/transfer linked to the schedule
+/// and occurrence, then either mark the schedule finished (one-off / end reached) or advance it
+/// to its next occurrence — or, for a partial payment that leaves part of the occurrence due,
+/// keep it as it is. A finished schedule cannot be paid (`Conflict`).
 ///
-/// The materialise + advance/delete writes run in a **single** database transaction, so a
-/// crash can never record the payment without also advancing/removing the schedule (which
+/// The materialise + history + advance/finish writes run in a **single** database transaction,
+/// so a crash can never record the payment without also advancing/finishing the schedule (which
 /// would let the same occurrence be paid twice). Reference validation happens first as reads.
 pub(crate) async fn pay_scheduled_impl(
     pool: &SqlitePool,
+    holidays: &Holidays,
     id: i32,
     body: &PostScheduledTransactionPay,
 ) -> Result<ScheduledTransaction, AppError> {
-    let st = db::scheduled_transactions::get(pool, id).await?;
+    let st = running(db::scheduled_transactions::get(pool, id).await?)?;
+    if body.partial && body.value <= 0 {
+        return Err(AppError::BadRequest);
+    }
 
     // 1. Validate the payment against the schedule kind and build the write to perform.
     let materialize = match st.kind {
@@ -450,6 +711,8 @@ pub(crate) async fn pay_scheduled_impl(
                 date: body.date,
                 account: account_id,
                 category: category_id,
+                scheduled_transaction_id: Some(st.id),
+                occurrence: st.current_repeat_count,
             })
         }
         ScheduledTransactionKinds::Transfer => {
@@ -472,14 +735,28 @@ pub(crate) async fn pay_scheduled_impl(
                 value: body.value,
                 description: body.description.clone(),
                 date: body.date,
+                scheduled_transaction_id: Some(st.id),
+                occurrence: st.current_repeat_count,
             })
         }
     };
 
-    // 2. Decide the schedule's fate (pure computation, no I/O).
-    let advance = compute_advance(&st)?;
+    // 2. Decide the schedule's fate. A partial payment leaves the occurrence due until its
+    // payments reach the scheduled value; a full one (or the payment completing it) moves on.
+    let mut outcome = OccurrenceOutcomes::Paid;
+    if body.partial {
+        let paid = db::scheduled_occurrences::paid_value(pool, id, st.current_repeat_count).await?;
+        if paid + body.value < st.value {
+            outcome = OccurrenceOutcomes::PartiallyPaid;
+        }
+    }
+    let advance = match outcome {
+        OccurrenceOutcomes::PartiallyPaid => Advance::Keep,
+        _ => compute_advance(&st, holidays)?,
+    };
+    let entry = occurrence_entry(&st, outcome, body.value, None);
 
-    // 3. Apply both effects atomically.
+    // 3. Apply all effects atomically.
     let mut tx = pool.begin().await?;
 
     match &materialize {
@@ -491,22 +768,100 @@ pub(crate) async fn pay_scheduled_impl(
         }
     }
 
-    let result = match &advance {
-        Advance::Remove => db::scheduled_transactions::delete_on(&mut tx, id).await?,
-        Advance::Next(updated) => {
-            db::scheduled_transactions::update_on(&mut tx, id, updated).await?
-        }
-    };
+    db::scheduled_occurrences::insert_on(&mut tx, &entry, Local::now().naive_local()).await?;
+    let result = apply_advance(&mut tx, st, &advance).await?;
 
     tx.commit().await?;
 
     Ok(result)
 }
 
+/// Move past the current occurrence of a schedule without booking anything, recording the
+/// skip in its history in the same database transaction.
+pub(crate) async fn skip_scheduled_impl(
+    pool: &SqlitePool,
+    holidays: &Holidays,
+    id: i32,
+) -> Result<ScheduledTransaction, AppError> {
+    let st = running(db::scheduled_transactions::get(pool, id).await?)?;
+    let advance = compute_advance(&st, holidays)?;
+    let entry = occurrence_entry(&st, OccurrenceOutcomes::Skipped, 0, None);
+
+    let mut tx = pool.begin().await?;
+    db::scheduled_occurrences::insert_on(&mut tx, &entry, Local::now().naive_local()).await?;
+    let result = apply_advance(&mut tx, st, &advance).await?;
+    tx.commit().await?;
+
+    Ok(result)
+}
+
+/// Move the current due date of a schedule to a later date (the following occurrences keep
+/// theirs), recording the snooze in its history in the same database transaction.
+pub(crate) async fn snooze_scheduled_impl(
+    pool: &SqlitePool,
+    id: i32,
+    body: &PostScheduledTransactionSnooze,
+) -> Result<ScheduledTransaction, AppError> {
+    let st = running(db::scheduled_transactions::get(pool, id).await?)?;
+    if body.date <= st.next_date.unwrap_or(st.created_date) {
+        return Err(AppError::BadRequest);
+    }
+
+    let snoozed = service::rescheduled(&st, st.current_repeat_count, Some(body.date));
+    let entry = occurrence_entry(&st, OccurrenceOutcomes::Snoozed, 0, Some(body.date));
+
+    let mut tx = pool.begin().await?;
+    db::scheduled_occurrences::insert_on(&mut tx, &entry, Local::now().naive_local()).await?;
+    let result = db::scheduled_transactions::update_on(&mut tx, id, &snoozed).await?;
+    tx.commit().await?;
+
+    Ok(result)
+}
+
+/// A finished schedule has no occurrence left to pay, skip or snooze (`Conflict`).
+fn running(st: ScheduledTransaction) -> Result<ScheduledTransaction, AppError> {
+    if st.finished {
+        return Err(AppError::Conflict);
+    }
+    Ok(st)
+}
+
+/// The history entry for the schedule's current occurrence.
+fn occurrence_entry(
+    st: &ScheduledTransaction,
+    outcome: OccurrenceOutcomes,
+    value: i32,
+    snoozed_to: Option<NaiveDateTime>,
+) -> NewScheduledOccurrence {
+    NewScheduledOccurrence {
+        scheduled_transaction_id: st.id,
+        occurrence: st.current_repeat_count,
+        due_date: st.next_date.unwrap_or(st.created_date),
+        outcome,
+        value,
+        snoozed_to,
+    }
+}
+
+/// Finish, advance or keep the schedule on the caller's transaction, returning it.
+async fn apply_advance(
+    conn: &mut SqliteConnection,
+    st: ScheduledTransaction,
+    advance: &Advance,
+) -> Result<ScheduledTransaction, AppError> {
+    match advance {
+        Advance::Finish(current_repeat_count) => {
+            db::scheduled_transactions::finish_on(conn, st.id, *current_repeat_count).await
+        }
+        Advance::Next(updated) => db::scheduled_transactions::update_on(conn, st.id, updated).await,
+        Advance::Keep => Ok(st),
+    }
+}
+
 /// Compute what should happen to a schedule after it is paid, without touching the database.
-fn compute_advance(st: &ScheduledTransaction) -> Result<Advance, AppError> {
+fn compute_advance(st: &ScheduledTransaction, holidays: &Holidays) -> Result<Advance, AppError> {
     if !st.repeat {
-        return Ok(Advance::Remove);
+        return Ok(Advance::Finish(None));
     }
 
     let internal = |m: &str| AppError::Internal(m.to_string());
@@ -524,49 +879,29 @@ fn compute_advance(st: &ScheduledTransaction) -> Result<Advance, AppError> {
             .end_after_repeats
             .ok_or_else(|| internal("finite schedule missing end_after_repeats"))?;
         if new_repeat_count >= end_after_repeats {
-            return Ok(Advance::Remove);
+            return Ok(Advance::Finish(Some(new_repeat_count)));
         }
     }
 
-    let repeat_freq = st
-        .repeat_freq
-        .ok_or_else(|| internal("repeating schedule missing repeat_freq"))?;
-    let repeat_interval = st
-        .repeat_interval
-        .ok_or_else(|| internal("repeating schedule missing repeat_interval"))?;
-
-    let next_date = service::calculate_next_date(
-        st.created_date,
-        st.repeat,
-        repeat_freq,
-        repeat_interval,
-        new_repeat_count,
-    );
-
-    Ok(Advance::Next(NewScheduledTransaction {
-        kind: st.kind,
-        value: st.value,
-        description: st.description.clone(),
-        created_date: st.created_date,
-        account_id: st.account_id,
-        category_id: st.category_id,
-        origin_account_id: st.origin_account_id,
-        destination_account_id: st.destination_account_id,
-        repeat: st.repeat,
-        repeat_freq: st.repeat_freq,
-        repeat_interval: st.repeat_interval,
-        infinite_repeat: st.infinite_repeat,
-        end_after_repeats: st.end_after_repeats,
-        current_repeat_count: Some(new_repeat_count),
-        next_date: Some(next_date),
-    }))
+    // A rule with no further occurrence (past its UNTIL or COUNT) is finished too.
+    let Some(next_date) = service::scheduled_occurrence(st, new_repeat_count, holidays)? else {
+        return Ok(Advance::Finish(Some(new_repeat_count)));
+    };
+
+    Ok(Advance::Next(service::rescheduled(
+        st,
+        Some(new_repeat_count),
+        Some(next_date),
+    )))
 }
 
 /// Validate and assemble a `NewScheduledTransaction` from a request body. Returns `Ok(None)`
 /// when the payload is invalid (missing repeat parameters, missing/unknown referenced
 /// accounts or category, or origin == destination), which the caller maps to a bad request.
+/// The first due date is moved off weekends and `holidays` per the body's adjustment.
 pub(crate) async fn build_new_scheduled(
     pool: &SqlitePool,
+    holidays: &Holidays,
     body: &PostScheduledTransaction,
 ) -> Result<Option<NewScheduledTransaction>, AppError> {
     let mut new = NewScheduledTransaction {
@@ -581,6 +916,9 @@ pub(crate) async fn build_new_scheduled(
         repeat: body.repeat,
         repeat_freq: None,
         repeat_interval: None,
+        rrule: None,
+        business_day_adjustment: body.business_day_adjustment,
+        amount_estimate: body.amount_estimate,
         infinite_repeat: None,
         end_after_repeats: None,
         current_repeat_count: None,
@@ -588,15 +926,26 @@ pub(crate) async fn build_new_scheduled(
     };
 
     if body.repeat {
-        let Some(freq) = body.repeat_freq else {
-            return Ok(None);
-        };
-        new.repeat_freq = Some(freq);
+        if let Some(rule) = &body.rrule {
+            // The rule replaces freq/interval; its first occurrence is the first due date.
+            let Some(first) =
+                RRule::parse(rule).and_then(|rule| rule.nth(body.created_date, 0))
+            else {
+                return Ok(None);
+            };
+            new.rrule = Some(rule.trim().to_string());
+            new.next_date = Some(first);
+        } else {
+            let Some(freq) = body.repeat_freq else {
+                return Ok(None);
+            };
+            new.repeat_freq = Some(freq);
 
-        let Some(interval) = body.repeat_interval else {
-            return Ok(None);
-        };
-        new.repeat_interval = Some(interval);
+            let Some(interval) = body.repeat_interval else {
+                return Ok(None);
+            };
+            new.repeat_interval = Some(interval);
+        }
 
         let Some(infinite) = body.infinite_repeat else {
             return Ok(None);
@@ -613,8 +962,14 @@ pub(crate) async fn build_new_scheduled(
         }
 
         new.current_repeat_count = Some(0);
+    } else if body.rrule.is_some() {
+        return Ok(None);
     }
 
+    new.next_date = new
+        .next_date
+        .map(|date| holidays.adjust(date, body.business_day_adjustment));
+
     match body.kind {
         ScheduledTransactionKinds::Transaction => {
             let Some(account_id) = body.account_id else {
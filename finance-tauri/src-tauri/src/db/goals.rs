@@ -0,0 +1,133 @@
+use chrono::NaiveDateTime;
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::error::AppError;
+use crate::models::{Goal, GoalAccount, PostGoal, PostGoalAccount};
+
+const COLUMNS: &str = "id, name, target_amount, target_date, created_date, scheduled_transaction_id";
+
+/// Insert a goal together with its linked accounts, in one database transaction.
+pub async fn insert(
+    pool: &SqlitePool,
+    new: &PostGoal,
+    created_date: NaiveDateTime,
+) -> Result<Goal, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let goal = sqlx::query_as::<_, Goal>(&format!(
+        "INSERT INTO goals (name, target_amount, target_date, created_date) \
+         VALUES (?, ?, ?, ?) RETURNING {COLUMNS}"
+    ))
+    .bind(new.name.as_str())
+    .bind(new.target_amount)
+    .bind(new.target_date)
+    .bind(created_date)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    insert_accounts_on(&mut tx, goal.id, &new.accounts).await?;
+
+    tx.commit().await?;
+    Ok(goal)
+}
+
+pub async fn get_all(pool: &SqlitePool) -> Result<Vec<Goal>, AppError> {
+    let goals = sqlx::query_as::<_, Goal>(&format!(
+        "SELECT {COLUMNS} FROM goals ORDER BY target_date IS NULL, target_date, id"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(goals)
+}
+
+pub async fn get(pool: &SqlitePool, id: i32) -> Result<Goal, AppError> {
+    let goal = sqlx::query_as::<_, Goal>(&format!("SELECT {COLUMNS} FROM goals WHERE id = ?"))
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(goal)
+}
+
+pub async fn get_accounts(pool: &SqlitePool, goal_id: i32) -> Result<Vec<GoalAccount>, AppError> {
+    let accounts = sqlx::query_as::<_, GoalAccount>(
+        "SELECT goal_id, account_id, earmark FROM goal_accounts WHERE goal_id = ? \
+         ORDER BY account_id",
+    )
+    .bind(goal_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(accounts)
+}
+
+/// Replace a goal's fields and linked accounts, in one database transaction.
+pub async fn update(pool: &SqlitePool, id: i32, new: &PostGoal) -> Result<Goal, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let goal = sqlx::query_as::<_, Goal>(&format!(
+        "UPDATE goals SET name = ?, target_amount = ?, target_date = ? WHERE id = ? \
+         RETURNING {COLUMNS}"
+    ))
+    .bind(new.name.as_str())
+    .bind(new.target_amount)
+    .bind(new.target_date)
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM goal_accounts WHERE goal_id = ?")
+        .bind(goal.id)
+        .execute(&mut *tx)
+        .await?;
+    insert_accounts_on(&mut tx, goal.id, &new.accounts).await?;
+
+    tx.commit().await?;
+    Ok(goal)
+}
+
+/// Link (or unlink, with `None`) the goal's monthly contribution schedule.
+pub async fn set_scheduled(
+    pool: &SqlitePool,
+    id: i32,
+    scheduled_transaction_id: Option<i32>,
+) -> Result<Goal, AppError> {
+    let goal = sqlx::query_as::<_, Goal>(&format!(
+        "UPDATE goals SET scheduled_transaction_id = ? WHERE id = ? RETURNING {COLUMNS}"
+    ))
+    .bind(scheduled_transaction_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(goal)
+}
+
+pub async fn delete(pool: &SqlitePool, id: i32) -> Result<Goal, AppError> {
+    let goal = sqlx::query_as::<_, Goal>(&format!(
+        "DELETE FROM goals WHERE id = ? RETURNING {COLUMNS}"
+    ))
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(goal)
+}
+
+async fn insert_accounts_on(
+    conn: &mut SqliteConnection,
+    goal_id: i32,
+    accounts: &[PostGoalAccount],
+) -> Result<(), AppError> {
+    for account in accounts {
+        sqlx::query("INSERT INTO goal_accounts (goal_id, account_id, earmark) VALUES (?, ?, ?)")
+            .bind(goal_id)
+            .bind(account.account_id)
+            .bind(account.earmark)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
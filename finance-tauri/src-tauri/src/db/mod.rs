@@ -1,5 +1,8 @@
 pub mod accounts;
 pub mod categories;
+pub mod goals;
+pub mod notification_settings;
+pub mod scheduled_occurrences;
 pub mod scheduled_transactions;
 pub mod transactions;
 pub mod transfers;
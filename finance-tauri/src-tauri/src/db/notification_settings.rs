@@ -0,0 +1,30 @@
+use sqlx::SqlitePool;
+
+use crate::error::AppError;
+use crate::models::NotificationSettings;
+
+pub async fn get(pool: &SqlitePool) -> Result<NotificationSettings, AppError> {
+    let settings = sqlx::query_as::<_, NotificationSettings>(
+        "SELECT enabled, lead_days FROM notification_settings WHERE id = 1",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(settings)
+}
+
+pub async fn update(
+    pool: &SqlitePool,
+    settings: &NotificationSettings,
+) -> Result<NotificationSettings, AppError> {
+    let settings = sqlx::query_as::<_, NotificationSettings>(
+        "UPDATE notification_settings SET enabled = ?, lead_days = ? WHERE id = 1 \
+         RETURNING enabled, lead_days",
+    )
+    .bind(settings.enabled)
+    .bind(settings.lead_days)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(settings)
+}
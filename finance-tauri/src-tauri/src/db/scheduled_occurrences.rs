@@ -0,0 +1,91 @@
+use chrono::NaiveDateTime;
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::error::AppError;
+use crate::models::{NewScheduledOccurrence, OccurrenceOutcomes, ScheduledOccurrence};
+
+const COLUMNS: &str = "id, scheduled_transaction_id, occurrence, due_date, outcome, value, \
+    snoozed_to, recorded_at";
+
+/// Record an occurrence on the caller's connection/transaction, so it is written atomically
+/// with the payment and the schedule update it describes.
+pub async fn insert_on(
+    conn: &mut SqliteConnection,
+    new: &NewScheduledOccurrence,
+    recorded_at: NaiveDateTime,
+) -> Result<ScheduledOccurrence, AppError> {
+    let occurrence = sqlx::query_as::<_, ScheduledOccurrence>(&format!(
+        "INSERT INTO scheduled_occurrences \
+         (scheduled_transaction_id, occurrence, due_date, outcome, value, snoozed_to, recorded_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?) RETURNING {COLUMNS}"
+    ))
+    .bind(new.scheduled_transaction_id)
+    .bind(new.occurrence)
+    .bind(new.due_date)
+    .bind(new.outcome)
+    .bind(new.value)
+    .bind(new.snoozed_to)
+    .bind(recorded_at)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(occurrence)
+}
+
+/// A schedule's occurrence history, oldest first.
+pub async fn get_by_scheduled(
+    pool: &SqlitePool,
+    scheduled_transaction_id: i32,
+) -> Result<Vec<ScheduledOccurrence>, AppError> {
+    let occurrences = sqlx::query_as::<_, ScheduledOccurrence>(&format!(
+        "SELECT {COLUMNS} FROM scheduled_occurrences WHERE scheduled_transaction_id = ? \
+         ORDER BY recorded_at, id"
+    ))
+    .bind(scheduled_transaction_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(occurrences)
+}
+
+/// The total of the partial payments made towards one occurrence of a schedule.
+pub async fn paid_value(
+    pool: &SqlitePool,
+    scheduled_transaction_id: i32,
+    occurrence: Option<i32>,
+) -> Result<i32, AppError> {
+    let paid: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(value), 0) FROM scheduled_occurrences \
+         WHERE scheduled_transaction_id = ? AND occurrence IS ? AND outcome = ?",
+    )
+    .bind(scheduled_transaction_id)
+    .bind(occurrence)
+    .bind(OccurrenceOutcomes::PartiallyPaid)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(paid as i32)
+}
+
+/// What was paid in total for each of a schedule's last `limit` paid occurrences, most recent
+/// first. Occurrences only partially paid so far are left out.
+pub async fn paid_totals(
+    pool: &SqlitePool,
+    scheduled_transaction_id: i32,
+    limit: i64,
+) -> Result<Vec<i32>, AppError> {
+    let totals: Vec<i64> = sqlx::query_scalar(
+        "SELECT SUM(value) FROM scheduled_occurrences \
+         WHERE scheduled_transaction_id = ?1 AND outcome IN (?2, ?3) \
+         GROUP BY occurrence HAVING MAX(outcome = ?2) \
+         ORDER BY MAX(recorded_at) DESC, MAX(id) DESC LIMIT ?4",
+    )
+    .bind(scheduled_transaction_id)
+    .bind(OccurrenceOutcomes::Paid)
+    .bind(OccurrenceOutcomes::PartiallyPaid)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(totals.into_iter().map(|total| total as i32).collect())
+}
@@ -1,11 +1,12 @@
 use sqlx::{SqliteConnection, SqlitePool};
 
 use crate::error::AppError;
-use crate::models::{NewScheduledTransaction, ScheduledTransaction};
+use crate::models::{NewScheduledTransaction, ScheduledEntry, ScheduledTransaction};
 
 const COLUMNS: &str = "id, kind, value, description, created_date, account_id, category_id, \
-    origin_account_id, destination_account_id, repeat, repeat_freq, repeat_interval, \
-    infinite_repeat, end_after_repeats, current_repeat_count, next_date";
+    origin_account_id, destination_account_id, repeat, repeat_freq, repeat_interval, rrule, \
+    business_day_adjustment, amount_estimate, infinite_repeat, end_after_repeats, current_repeat_count, next_date, \
+    finished";
 
 pub async fn insert(
     pool: &SqlitePool,
@@ -15,8 +16,9 @@ pub async fn insert(
         "INSERT INTO scheduled_transactions \
          (kind, value, description, created_date, account_id, category_id, origin_account_id, \
           destination_account_id, repeat, repeat_freq, repeat_interval, infinite_repeat, \
-          end_after_repeats, current_repeat_count, next_date) \
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+          end_after_repeats, current_repeat_count, next_date, rrule, business_day_adjustment, \
+          amount_estimate) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
          RETURNING {COLUMNS}"
     ))
     .bind(new.kind)
@@ -34,15 +36,30 @@ pub async fn insert(
     .bind(new.end_after_repeats)
     .bind(new.current_repeat_count)
     .bind(new.next_date)
+    .bind(new.rrule.as_deref())
+    .bind(new.business_day_adjustment)
+    .bind(new.amount_estimate)
     .fetch_one(pool)
     .await?;
 
     Ok(st)
 }
 
+/// The schedules still running (not finished).
 pub async fn get_all(pool: &SqlitePool) -> Result<Vec<ScheduledTransaction>, AppError> {
     let rows = sqlx::query_as::<_, ScheduledTransaction>(&format!(
-        "SELECT {COLUMNS} FROM scheduled_transactions ORDER BY created_date DESC"
+        "SELECT {COLUMNS} FROM scheduled_transactions WHERE NOT finished ORDER BY created_date DESC"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// The schedules that ran to completion, kept for their history.
+pub async fn get_finished(pool: &SqlitePool) -> Result<Vec<ScheduledTransaction>, AppError> {
+    let rows = sqlx::query_as::<_, ScheduledTransaction>(&format!(
+        "SELECT {COLUMNS} FROM scheduled_transactions WHERE finished ORDER BY created_date DESC"
     ))
     .fetch_all(pool)
     .await?;
@@ -71,7 +88,8 @@ pub async fn update(
 }
 
 /// Update a scheduled transaction on the caller's connection/transaction, so paying a
-/// repeating schedule can advance it atomically with materialising the payment.
+/// repeating schedule can advance it atomically with materialising the payment. Updating a
+/// finished schedule starts it running again.
 pub async fn update_on(
     conn: &mut SqliteConnection,
     id: i32,
@@ -82,7 +100,8 @@ pub async fn update_on(
             kind = ?, value = ?, description = ?, created_date = ?, account_id = ?, \
             category_id = ?, origin_account_id = ?, destination_account_id = ?, repeat = ?, \
             repeat_freq = ?, repeat_interval = ?, infinite_repeat = ?, end_after_repeats = ?, \
-            current_repeat_count = ?, next_date = ? \
+            current_repeat_count = ?, next_date = ?, rrule = ?, \
+            business_day_adjustment = ?, amount_estimate = ?, finished = 0 \
          WHERE id = ? RETURNING {COLUMNS}"
     ))
     .bind(new.kind)
@@ -100,6 +119,49 @@ pub async fn update_on(
     .bind(new.end_after_repeats)
     .bind(new.current_repeat_count)
     .bind(new.next_date)
+    .bind(new.rrule.as_deref())
+    .bind(new.business_day_adjustment)
+    .bind(new.amount_estimate)
+    .bind(id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(st)
+}
+
+/// The transactions and transfers booked by paying a schedule, by occurrence.
+pub async fn get_entries(pool: &SqlitePool, id: i32) -> Result<Vec<ScheduledEntry>, AppError> {
+    let rows = sqlx::query_as::<_, ScheduledEntry>(
+        "SELECT 'transaction' AS kind, id, occurrence, value, description, date \
+         FROM transactions WHERE scheduled_transaction_id = ? \
+         UNION ALL \
+         SELECT 'transfer' AS kind, id, occurrence, value, description, date \
+         FROM transfers WHERE scheduled_transaction_id = ? \
+         ORDER BY occurrence, date, id",
+    )
+    .bind(id)
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Mark a schedule as finished after its last occurrence, with `current_repeat_count` moved
+/// past it and no next date, on the caller's connection/transaction so paying the last
+/// occurrence finishes it atomically with materialising the payment. The row is kept so its
+/// history and entries stay available.
+pub async fn finish_on(
+    conn: &mut SqliteConnection,
+    id: i32,
+    current_repeat_count: Option<i32>,
+) -> Result<ScheduledTransaction, AppError> {
+    let st = sqlx::query_as::<_, ScheduledTransaction>(&format!(
+        "UPDATE scheduled_transactions SET finished = 1, current_repeat_count = ?, \
+            next_date = NULL \
+         WHERE id = ? RETURNING {COLUMNS}"
+    ))
+    .bind(current_repeat_count)
     .bind(id)
     .fetch_one(&mut *conn)
     .await?;
@@ -112,8 +174,7 @@ pub async fn delete(pool: &SqlitePool, id: i32) -> Result<ScheduledTransaction,
     delete_on(&mut conn, id).await
 }
 
-/// Delete a scheduled transaction on the caller's connection/transaction, so paying a
-/// one-off schedule can remove it atomically with materialising the payment.
+/// Delete a scheduled transaction on the caller's connection/transaction.
 pub async fn delete_on(
     conn: &mut SqliteConnection,
     id: i32,
@@ -4,7 +4,8 @@ use sqlx::{SqliteConnection, SqlitePool};
 use crate::error::AppError;
 use crate::models::{CategoryTypes, NewTransactionData, Transaction};
 
-const COLUMNS: &str = "id, value, description, date, account, category";
+const COLUMNS: &str =
+    "id, value, description, date, account, category, scheduled_transaction_id, occurrence";
 
 /// A transaction joined with its category and account, used to build the
 /// `TransactionTransferJoined` response.
@@ -52,8 +53,9 @@ pub async fn insert_on(
         .await?;
 
     let transaction = sqlx::query_as::<_, Transaction>(&format!(
-        "INSERT INTO transactions (id, value, description, date, account, category) \
-         VALUES (?, ?, ?, ?, ?, ?) RETURNING {COLUMNS}"
+        "INSERT INTO transactions \
+         (id, value, description, date, account, category, scheduled_transaction_id, occurrence) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?) RETURNING {COLUMNS}"
     ))
     .bind(id)
     .bind(new.value)
@@ -61,6 +63,8 @@ pub async fn insert_on(
     .bind(new.date)
     .bind(new.account)
     .bind(new.category)
+    .bind(new.scheduled_transaction_id)
+    .bind(new.occurrence)
     .fetch_one(&mut *conn)
     .await?;
 
@@ -121,3 +125,23 @@ pub async fn delete(pool: &SqlitePool, id: i32) -> Result<Transaction, AppError>
 
     Ok(transaction)
 }
+
+/// The total of a category's transactions dated from `from` up to (not including) `to`.
+pub async fn sum_in_category(
+    pool: &SqlitePool,
+    category_id: i32,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Result<i32, AppError> {
+    let total: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(value), 0) FROM transactions \
+         WHERE category = ? AND date >= ? AND date < ?",
+    )
+    .bind(category_id)
+    .bind(from)
+    .bind(to)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(total as i32)
+}
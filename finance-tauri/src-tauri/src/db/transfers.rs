@@ -4,7 +4,8 @@ use sqlx::{SqliteConnection, SqlitePool};
 use crate::error::AppError;
 use crate::models::{NewTransferData, Transfer};
 
-const COLUMNS: &str = "id, origin_account, destination_account, value, description, date";
+const COLUMNS: &str = "id, origin_account, destination_account, value, description, date, \
+    scheduled_transaction_id, occurrence";
 
 /// A transfer leaving an account (origin = the account being viewed), joined with the
 /// origin account name.
@@ -51,8 +52,9 @@ pub async fn insert_on(
         .await?;
 
     let transfer = sqlx::query_as::<_, Transfer>(&format!(
-        "INSERT INTO transfers (id, origin_account, destination_account, value, description, date) \
-         VALUES (?, ?, ?, ?, ?, ?) RETURNING {COLUMNS}"
+        "INSERT INTO transfers (id, origin_account, destination_account, value, description, date, \
+            scheduled_transaction_id, occurrence) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?) RETURNING {COLUMNS}"
     ))
     .bind(id)
     .bind(new.origin_account)
@@ -60,6 +62,8 @@ pub async fn insert_on(
     .bind(new.value)
     .bind(new.description.as_str())
     .bind(new.date)
+    .bind(new.scheduled_transaction_id)
+    .bind(new.occurrence)
     .fetch_one(&mut *conn)
     .await?;
 
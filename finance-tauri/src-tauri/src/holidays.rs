@@ -0,0 +1,129 @@
+//! Holiday calendar used to move scheduled due dates off weekends and bank holidays.
+//!
+//! The calendar is read from a local file, in one of two formats:
+//!
+//! * iCalendar (`.ics`, e.g. a bank-holiday calendar export): the `DTSTART` of every event is a
+//!   holiday. Recurring events (`RRULE`) are not expanded, so the file must list each year's
+//!   date, as the published bank-holiday calendars do.
+//! * CSV: one `YYYY-MM-DD` date per line in the first column. Blank lines, `#` comments and a
+//!   header line are ignored, so `date,name` exports work as they are.
+//!
+//! Saturdays and Sundays are never business days, whether or not they are in the calendar.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Weekday};
+
+use crate::models::BusinessDayAdjustments;
+
+/// Days searched in each direction for a business day before giving up and leaving the date
+/// as it is (only reachable with a calendar that marks a whole year as holidays).
+const MAX_STEPS: u32 = 366;
+
+#[derive(Debug, Clone, Default)]
+pub struct Holidays {
+    dates: HashSet<NaiveDate>,
+}
+
+impl Holidays {
+    /// Parse an iCalendar or CSV calendar (see the module docs). `None` if a line that should
+    /// hold a date does not.
+    pub fn parse(content: &str) -> Option<Holidays> {
+        if content.trim_start().starts_with("BEGIN:VCALENDAR") {
+            Self::parse_ical(content)
+        } else {
+            Self::parse_csv(content)
+        }
+    }
+
+    /// Read and parse the calendar at `path`.
+    pub fn load(path: &Path) -> anyhow::Result<Holidays> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read holiday calendar {}", path.display()))?;
+        Self::parse(&content)
+            .with_context(|| format!("{} is not a valid holiday calendar", path.display()))
+    }
+
+    fn parse_ical(content: &str) -> Option<Holidays> {
+        let mut dates = HashSet::new();
+
+        for line in content.lines() {
+            let line = line.trim();
+            // `DTSTART;VALUE=DATE:20261225` or `DTSTART:20261225T000000Z`.
+            let Some(property) = line.strip_prefix("DTSTART") else {
+                continue;
+            };
+            if !property.starts_with([':', ';']) {
+                continue;
+            }
+            let (_, value) = property.split_once(':')?;
+            let date = NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()?;
+            dates.insert(date);
+        }
+
+        Some(Holidays { dates })
+    }
+
+    fn parse_csv(content: &str) -> Option<Holidays> {
+        let mut dates = HashSet::new();
+        let mut first = true;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let field = line.split(',').next().unwrap_or_default().trim().trim_matches('"');
+            match NaiveDate::parse_from_str(field, "%Y-%m-%d") {
+                Ok(date) => {
+                    dates.insert(date);
+                }
+                // A header line.
+                Err(_) if first => {}
+                Err(_) => return None,
+            }
+            first = false;
+        }
+
+        Some(Holidays { dates })
+    }
+
+    pub fn len(&self) -> usize {
+        self.dates.len()
+    }
+
+    /// Monday to Friday, and not a holiday.
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.dates.contains(&date)
+    }
+
+    /// Move `date` (keeping its time of day) to a business day according to `adjustment`.
+    /// A date that already is a business day is returned unchanged.
+    pub fn adjust(&self, date: NaiveDateTime, adjustment: BusinessDayAdjustments) -> NaiveDateTime {
+        if self.is_business_day(date.date()) {
+            return date;
+        }
+
+        let adjusted = match adjustment {
+            BusinessDayAdjustments::None => None,
+            BusinessDayAdjustments::Previous => self.step(date, -1),
+            BusinessDayAdjustments::Next => self.step(date, 1),
+            BusinessDayAdjustments::ModifiedFollowing => self
+                .step(date, 1)
+                .filter(|next| next.month() == date.month())
+                .or_else(|| self.step(date, -1)),
+        };
+
+        adjusted.unwrap_or(date)
+    }
+
+    /// The nearest business day after (`direction` 1) or before (-1) `date`.
+    fn step(&self, date: NaiveDateTime, direction: i64) -> Option<NaiveDateTime> {
+        (1..=MAX_STEPS as i64)
+            .map(|days| date + Duration::days(days * direction))
+            .find(|candidate| self.is_business_day(candidate.date()))
+    }
+}
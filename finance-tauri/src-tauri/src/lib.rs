@@ -2,7 +2,10 @@ mod bootstrap;
 mod commands;
 mod db;
 mod error;
+mod holidays;
 mod models;
+mod notifications;
+mod recurrence;
 mod service;
 mod state;
 
@@ -12,6 +15,7 @@ mod tests;
 use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
 use tauri_plugin_log::{Target, TargetKind};
 
+use crate::holidays::Holidays;
 use crate::state::AppState;
 
 /// Show a native error dialog for an unrecoverable startup failure and exit.
@@ -62,10 +66,34 @@ fn install_panic_hook() {
     }));
 }
 
-/// Build the tauri-specta command registry shared by the runtime invoke handler (in [`run`])
-/// and the binding-export test, so the generated TypeScript can never cover a different set of
-/// commands than the app actually serves. Adding a command here is the single place it needs
-/// to be registered for both wiring and type generation.
+/// Read the holiday calendar from `holidays.ics` (or else `holidays.csv`) in the app-data
+/// directory. A missing or invalid calendar is not fatal: due dates are then only moved off
+/// weekends, and an invalid one is reported in the log.
+fn load_holidays(data_dir: &std::path::Path) -> Holidays {
+    let Some(path) = ["holidays.ics", "holidays.csv"]
+        .into_iter()
+        .map(|name| data_dir.join(name))
+        .find(|path| path.exists())
+    else {
+        return Holidays::default();
+    };
+
+    match Holidays::load(&path) {
+        Ok(holidays) => {
+            log::info!("loaded {} holidays from {}", holidays.len(), path.display());
+            holidays
+        }
+        Err(err) => {
+            log::warn!("ignoring the holiday calendar: {err:#}");
+            Holidays::default()
+        }
+    }
+}
+
+/// Build the tauri-specta command and event registry shared by the runtime invoke handler (in
+/// [`run`]) and the binding-export test, so the generated TypeScript can never cover a different
+/// set of commands than the app actually serves. Adding a command (or event) here is the single
+/// place it needs to be registered for both wiring and type generation.
 pub(crate) fn specta_builder() -> tauri_specta::Builder<tauri::Wry> {
     tauri_specta::Builder::<tauri::Wry>::new().commands(tauri_specta::collect_commands![
         commands::get_initial_data,
@@ -91,10 +119,31 @@ pub(crate) fn specta_builder() -> tauri_specta::Builder<tauri::Wry> {
         commands::delete_transfer,
         commands::create_scheduled_transaction,
         commands::get_scheduled_transactions,
+        commands::preview_scheduled_transaction,
+        commands::get_finished_scheduled_transactions,
         commands::get_scheduled_transaction,
         commands::update_scheduled_transaction,
         commands::delete_scheduled_transaction,
         commands::pay_scheduled_transaction,
+        commands::skip_scheduled_transaction,
+        commands::snooze_scheduled_transaction,
+        commands::get_scheduled_transaction_history,
+        commands::get_scheduled_transaction_occurrences,
+        commands::get_scheduled_transaction_entries,
+        commands::create_goal,
+        commands::get_goals,
+        commands::get_goal,
+        commands::update_goal,
+        commands::delete_goal,
+        commands::get_goal_progress,
+        commands::set_goal_contribution,
+        commands::delete_goal_contribution,
+        commands::get_due_scheduled_transactions,
+        commands::get_notification_settings,
+        commands::update_notification_settings,
+    ])
+    .events(tauri_specta::collect_events![
+        notifications::DueScheduledTransactions
     ])
 }
 
@@ -182,7 +231,12 @@ pub fn run() {
                 ])
                 .build(),
         )
-        .setup(|app| {
+        .plugin(tauri_plugin_notification::init())
+        .invoke_handler(specta_builder.invoke_handler())
+        .setup(move |app| {
+            // Typed events can only be emitted once they are registered with the app.
+            specta_builder.mount_events(app);
+
             // A per-user, writable location for the database file.
             let data_dir = match app.path().app_data_dir() {
                 Ok(dir) => dir,
@@ -202,9 +256,14 @@ pub fn run() {
                 )),
             };
 
+            let holidays = load_holidays(&data_dir);
+
             // Register shared state *before* the window is created, so the frontend can never
             // load and invoke a command before the pool is available.
-            app.manage(AppState { pool });
+            app.manage(AppState { pool, holidays });
+
+            // Announce due and overdue scheduled transactions now and then periodically.
+            notifications::start(app.handle().clone());
 
             WebviewWindowBuilder::new(app, "main", WebviewUrl::default())
                 .title("Finance")
@@ -213,7 +272,6 @@ pub fn run() {
 
             Ok(())
         })
-        .invoke_handler(specta_builder.invoke_handler())
         .build(tauri::generate_context!())
         .expect("error while building the Finance application")
         .run(|app_handle, event| {
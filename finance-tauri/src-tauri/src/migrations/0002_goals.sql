@@ -0,0 +1,24 @@
+-- Migration 0002: savings goals.
+--
+-- A goal has a target amount and an optional target date, and is funded by one or more
+-- linked accounts. A link without an `earmark` counts the account's whole balance towards the
+-- goal; with one, only up to that amount of the balance is set aside for it.
+-- `scheduled_transaction_id` is the optional monthly contribution transfer.
+
+CREATE TABLE IF NOT EXISTS goals
+(
+    id                       INTEGER PRIMARY KEY AUTOINCREMENT,
+    name                     TEXT    NOT NULL,
+    target_amount            INTEGER NOT NULL CHECK (target_amount > 0),
+    target_date              TEXT,
+    created_date             TEXT    NOT NULL,
+    scheduled_transaction_id INTEGER REFERENCES scheduled_transactions (id) ON DELETE SET NULL
+);
+
+CREATE TABLE IF NOT EXISTS goal_accounts
+(
+    goal_id    INTEGER NOT NULL REFERENCES goals (id) ON DELETE CASCADE,
+    account_id INTEGER NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    earmark    INTEGER CHECK (earmark >= 0),
+    PRIMARY KEY (goal_id, account_id)
+);
@@ -0,0 +1,7 @@
+-- Migration 0003: RFC 5545 recurrence rules for scheduled transactions.
+--
+-- A repeating schedule either repeats every `repeat_interval` `repeat_freq` from its
+-- `created_date`, or (when `rrule` is set, e.g. 'FREQ=MONTHLY;BYDAY=2TU') on the dates of the
+-- rule, with `created_date` as its start. `infinite_repeat` / `end_after_repeats` apply to both.
+
+ALTER TABLE scheduled_transactions ADD COLUMN rrule TEXT;
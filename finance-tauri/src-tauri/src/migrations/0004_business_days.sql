@@ -0,0 +1,9 @@
+-- Migration 0004: weekend and holiday adjustment of scheduled due dates.
+--
+-- A due date that falls on a weekend or on a date of the holiday calendar is moved to the
+-- previous business day, the next one, or the next one unless it is in the following month
+-- ('modified_following'). Only `next_date` is moved: the following occurrences are still
+-- computed from `created_date` / the rrule, so adjusted dates never drift the schedule.
+
+ALTER TABLE scheduled_transactions
+    ADD COLUMN business_day_adjustment TEXT NOT NULL DEFAULT 'none';
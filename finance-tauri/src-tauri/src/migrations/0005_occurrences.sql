@@ -0,0 +1,21 @@
+-- Migration 0005: history of what happened to each due occurrence of a scheduled transaction.
+--
+-- Every pay, partial pay, skip and snooze records a row: `occurrence` is the schedule's
+-- `current_repeat_count` at the time (NULL for a one-off), `due_date` its `next_date`, `value`
+-- the amount booked (0 for a skip or snooze) and `snoozed_to` the new due date of a snooze.
+-- A partially paid occurrence stays due until its payments reach the scheduled value.
+
+CREATE TABLE IF NOT EXISTS scheduled_occurrences
+(
+    id                       INTEGER PRIMARY KEY AUTOINCREMENT,
+    scheduled_transaction_id INTEGER NOT NULL REFERENCES scheduled_transactions (id) ON DELETE CASCADE,
+    occurrence               INTEGER,
+    due_date                 TEXT    NOT NULL,
+    outcome                  TEXT    NOT NULL,
+    value                    INTEGER NOT NULL DEFAULT 0,
+    snoozed_to               TEXT,
+    recorded_at              TEXT    NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS scheduled_occurrences_schedule_idx
+    ON scheduled_occurrences (scheduled_transaction_id);
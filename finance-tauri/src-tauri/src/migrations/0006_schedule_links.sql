@@ -0,0 +1,20 @@
+-- Migration 0006: link the transactions and transfers a scheduled transaction produces back to
+-- it, and keep schedules once they have finished.
+--
+-- Paying a schedule records its id and the 0-based `occurrence` paid (NULL for a one-off) on the
+-- transactions and transfers it creates. A schedule whose last occurrence has been paid or
+-- skipped is marked `finished` (with no `next_date`) instead of being deleted, so its entries
+-- and occurrence history stay available.
+
+ALTER TABLE transactions
+    ADD COLUMN scheduled_transaction_id INTEGER REFERENCES scheduled_transactions (id) ON DELETE SET NULL;
+ALTER TABLE transactions ADD COLUMN occurrence INTEGER;
+
+ALTER TABLE transfers
+    ADD COLUMN scheduled_transaction_id INTEGER REFERENCES scheduled_transactions (id) ON DELETE SET NULL;
+ALTER TABLE transfers ADD COLUMN occurrence INTEGER;
+
+CREATE INDEX IF NOT EXISTS transactions_scheduled_idx ON transactions (scheduled_transaction_id);
+CREATE INDEX IF NOT EXISTS transfers_scheduled_idx ON transfers (scheduled_transaction_id);
+
+ALTER TABLE scheduled_transactions ADD COLUMN finished INTEGER NOT NULL DEFAULT 0;
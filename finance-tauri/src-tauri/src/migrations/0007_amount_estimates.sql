@@ -0,0 +1,8 @@
+-- Migration 0007: estimated amounts for schedules that recur with a varying value.
+--
+-- Instead of its fixed `value`, a schedule can show the average, median or last of the amounts
+-- paid for its previous occurrences, or the recent monthly spend in its category. `value` stays
+-- the scheduled amount and is used until there is anything to estimate from.
+
+ALTER TABLE scheduled_transactions
+    ADD COLUMN amount_estimate TEXT NOT NULL DEFAULT 'fixed';
@@ -0,0 +1,13 @@
+-- Migration 0008: notifications for scheduled transactions that are due or overdue.
+--
+-- A single row of settings: whether native notifications are shown at all, and how many days
+-- before its due date a scheduled transaction is announced (0 = on the day it is due).
+
+CREATE TABLE notification_settings
+(
+    id        INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled   INTEGER NOT NULL DEFAULT 1,
+    lead_days INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO notification_settings (id) VALUES (1);
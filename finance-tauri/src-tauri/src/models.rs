@@ -41,6 +41,52 @@ pub enum RepeatFrequencies {
     Years,
 }
 
+/// How a scheduled due date that falls on a weekend or holiday is moved: not at all, to the
+/// previous or the next business day, or to the next one unless that is in the following month
+/// (then the previous one, "modified following").
+#[derive(sqlx::Type, specta::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[sqlx(rename_all = "snake_case")]
+pub enum BusinessDayAdjustments {
+    #[default]
+    None,
+    Previous,
+    Next,
+    ModifiedFollowing,
+}
+
+/// The amount a schedule's occurrences are expected to need: its fixed `value`, or an estimate
+/// from what was paid for its previous occurrences (average, median or last) or from the recent
+/// monthly spend in its category.
+#[derive(sqlx::Type, specta::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[sqlx(rename_all = "snake_case")]
+pub enum AmountEstimates {
+    #[default]
+    Fixed,
+    Average,
+    Median,
+    Last,
+    CategorySpend,
+}
+
+/// What happened to a due occurrence of a scheduled transaction.
+#[derive(sqlx::Type, specta::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[sqlx(rename_all = "snake_case")]
+pub enum OccurrenceOutcomes {
+    Paid,
+    PartiallyPaid,
+    Skipped,
+    Snoozed,
+}
+
+/// How a scheduled transaction's due date compares with today: coming up within the
+/// notification lead time, due today or overdue. Only used in responses; it is not stored.
+#[derive(specta::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DueStatuses {
+    Upcoming,
+    DueToday,
+    Overdue,
+}
+
 // ---------------------------------------------------------------------------
 // Database row models
 // ---------------------------------------------------------------------------
@@ -58,6 +104,8 @@ pub struct Account {
     pub name: String,
 }
 
+/// A transaction. `scheduled_transaction_id` and `occurrence` are set when it was booked by paying
+/// that occurrence of a schedule.
 #[derive(sqlx::FromRow, specta::Type, Debug, Serialize, Deserialize)]
 pub struct Transaction {
     pub id: i32,
@@ -66,8 +114,12 @@ pub struct Transaction {
     pub date: NaiveDateTime,
     pub account: i32,
     pub category: i32,
+    pub scheduled_transaction_id: Option<i32>,
+    pub occurrence: Option<i32>,
 }
 
+/// A transfer. `scheduled_transaction_id` and `occurrence` are set when it was booked by paying
+/// that occurrence of a schedule.
 #[derive(sqlx::FromRow, specta::Type, Debug, Serialize, Deserialize)]
 pub struct Transfer {
     pub id: i32,
@@ -76,6 +128,27 @@ pub struct Transfer {
     pub value: i32,
     pub description: String,
     pub date: NaiveDateTime,
+    pub scheduled_transaction_id: Option<i32>,
+    pub occurrence: Option<i32>,
+}
+
+#[derive(sqlx::FromRow, specta::Type, Debug, Serialize, Deserialize)]
+pub struct Goal {
+    pub id: i32,
+    pub name: String,
+    pub target_amount: i32,
+    pub target_date: Option<NaiveDateTime>,
+    pub created_date: NaiveDateTime,
+    pub scheduled_transaction_id: Option<i32>,
+}
+
+/// An account funding a goal. Without an `earmark` the whole balance counts towards the goal,
+/// otherwise only up to the earmarked amount.
+#[derive(sqlx::FromRow, specta::Type, Debug, Serialize, Deserialize)]
+pub struct GoalAccount {
+    pub goal_id: i32,
+    pub account_id: i32,
+    pub earmark: Option<i32>,
 }
 
 #[derive(sqlx::FromRow, specta::Type, Debug, Serialize, Deserialize)]
@@ -92,10 +165,48 @@ pub struct ScheduledTransaction {
     pub repeat: bool,
     pub repeat_freq: Option<RepeatFrequencies>,
     pub repeat_interval: Option<i32>,
+    pub rrule: Option<String>,
+    pub business_day_adjustment: BusinessDayAdjustments,
+    pub amount_estimate: AmountEstimates,
     pub infinite_repeat: Option<bool>,
     pub end_after_repeats: Option<i32>,
     pub current_repeat_count: Option<i32>,
     pub next_date: Option<NaiveDateTime>,
+    pub finished: bool,
+}
+
+/// One entry of a schedule's occurrence history. `occurrence` is the schedule's
+/// `current_repeat_count` when it was recorded (`None` for a one-off), `value` the amount booked
+/// (0 for a skip or snooze) and `snoozed_to` the due date a snooze moved it to.
+#[derive(sqlx::FromRow, specta::Type, Debug, Serialize, Deserialize)]
+pub struct ScheduledOccurrence {
+    pub id: i32,
+    pub scheduled_transaction_id: i32,
+    pub occurrence: Option<i32>,
+    pub due_date: NaiveDateTime,
+    pub outcome: OccurrenceOutcomes,
+    pub value: i32,
+    pub snoozed_to: Option<NaiveDateTime>,
+    pub recorded_at: NaiveDateTime,
+}
+
+/// A transaction or transfer booked by paying a schedule, tagged with the occurrence it paid.
+#[derive(sqlx::FromRow, specta::Type, Debug, Serialize, Deserialize)]
+pub struct ScheduledEntry {
+    pub kind: ScheduledTransactionKinds,
+    pub id: i32,
+    pub occurrence: Option<i32>,
+    pub value: i32,
+    pub description: String,
+    pub date: NaiveDateTime,
+}
+
+/// Settings of the due/overdue notifications: whether they are shown, and how many days before
+/// its due date a scheduled transaction is announced (0 = on the day).
+#[derive(sqlx::FromRow, specta::Type, Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub lead_days: i32,
 }
 
 // ---------------------------------------------------------------------------
@@ -156,6 +267,11 @@ pub struct PostScheduledTransaction {
     pub repeat: bool,
     pub repeat_freq: Option<RepeatFrequencies>,
     pub repeat_interval: Option<i32>,
+    pub rrule: Option<String>,
+    #[serde(default)]
+    pub business_day_adjustment: BusinessDayAdjustments,
+    #[serde(default)]
+    pub amount_estimate: AmountEstimates,
     pub infinite_repeat: Option<bool>,
     pub end_after_repeats: Option<i32>,
     pub current_repeat_count: Option<i32>,
@@ -164,7 +280,35 @@ pub struct PostScheduledTransaction {
 
 pub type PatchScheduledTransaction = PostScheduledTransaction;
 
-/// Internal (non-serialized) value object used to insert/update a transaction row.
+/// New goal, or the full replacement of one (`PatchGoal`), including its linked accounts.
+#[derive(specta::Type, Debug, Serialize, Deserialize)]
+pub struct PostGoal {
+    pub name: String,
+    pub target_amount: i32,
+    pub target_date: Option<NaiveDateTime>,
+    pub accounts: Vec<PostGoalAccount>,
+}
+
+pub type PatchGoal = PostGoal;
+
+#[derive(specta::Type, Debug, Serialize, Deserialize)]
+pub struct PostGoalAccount {
+    pub account_id: i32,
+    pub earmark: Option<i32>,
+}
+
+/// Set up the goal's monthly contribution: a transfer from `origin_account_id` into one of the
+/// goal's accounts for the required monthly amount, starting on `start_date` and repeating
+/// until the target date.
+#[derive(specta::Type, Debug, Serialize, Deserialize)]
+pub struct PostGoalContribution {
+    pub origin_account_id: i32,
+    pub destination_account_id: i32,
+    pub start_date: NaiveDateTime,
+}
+
+/// Internal (non-serialized) value object used to insert/update a transaction row. The
+/// schedule link (`scheduled_transaction_id`, `occurrence`) is set on insert only.
 #[derive(Debug, Clone)]
 pub struct NewTransactionData {
     pub value: i32,
@@ -172,9 +316,12 @@ pub struct NewTransactionData {
     pub date: NaiveDateTime,
     pub account: i32,
     pub category: i32,
+    pub scheduled_transaction_id: Option<i32>,
+    pub occurrence: Option<i32>,
 }
 
-/// Internal (non-serialized) value object used to insert/update a transfer row.
+/// Internal (non-serialized) value object used to insert/update a transfer row. The schedule
+/// link (`scheduled_transaction_id`, `occurrence`) is set on insert only.
 #[derive(Debug, Clone)]
 pub struct NewTransferData {
     pub origin_account: i32,
@@ -182,6 +329,8 @@ pub struct NewTransferData {
     pub value: i32,
     pub description: String,
     pub date: NaiveDateTime,
+    pub scheduled_transaction_id: Option<i32>,
+    pub occurrence: Option<i32>,
 }
 
 /// Internal (non-serialized) value object used to insert/update a scheduled transaction row.
@@ -198,12 +347,17 @@ pub struct NewScheduledTransaction {
     pub repeat: bool,
     pub repeat_freq: Option<RepeatFrequencies>,
     pub repeat_interval: Option<i32>,
+    pub rrule: Option<String>,
+    pub business_day_adjustment: BusinessDayAdjustments,
+    pub amount_estimate: AmountEstimates,
     pub infinite_repeat: Option<bool>,
     pub end_after_repeats: Option<i32>,
     pub current_repeat_count: Option<i32>,
     pub next_date: Option<NaiveDateTime>,
 }
 
+/// Payment of a schedule's current occurrence. With `partial`, only part of it is paid: the
+/// occurrence stays due until its payments add up to the scheduled value.
 #[derive(specta::Type, Debug, Serialize, Deserialize)]
 pub struct PostScheduledTransactionPay {
     pub value: i32,
@@ -213,6 +367,27 @@ pub struct PostScheduledTransactionPay {
     pub account_id: Option<i32>,
     pub origin_account_id: Option<i32>,
     pub destination_account_id: Option<i32>,
+    #[serde(default)]
+    pub partial: bool,
+}
+
+/// Move the current due date of a schedule to a later `date`, without changing the following
+/// occurrences.
+#[derive(specta::Type, Debug, Serialize, Deserialize)]
+pub struct PostScheduledTransactionSnooze {
+    pub date: NaiveDateTime,
+}
+
+/// Internal (non-serialized) value object used to record an entry of a schedule's occurrence
+/// history.
+#[derive(Debug, Clone)]
+pub struct NewScheduledOccurrence {
+    pub scheduled_transaction_id: i32,
+    pub occurrence: Option<i32>,
+    pub due_date: NaiveDateTime,
+    pub outcome: OccurrenceOutcomes,
+    pub value: i32,
+    pub snoozed_to: Option<NaiveDateTime>,
 }
 
 // ---------------------------------------------------------------------------
@@ -248,6 +423,23 @@ pub struct TransactionTransferJoined {
     pub from_account_name: Option<String>,
 }
 
+/// One upcoming occurrence of a schedule (`occurrence` is `None` for a one-off). `date` is when
+/// it is due, after any move off a weekend or holiday (or a snooze), and `scheduled_date` the
+/// date the recurrence gives; `clamped` is set when that date is earlier in the month than the
+/// schedule's start because the month is shorter (the 31st becoming the 28th of February).
+#[derive(specta::Type, Debug, Serialize, Deserialize)]
+pub struct UpcomingOccurrence {
+    pub occurrence: Option<i32>,
+    pub date: NaiveDateTime,
+    pub scheduled_date: NaiveDateTime,
+    pub value: i32,
+    pub clamped: bool,
+}
+
+/// A scheduled transaction with the names of what it references. `paid_value` is what partial
+/// payments have already paid towards the current occurrence and `estimated_value` the amount
+/// expected for it (`value` unless it has an `amount_estimate`); `finished` is set once the last
+/// occurrence has been paid or skipped.
 #[derive(specta::Type, Debug, Serialize, Deserialize)]
 pub struct GetScheduledTransaction {
     pub id: i32,
@@ -267,8 +459,68 @@ pub struct GetScheduledTransaction {
     pub repeat: bool,
     pub repeat_freq: Option<RepeatFrequencies>,
     pub repeat_interval: Option<i32>,
+    pub rrule: Option<String>,
+    pub business_day_adjustment: BusinessDayAdjustments,
+    pub amount_estimate: AmountEstimates,
     pub infinite_repeat: Option<bool>,
     pub end_after_repeats: Option<i32>,
     pub current_repeat_count: Option<i32>,
     pub next_date: Option<NaiveDateTime>,
+    pub finished: bool,
+    pub paid_value: i32,
+    pub estimated_value: i32,
+}
+
+/// A scheduled transaction that is due, overdue or coming up within the notification lead time.
+/// `label` is its description (or category), `accounts` the account it is booked in (or the
+/// origin → destination of a transfer), `value` what is still expected for the occurrence and
+/// `days_until` the days left until `next_date` (negative once overdue).
+#[derive(specta::Type, Debug, Clone, Serialize, Deserialize)]
+pub struct DueScheduledTransaction {
+    pub id: i32,
+    pub kind: ScheduledTransactionKinds,
+    pub label: String,
+    pub accounts: String,
+    pub value: i32,
+    pub next_date: NaiveDateTime,
+    pub days_until: i32,
+    pub status: DueStatuses,
+}
+
+#[derive(specta::Type, Debug, Serialize, Deserialize)]
+pub struct GetGoal {
+    pub id: i32,
+    pub name: String,
+    pub target_amount: i32,
+    pub target_date: Option<NaiveDateTime>,
+    pub created_date: NaiveDateTime,
+    pub scheduled_transaction_id: Option<i32>,
+    pub accounts: Vec<GoalAccount>,
+}
+
+/// How much of an account's balance counts towards a goal: the balance capped by the
+/// earmark, and never negative.
+#[derive(specta::Type, Debug, Serialize, Deserialize)]
+pub struct GoalAccountProgress {
+    pub account_id: i32,
+    pub account_name: String,
+    pub earmark: Option<i32>,
+    pub balance: i32,
+    pub contribution: i32,
+}
+
+/// A goal's progress as of today. `months_left` counts the monthly contributions still possible
+/// before the target date, and `required_monthly` is what each must be to reach the target
+/// (all of `remaining` when the date has come); both are `None` without a target date.
+#[derive(specta::Type, Debug, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub goal_id: i32,
+    pub target_amount: i32,
+    pub target_date: Option<NaiveDateTime>,
+    pub saved: i32,
+    pub remaining: i32,
+    pub percent: i32,
+    pub months_left: Option<i32>,
+    pub required_monthly: Option<i32>,
+    pub accounts: Vec<GoalAccountProgress>,
 }
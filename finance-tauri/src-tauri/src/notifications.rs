@@ -0,0 +1,91 @@
+//! Native notifications for scheduled transactions that are due or overdue.
+//!
+//! A background task checks the schedules at startup and then every [`CHECK_INTERVAL`]. Each
+//! check raises an OS notification for every schedule that became due (within the configured
+//! lead time), due today or overdue since it was last announced, and emits the
+//! [`DueScheduledTransactions`] event with the whole list, so the scheduled list badge in the
+//! frontend follows without polling. The detection itself is [`service::due_scheduled`].
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tauri_specta::Event;
+
+use crate::db;
+use crate::error::AppError;
+use crate::models::{DueScheduledTransaction, DueStatuses};
+use crate::service;
+use crate::state::AppState;
+
+/// Time between two checks of the due dates.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// The scheduled transactions that are overdue, due today or due within the lead time, sent to
+/// the frontend after every check.
+#[derive(specta::Type, tauri_specta::Event, Debug, Clone, Serialize, Deserialize)]
+pub struct DueScheduledTransactions {
+    pub due: Vec<DueScheduledTransaction>,
+}
+
+/// Start the periodic check. A schedule is announced once per due date and status, so it is
+/// notified again when it moves from upcoming to due today to overdue, but not on every check.
+/// That memory only lives as long as the app: everything still due is announced again at the
+/// next startup.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut announced = HashSet::new();
+        loop {
+            if let Err(err) = check(&app, &mut announced).await {
+                log::warn!("checking for due scheduled transactions failed: {err}");
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+async fn check(
+    app: &AppHandle,
+    announced: &mut HashSet<(i32, NaiveDateTime, DueStatuses)>,
+) -> Result<(), AppError> {
+    let state = app.state::<AppState>();
+    let pool = &state.pool;
+    let settings = db::notification_settings::get(pool).await?;
+    let due = service::due_scheduled(pool, Local::now().date_naive(), settings.lead_days).await?;
+
+    if settings.enabled {
+        for item in &due {
+            if announced.insert((item.id, item.next_date, item.status)) {
+                notify(app, item);
+            }
+        }
+    }
+
+    DueScheduledTransactions { due }
+        .emit(app)
+        .map_err(|err| AppError::Internal(format!("failed to emit the due list: {err}")))
+}
+
+/// Show one native notification, e.g. "Overdue: Rent" / "850.00 · Checking".
+fn notify(app: &AppHandle, item: &DueScheduledTransaction) {
+    let when = match item.status {
+        DueStatuses::Overdue => "Overdue".to_string(),
+        DueStatuses::DueToday => "Due today".to_string(),
+        DueStatuses::Upcoming if item.days_until == 1 => "Due tomorrow".to_string(),
+        DueStatuses::Upcoming => format!("Due in {} days", item.days_until),
+    };
+    let amount = format!("{}.{:02}", item.value / 100, item.value % 100);
+
+    let result = app
+        .notification()
+        .builder()
+        .title(format!("{when}: {}", item.label))
+        .body(format!("{amount} · {}", item.accounts))
+        .show();
+    if let Err(err) = result {
+        log::warn!("could not show a notification for scheduled transaction {}: {err}", item.id);
+    }
+}
@@ -1,4 +1,4 @@
-use chrono::{Duration, NaiveDateTime};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
 use chronoutil::RelativeDuration;
 use sqlx::SqlitePool;
 
@@ -6,10 +6,14 @@ use crate::db;
 use crate::db::transactions::TxJoinRow;
 use crate::db::transfers::{TransferFromRow, TransferToRow};
 use crate::error::AppError;
+use crate::holidays::Holidays;
 use crate::models::{
-    CategoryTypes, GetAccount, GetScheduledTransaction, InitialData, RepeatFrequencies,
-    ScheduledTransaction, ScheduledTransactionKinds, TransactionTransferJoined,
+    AmountEstimates, BusinessDayAdjustments, CategoryTypes, DueScheduledTransaction, DueStatuses,
+    GetAccount, GetGoal, GetScheduledTransaction, Goal, GoalAccountProgress, GoalProgress,
+    InitialData, NewScheduledTransaction, PostGoal, PostGoalContribution, RepeatFrequencies,
+    ScheduledTransaction, ScheduledTransactionKinds, TransactionTransferJoined, UpcomingOccurrence,
 };
+use crate::recurrence::RRule;
 
 /// Load all accounts, each with its computed balance.
 pub async fn accounts_with_balance(pool: &SqlitePool) -> Result<Vec<GetAccount>, AppError> {
@@ -77,10 +81,17 @@ pub async fn enrich_scheduled(
         repeat: st.repeat,
         repeat_freq: st.repeat_freq,
         repeat_interval: st.repeat_interval,
+        rrule: st.rrule.clone(),
+        business_day_adjustment: st.business_day_adjustment,
+        amount_estimate: st.amount_estimate,
         infinite_repeat: st.infinite_repeat,
         end_after_repeats: st.end_after_repeats,
         current_repeat_count: st.current_repeat_count,
         next_date: st.next_date,
+        finished: st.finished,
+        paid_value: db::scheduled_occurrences::paid_value(pool, st.id, st.current_repeat_count)
+            .await?,
+        estimated_value: estimated_value(pool, st).await?,
     };
 
     let missing = || AppError::Internal("scheduled transaction has an unresolved reference".into());
@@ -175,6 +186,439 @@ pub fn transfer_to_to_dto(row: TransferToRow) -> TransactionTransferJoined {
     }
 }
 
+/// Check a goal body: a positive target, non-negative earmarks, and linked accounts that exist
+/// (`NotFound` otherwise).
+pub async fn validate_goal(pool: &SqlitePool, body: &PostGoal) -> Result<(), AppError> {
+    if body.target_amount <= 0 || body.accounts.iter().any(|a| a.earmark.is_some_and(|e| e < 0)) {
+        return Err(AppError::BadRequest);
+    }
+
+    for account in &body.accounts {
+        db::accounts::get(pool, account.account_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Add the linked accounts to a goal row.
+pub async fn goal_to_dto(pool: &SqlitePool, goal: Goal) -> Result<GetGoal, AppError> {
+    Ok(GetGoal {
+        accounts: db::goals::get_accounts(pool, goal.id).await?,
+        id: goal.id,
+        name: goal.name,
+        target_amount: goal.target_amount,
+        target_date: goal.target_date,
+        created_date: goal.created_date,
+        scheduled_transaction_id: goal.scheduled_transaction_id,
+    })
+}
+
+/// The number of monthly contributions that still fit between `today` and `target`: whole
+/// months, so a target on the 10th seen on the 19th is one month fewer. Never negative.
+pub fn months_until(today: NaiveDate, target: NaiveDate) -> i32 {
+    let months = (target.year() - today.year()) * 12 + target.month() as i32
+        - today.month() as i32
+        - i32::from(target.day() < today.day());
+    months.max(0)
+}
+
+/// The monthly contribution needed to save `remaining` in `months_left` contributions,
+/// rounded up to the cent. With no months left, everything is needed now.
+pub fn required_monthly(remaining: i32, months_left: i32) -> i32 {
+    if months_left <= 0 {
+        return remaining;
+    }
+    (remaining + months_left - 1) / months_left
+}
+
+/// Compute a goal's progress as of `today` from the current balances of its linked accounts.
+pub async fn goal_progress(
+    pool: &SqlitePool,
+    goal: &Goal,
+    today: NaiveDate,
+) -> Result<GoalProgress, AppError> {
+    let mut accounts = Vec::new();
+
+    for link in db::goals::get_accounts(pool, goal.id).await? {
+        let account = db::accounts::get(pool, link.account_id).await?;
+        let balance = db::accounts::balance(pool, link.account_id).await?;
+        let contribution = link.earmark.map_or(balance, |earmark| balance.min(earmark)).max(0);
+
+        accounts.push(GoalAccountProgress {
+            account_id: account.id,
+            account_name: account.name,
+            earmark: link.earmark,
+            balance,
+            contribution,
+        });
+    }
+
+    let saved: i32 = accounts.iter().map(|a| a.contribution).sum();
+    let remaining = (goal.target_amount - saved).max(0);
+    let percent = (i64::from(saved) * 100 / i64::from(goal.target_amount)).min(100) as i32;
+    let months_left = goal.target_date.map(|target| months_until(today, target.date()));
+
+    Ok(GoalProgress {
+        goal_id: goal.id,
+        target_amount: goal.target_amount,
+        target_date: goal.target_date,
+        saved,
+        remaining,
+        percent,
+        months_left,
+        required_monthly: months_left.map(|months| required_monthly(remaining, months)),
+        accounts,
+    })
+}
+
+/// Create (or replace) a goal's monthly contribution: a transfer for the required monthly
+/// amount into one of the goal's accounts, repeating once per month left. `BadRequest` when
+/// the goal has no target date, nothing remains to save, no months are left, or the
+/// destination is not one of the goal's accounts.
+pub async fn set_goal_contribution(
+    pool: &SqlitePool,
+    goal: &Goal,
+    body: &PostGoalContribution,
+    today: NaiveDate,
+) -> Result<GetGoal, AppError> {
+    let progress = goal_progress(pool, goal, today).await?;
+    let (Some(months_left), Some(monthly)) = (progress.months_left, progress.required_monthly)
+    else {
+        return Err(AppError::BadRequest);
+    };
+    if months_left <= 0
+        || monthly <= 0
+        || body.origin_account_id == body.destination_account_id
+        || !progress.accounts.iter().any(|a| a.account_id == body.destination_account_id)
+    {
+        return Err(AppError::BadRequest);
+    }
+    db::accounts::get(pool, body.origin_account_id).await?;
+
+    let scheduled = db::scheduled_transactions::insert(
+        pool,
+        &NewScheduledTransaction {
+            kind: ScheduledTransactionKinds::Transfer,
+            value: monthly,
+            description: Some(format!("{} contribution", goal.name)),
+            created_date: body.start_date,
+            account_id: None,
+            category_id: None,
+            origin_account_id: Some(body.origin_account_id),
+            destination_account_id: Some(body.destination_account_id),
+            repeat: true,
+            repeat_freq: Some(RepeatFrequencies::Months),
+            repeat_interval: Some(1),
+            rrule: None,
+            business_day_adjustment: BusinessDayAdjustments::None,
+            amount_estimate: AmountEstimates::Fixed,
+            infinite_repeat: Some(false),
+            end_after_repeats: Some(months_left),
+            current_repeat_count: Some(0),
+            next_date: Some(body.start_date),
+        },
+    )
+    .await?;
+
+    if let Some(previous) = goal.scheduled_transaction_id {
+        db::scheduled_transactions::delete(pool, previous).await?;
+    }
+
+    let goal = db::goals::set_scheduled(pool, goal.id, Some(scheduled.id)).await?;
+    goal_to_dto(pool, goal).await
+}
+
+/// The update that keeps a schedule as it is but moves it to another occurrence and due date.
+pub fn rescheduled(
+    st: &ScheduledTransaction,
+    current_repeat_count: Option<i32>,
+    next_date: Option<NaiveDateTime>,
+) -> NewScheduledTransaction {
+    NewScheduledTransaction {
+        kind: st.kind,
+        value: st.value,
+        description: st.description.clone(),
+        created_date: st.created_date,
+        account_id: st.account_id,
+        category_id: st.category_id,
+        origin_account_id: st.origin_account_id,
+        destination_account_id: st.destination_account_id,
+        repeat: st.repeat,
+        repeat_freq: st.repeat_freq,
+        repeat_interval: st.repeat_interval,
+        rrule: st.rrule.clone(),
+        business_day_adjustment: st.business_day_adjustment,
+        amount_estimate: st.amount_estimate,
+        infinite_repeat: st.infinite_repeat,
+        end_after_repeats: st.end_after_repeats,
+        current_repeat_count,
+        next_date,
+    }
+}
+
+/// The due date of occurrence `repeat_count` (0-based) of a repeating schedule, moved off
+/// weekends and `holidays` as the schedule's `business_day_adjustment` says (see
+/// [`recurrence_date`]). Each occurrence is computed from the unadjusted anchor, so a moved due
+/// date never shifts the ones after it.
+pub fn scheduled_occurrence(
+    st: &ScheduledTransaction,
+    repeat_count: i32,
+    holidays: &Holidays,
+) -> Result<Option<NaiveDateTime>, AppError> {
+    Ok(recurrence_date(st, repeat_count)?
+        .map(|date| holidays.adjust(date, st.business_day_adjustment)))
+}
+
+/// The date occurrence `repeat_count` (0-based) of a repeating schedule falls on before any
+/// business-day move: from its RRULE when it has one (`None` once the rule has ended),
+/// otherwise every `repeat_interval` `repeat_freq` from its `created_date`.
+fn recurrence_date(
+    st: &ScheduledTransaction,
+    repeat_count: i32,
+) -> Result<Option<NaiveDateTime>, AppError> {
+    let internal = |m: &str| AppError::Internal(m.to_string());
+
+    if let Some(rule) = &st.rrule {
+        let rule = RRule::parse(rule).ok_or_else(|| internal("schedule has an invalid rrule"))?;
+        return Ok(rule.nth(st.created_date, repeat_count));
+    }
+
+    let repeat_freq = st
+        .repeat_freq
+        .ok_or_else(|| internal("repeating schedule missing repeat_freq"))?;
+    let repeat_interval = st
+        .repeat_interval
+        .ok_or_else(|| internal("repeating schedule missing repeat_interval"))?;
+
+    Ok(Some(calculate_next_date(
+        st.created_date,
+        st.repeat,
+        repeat_freq,
+        repeat_interval,
+        repeat_count,
+    )))
+}
+
+/// The stored row an unsaved schedule would become, for [`upcoming_occurrences`].
+pub fn scheduled_transaction_from(new: &NewScheduledTransaction) -> ScheduledTransaction {
+    ScheduledTransaction {
+        id: 0,
+        kind: new.kind,
+        value: new.value,
+        description: new.description.clone(),
+        created_date: new.created_date,
+        account_id: new.account_id,
+        category_id: new.category_id,
+        origin_account_id: new.origin_account_id,
+        destination_account_id: new.destination_account_id,
+        repeat: new.repeat,
+        repeat_freq: new.repeat_freq,
+        repeat_interval: new.repeat_interval,
+        rrule: new.rrule.clone(),
+        business_day_adjustment: new.business_day_adjustment,
+        amount_estimate: new.amount_estimate,
+        infinite_repeat: new.infinite_repeat,
+        end_after_repeats: new.end_after_repeats,
+        current_repeat_count: new.current_repeat_count,
+        next_date: new.next_date,
+        finished: false,
+    }
+}
+
+/// Paid occurrences an `Average`, `Median` or `Last` estimate is based on.
+pub const ESTIMATE_OCCURRENCES: i64 = 6;
+
+/// Calendar months of category spend a `CategorySpend` estimate averages.
+pub const ESTIMATE_MONTHS: i32 = 3;
+
+/// The amount the schedule's occurrences are expected to need: its `value`, or what its
+/// `amount_estimate` asks for — from the totals paid for its last [`ESTIMATE_OCCURRENCES`]
+/// occurrences, or the monthly spend in its category over the [`ESTIMATE_MONTHS`] calendar
+/// months before the one its current occurrence is due in. Falls back to `value` when there is
+/// nothing to estimate from (no paid occurrence or category spend yet, or no category).
+pub async fn estimated_value(
+    pool: &SqlitePool,
+    st: &ScheduledTransaction,
+) -> Result<i32, AppError> {
+    let estimate = match st.amount_estimate {
+        AmountEstimates::Fixed => None,
+        AmountEstimates::CategorySpend => match st.category_id {
+            Some(category_id) => {
+                let due = st.next_date.unwrap_or(st.created_date).date();
+                let to = due.with_day(1).expect("day 1 exists").and_time(Default::default());
+                let from = to - RelativeDuration::months(ESTIMATE_MONTHS);
+                let spent =
+                    db::transactions::sum_in_category(pool, category_id, from, to).await?;
+                Some((spent as f64 / ESTIMATE_MONTHS as f64).round() as i32).filter(|v| *v > 0)
+            }
+            None => None,
+        },
+        estimate => {
+            let totals =
+                db::scheduled_occurrences::paid_totals(pool, st.id, ESTIMATE_OCCURRENCES).await?;
+            estimate_from(estimate, &totals)
+        }
+    };
+
+    Ok(estimate.unwrap_or(st.value))
+}
+
+/// An `Average`, `Median` or `Last` estimate from paid totals (most recent first), rounded to
+/// the cent; `None` without any.
+fn estimate_from(estimate: AmountEstimates, totals: &[i32]) -> Option<i32> {
+    let last = *totals.first()?;
+    let mean = |values: &[i32]| {
+        let sum: i64 = values.iter().map(|&v| v as i64).sum();
+        (sum as f64 / values.len() as f64).round() as i32
+    };
+
+    Some(match estimate {
+        AmountEstimates::Average => mean(totals),
+        AmountEstimates::Median => {
+            let mut sorted = totals.to_vec();
+            sorted.sort_unstable();
+            let middle = sorted.len() / 2;
+            if sorted.len() % 2 == 1 {
+                sorted[middle]
+            } else {
+                mean(&sorted[middle - 1..=middle])
+            }
+        }
+        _ => last,
+    })
+}
+
+/// Default number of occurrences listed by an occurrence preview.
+pub const PREVIEW_OCCURRENCES: i32 = 12;
+
+/// Most occurrences an occurrence preview lists.
+pub const MAX_PREVIEW_OCCURRENCES: i32 = 366;
+
+/// The schedule's upcoming occurrences, starting with the one currently due, each expected to
+/// need `value`: at most `count` (1 to [`MAX_PREVIEW_OCCURRENCES`], `BadRequest` otherwise) and
+/// none due after `until`. A finished schedule has none, a one-off just its due date.
+pub fn upcoming_occurrences(
+    st: &ScheduledTransaction,
+    holidays: &Holidays,
+    value: i32,
+    count: i32,
+    until: Option<NaiveDateTime>,
+) -> Result<Vec<UpcomingOccurrence>, AppError> {
+    if !(1..=MAX_PREVIEW_OCCURRENCES).contains(&count) {
+        return Err(AppError::BadRequest);
+    }
+
+    let mut upcoming = Vec::new();
+    let Some(next_date) = st.next_date.filter(|_| !st.finished) else {
+        return Ok(upcoming);
+    };
+
+    if !st.repeat {
+        if until.is_none_or(|until| next_date <= until) {
+            upcoming.push(UpcomingOccurrence {
+                occurrence: None,
+                date: next_date,
+                scheduled_date: st.created_date,
+                value,
+                clamped: false,
+            });
+        }
+        return Ok(upcoming);
+    }
+
+    let first = st.current_repeat_count.unwrap_or(0);
+    let end = match st.infinite_repeat {
+        Some(false) => st.end_after_repeats.unwrap_or(first),
+        _ => i32::MAX,
+    };
+
+    for occurrence in (first..end).take(count as usize) {
+        let Some(scheduled_date) = recurrence_date(st, occurrence)? else {
+            break;
+        };
+        // The current occurrence keeps its stored due date, which a snooze may have moved.
+        let date = if occurrence == first {
+            next_date
+        } else {
+            holidays.adjust(scheduled_date, st.business_day_adjustment)
+        };
+        if until.is_some_and(|until| date > until) {
+            break;
+        }
+
+        upcoming.push(UpcomingOccurrence {
+            occurrence: Some(occurrence),
+            date,
+            scheduled_date,
+            value,
+            clamped: st.rrule.is_none()
+                && matches!(
+                    st.repeat_freq,
+                    Some(RepeatFrequencies::Months | RepeatFrequencies::Years)
+                )
+                && scheduled_date.day() < st.created_date.day(),
+        });
+    }
+
+    Ok(upcoming)
+}
+
+/// Longest notification lead time, in days.
+pub const MAX_LEAD_DAYS: i32 = 30;
+
+/// The running schedules whose current occurrence is overdue, due `today` or due within
+/// `lead_days` after it, soonest (most overdue) first.
+pub async fn due_scheduled(
+    pool: &SqlitePool,
+    today: NaiveDate,
+    lead_days: i32,
+) -> Result<Vec<DueScheduledTransaction>, AppError> {
+    let mut due = Vec::new();
+
+    for st in all_scheduled_enriched(pool).await? {
+        let Some(next_date) = st.next_date else {
+            continue;
+        };
+        let days_until = (next_date.date() - today).num_days() as i32;
+        if days_until > lead_days {
+            continue;
+        }
+
+        let accounts = match st.kind {
+            ScheduledTransactionKinds::Transaction => st.account_name.clone().unwrap_or_default(),
+            ScheduledTransactionKinds::Transfer => format!(
+                "{} → {}",
+                st.origin_account_name.as_deref().unwrap_or_default(),
+                st.destination_account_name.as_deref().unwrap_or_default()
+            ),
+        };
+        let label = st
+            .description
+            .clone()
+            .filter(|description| !description.trim().is_empty())
+            .or_else(|| st.category_name.clone())
+            .unwrap_or_else(|| "Transfer".to_string());
+
+        due.push(DueScheduledTransaction {
+            id: st.id,
+            kind: st.kind,
+            label,
+            accounts,
+            value: (st.estimated_value - st.paid_value).max(0),
+            next_date,
+            days_until,
+            status: match days_until {
+                ..0 => DueStatuses::Overdue,
+                0 => DueStatuses::DueToday,
+                _ => DueStatuses::Upcoming,
+            },
+        });
+    }
+
+    due.sort_by_key(|item| (item.next_date, item.id));
+    Ok(due)
+}
+
 /// Compute the next due date for a repeating scheduled transaction.
 pub fn calculate_next_date(
     initial_date: NaiveDateTime,
@@ -1,5 +1,7 @@
 use sqlx::SqlitePool;
 
+use crate::holidays::Holidays;
+
 /// Shared application state, registered once with `app.manage(...)` during setup and
 /// injected into every command via `tauri::State<'_, AppState>`.
 ///
@@ -7,4 +9,8 @@ use sqlx::SqlitePool;
 /// commands, so no `Mutex` is needed.
 pub struct AppState {
     pub pool: SqlitePool,
+    /// Holiday calendar for business-day adjustment of scheduled due dates, read at startup
+    /// from `holidays.ics` or `holidays.csv` in the app-data directory (weekends only if
+    /// neither exists).
+    pub holidays: Holidays,
 }
@@ -13,17 +13,23 @@
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use sqlx::SqlitePool;
 
 use crate::bootstrap;
-use crate::commands::{build_new_scheduled, pay_scheduled_impl};
+use crate::commands::{
+    build_new_scheduled, pay_scheduled_impl, skip_scheduled_impl, snooze_scheduled_impl,
+};
 use crate::db;
 use crate::error::AppError;
+use crate::holidays::Holidays;
 use crate::models::{
-    CategoryTypes, NewTransactionData, NewTransferData, PostScheduledTransaction,
-    PostScheduledTransactionPay, RepeatFrequencies, ScheduledTransactionKinds,
+    AmountEstimates, BusinessDayAdjustments, CategoryTypes, DueStatuses, NewTransactionData,
+    NewTransferData, NotificationSettings, OccurrenceOutcomes, PostGoal, PostGoalAccount,
+    PostGoalContribution, PostScheduledTransaction, PostScheduledTransactionPay,
+    PostScheduledTransactionSnooze, RepeatFrequencies, ScheduledTransactionKinds,
 };
+use crate::recurrence::RRule;
 use crate::service;
 
 /// Open a fresh, isolated database in the OS temp dir.
@@ -81,6 +87,8 @@ async fn full_flow() {
             date: dt("2024-01-05T09:00:00"),
             account: checking.id,
             category: salary.id,
+            scheduled_transaction_id: None,
+            occurrence: None,
         },
     )
     .await
@@ -93,6 +101,8 @@ async fn full_flow() {
             date: dt("2024-01-06T18:30:00"),
             account: checking.id,
             category: groceries.id,
+            scheduled_transaction_id: None,
+            occurrence: None,
         },
     )
     .await
@@ -113,6 +123,8 @@ async fn full_flow() {
             value: 5_000,
             description: "Move to savings".into(),
             date: dt("2024-01-07T12:00:00"),
+            scheduled_transaction_id: None,
+            occurrence: None,
         },
     )
     .await
@@ -161,12 +173,15 @@ async fn full_flow() {
         repeat: false,
         repeat_freq: None,
         repeat_interval: None,
+        rrule: None,
+        business_day_adjustment: BusinessDayAdjustments::None,
+        amount_estimate: AmountEstimates::Fixed,
         infinite_repeat: None,
         end_after_repeats: None,
         current_repeat_count: None,
         next_date: None,
     };
-    let new = build_new_scheduled(&pool, &body)
+    let new = build_new_scheduled(&pool, &Holidays::default(), &body)
         .await
         .unwrap()
         .expect("valid schedule");
@@ -174,7 +189,7 @@ async fn full_flow() {
     let enriched = service::enrich_scheduled(&pool, &inserted).await.unwrap();
     assert_eq!(enriched.account_name.as_deref(), Some("Checking"));
 
-    // Paying a one-off schedule creates a real 2000 expense and removes the schedule.
+    // Paying a one-off schedule creates a real 2000 expense and finishes the schedule.
     let pay = PostScheduledTransactionPay {
         value: 2_000,
         description: "Paid scheduled expense".into(),
@@ -183,15 +198,13 @@ async fn full_flow() {
         account_id: Some(checking.id),
         origin_account_id: None,
         destination_account_id: None,
+        partial: false,
     };
-    pay_scheduled_impl(&pool, inserted.id, &pay).await.unwrap();
+    pay_scheduled_impl(&pool, &Holidays::default(), inserted.id, &pay).await.unwrap();
 
     assert!(
-        matches!(
-            db::scheduled_transactions::get(&pool, inserted.id).await,
-            Err(AppError::NotFound)
-        ),
-        "paid one-off schedule is removed"
+        db::scheduled_transactions::get(&pool, inserted.id).await.unwrap().finished,
+        "paid one-off schedule is kept as finished"
     );
     assert_eq!(
         db::accounts::balance(&pool, checking.id).await.unwrap(),
@@ -234,7 +247,7 @@ fn app_error_serializes_to_status_and_message() {
 }
 
 /// Paying a finite repeating schedule must advance it (count + next date) on each occurrence,
-/// then remove it once the final occurrence is paid — and each payment's transaction + the
+/// then finish it once the final occurrence is paid — and each payment's transaction + the
 /// schedule change must land together (the pay path runs them in one database transaction).
 #[tokio::test]
 async fn paying_a_repeating_schedule_advances_then_finishes() {
@@ -257,12 +270,15 @@ async fn paying_a_repeating_schedule_advances_then_finishes() {
         repeat: true,
         repeat_freq: Some(RepeatFrequencies::Months),
         repeat_interval: Some(1),
+        rrule: None,
+        business_day_adjustment: BusinessDayAdjustments::None,
+        amount_estimate: AmountEstimates::Fixed,
         infinite_repeat: Some(false),
         end_after_repeats: Some(3),
         current_repeat_count: None,
         next_date: None,
     };
-    let new = build_new_scheduled(&pool, &body)
+    let new = build_new_scheduled(&pool, &Holidays::default(), &body)
         .await
         .unwrap()
         .expect("valid repeating schedule");
@@ -277,10 +293,11 @@ async fn paying_a_repeating_schedule_advances_then_finishes() {
         account_id: Some(wallet.id),
         origin_account_id: None,
         destination_account_id: None,
+        partial: false,
     };
 
     // First payment: schedule survives, count -> 1, next date advances one month.
-    let advanced = pay_scheduled_impl(&pool, inserted.id, &pay).await.unwrap();
+    let advanced = pay_scheduled_impl(&pool, &Holidays::default(), inserted.id, &pay).await.unwrap();
     assert_eq!(advanced.current_repeat_count, Some(1), "repeat count advanced");
     assert_eq!(
         advanced.next_date,
@@ -293,16 +310,13 @@ async fn paying_a_repeating_schedule_advances_then_finishes() {
         "the paid occurrence recorded a 1000 expense"
     );
 
-    // Second and third payments; the third reaches end_after_repeats and removes the schedule.
-    pay_scheduled_impl(&pool, inserted.id, &pay).await.unwrap();
-    pay_scheduled_impl(&pool, inserted.id, &pay).await.unwrap();
+    // Second and third payments; the third reaches end_after_repeats and finishes the schedule.
+    pay_scheduled_impl(&pool, &Holidays::default(), inserted.id, &pay).await.unwrap();
+    pay_scheduled_impl(&pool, &Holidays::default(), inserted.id, &pay).await.unwrap();
 
     assert!(
-        matches!(
-            db::scheduled_transactions::get(&pool, inserted.id).await,
-            Err(AppError::NotFound)
-        ),
-        "the schedule is removed after its final occurrence"
+        db::scheduled_transactions::get(&pool, inserted.id).await.unwrap().finished,
+        "the schedule is kept as finished after its final occurrence"
     );
     assert_eq!(
         db::accounts::balance(&pool, wallet.id).await.unwrap(),
@@ -312,9 +326,9 @@ async fn paying_a_repeating_schedule_advances_then_finishes() {
 }
 
 /// Paying a transfer-kind schedule must materialise a real transfer (moving money between the
-/// two accounts) and, for a one-off, remove the schedule.
+/// two accounts) and, for a one-off, finish the schedule.
 #[tokio::test]
-async fn paying_a_transfer_schedule_moves_money_and_removes_it() {
+async fn paying_a_transfer_schedule_moves_money_and_finishes_it() {
     let pool = fresh_pool().await;
     let a = db::accounts::insert(&pool, "A").await.unwrap();
     let b = db::accounts::insert(&pool, "B").await.unwrap();
@@ -331,6 +345,8 @@ async fn paying_a_transfer_schedule_moves_money_and_removes_it() {
             date: dt("2024-01-01T00:00:00"),
             account: a.id,
             category: seed.id,
+            scheduled_transaction_id: None,
+            occurrence: None,
         },
     )
     .await
@@ -348,12 +364,15 @@ async fn paying_a_transfer_schedule_moves_money_and_removes_it() {
         repeat: false,
         repeat_freq: None,
         repeat_interval: None,
+        rrule: None,
+        business_day_adjustment: BusinessDayAdjustments::None,
+        amount_estimate: AmountEstimates::Fixed,
         infinite_repeat: None,
         end_after_repeats: None,
         current_repeat_count: None,
         next_date: None,
     };
-    let new = build_new_scheduled(&pool, &body)
+    let new = build_new_scheduled(&pool, &Holidays::default(), &body)
         .await
         .unwrap()
         .expect("valid transfer schedule");
@@ -367,8 +386,9 @@ async fn paying_a_transfer_schedule_moves_money_and_removes_it() {
         account_id: None,
         origin_account_id: Some(a.id),
         destination_account_id: Some(b.id),
+        partial: false,
     };
-    pay_scheduled_impl(&pool, inserted.id, &pay).await.unwrap();
+    pay_scheduled_impl(&pool, &Holidays::default(), inserted.id, &pay).await.unwrap();
 
     assert_eq!(
         db::accounts::balance(&pool, a.id).await.unwrap(),
@@ -381,11 +401,8 @@ async fn paying_a_transfer_schedule_moves_money_and_removes_it() {
         "4000 transferred in"
     );
     assert!(
-        matches!(
-            db::scheduled_transactions::get(&pool, inserted.id).await,
-            Err(AppError::NotFound)
-        ),
-        "the one-off transfer schedule is removed after payment"
+        db::scheduled_transactions::get(&pool, inserted.id).await.unwrap().finished,
+        "the one-off transfer schedule is kept as finished after payment"
     );
 }
 
@@ -411,12 +428,15 @@ async fn paying_with_missing_or_unknown_refs_is_rejected() {
         repeat: false,
         repeat_freq: None,
         repeat_interval: None,
+        rrule: None,
+        business_day_adjustment: BusinessDayAdjustments::None,
+        amount_estimate: AmountEstimates::Fixed,
         infinite_repeat: None,
         end_after_repeats: None,
         current_repeat_count: None,
         next_date: None,
     };
-    let new = build_new_scheduled(&pool, &body).await.unwrap().unwrap();
+    let new = build_new_scheduled(&pool, &Holidays::default(), &body).await.unwrap().unwrap();
     let inserted = db::scheduled_transactions::insert(&pool, &new).await.unwrap();
 
     // Missing account/category on the payment body -> BadRequest.
@@ -428,10 +448,11 @@ async fn paying_with_missing_or_unknown_refs_is_rejected() {
         account_id: None,
         origin_account_id: None,
         destination_account_id: None,
+        partial: false,
     };
     assert!(
         matches!(
-            pay_scheduled_impl(&pool, inserted.id, &missing).await,
+            pay_scheduled_impl(&pool, &Holidays::default(), inserted.id, &missing).await,
             Err(AppError::BadRequest)
         ),
         "a transaction payment with no account/category is a bad request"
@@ -446,10 +467,11 @@ async fn paying_with_missing_or_unknown_refs_is_rejected() {
         account_id: Some(9_999),
         origin_account_id: None,
         destination_account_id: None,
+        partial: false,
     };
     assert!(
         matches!(
-            pay_scheduled_impl(&pool, inserted.id, &unknown).await,
+            pay_scheduled_impl(&pool, &Holidays::default(), inserted.id, &unknown).await,
             Err(AppError::NotFound)
         ),
         "an unknown account maps to not found"
@@ -468,3 +490,783 @@ async fn paying_with_missing_or_unknown_refs_is_rejected() {
         "the schedule survives rejected payments"
     );
 }
+
+/// Goal progress counts each linked account's balance (capped by its earmark), and the
+/// contribution schedule spreads what remains over the months left to the target date.
+#[tokio::test]
+async fn goal_progress_and_contribution_schedule() {
+    let pool = fresh_pool().await;
+    let checking = db::accounts::insert(&pool, "Checking").await.unwrap();
+    let savings = db::accounts::insert(&pool, "Savings").await.unwrap();
+    let brokerage = db::accounts::insert(&pool, "Brokerage").await.unwrap();
+
+    let seed = db::categories::insert(&pool, CategoryTypes::Income, "Seed")
+        .await
+        .unwrap();
+    for (account, value) in [(savings.id, 30_000), (brokerage.id, 50_000)] {
+        db::transactions::insert(
+            &pool,
+            &NewTransactionData {
+                value,
+                description: "seed".into(),
+                date: dt("2024-01-01T00:00:00"),
+                account,
+                category: seed.id,
+                scheduled_transaction_id: None,
+                occurrence: None,
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    let body = PostGoal {
+        name: "Trip".into(),
+        target_amount: 100_000,
+        target_date: Some(dt("2024-07-01T00:00:00")),
+        accounts: vec![
+            PostGoalAccount {
+                account_id: savings.id,
+                earmark: None,
+            },
+            PostGoalAccount {
+                account_id: brokerage.id,
+                earmark: Some(20_000),
+            },
+        ],
+    };
+    service::validate_goal(&pool, &body).await.unwrap();
+    let goal = db::goals::insert(&pool, &body, dt("2024-01-01T00:00:00"))
+        .await
+        .unwrap();
+
+    let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    let progress = service::goal_progress(&pool, &goal, today).await.unwrap();
+    assert_eq!(progress.saved, 50_000, "30000 savings + brokerage capped at its 20000 earmark");
+    assert_eq!(progress.remaining, 50_000);
+    assert_eq!(progress.percent, 50);
+    assert_eq!(progress.months_left, Some(6));
+    assert_eq!(progress.required_monthly, Some(8_334), "rounded up to the cent");
+
+    // The contribution must land in one of the goal's accounts.
+    let outside = PostGoalContribution {
+        origin_account_id: savings.id,
+        destination_account_id: checking.id,
+        start_date: dt("2024-01-15T00:00:00"),
+    };
+    assert!(matches!(
+        service::set_goal_contribution(&pool, &goal, &outside, today).await,
+        Err(AppError::BadRequest)
+    ));
+
+    let contribution = PostGoalContribution {
+        origin_account_id: checking.id,
+        destination_account_id: savings.id,
+        start_date: dt("2024-01-15T00:00:00"),
+    };
+    let updated = service::set_goal_contribution(&pool, &goal, &contribution, today)
+        .await
+        .unwrap();
+    let scheduled_id = updated.scheduled_transaction_id.expect("contribution scheduled");
+    let st = db::scheduled_transactions::get(&pool, scheduled_id).await.unwrap();
+    assert_eq!(st.kind, ScheduledTransactionKinds::Transfer);
+    assert_eq!(st.value, 8_334);
+    assert_eq!(st.end_after_repeats, Some(6));
+    assert_eq!(st.destination_account_id, Some(savings.id));
+
+    // Setting it again replaces the previous schedule rather than adding a second one.
+    let goal = db::goals::get(&pool, goal.id).await.unwrap();
+    let replaced = service::set_goal_contribution(&pool, &goal, &contribution, today)
+        .await
+        .unwrap();
+    assert_ne!(replaced.scheduled_transaction_id, Some(scheduled_id));
+    assert!(matches!(
+        db::scheduled_transactions::get(&pool, scheduled_id).await,
+        Err(AppError::NotFound)
+    ));
+}
+
+fn first_occurrences(rule: &str, start: &str, n: usize) -> Vec<NaiveDateTime> {
+    RRule::parse(rule)
+        .expect("valid rule")
+        .occurrences(dt(start))
+        .take(n)
+        .collect()
+}
+
+/// The recurrence module expands the rules the scheduler is meant to support, and rejects
+/// malformed or unsupported ones.
+#[test]
+fn rrule_rules_expand_to_the_expected_dates() {
+    assert_eq!(
+        first_occurrences(
+            "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+            "2024-01-01T00:00:00",
+            3
+        ),
+        vec![
+            dt("2024-01-31T00:00:00"),
+            dt("2024-02-29T00:00:00"),
+            dt("2024-03-29T00:00:00"),
+        ],
+        "last business day of the month"
+    );
+    assert_eq!(
+        first_occurrences("FREQ=MONTHLY;BYDAY=2TU", "2024-01-01T00:00:00", 2),
+        vec![dt("2024-01-09T00:00:00"), dt("2024-02-13T00:00:00")],
+        "second Tuesday"
+    );
+    assert_eq!(
+        first_occurrences("RRULE:FREQ=MONTHLY;BYMONTHDAY=15,-1", "2024-01-01T00:00:00", 4),
+        vec![
+            dt("2024-01-15T00:00:00"),
+            dt("2024-01-31T00:00:00"),
+            dt("2024-02-15T00:00:00"),
+            dt("2024-02-29T00:00:00"),
+        ],
+        "the 15th and the last day"
+    );
+    assert_eq!(
+        first_occurrences("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR", "2024-01-05T09:30:00", 3),
+        vec![
+            dt("2024-01-05T09:30:00"),
+            dt("2024-01-08T09:30:00"),
+            dt("2024-01-09T09:30:00"),
+        ],
+        "every weekday, at the start's time of day"
+    );
+    assert_eq!(
+        first_occurrences("FREQ=MONTHLY", "2024-01-31T00:00:00", 2),
+        vec![dt("2024-01-31T00:00:00"), dt("2024-03-31T00:00:00")],
+        "months without the day are skipped"
+    );
+    assert_eq!(
+        first_occurrences("FREQ=DAILY;UNTIL=20240103", "2024-01-01T09:00:00", 10).len(),
+        3,
+        "a date-only UNTIL includes that day"
+    );
+    assert_eq!(
+        RRule::parse("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30")
+            .unwrap()
+            .nth(dt("2024-01-01T00:00:00"), 0),
+        None,
+        "a rule that can never match ends"
+    );
+
+    for invalid in [
+        "FREQ=HOURLY",
+        "BYDAY=MO",
+        "FREQ=DAILY;BYDAY=1MO",
+        "FREQ=MONTHLY;COUNT=2;UNTIL=20240101",
+        "FREQ=MONTHLY;BYMONTHDAY=32",
+        "FREQ=WEEKLY;WKST=SU",
+    ] {
+        assert!(RRule::parse(invalid).is_none(), "{invalid} is rejected");
+    }
+}
+
+/// A schedule with an RRULE starts on the rule's first occurrence, advances along the rule when
+/// paid, and is finished once the rule has no further occurrence.
+#[tokio::test]
+async fn paying_an_rrule_schedule_follows_the_rule() {
+    let pool = fresh_pool().await;
+    let wallet = db::accounts::insert(&pool, "Wallet").await.unwrap();
+    let bills = db::categories::insert(&pool, CategoryTypes::Expense, "Bills")
+        .await
+        .unwrap();
+
+    let mut body = PostScheduledTransaction {
+        kind: ScheduledTransactionKinds::Transaction,
+        value: 1_000,
+        description: Some("Club".into()),
+        created_date: dt("2024-01-01T00:00:00"),
+        account_id: Some(wallet.id),
+        category_id: Some(bills.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        repeat: true,
+        repeat_freq: None,
+        repeat_interval: None,
+        rrule: Some("FREQ=MONTHLY;BYDAY=2TU;COUNT=2".into()),
+        business_day_adjustment: BusinessDayAdjustments::None,
+        amount_estimate: AmountEstimates::Fixed,
+        infinite_repeat: Some(true),
+        end_after_repeats: None,
+        current_repeat_count: None,
+        next_date: None,
+    };
+    let new = build_new_scheduled(&pool, &Holidays::default(), &body)
+        .await
+        .unwrap()
+        .expect("valid rrule schedule");
+    assert_eq!(new.next_date, Some(dt("2024-01-09T00:00:00")), "first second Tuesday");
+    let inserted = db::scheduled_transactions::insert(&pool, &new).await.unwrap();
+
+    let pay = PostScheduledTransactionPay {
+        value: 1_000,
+        description: "Club fee".into(),
+        date: dt("2024-01-09T10:00:00"),
+        category_id: Some(bills.id),
+        account_id: Some(wallet.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        partial: false,
+    };
+    let advanced = pay_scheduled_impl(&pool, &Holidays::default(), inserted.id, &pay).await.unwrap();
+    assert_eq!(advanced.next_date, Some(dt("2024-02-13T00:00:00")));
+    assert_eq!(advanced.rrule.as_deref(), Some("FREQ=MONTHLY;BYDAY=2TU;COUNT=2"));
+
+    pay_scheduled_impl(&pool, &Holidays::default(), inserted.id, &pay).await.unwrap();
+    assert!(
+        db::scheduled_transactions::get(&pool, inserted.id).await.unwrap().finished,
+        "the schedule is kept as finished after the rule's last occurrence"
+    );
+
+    // An invalid rule, or a rule on a one-off schedule, is a bad request.
+    body.rrule = Some("FREQ=SOMETIMES".into());
+    assert!(build_new_scheduled(&pool, &Holidays::default(), &body).await.unwrap().is_none());
+    body.rrule = Some("FREQ=DAILY".into());
+    body.repeat = false;
+    assert!(build_new_scheduled(&pool, &Holidays::default(), &body).await.unwrap().is_none());
+}
+
+/// Due dates on a weekend or holiday move as the schedule's adjustment says, while later
+/// occurrences are still computed from the original anchor (no drift).
+#[tokio::test]
+async fn business_day_adjustment_moves_due_dates_without_drift() {
+    let holidays = Holidays::parse("date,name\n2024-12-25,Christmas\n2024-12-26,Boxing Day\n")
+        .expect("valid CSV calendar");
+    let ical = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20241225\r\n\
+                SUMMARY:Christmas\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+    assert!(!Holidays::parse(ical).unwrap().is_business_day(NaiveDate::from_ymd_opt(2024, 12, 25).unwrap()));
+    assert!(Holidays::parse("2024-12-25\nnot a date\n").is_none());
+
+    // Saturday 2024-08-31 is the last day of its month: modified following stays in August.
+    let saturday = dt("2024-08-31T00:00:00");
+    let adjust = |policy| holidays.adjust(saturday, policy);
+    assert_eq!(adjust(BusinessDayAdjustments::None), saturday);
+    assert_eq!(adjust(BusinessDayAdjustments::Previous), dt("2024-08-30T00:00:00"));
+    assert_eq!(adjust(BusinessDayAdjustments::Next), dt("2024-09-02T00:00:00"));
+    assert_eq!(adjust(BusinessDayAdjustments::ModifiedFollowing), dt("2024-08-30T00:00:00"));
+    assert_eq!(
+        holidays.adjust(dt("2024-12-25T00:00:00"), BusinessDayAdjustments::Next),
+        dt("2024-12-27T00:00:00"),
+        "skips both holidays"
+    );
+
+    let pool = fresh_pool().await;
+    let wallet = db::accounts::insert(&pool, "Wallet").await.unwrap();
+    let salary = db::categories::insert(&pool, CategoryTypes::Income, "Salary")
+        .await
+        .unwrap();
+
+    // Monthly on the 25th: November 25th is a Monday, December 25th a holiday.
+    let body = PostScheduledTransaction {
+        kind: ScheduledTransactionKinds::Transaction,
+        value: 300_000,
+        description: Some("Salary".into()),
+        created_date: dt("2024-08-25T00:00:00"),
+        account_id: Some(wallet.id),
+        category_id: Some(salary.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        repeat: true,
+        repeat_freq: Some(RepeatFrequencies::Months),
+        repeat_interval: Some(1),
+        rrule: None,
+        business_day_adjustment: BusinessDayAdjustments::Previous,
+        amount_estimate: AmountEstimates::Fixed,
+        infinite_repeat: Some(true),
+        end_after_repeats: None,
+        current_repeat_count: None,
+        next_date: None,
+    };
+    let new = build_new_scheduled(&pool, &holidays, &body)
+        .await
+        .unwrap()
+        .expect("valid schedule");
+    assert_eq!(new.next_date, Some(dt("2024-08-23T00:00:00")), "Sunday moves to Friday");
+    assert_eq!(new.created_date, body.created_date, "the anchor is not moved");
+    let inserted = db::scheduled_transactions::insert(&pool, &new).await.unwrap();
+
+    let pay = PostScheduledTransactionPay {
+        value: 300_000,
+        description: "Salary".into(),
+        date: dt("2024-08-23T00:00:00"),
+        category_id: Some(salary.id),
+        account_id: Some(wallet.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        partial: false,
+    };
+    let mut due_dates = Vec::new();
+    for _ in 0..4 {
+        let advanced = pay_scheduled_impl(&pool, &holidays, inserted.id, &pay).await.unwrap();
+        due_dates.push(advanced.next_date.unwrap());
+    }
+    assert_eq!(
+        due_dates,
+        vec![
+            dt("2024-09-25T00:00:00"),
+            dt("2024-10-25T00:00:00"),
+            dt("2024-11-25T00:00:00"),
+            dt("2024-12-24T00:00:00"),
+        ]
+    );
+}
+
+/// Partial payments keep an occurrence due until they add up to the scheduled value, a skip
+/// advances without booking, a snooze moves only the current due date, and each is recorded in
+/// the schedule's history.
+#[tokio::test]
+async fn skip_snooze_and_partial_pay_are_recorded() {
+    let pool = fresh_pool().await;
+    let holidays = Holidays::default();
+    let wallet = db::accounts::insert(&pool, "Wallet").await.unwrap();
+    let rent = db::categories::insert(&pool, CategoryTypes::Expense, "Rent")
+        .await
+        .unwrap();
+
+    let body = PostScheduledTransaction {
+        kind: ScheduledTransactionKinds::Transaction,
+        value: 1_000,
+        description: Some("Rent".into()),
+        created_date: dt("2024-01-01T00:00:00"),
+        account_id: Some(wallet.id),
+        category_id: Some(rent.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        repeat: true,
+        repeat_freq: Some(RepeatFrequencies::Months),
+        repeat_interval: Some(1),
+        rrule: None,
+        business_day_adjustment: BusinessDayAdjustments::None,
+        amount_estimate: AmountEstimates::Fixed,
+        infinite_repeat: Some(true),
+        end_after_repeats: None,
+        current_repeat_count: None,
+        next_date: None,
+    };
+    let new = build_new_scheduled(&pool, &holidays, &body)
+        .await
+        .unwrap()
+        .unwrap();
+    let id = db::scheduled_transactions::insert(&pool, &new).await.unwrap().id;
+
+    let mut pay = PostScheduledTransactionPay {
+        value: 600,
+        description: "Rent".into(),
+        date: dt("2024-01-01T10:00:00"),
+        category_id: Some(rent.id),
+        account_id: Some(wallet.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        partial: true,
+    };
+    let kept = pay_scheduled_impl(&pool, &holidays, id, &pay).await.unwrap();
+    assert_eq!(kept.current_repeat_count, Some(0), "partially paid stays due");
+    let enriched = service::enrich_scheduled(&pool, &kept).await.unwrap();
+    assert_eq!(enriched.paid_value, 600);
+
+    pay.value = 400;
+    let advanced = pay_scheduled_impl(&pool, &holidays, id, &pay).await.unwrap();
+    assert_eq!(advanced.current_repeat_count, Some(1), "the remainder completes it");
+    assert_eq!(advanced.next_date, Some(dt("2024-02-01T00:00:00")));
+    assert_eq!(db::accounts::balance(&pool, wallet.id).await.unwrap(), -1_000);
+
+    // A snooze must move the due date later, and does not shift the following occurrence.
+    let too_early = PostScheduledTransactionSnooze { date: dt("2024-01-20T00:00:00") };
+    assert!(matches!(
+        snooze_scheduled_impl(&pool, id, &too_early).await,
+        Err(AppError::BadRequest)
+    ));
+    let snooze = PostScheduledTransactionSnooze { date: dt("2024-02-05T00:00:00") };
+    let snoozed = snooze_scheduled_impl(&pool, id, &snooze).await.unwrap();
+    assert_eq!(snoozed.next_date, Some(dt("2024-02-05T00:00:00")));
+    assert_eq!(snoozed.current_repeat_count, Some(1));
+
+    let skipped = skip_scheduled_impl(&pool, &holidays, id).await.unwrap();
+    assert_eq!(skipped.next_date, Some(dt("2024-03-01T00:00:00")));
+    assert_eq!(
+        db::accounts::balance(&pool, wallet.id).await.unwrap(),
+        -1_000,
+        "a skip books nothing"
+    );
+
+    let history = db::scheduled_occurrences::get_by_scheduled(&pool, id).await.unwrap();
+    let summary: Vec<_> = history
+        .iter()
+        .map(|o| (o.occurrence, o.outcome, o.value, o.snoozed_to))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (Some(0), OccurrenceOutcomes::PartiallyPaid, 600, None),
+            (Some(0), OccurrenceOutcomes::Paid, 400, None),
+            (Some(1), OccurrenceOutcomes::Snoozed, 0, Some(dt("2024-02-05T00:00:00"))),
+            (Some(1), OccurrenceOutcomes::Skipped, 0, None),
+        ]
+    );
+    assert_eq!(history[3].due_date, dt("2024-02-05T00:00:00"), "skipped after the snooze");
+}
+
+/// Every payment is linked to the schedule and the occurrence it paid, and a schedule past its
+/// last occurrence is kept as finished (out of the running list, closed to further payments).
+#[tokio::test]
+async fn paid_entries_link_back_to_a_finished_schedule() {
+    let pool = fresh_pool().await;
+    let wallet = db::accounts::insert(&pool, "Wallet").await.unwrap();
+    let rent = db::categories::insert(&pool, CategoryTypes::Expense, "Rent")
+        .await
+        .unwrap();
+
+    let body = PostScheduledTransaction {
+        kind: ScheduledTransactionKinds::Transaction,
+        value: 1_000,
+        description: Some("Rent".into()),
+        created_date: dt("2024-01-01T00:00:00"),
+        account_id: Some(wallet.id),
+        category_id: Some(rent.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        repeat: true,
+        repeat_freq: Some(RepeatFrequencies::Months),
+        repeat_interval: Some(1),
+        rrule: None,
+        business_day_adjustment: BusinessDayAdjustments::None,
+        amount_estimate: AmountEstimates::Fixed,
+        infinite_repeat: Some(false),
+        end_after_repeats: Some(2),
+        current_repeat_count: None,
+        next_date: None,
+    };
+    let new = build_new_scheduled(&pool, &Holidays::default(), &body)
+        .await
+        .unwrap()
+        .unwrap();
+    let id = db::scheduled_transactions::insert(&pool, &new).await.unwrap().id;
+
+    let mut pay = PostScheduledTransactionPay {
+        value: 400,
+        description: "Rent".into(),
+        date: dt("2024-01-02T10:00:00"),
+        category_id: Some(rent.id),
+        account_id: Some(wallet.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        partial: true,
+    };
+    pay_scheduled_impl(&pool, &Holidays::default(), id, &pay).await.unwrap();
+    pay.value = 600;
+    pay_scheduled_impl(&pool, &Holidays::default(), id, &pay).await.unwrap();
+    pay.value = 1_050;
+    pay.partial = false;
+    pay.date = dt("2024-02-01T10:00:00");
+    let finished = pay_scheduled_impl(&pool, &Holidays::default(), id, &pay).await.unwrap();
+
+    assert!(finished.finished);
+    assert_eq!(finished.current_repeat_count, Some(2));
+    assert_eq!(finished.next_date, None);
+
+    let entries = db::scheduled_transactions::get_entries(&pool, id).await.unwrap();
+    let summary: Vec<_> = entries.iter().map(|e| (e.kind, e.occurrence, e.value)).collect();
+    assert_eq!(
+        summary,
+        vec![
+            (ScheduledTransactionKinds::Transaction, Some(0), 400),
+            (ScheduledTransactionKinds::Transaction, Some(0), 600),
+            (ScheduledTransactionKinds::Transaction, Some(1), 1_050),
+        ]
+    );
+    let running = db::scheduled_transactions::get_all(&pool).await.unwrap();
+    assert!(running.iter().all(|st| st.id != id), "finished schedules are not listed as running");
+    let done = db::scheduled_transactions::get_finished(&pool).await.unwrap();
+    assert_eq!(done.iter().map(|st| st.id).collect::<Vec<_>>(), vec![id]);
+
+    assert!(matches!(
+        pay_scheduled_impl(&pool, &Holidays::default(), id, &pay).await,
+        Err(AppError::Conflict)
+    ));
+    assert!(matches!(
+        skip_scheduled_impl(&pool, &Holidays::default(), id).await,
+        Err(AppError::Conflict)
+    ));
+
+    // Deleting the schedule keeps the entries it booked, unlinked.
+    db::scheduled_transactions::delete(&pool, id).await.unwrap();
+    assert!(db::scheduled_transactions::get_entries(&pool, id).await.unwrap().is_empty());
+    assert_eq!(db::accounts::balance(&pool, wallet.id).await.unwrap(), -2_050);
+}
+
+/// The occurrence preview lists the dates the pay path will use, flags month-end clamping,
+/// and stops at `count`, `until` or the end of the schedule.
+#[tokio::test]
+async fn upcoming_occurrences_preview_the_schedule() {
+    let pool = fresh_pool().await;
+    let wallet = db::accounts::insert(&pool, "Wallet").await.unwrap();
+    let rent = db::categories::insert(&pool, CategoryTypes::Expense, "Rent")
+        .await
+        .unwrap();
+    let holidays = Holidays::default();
+
+    // Monthly from a Saturday the 31st, moved to the previous business day.
+    let mut body = PostScheduledTransaction {
+        kind: ScheduledTransactionKinds::Transaction,
+        value: 1_000,
+        description: Some("Rent".into()),
+        created_date: dt("2026-01-31T00:00:00"),
+        account_id: Some(wallet.id),
+        category_id: Some(rent.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        repeat: true,
+        repeat_freq: Some(RepeatFrequencies::Months),
+        repeat_interval: Some(1),
+        rrule: None,
+        business_day_adjustment: BusinessDayAdjustments::Previous,
+        amount_estimate: AmountEstimates::Fixed,
+        infinite_repeat: Some(true),
+        end_after_repeats: None,
+        current_repeat_count: None,
+        next_date: None,
+    };
+    let new = build_new_scheduled(&pool, &holidays, &body).await.unwrap().unwrap();
+    let unsaved = service::scheduled_transaction_from(&new);
+
+    let upcoming = service::upcoming_occurrences(&unsaved, &holidays, 1_000, 4, None).unwrap();
+    let summary: Vec<_> = upcoming
+        .iter()
+        .map(|o| (o.occurrence, o.date, o.scheduled_date, o.clamped))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (Some(0), dt("2026-01-30T00:00:00"), dt("2026-01-31T00:00:00"), false),
+            (Some(1), dt("2026-02-27T00:00:00"), dt("2026-02-28T00:00:00"), true),
+            (Some(2), dt("2026-03-31T00:00:00"), dt("2026-03-31T00:00:00"), false),
+            (Some(3), dt("2026-04-30T00:00:00"), dt("2026-04-30T00:00:00"), true),
+        ]
+    );
+
+    let until = Some(dt("2026-03-01T00:00:00"));
+    let until_march = service::upcoming_occurrences(&unsaved, &holidays, 1_000, 12, until).unwrap();
+    assert_eq!(until_march.len(), 2);
+    assert!(matches!(
+        service::upcoming_occurrences(&unsaved, &holidays, 1_000, 0, None),
+        Err(AppError::BadRequest)
+    ));
+
+    // A finite schedule lists only the occurrences it has left.
+    body.infinite_repeat = Some(false);
+    body.end_after_repeats = Some(3);
+    let new = build_new_scheduled(&pool, &holidays, &body).await.unwrap().unwrap();
+    let st = db::scheduled_transactions::insert(&pool, &new).await.unwrap();
+    let pay = PostScheduledTransactionPay {
+        value: 1_000,
+        description: "Rent".into(),
+        date: dt("2026-01-30T10:00:00"),
+        category_id: Some(rent.id),
+        account_id: Some(wallet.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        partial: false,
+    };
+    let st = pay_scheduled_impl(&pool, &holidays, st.id, &pay).await.unwrap();
+    let left = service::upcoming_occurrences(&st, &holidays, 1_000, 12, None).unwrap();
+    assert_eq!(
+        left.iter().map(|o| o.occurrence).collect::<Vec<_>>(),
+        vec![Some(1), Some(2)]
+    );
+
+    pay_scheduled_impl(&pool, &holidays, st.id, &pay).await.unwrap();
+    let finished = pay_scheduled_impl(&pool, &holidays, st.id, &pay).await.unwrap();
+    assert!(service::upcoming_occurrences(&finished, &holidays, 1_000, 12, None).unwrap().is_empty());
+}
+
+/// A variable schedule's expected amount comes from what its previous occurrences were paid
+/// (average, median or last), or from its category's recent monthly spend, and falls back to its
+/// `value` while there is nothing to estimate from.
+#[tokio::test]
+async fn estimated_amounts_follow_the_payment_history() {
+    let pool = fresh_pool().await;
+    let wallet = db::accounts::insert(&pool, "Wallet").await.unwrap();
+    let power = db::categories::insert(&pool, CategoryTypes::Expense, "Power")
+        .await
+        .unwrap();
+
+    let body = PostScheduledTransaction {
+        kind: ScheduledTransactionKinds::Transaction,
+        value: 5_000,
+        description: Some("Power bill".into()),
+        created_date: dt("2024-01-15T00:00:00"),
+        account_id: Some(wallet.id),
+        category_id: Some(power.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        repeat: true,
+        repeat_freq: Some(RepeatFrequencies::Months),
+        repeat_interval: Some(1),
+        rrule: None,
+        business_day_adjustment: BusinessDayAdjustments::None,
+        amount_estimate: AmountEstimates::Average,
+        infinite_repeat: Some(true),
+        end_after_repeats: None,
+        current_repeat_count: None,
+        next_date: None,
+    };
+    let new = build_new_scheduled(&pool, &Holidays::default(), &body)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut st = db::scheduled_transactions::insert(&pool, &new).await.unwrap();
+    assert_eq!(service::estimated_value(&pool, &st).await.unwrap(), 5_000, "no history yet");
+
+    // Bills of 4000, 6000 (in two parts) and 8500.
+    let mut pay = PostScheduledTransactionPay {
+        value: 4_000,
+        description: "Power bill".into(),
+        date: dt("2024-01-15T10:00:00"),
+        category_id: Some(power.id),
+        account_id: Some(wallet.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        partial: false,
+    };
+    pay_scheduled_impl(&pool, &Holidays::default(), st.id, &pay).await.unwrap();
+    pay.partial = true;
+    pay.value = 2_500;
+    pay.date = dt("2024-02-15T10:00:00");
+    pay_scheduled_impl(&pool, &Holidays::default(), st.id, &pay).await.unwrap();
+    pay.value = 3_500;
+    pay_scheduled_impl(&pool, &Holidays::default(), st.id, &pay).await.unwrap();
+    pay.partial = false;
+    pay.value = 8_500;
+    pay.date = dt("2024-03-15T10:00:00");
+    st = pay_scheduled_impl(&pool, &Holidays::default(), st.id, &pay).await.unwrap();
+
+    // A partial payment towards the current occurrence is not part of the estimate.
+    pay.partial = true;
+    pay.value = 1_000;
+    pay.date = dt("2024-04-15T10:00:00");
+    st = pay_scheduled_impl(&pool, &Holidays::default(), st.id, &pay).await.unwrap();
+
+    let enriched = service::enrich_scheduled(&pool, &st).await.unwrap();
+    assert_eq!(enriched.estimated_value, 6_167, "(4000 + 6000 + 8500) / 3");
+    st.amount_estimate = AmountEstimates::Median;
+    assert_eq!(service::estimated_value(&pool, &st).await.unwrap(), 6_000);
+    st.amount_estimate = AmountEstimates::Last;
+    assert_eq!(service::estimated_value(&pool, &st).await.unwrap(), 8_500);
+    st.amount_estimate = AmountEstimates::Fixed;
+    assert_eq!(service::estimated_value(&pool, &st).await.unwrap(), 5_000);
+
+    // Due in April: the spend in January to March, 18500 over three months.
+    st.amount_estimate = AmountEstimates::CategorySpend;
+    assert_eq!(service::estimated_value(&pool, &st).await.unwrap(), 6_167);
+
+    let upcoming = service::upcoming_occurrences(&st, &Holidays::default(), 6_167, 2, None);
+    assert!(upcoming.unwrap().iter().all(|o| o.value == 6_167));
+}
+
+#[tokio::test]
+async fn due_scheduled_follows_the_lead_time() {
+    let pool = fresh_pool().await;
+    let wallet = db::accounts::insert(&pool, "Wallet").await.unwrap();
+    let savings = db::accounts::insert(&pool, "Savings").await.unwrap();
+    let bills = db::categories::insert(&pool, CategoryTypes::Expense, "Bills")
+        .await
+        .unwrap();
+
+    let settings = db::notification_settings::get(&pool).await.unwrap();
+    assert!(settings.enabled, "notifications are on by default");
+    assert_eq!(settings.lead_days, 0);
+
+    let schedule = |description: &str, date: &str, transfer: bool| PostScheduledTransaction {
+        kind: if transfer {
+            ScheduledTransactionKinds::Transfer
+        } else {
+            ScheduledTransactionKinds::Transaction
+        },
+        value: 10_000,
+        description: Some(description.into()),
+        created_date: dt(date),
+        account_id: (!transfer).then_some(wallet.id),
+        category_id: (!transfer).then_some(bills.id),
+        origin_account_id: transfer.then_some(wallet.id),
+        destination_account_id: transfer.then_some(savings.id),
+        repeat: !transfer,
+        repeat_freq: (!transfer).then_some(RepeatFrequencies::Months),
+        repeat_interval: (!transfer).then_some(1),
+        rrule: None,
+        business_day_adjustment: BusinessDayAdjustments::None,
+        amount_estimate: AmountEstimates::Fixed,
+        infinite_repeat: (!transfer).then_some(true),
+        end_after_repeats: None,
+        current_repeat_count: None,
+        next_date: None,
+    };
+    let mut ids = Vec::new();
+    for body in [
+        schedule("Rent", "2024-03-01T00:00:00", false),
+        schedule("Savings", "2024-03-05T09:00:00", true),
+        schedule("Phone", "2024-03-08T00:00:00", false),
+        schedule("Insurance", "2024-03-20T00:00:00", false),
+    ] {
+        let new = build_new_scheduled(&pool, &Holidays::default(), &body)
+            .await
+            .unwrap()
+            .unwrap();
+        ids.push(db::scheduled_transactions::insert(&pool, &new).await.unwrap().id);
+    }
+    let today = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+
+    // On the day: the overdue rent and today's transfer, most overdue first.
+    let due = service::due_scheduled(&pool, today, 0).await.unwrap();
+    let listed: Vec<_> = due
+        .iter()
+        .map(|item| (item.label.as_str(), item.days_until, item.status))
+        .collect();
+    assert_eq!(
+        listed,
+        [("Rent", -4, DueStatuses::Overdue), ("Savings", 0, DueStatuses::DueToday)]
+    );
+    assert_eq!(due[0].accounts, "Wallet");
+    assert_eq!(due[1].accounts, "Wallet → Savings");
+
+    // Three days ahead also announces the phone bill, but not the insurance.
+    let due = service::due_scheduled(&pool, today, 3).await.unwrap();
+    assert_eq!(due.len(), 3);
+    assert_eq!((due[2].id, due[2].status), (ids[2], DueStatuses::Upcoming));
+
+    // A partial payment lowers what is still expected; paying it moves the rent to April.
+    let mut pay = PostScheduledTransactionPay {
+        value: 4_000,
+        description: "Rent".into(),
+        date: dt("2024-03-05T10:00:00"),
+        category_id: Some(bills.id),
+        account_id: Some(wallet.id),
+        origin_account_id: None,
+        destination_account_id: None,
+        partial: true,
+    };
+    pay_scheduled_impl(&pool, &Holidays::default(), ids[0], &pay).await.unwrap();
+    let due = service::due_scheduled(&pool, today, 0).await.unwrap();
+    assert_eq!((due[0].id, due[0].value), (ids[0], 6_000));
+
+    pay.partial = false;
+    pay.value = 6_000;
+    pay_scheduled_impl(&pool, &Holidays::default(), ids[0], &pay).await.unwrap();
+    let due = service::due_scheduled(&pool, today, 0).await.unwrap();
+    assert_eq!(due.iter().map(|item| item.id).collect::<Vec<_>>(), [ids[1]]);
+
+    let settings = db::notification_settings::update(
+        &pool,
+        &NotificationSettings {
+            enabled: false,
+            lead_days: 3,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(!settings.enabled);
+    assert_eq!(settings.lead_days, 3);
+}
@@ -197,6 +197,22 @@ async getScheduledTransactions() : Promise<Result<GetScheduledTransaction[], { s
     else return { status: "error", error: e  as any };
 }
 },
+async previewScheduledTransaction(req: PostScheduledTransaction, count: number | null, until: string | null) : Promise<Result<UpcomingOccurrence[], { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("preview_scheduled_transaction", { req, count, until }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getFinishedScheduledTransactions() : Promise<Result<GetScheduledTransaction[], { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_finished_scheduled_transactions") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
 async getScheduledTransaction(scheduledTransactionId: number) : Promise<Result<GetScheduledTransaction, { status: number; message: string }>> {
     try {
     return { status: "ok", data: await TAURI_INVOKE("get_scheduled_transaction", { scheduledTransactionId }) };
@@ -228,12 +244,172 @@ async payScheduledTransaction(scheduledTransactionId: number, req: PostScheduled
     if(e instanceof Error) throw e;
     else return { status: "error", error: e  as any };
 }
+},
+/**
+ * Move past the current occurrence without booking anything.
+ */
+async skipScheduledTransaction(scheduledTransactionId: number) : Promise<Result<ScheduledTransaction, { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("skip_scheduled_transaction", { scheduledTransactionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Move the current due date to a later date. The following occurrences keep their dates.
+ */
+async snoozeScheduledTransaction(scheduledTransactionId: number, req: PostScheduledTransactionSnooze) : Promise<Result<ScheduledTransaction, { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("snooze_scheduled_transaction", { scheduledTransactionId, req }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * What happened to the schedule's occurrences so far, oldest first.
+ */
+async getScheduledTransactionHistory(scheduledTransactionId: number) : Promise<Result<ScheduledOccurrence[], { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_scheduled_transaction_history", { scheduledTransactionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getScheduledTransactionOccurrences(scheduledTransactionId: number, count: number | null, until: string | null) : Promise<Result<UpcomingOccurrence[], { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_scheduled_transaction_occurrences", { scheduledTransactionId, count, until }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getScheduledTransactionEntries(scheduledTransactionId: number) : Promise<Result<ScheduledEntry[], { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_scheduled_transaction_entries", { scheduledTransactionId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async createGoal(req: PostGoal) : Promise<Result<GetGoal, { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("create_goal", { req }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getGoals() : Promise<Result<GetGoal[], { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_goals") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getGoal(goalId: number) : Promise<Result<GetGoal, { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_goal", { goalId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async updateGoal(goalId: number, req: PostGoal) : Promise<Result<GetGoal, { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_goal", { goalId, req }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Delete a goal along with its contribution schedule, if any. The linked accounts are kept.
+ */
+async deleteGoal(goalId: number) : Promise<Result<Goal, { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_goal", { goalId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getGoalProgress(goalId: number) : Promise<Result<GoalProgress, { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_goal_progress", { goalId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Create (or replace) the goal's recurring monthly contribution transfer.
+ */
+async setGoalContribution(goalId: number, req: PostGoalContribution) : Promise<Result<GetGoal, { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("set_goal_contribution", { goalId, req }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Stop the goal's contribution by deleting its scheduled transfer, if any.
+ */
+async deleteGoalContribution(goalId: number) : Promise<Result<GetGoal, { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("delete_goal_contribution", { goalId }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * The scheduled transactions that are overdue, due today or due within the notification lead
+ * time — what the scheduled list badge counts. The same list is pushed to the frontend as the
+ * `dueScheduledTransactions` event after every background check.
+ */
+async getDueScheduledTransactions() : Promise<Result<DueScheduledTransaction[], { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_due_scheduled_transactions") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+async getNotificationSettings() : Promise<Result<NotificationSettings, { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("get_notification_settings") };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
+},
+/**
+ * Turn the notifications on or off and set their lead time (0 to 30 days, `BadRequest`
+ * otherwise). The background check picks the new settings up on its next run.
+ */
+async updateNotificationSettings(req: NotificationSettings) : Promise<Result<NotificationSettings, { status: number; message: string }>> {
+    try {
+    return { status: "ok", data: await TAURI_INVOKE("update_notification_settings", { req }) };
+} catch (e) {
+    if(e instanceof Error) throw e;
+    else return { status: "error", error: e  as any };
+}
 }
 }
 
 /** user-defined events **/
 
 
+export const events = __makeEvents__<{
+dueScheduledTransactions: DueScheduledTransactions
+}>({
+dueScheduledTransactions: "due-scheduled-transactions"
+})
 
 /** user-defined constants **/
 
@@ -242,24 +418,131 @@ async payScheduledTransaction(scheduledTransactionId: number, req: PostScheduled
 /** user-defined types **/
 
 export type Account = { id: number; name: string }
+/**
+ * The amount a schedule's occurrences are expected to need: its fixed `value`, or an estimate
+ * from what was paid for its previous occurrences (average, median or last) or from the recent
+ * monthly spend in its category.
+ */
+export type AmountEstimates = "Fixed" | "Average" | "Median" | "Last" | "CategorySpend"
+/**
+ * How a scheduled due date that falls on a weekend or holiday is moved: not at all, to the
+ * previous or the next business day, or to the next one unless that is in the following month
+ * (then the previous one, "modified following").
+ */
+export type BusinessDayAdjustments = "None" | "Previous" | "Next" | "ModifiedFollowing"
 export type Category = { id: number; categorytype: CategoryTypes; name: string }
 export type CategoryTypes = "Expense" | "Income" | "TransferIncome" | "TransferExpense"
+/**
+ * A scheduled transaction that is due, overdue or coming up within the notification lead time.
+ * `label` is its description (or category), `accounts` the account it is booked in (or the
+ * origin → destination of a transfer), `value` what is still expected for the occurrence and
+ * `days_until` the days left until `next_date` (negative once overdue).
+ */
+export type DueScheduledTransaction = { id: number; kind: ScheduledTransactionKinds; label: string; accounts: string; value: number; next_date: string; days_until: number; status: DueStatuses }
+/**
+ * The scheduled transactions that are overdue, due today or due within the lead time, sent to
+ * the frontend after every check.
+ */
+export type DueScheduledTransactions = { due: DueScheduledTransaction[] }
+/**
+ * How a scheduled transaction's due date compares with today: coming up within the
+ * notification lead time, due today or overdue. Only used in responses; it is not stored.
+ */
+export type DueStatuses = "Upcoming" | "DueToday" | "Overdue"
 export type GetAccount = { id: number; name: string; balance: number }
-export type GetScheduledTransaction = { id: number; kind: ScheduledTransactionKinds; value: number; description: string | null; created_date: string; account_id: number | null; account_name: string | null; category_id: number | null; category_type: CategoryTypes | null; category_name: string | null; origin_account_id: number | null; origin_account_name: string | null; destination_account_id: number | null; destination_account_name: string | null; repeat: boolean; repeat_freq: RepeatFrequencies | null; repeat_interval: number | null; infinite_repeat: boolean | null; end_after_repeats: number | null; current_repeat_count: number | null; next_date: string | null }
+export type GetGoal = { id: number; name: string; target_amount: number; target_date: string | null; created_date: string; scheduled_transaction_id: number | null; accounts: GoalAccount[] }
+/**
+ * A scheduled transaction with the names of what it references. `paid_value` is what partial
+ * payments have already paid towards the current occurrence and `estimated_value` the amount
+ * expected for it (`value` unless it has an `amount_estimate`); `finished` is set once the last
+ * occurrence has been paid or skipped.
+ */
+export type GetScheduledTransaction = { id: number; kind: ScheduledTransactionKinds; value: number; description: string | null; created_date: string; account_id: number | null; account_name: string | null; category_id: number | null; category_type: CategoryTypes | null; category_name: string | null; origin_account_id: number | null; origin_account_name: string | null; destination_account_id: number | null; destination_account_name: string | null; repeat: boolean; repeat_freq: RepeatFrequencies | null; repeat_interval: number | null; rrule: string | null; business_day_adjustment: BusinessDayAdjustments; amount_estimate: AmountEstimates; infinite_repeat: boolean | null; end_after_repeats: number | null; current_repeat_count: number | null; next_date: string | null; finished: boolean; paid_value: number; estimated_value: number }
+export type Goal = { id: number; name: string; target_amount: number; target_date: string | null; created_date: string; scheduled_transaction_id: number | null }
+/**
+ * An account funding a goal. Without an `earmark` the whole balance counts towards the goal,
+ * otherwise only up to the earmarked amount.
+ */
+export type GoalAccount = { goal_id: number; account_id: number; earmark: number | null }
+/**
+ * How much of an account's balance counts towards a goal: the balance capped by the
+ * earmark, and never negative.
+ */
+export type GoalAccountProgress = { account_id: number; account_name: string; earmark: number | null; balance: number; contribution: number }
+/**
+ * A goal's progress as of today. `months_left` counts the monthly contributions still possible
+ * before the target date, and `required_monthly` is what each must be to reach the target
+ * (all of `remaining` when the date has come); both are `None` without a target date.
+ */
+export type GoalProgress = { goal_id: number; target_amount: number; target_date: string | null; saved: number; remaining: number; percent: number; months_left: number | null; required_monthly: number | null; accounts: GoalAccountProgress[] }
 export type InitialData = { accounts: GetAccount[]; categories: Category[]; scheduled_transactions: GetScheduledTransaction[] }
+/**
+ * Settings of the due/overdue notifications: whether they are shown, and how many days before
+ * its due date a scheduled transaction is announced (0 = on the day).
+ */
+export type NotificationSettings = { enabled: boolean; lead_days: number }
+/**
+ * What happened to a due occurrence of a scheduled transaction.
+ */
+export type OccurrenceOutcomes = "Paid" | "PartiallyPaid" | "Skipped" | "Snoozed"
 export type PatchTransaction = { value: number; description: string; date: string; account: number; category: number }
 export type PatchTransfer = { origin_account: number; destination_account: number; value: number; description: string; date: string }
 export type PostCategory = { categorytype: CategoryTypes; name: string }
-export type PostScheduledTransaction = { kind: ScheduledTransactionKinds; value: number; description: string | null; created_date: string; account_id: number | null; category_id: number | null; origin_account_id: number | null; destination_account_id: number | null; repeat: boolean; repeat_freq: RepeatFrequencies | null; repeat_interval: number | null; infinite_repeat: boolean | null; end_after_repeats: number | null; current_repeat_count: number | null; next_date: string | null }
-export type PostScheduledTransactionPay = { value: number; description: string; date: string; category_id: number | null; account_id: number | null; origin_account_id: number | null; destination_account_id: number | null }
+/**
+ * New goal, or the full replacement of one (`PatchGoal`), including its linked accounts.
+ */
+export type PostGoal = { name: string; target_amount: number; target_date: string | null; accounts: PostGoalAccount[] }
+export type PostGoalAccount = { account_id: number; earmark: number | null }
+/**
+ * Set up the goal's monthly contribution: a transfer from `origin_account_id` into one of the
+ * goal's accounts for the required monthly amount, starting on `start_date` and repeating
+ * until the target date.
+ */
+export type PostGoalContribution = { origin_account_id: number; destination_account_id: number; start_date: string }
+export type PostScheduledTransaction = { kind: ScheduledTransactionKinds; value: number; description: string | null; created_date: string; account_id: number | null; category_id: number | null; origin_account_id: number | null; destination_account_id: number | null; repeat: boolean; repeat_freq: RepeatFrequencies | null; repeat_interval: number | null; rrule: string | null; business_day_adjustment: BusinessDayAdjustments; amount_estimate: AmountEstimates; infinite_repeat: boolean | null; end_after_repeats: number | null; current_repeat_count: number | null; next_date: string | null }
+/**
+ * Payment of a schedule's current occurrence. With `partial`, only part of it is paid: the
+ * occurrence stays due until its payments add up to the scheduled value.
+ */
+export type PostScheduledTransactionPay = { value: number; description: string; date: string; category_id: number | null; account_id: number | null; origin_account_id: number | null; destination_account_id: number | null ; partial: boolean }
+/**
+ * Move the current due date of a schedule to a later `date`, without changing the following
+ * occurrences.
+ */
+export type PostScheduledTransactionSnooze = { date: string }
 export type PostTransaction = { value: number; description: string; date: string; category: number }
 export type PostTransfer = { value: number; description: string; date: string }
 export type RepeatFrequencies = "Days" | "Weeks" | "Months" | "Years"
-export type ScheduledTransaction = { id: number; kind: ScheduledTransactionKinds; value: number; description: string | null; created_date: string; account_id: number | null; category_id: number | null; origin_account_id: number | null; destination_account_id: number | null; repeat: boolean; repeat_freq: RepeatFrequencies | null; repeat_interval: number | null; infinite_repeat: boolean | null; end_after_repeats: number | null; current_repeat_count: number | null; next_date: string | null }
+/**
+ * A transaction or transfer booked by paying a schedule, tagged with the occurrence it paid.
+ */
+export type ScheduledEntry = { kind: ScheduledTransactionKinds; id: number; occurrence: number | null; value: number; description: string; date: string }
+/**
+ * One entry of a schedule's occurrence history. `occurrence` is the schedule's
+ * `current_repeat_count` when it was recorded (`None` for a one-off), `value` the amount booked
+ * (0 for a skip or snooze) and `snoozed_to` the due date a snooze moved it to.
+ */
+export type ScheduledOccurrence = { id: number; scheduled_transaction_id: number; occurrence: number | null; due_date: string; outcome: OccurrenceOutcomes; value: number; snoozed_to: string | null; recorded_at: string }
+export type ScheduledTransaction = { id: number; kind: ScheduledTransactionKinds; value: number; description: string | null; created_date: string; account_id: number | null; category_id: number | null; origin_account_id: number | null; destination_account_id: number | null; repeat: boolean; repeat_freq: RepeatFrequencies | null; repeat_interval: number | null; rrule: string | null; business_day_adjustment: BusinessDayAdjustments; amount_estimate: AmountEstimates; infinite_repeat: boolean | null; end_after_repeats: number | null; current_repeat_count: number | null; next_date: string | null; finished: boolean }
 export type ScheduledTransactionKinds = "Transaction" | "Transfer"
-export type Transaction = { id: number; value: number; description: string; date: string; account: number; category: number }
+/**
+ * A transaction. `scheduled_transaction_id` and `occurrence` are set when it was booked by paying
+ * that occurrence of a schedule.
+ */
+export type Transaction = { id: number; value: number; description: string; date: string; account: number; category: number; scheduled_transaction_id: number | null; occurrence: number | null }
 export type TransactionTransferJoined = { id: number; value: number; description: string; date: string; category_id: number | null; category_type: CategoryTypes; category_name: string | null; account_id: number; account_name: string; from_account_id: number | null; from_account_name: string | null }
-export type Transfer = { id: number; origin_account: number; destination_account: number; value: number; description: string; date: string }
+/**
+ * A transfer. `scheduled_transaction_id` and `occurrence` are set when it was booked by paying
+ * that occurrence of a schedule.
+ */
+export type Transfer = { id: number; origin_account: number; destination_account: number; value: number; description: string; date: string; scheduled_transaction_id: number | null; occurrence: number | null }
+/**
+ * One upcoming occurrence of a schedule (`occurrence` is `None` for a one-off). `date` is when
+ * it is due, after any move off a weekend or holiday (or a snooze), and `scheduled_date` the
+ * date the recurrence gives; `clamped` is set when that date is earlier in the month than the
+ * schedule's start because the month is shorter (the 31st becoming the 28th of February).
+ */
+export type UpcomingOccurrence = { occurrence: number | null; date: string; scheduled_date: string; value: number; clamped: boolean }
 
 /** tauri-specta globals **/
 
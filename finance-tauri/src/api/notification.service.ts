@@ -0,0 +1,30 @@
+import { commands, call } from './finance';
+import { events, type DueScheduledTransaction } from './bindings';
+
+const getDueScheduledTransactions = async () => {
+    const { data } = await call(commands.getDueScheduledTransactions());
+    return data;
+};
+
+const getNotificationSettings = async () => {
+    const { data } = await call(commands.getNotificationSettings());
+    return data;
+};
+
+const editNotificationSettings = async (enabled: boolean, leadDays: number) => {
+    const { data } = await call(commands.updateNotificationSettings({ enabled, lead_days: leadDays }));
+    return data;
+};
+
+/// Call `callback` with the due list every time the background check runs. Resolves to the
+/// function that stops listening.
+const onDueScheduledTransactions = (callback: (due: DueScheduledTransaction[]) => void) => {
+    return events.dueScheduledTransactions.listen(event => callback(event.payload.due));
+};
+
+export const notificationService = {
+    getDueScheduledTransactions,
+    getNotificationSettings,
+    editNotificationSettings,
+    onDueScheduledTransactions,
+};
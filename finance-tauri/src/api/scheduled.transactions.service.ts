@@ -1,11 +1,22 @@
 import { commands, call } from './finance';
-import type { ScheduledTransactionKinds, RepeatFrequencies } from './bindings';
+import type {
+    AmountEstimates,
+    BusinessDayAdjustments,
+    PostScheduledTransaction,
+    ScheduledTransactionKinds,
+    RepeatFrequencies,
+} from './bindings';
 
 const getAllScheduledTransactions = async () => {
     const { data } = await call(commands.getScheduledTransactions());
     return data;
 };
 
+const getFinishedScheduledTransactions = async () => {
+    const { data } = await call(commands.getFinishedScheduledTransactions());
+    return data;
+};
+
 const getScheduledTransactionById = async (scheduledTransactionId: number) => {
     const { data } = await call(commands.getScheduledTransaction(scheduledTransactionId));
     return data;
@@ -25,6 +36,9 @@ const newScheduledTransaction = async (
     repeatInterval: number | null,
     infiniteRepeat: boolean | null,
     endAfterRepeats: number | null,
+    rrule: string | null = null,
+    businessDayAdjustment: BusinessDayAdjustments = 'None',
+    amountEstimate: AmountEstimates = 'Fixed',
 ) => {
     const { data } = await call(
         commands.createScheduledTransaction({
@@ -39,6 +53,9 @@ const newScheduledTransaction = async (
             repeat,
             repeat_freq: repeat ? repeatFreq : null,
             repeat_interval: repeat ? repeatInterval : null,
+            rrule: repeat ? rrule : null,
+            business_day_adjustment: businessDayAdjustment,
+            amount_estimate: amountEstimate,
             infinite_repeat: repeat ? infiniteRepeat : null,
             end_after_repeats: repeat ? (infiniteRepeat ? null : endAfterRepeats) : null,
             // Server-computed on create; sent explicitly as null (equivalent to omitting them)
@@ -51,6 +68,26 @@ const newScheduledTransaction = async (
     return data;
 };
 
+const previewScheduledTransaction = async (
+    scheduledTransaction: PostScheduledTransaction,
+    count: number | null = null,
+    until: string | null = null,
+) => {
+    const { data } = await call(commands.previewScheduledTransaction(scheduledTransaction, count, until));
+    return data;
+};
+
+const getScheduledTransactionOccurrences = async (
+    scheduledTransactionId: number,
+    count: number | null = null,
+    until: string | null = null,
+) => {
+    const { data } = await call(
+        commands.getScheduledTransactionOccurrences(scheduledTransactionId, count, until),
+    );
+    return data;
+};
+
 const payScheduledTransaction = async (
     scheduledTransactionId: number,
     value: number,
@@ -60,6 +97,7 @@ const payScheduledTransaction = async (
     accountId: number | null,
     originAccountId: number | null,
     destinationAccountId: number | null,
+    partial = false,
 ) => {
     const { data } = await call(
         commands.payScheduledTransaction(scheduledTransactionId, {
@@ -70,6 +108,7 @@ const payScheduledTransaction = async (
             account_id: accountId,
             origin_account_id: originAccountId,
             destination_account_id: destinationAccountId,
+            partial,
         }),
     );
 
@@ -91,6 +130,9 @@ const editScheduledTransactionById = async (
     repeatInterval: number | null,
     infiniteRepeat: boolean | null,
     endAfterRepeats: number | null,
+    rrule: string | null = null,
+    businessDayAdjustment: BusinessDayAdjustments = 'None',
+    amountEstimate: AmountEstimates = 'Fixed',
 ) => {
     const { data } = await call(
         commands.updateScheduledTransaction(scheduledTransactionId, {
@@ -105,6 +147,9 @@ const editScheduledTransactionById = async (
             repeat,
             repeat_freq: repeat ? repeatFreq : null,
             repeat_interval: repeat ? repeatInterval : null,
+            rrule: repeat ? rrule : null,
+            business_day_adjustment: businessDayAdjustment,
+            amount_estimate: amountEstimate,
             infinite_repeat: repeat ? infiniteRepeat : null,
             end_after_repeats: repeat ? (infiniteRepeat ? null : endAfterRepeats) : null,
             current_repeat_count: null,
@@ -115,6 +160,26 @@ const editScheduledTransactionById = async (
     return data;
 };
 
+const skipScheduledTransaction = async (scheduledTransactionId: number) => {
+    const { data } = await call(commands.skipScheduledTransaction(scheduledTransactionId));
+    return data;
+};
+
+const snoozeScheduledTransaction = async (scheduledTransactionId: number, date: string) => {
+    const { data } = await call(commands.snoozeScheduledTransaction(scheduledTransactionId, { date }));
+    return data;
+};
+
+const getScheduledTransactionHistory = async (scheduledTransactionId: number) => {
+    const { data } = await call(commands.getScheduledTransactionHistory(scheduledTransactionId));
+    return data;
+};
+
+const getScheduledTransactionEntries = async (scheduledTransactionId: number) => {
+    const { data } = await call(commands.getScheduledTransactionEntries(scheduledTransactionId));
+    return data;
+};
+
 const deleteScheduledTransactionById = async (transactionId: number) => {
     const { data } = await call(commands.deleteScheduledTransaction(transactionId));
     return data;
@@ -122,9 +187,16 @@ const deleteScheduledTransactionById = async (transactionId: number) => {
 
 export const scheduledTransactionService = {
     getAllScheduledTransactions,
+    getFinishedScheduledTransactions,
     getScheduledTransactionById,
     newScheduledTransaction,
+    previewScheduledTransaction,
+    getScheduledTransactionOccurrences,
     payScheduledTransaction,
+    skipScheduledTransaction,
+    snoozeScheduledTransaction,
+    getScheduledTransactionHistory,
+    getScheduledTransactionEntries,
     editScheduledTransactionById,
     deleteScheduledTransactionById,
 };
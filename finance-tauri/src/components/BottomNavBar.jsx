@@ -7,17 +7,32 @@ import SettingsIcon from "@mui/icons-material/Settings";
 import EventIcon from "@mui/icons-material/Event";
 import AssessmentIcon from "@mui/icons-material/Assessment";
 import {Badge} from "@mui/material";
-import {dueScheduledTransactions} from "./scheduled-transactions/due.scheduled.transactions";
 import {useSelector} from "react-redux";
+import {notificationService} from "../api/notification.service";
 
 const BottomNavBar = () => {
     const [value, setValue] = React.useState('home');
 
     const allScheduledTransactions = useSelector(state => state.scheduledTransactions);
 
+    const [dueBadgeCount, setDueBadgeCount] = React.useState(0);
+
     let location = useLocation();
 
-    const dueBadgeCount = dueScheduledTransactions(allScheduledTransactions);
+    // The due list comes from Rust (it honours the notification lead time): fetched again when
+    // the scheduled transactions change here, and pushed by the background check as they fall due.
+    React.useEffect(() => {
+        notificationService.getDueScheduledTransactions()
+            .then(due => setDueBadgeCount(due.length))
+            .catch(() => setDueBadgeCount(0));
+    }, [allScheduledTransactions]);
+
+    React.useEffect(() => {
+        const unlisten = notificationService.onDueScheduledTransactions(due => setDueBadgeCount(due.length));
+        return () => {
+            unlisten.then(stop => stop());
+        };
+    }, []);
 
     React.useEffect(() => {
         if (location.pathname.startsWith('/settings') || location.pathname.startsWith('/categories')) {
@@ -2,12 +2,49 @@ import React from 'react';
 import AppBar from '@mui/material/AppBar';
 import Toolbar from '@mui/material/Toolbar';
 import Typography from '@mui/material/Typography';
-import {Card, Container, List, ListItemButton, ListItemIcon, ListItemText} from '@mui/material';
+import {
+    Card,
+    Container,
+    FormControl,
+    InputLabel,
+    List,
+    ListItem,
+    ListItemButton,
+    ListItemIcon,
+    ListItemText,
+    MenuItem,
+    Select,
+    Switch
+} from '@mui/material';
 import ImportExportIcon from '@mui/icons-material/ImportExport';
+import NotificationsIcon from '@mui/icons-material/Notifications';
 import ChevronRightIcon from '@mui/icons-material/ChevronRight';
 import {Link} from 'react-router-dom';
+import {notificationService} from '../api/notification.service';
+
+const leadTimes = [
+    {days: 0, label: 'On the due date'},
+    {days: 1, label: '1 day before'},
+    {days: 3, label: '3 days before'},
+    {days: 7, label: '1 week before'},
+    {days: 14, label: '2 weeks before'}
+];
 
 const Settings = () => {
+    const [notifications, setNotifications] = React.useState(null);
+
+    React.useEffect(() => {
+        notificationService.getNotificationSettings()
+            .then(settings => setNotifications(settings))
+            .catch(() => setNotifications(null));
+    }, []);
+
+    const saveNotifications = (enabled, leadDays) => {
+        notificationService.editNotificationSettings(enabled, leadDays)
+            .then(settings => setNotifications(settings))
+            .catch(() => {});
+    };
+
     return (
         <>
             <AppBar position='sticky'>
@@ -27,6 +64,45 @@ const Settings = () => {
                         </ListItemButton>
                     </List>
                 </Card>
+                {notifications &&
+                    <Card variant='outlined' sx={{mt: 3}}>
+                        <List disablePadding>
+                            <ListItem
+                                secondaryAction={
+                                    <Switch
+                                        edge='end'
+                                        checked={notifications.enabled}
+                                        onChange={e => saveNotifications(e.target.checked, notifications.lead_days)}
+                                    />
+                                }
+                            >
+                                <ListItemIcon>
+                                    <NotificationsIcon/>
+                                </ListItemIcon>
+                                <ListItemText
+                                    primary='Due date notifications'
+                                    secondary='Notify me about scheduled transactions that are due or overdue'
+                                />
+                            </ListItem>
+                            <ListItem>
+                                <FormControl fullWidth variant='outlined' disabled={!notifications.enabled}>
+                                    <InputLabel id='leadDays-label'>Notify</InputLabel>
+                                    <Select
+                                        labelId='leadDays-label'
+                                        id='leadDays'
+                                        label='Notify'
+                                        value={notifications.lead_days}
+                                        onChange={e => saveNotifications(notifications.enabled, e.target.value)}
+                                    >
+                                        {leadTimes.map(leadTime =>
+                                            <MenuItem value={leadTime.days} key={leadTime.days}>{leadTime.label}</MenuItem>
+                                        )}
+                                    </Select>
+                                </FormControl>
+                            </ListItem>
+                        </List>
+                    </Card>
+                }
             </Container>
         </>
     );
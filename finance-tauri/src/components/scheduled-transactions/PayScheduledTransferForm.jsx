@@ -28,7 +28,7 @@ const PayScheduledTransferForm = (props) => {
 
                 await formik.setFieldValue('originAccountId', scheduledTransaction.origin_account_id);
                 await formik.setFieldValue('destinationAccountId', scheduledTransaction.destination_account_id);
-                await formik.setFieldValue('value', currency(scheduledTransaction.value, {fromCents: true}));
+                await formik.setFieldValue('value', currency(scheduledTransaction.estimated_value, {fromCents: true}));
                 await formik.setFieldValue('description', scheduledTransaction.description);
                 await formik.setFieldValue('date', moment(scheduledTransaction.next_date));
 
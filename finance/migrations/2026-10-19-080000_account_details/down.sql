@@ -0,0 +1,8 @@
+ALTER TABLE accounts
+    DROP COLUMN account_type,
+    DROP COLUMN opening_balance,
+    DROP COLUMN opening_date,
+    DROP COLUMN archived,
+    DROP COLUMN sort_order;
+
+DROP TYPE account_types;
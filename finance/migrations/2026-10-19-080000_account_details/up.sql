@@ -0,0 +1,15 @@
+-- Account types, opening balances, archiving and a user-defined sort order.
+--
+-- Existing accounts become plain checking accounts with a zero opening balance, no opening
+-- date (every movement counts towards the balance), not archived, and sorted by id.
+
+CREATE TYPE account_types AS ENUM ('checking', 'savings', 'credit_card', 'cash', 'loan', 'investment', 'asset');
+
+ALTER TABLE accounts
+    ADD COLUMN account_type    account_types NOT NULL DEFAULT 'checking',
+    ADD COLUMN opening_balance INTEGER       NOT NULL DEFAULT 0,
+    ADD COLUMN opening_date    TIMESTAMP,
+    ADD COLUMN archived        BOOLEAN       NOT NULL DEFAULT FALSE,
+    ADD COLUMN sort_order      INTEGER       NOT NULL DEFAULT 0;
+
+UPDATE accounts SET sort_order = id;
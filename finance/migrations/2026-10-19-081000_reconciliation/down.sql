@@ -0,0 +1,6 @@
+DROP TABLE reconciliations;
+
+ALTER TABLE transfers DROP COLUMN origin_status, DROP COLUMN destination_status;
+ALTER TABLE transactions DROP COLUMN status;
+
+DROP TYPE transaction_statuses;
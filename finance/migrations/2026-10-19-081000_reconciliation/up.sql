@@ -0,0 +1,28 @@
+-- Cleared/reconciled states for transactions and transfers, plus reconciliation sessions.
+--
+-- Every existing movement starts out uncleared. A transfer has a status for each of its sides, as
+-- each account is reconciled against its own statements.
+
+CREATE TYPE transaction_statuses AS ENUM ('uncleared', 'cleared', 'reconciled');
+
+ALTER TABLE transactions
+    ADD COLUMN status transaction_statuses NOT NULL DEFAULT 'uncleared';
+
+ALTER TABLE transfers
+    ADD COLUMN origin_status      transaction_statuses NOT NULL DEFAULT 'uncleared',
+    ADD COLUMN destination_status transaction_statuses NOT NULL DEFAULT 'uncleared';
+
+CREATE TABLE reconciliations
+(
+    id                SERIAL PRIMARY KEY                                  NOT NULL,
+    account_id        INTEGER REFERENCES accounts (id) ON DELETE CASCADE  NOT NULL,
+    statement_date    TIMESTAMP                                           NOT NULL,
+    statement_balance INTEGER                                             NOT NULL,
+    created_date      TIMESTAMP                                           NOT NULL DEFAULT now(),
+    finished_date     TIMESTAMP,
+    user_id           INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL
+);
+
+-- At most one open (unfinished) session per account.
+CREATE UNIQUE INDEX reconciliations_one_open_per_account
+    ON reconciliations (account_id) WHERE finished_date IS NULL;
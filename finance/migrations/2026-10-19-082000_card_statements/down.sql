@@ -0,0 +1,9 @@
+DROP INDEX scheduled_transactions_one_autopay_per_card;
+
+ALTER TABLE scheduled_transactions
+    DROP COLUMN autopay_card_id;
+
+ALTER TABLE accounts
+    DROP COLUMN autopay_account_id,
+    DROP COLUMN payment_due_day,
+    DROP COLUMN statement_closing_day;
@@ -0,0 +1,18 @@
+-- Credit-card statement cycles and autopay.
+--
+-- A card's statement closes on `statement_closing_day` each month and is due on the next
+-- `payment_due_day` after that (days past the end of a short month fall on its last day).
+-- With an `autopay_account_id`, a one-off scheduled transfer from that account pays the
+-- outstanding statement balance on the due date; `scheduled_transactions.autopay_card_id`
+-- marks that managed schedule (at most one per card).
+
+ALTER TABLE accounts
+    ADD COLUMN statement_closing_day INTEGER CHECK (statement_closing_day BETWEEN 1 AND 31),
+    ADD COLUMN payment_due_day       INTEGER CHECK (payment_due_day BETWEEN 1 AND 31),
+    ADD COLUMN autopay_account_id    INTEGER REFERENCES accounts (id) ON DELETE SET NULL;
+
+ALTER TABLE scheduled_transactions
+    ADD COLUMN autopay_card_id INTEGER REFERENCES accounts (id) ON DELETE CASCADE;
+
+CREATE UNIQUE INDEX scheduled_transactions_one_autopay_per_card
+    ON scheduled_transactions (autopay_card_id) WHERE autopay_card_id IS NOT NULL;
@@ -0,0 +1,20 @@
+-- Loan terms for `loan` accounts.
+--
+-- The loan account itself holds the balance (opening balance = -principal); this table keeps
+-- the terms used to build the amortization table and to split each payment into principal
+-- and interest. `annual_rate_bps` is the annual rate in basis points (525 = 5.25%).
+-- `scheduled_transaction_id` is the monthly payment transfer from `payment_account_id`.
+
+CREATE TABLE loans
+(
+    account_id               INTEGER PRIMARY KEY REFERENCES accounts (id) ON DELETE CASCADE NOT NULL,
+    principal                INTEGER                                             NOT NULL CHECK (principal > 0),
+    annual_rate_bps          INTEGER                                             NOT NULL CHECK (annual_rate_bps >= 0),
+    term_months              INTEGER                                             NOT NULL CHECK (term_months > 0),
+    payment_day              INTEGER                                             NOT NULL CHECK (payment_day BETWEEN 1 AND 31),
+    start_date               TIMESTAMP                                           NOT NULL,
+    payment_account_id       INTEGER REFERENCES accounts (id) ON DELETE CASCADE  NOT NULL,
+    interest_category_id     INTEGER REFERENCES categories (id) ON DELETE CASCADE NOT NULL,
+    scheduled_transaction_id INTEGER REFERENCES scheduled_transactions (id) ON DELETE SET NULL,
+    user_id                  INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL
+);
@@ -0,0 +1,25 @@
+-- Savings goals.
+--
+-- A goal has a target amount and an optional target date, and is funded by one or more
+-- linked accounts. A link without an `earmark` counts the account's whole balance towards the
+-- goal; with one, only up to that amount of the balance is set aside for it.
+-- `scheduled_transaction_id` is the optional monthly contribution transfer.
+
+CREATE TABLE goals
+(
+    id                       SERIAL PRIMARY KEY                                  NOT NULL,
+    name                     TEXT                                                NOT NULL,
+    target_amount            INTEGER                                             NOT NULL CHECK (target_amount > 0),
+    target_date              TIMESTAMP,
+    created_date             TIMESTAMP                                           NOT NULL DEFAULT now(),
+    scheduled_transaction_id INTEGER REFERENCES scheduled_transactions (id) ON DELETE SET NULL,
+    user_id                  INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL
+);
+
+CREATE TABLE goal_accounts
+(
+    goal_id    INTEGER REFERENCES goals (id) ON DELETE CASCADE    NOT NULL,
+    account_id INTEGER REFERENCES accounts (id) ON DELETE CASCADE NOT NULL,
+    earmark    INTEGER CHECK (earmark >= 0),
+    PRIMARY KEY (goal_id, account_id)
+);
@@ -0,0 +1,5 @@
+DROP TABLE prices;
+DROP TABLE investment_transactions;
+DROP TABLE securities;
+
+DROP TYPE investment_kinds;
@@ -0,0 +1,41 @@
+-- Investment accounts: securities, buy/sell/dividend transactions and prices.
+--
+-- `quantity` may be fractional. `price` is in cents per unit and `value` is the cash amount in
+-- cents: the cost of a buy, the proceeds of a sell or the amount of a dividend. The cash side
+-- of each transaction moves the account balance like a transaction would; the units held are
+-- valued with the latest row in `prices` for the security.
+
+CREATE TYPE investment_kinds AS ENUM ('buy', 'sell', 'dividend');
+
+CREATE TABLE securities
+(
+    id      SERIAL PRIMARY KEY                                  NOT NULL,
+    symbol  TEXT                                                NOT NULL,
+    name    TEXT                                                NOT NULL,
+    user_id INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL,
+    UNIQUE (user_id, symbol)
+);
+
+CREATE TABLE investment_transactions
+(
+    id          SERIAL PRIMARY KEY                                   NOT NULL,
+    account_id  INTEGER REFERENCES accounts (id) ON DELETE CASCADE   NOT NULL,
+    security_id INTEGER REFERENCES securities (id) ON DELETE CASCADE NOT NULL,
+    kind        investment_kinds                                     NOT NULL,
+    quantity    DOUBLE PRECISION                                     NOT NULL CHECK (quantity >= 0),
+    price       INTEGER                                              NOT NULL CHECK (price >= 0),
+    value       INTEGER                                              NOT NULL CHECK (value >= 0),
+    description TEXT                                                 NOT NULL,
+    date        TIMESTAMP                                            NOT NULL,
+    user_id     INTEGER REFERENCES app_users (id) ON DELETE CASCADE  NOT NULL
+);
+
+CREATE INDEX investment_transactions_account_idx ON investment_transactions (account_id, security_id, date);
+
+CREATE TABLE prices
+(
+    security_id INTEGER REFERENCES securities (id) ON DELETE CASCADE NOT NULL,
+    date        TIMESTAMP                                            NOT NULL,
+    price       INTEGER                                              NOT NULL CHECK (price >= 0),
+    PRIMARY KEY (security_id, date)
+);
@@ -0,0 +1,4 @@
+ALTER TABLE scheduled_transactions
+    DROP COLUMN business_day_adjustment;
+
+DROP TYPE business_day_adjustments;
@@ -0,0 +1,16 @@
+-- Weekend and holiday adjustment of scheduled due dates.
+--
+-- A due date that falls on a weekend or on a date of the configured holiday calendar is moved
+-- to the previous business day, the next one, or the next one unless it is in the following
+-- month ("modified following"). Only `next_date` is moved: the following occurrences are still
+-- computed from `created_date` / the rrule, so adjusted dates never drift the schedule.
+
+CREATE TYPE business_day_adjustments AS ENUM (
+    'none',
+    'previous',
+    'next',
+    'modified_following'
+);
+
+ALTER TABLE scheduled_transactions
+    ADD COLUMN business_day_adjustment business_day_adjustments NOT NULL DEFAULT 'none';
@@ -0,0 +1,3 @@
+DROP TABLE scheduled_occurrences;
+
+DROP TYPE occurrence_outcomes;
@@ -0,0 +1,28 @@
+-- History of what happened to each due occurrence of a scheduled transaction.
+--
+-- Every pay, partial pay, skip and snooze records a row: `occurrence` is the schedule's
+-- `current_repeat_count` at the time (NULL for a one-off), `due_date` its `next_date`, `value`
+-- the amount booked (0 for a skip or snooze) and `snoozed_to` the new due date of a snooze.
+-- A partially paid occurrence stays due until its payments reach the scheduled value.
+
+CREATE TYPE occurrence_outcomes AS ENUM (
+    'paid',
+    'partially_paid',
+    'skipped',
+    'snoozed'
+);
+
+CREATE TABLE scheduled_occurrences
+(
+    id                       SERIAL PRIMARY KEY                                                  NOT NULL,
+    scheduled_transaction_id INTEGER REFERENCES scheduled_transactions (id) ON DELETE CASCADE    NOT NULL,
+    occurrence               INTEGER,
+    due_date                 TIMESTAMP                                                           NOT NULL,
+    outcome                  occurrence_outcomes                                                 NOT NULL,
+    value                    INTEGER                                                             NOT NULL DEFAULT 0,
+    snoozed_to               TIMESTAMP,
+    recorded_at              TIMESTAMP                                                           NOT NULL DEFAULT now(),
+    user_id                  INTEGER REFERENCES app_users (id) ON DELETE CASCADE                 NOT NULL
+);
+
+CREATE INDEX scheduled_occurrences_schedule_idx ON scheduled_occurrences (scheduled_transaction_id);
@@ -0,0 +1,19 @@
+DELETE FROM scheduled_transactions WHERE finished;
+
+DROP INDEX scheduled_transactions_one_autopay_per_card;
+CREATE UNIQUE INDEX scheduled_transactions_one_autopay_per_card
+    ON scheduled_transactions (autopay_card_id) WHERE autopay_card_id IS NOT NULL;
+
+ALTER TABLE scheduled_transactions
+    DROP COLUMN finished;
+
+DROP INDEX transfers_scheduled_idx;
+DROP INDEX transactions_scheduled_idx;
+
+ALTER TABLE transfers
+    DROP COLUMN occurrence,
+    DROP COLUMN scheduled_transaction_id;
+
+ALTER TABLE transactions
+    DROP COLUMN occurrence,
+    DROP COLUMN scheduled_transaction_id;
@@ -0,0 +1,26 @@
+-- Link the transactions and transfers a scheduled transaction produces back to it, and keep
+-- schedules once they have finished.
+--
+-- Paying a schedule records its id and the 0-based `occurrence` paid (NULL for a one-off) on the
+-- transactions and transfers it creates. A schedule whose last occurrence has been paid or
+-- skipped is marked `finished` (with no `next_date`) instead of being deleted, so its entries
+-- and occurrence history stay available. A card's finished autopay schedules no longer count
+-- against its one autopay schedule, so the next statement can get a new one.
+
+ALTER TABLE transactions
+    ADD COLUMN scheduled_transaction_id INTEGER REFERENCES scheduled_transactions (id) ON DELETE SET NULL,
+    ADD COLUMN occurrence               INTEGER;
+
+ALTER TABLE transfers
+    ADD COLUMN scheduled_transaction_id INTEGER REFERENCES scheduled_transactions (id) ON DELETE SET NULL,
+    ADD COLUMN occurrence               INTEGER;
+
+CREATE INDEX transactions_scheduled_idx ON transactions (scheduled_transaction_id);
+CREATE INDEX transfers_scheduled_idx ON transfers (scheduled_transaction_id);
+
+ALTER TABLE scheduled_transactions
+    ADD COLUMN finished BOOLEAN NOT NULL DEFAULT false;
+
+DROP INDEX scheduled_transactions_one_autopay_per_card;
+CREATE UNIQUE INDEX scheduled_transactions_one_autopay_per_card
+    ON scheduled_transactions (autopay_card_id) WHERE autopay_card_id IS NOT NULL AND NOT finished;
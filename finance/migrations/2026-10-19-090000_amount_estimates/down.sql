@@ -0,0 +1,4 @@
+ALTER TABLE scheduled_transactions
+    DROP COLUMN amount_estimate;
+
+DROP TYPE amount_estimates;
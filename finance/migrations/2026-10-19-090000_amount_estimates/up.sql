@@ -0,0 +1,16 @@
+-- Estimated amounts for schedules that recur with a varying value (utility bills).
+--
+-- Instead of its fixed `value`, a schedule can show the average, median or last of the amounts
+-- paid for its previous occurrences, or the recent monthly spend in its category. `value` stays
+-- the scheduled amount and is used until there is anything to estimate from.
+
+CREATE TYPE amount_estimates AS ENUM (
+    'fixed',
+    'average',
+    'median',
+    'last',
+    'category_spend'
+);
+
+ALTER TABLE scheduled_transactions
+    ADD COLUMN amount_estimate amount_estimates NOT NULL DEFAULT 'fixed';
@@ -0,0 +1,18 @@
+-- Secret tokens for the iCalendar feed of upcoming scheduled transactions.
+--
+-- A calendar application fetches `/api/calendar/{token}.ics` without a login, so the token in
+-- the URL is the only credential. It grants read access to that feed and nothing else, and is
+-- revoked by deleting its row. Only the SHA-256 hash of a token is stored; the token itself is
+-- shown once, when it is created.
+
+CREATE TABLE calendar_tokens
+(
+    id             SERIAL PRIMARY KEY                                  NOT NULL,
+    name           VARCHAR                                             NOT NULL,
+    token_hash     VARCHAR UNIQUE                                      NOT NULL,
+    created_date   TIMESTAMP                                           NOT NULL DEFAULT now(),
+    last_used_date TIMESTAMP,
+    user_id        INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL
+);
+
+CREATE INDEX calendar_tokens_user_idx ON calendar_tokens (user_id);
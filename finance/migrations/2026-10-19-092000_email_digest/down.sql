@@ -0,0 +1,6 @@
+ALTER TABLE app_users
+    DROP COLUMN email,
+    DROP COLUMN digest_enabled,
+    DROP COLUMN digest_lead_days,
+    DROP COLUMN unsubscribe_token,
+    DROP COLUMN last_digest_date;
@@ -0,0 +1,18 @@
+-- Daily email digest of due scheduled transactions and projected overdrafts.
+--
+-- A user can store an `email` address and opt in to the digest, listing what is overdue or due
+-- within `digest_lead_days`. `unsubscribe_token` is the secret in the digest's unsubscribe link
+-- and `last_digest_date` the day the last digest was handled, so each user gets at most one a
+-- day even across restarts. New users get their token from the application; existing ones get
+-- one here, from two random UUIDs.
+
+ALTER TABLE app_users
+    ADD COLUMN email             VARCHAR,
+    ADD COLUMN digest_enabled    BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN digest_lead_days  INTEGER NOT NULL DEFAULT 3,
+    ADD COLUMN unsubscribe_token VARCHAR UNIQUE,
+    ADD COLUMN last_digest_date  DATE;
+
+UPDATE app_users
+    SET unsubscribe_token = replace(gen_random_uuid()::text || gen_random_uuid()::text, '-', '');
+ALTER TABLE app_users ALTER COLUMN unsubscribe_token SET NOT NULL;
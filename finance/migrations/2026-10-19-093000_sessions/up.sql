@@ -0,0 +1,32 @@
+-- Server-side login sessions with rotating refresh tokens.
+--
+-- Logging in opens a session (one per device) and issues a short-lived access token (a JWT
+-- naming the session) and a refresh token. Every refresh marks the refresh token used and issues
+-- a new one, so each session has a chain of refresh tokens of which only the newest is valid.
+-- Presenting a used one means it was copied, so the whole session is revoked. Revoking a session
+-- also stops its access tokens, which are checked against this table on every request. Only the
+-- SHA-256 hashes of refresh tokens are stored.
+
+CREATE TABLE sessions
+(
+    id             SERIAL PRIMARY KEY                                  NOT NULL,
+    device         VARCHAR,
+    created_date   TIMESTAMP                                           NOT NULL DEFAULT now(),
+    last_seen_date TIMESTAMP                                           NOT NULL DEFAULT now(),
+    expires_date   TIMESTAMP                                           NOT NULL,
+    revoked_date   TIMESTAMP,
+    user_id        INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL
+);
+
+CREATE INDEX sessions_user_idx ON sessions (user_id);
+
+CREATE TABLE refresh_tokens
+(
+    id           SERIAL PRIMARY KEY                                 NOT NULL,
+    token_hash   VARCHAR UNIQUE                                     NOT NULL,
+    created_date TIMESTAMP                                          NOT NULL DEFAULT now(),
+    used_date    TIMESTAMP,
+    session_id   INTEGER REFERENCES sessions (id) ON DELETE CASCADE NOT NULL
+);
+
+CREATE INDEX refresh_tokens_session_idx ON refresh_tokens (session_id);
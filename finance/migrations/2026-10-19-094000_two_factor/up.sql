@@ -0,0 +1,22 @@
+-- Optional TOTP (RFC 6238) two-factor authentication.
+--
+-- Enrolling stores a new TOTP secret, encrypted with AES-256-GCM under `TOTP_ENCRYPTION_KEY`
+-- (the nonce followed by the ciphertext); it is only used once a first code confirms it
+-- (`totp_enabled`). `totp_last_step` is the time step of the last code accepted, so a code
+-- works once. Recovery codes are one-time codes for logging in without the authenticator;
+-- only their SHA-256 hashes are stored.
+
+ALTER TABLE app_users
+    ADD COLUMN totp_secret    BYTEA,
+    ADD COLUMN totp_enabled   BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN totp_last_step BIGINT;
+
+CREATE TABLE recovery_codes
+(
+    id           SERIAL PRIMARY KEY                                  NOT NULL,
+    code_hash    VARCHAR                                             NOT NULL,
+    created_date TIMESTAMP                                           NOT NULL DEFAULT now(),
+    used_date    TIMESTAMP,
+    user_id      INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL,
+    UNIQUE (user_id, code_hash)
+);
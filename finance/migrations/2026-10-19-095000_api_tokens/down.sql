@@ -0,0 +1,3 @@
+DROP TABLE api_tokens;
+
+DROP TYPE api_token_scopes;
@@ -0,0 +1,23 @@
+-- Personal access tokens for scripts and integrations.
+--
+-- A token is sent as `Authorization: Bearer fin_…` in place of a JWT, so a cron job can call the
+-- API without the login password. Its scope limits what it can do: read (`GET`) requests only,
+-- everything but account management, or only the routes that import new entries. Tokens expire
+-- on `expires_date` and are revoked by deleting their row. Only the SHA-256 hash of a token is
+-- stored; the token itself is shown once, when it is created.
+
+CREATE TYPE api_token_scopes AS ENUM ('read_only', 'read_write', 'import_only');
+
+CREATE TABLE api_tokens
+(
+    id             SERIAL PRIMARY KEY                                  NOT NULL,
+    name           VARCHAR                                             NOT NULL,
+    token_hash     VARCHAR UNIQUE                                      NOT NULL,
+    scope          api_token_scopes                                    NOT NULL,
+    created_date   TIMESTAMP                                           NOT NULL DEFAULT now(),
+    expires_date   TIMESTAMP                                           NOT NULL,
+    last_used_date TIMESTAMP,
+    user_id        INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL
+);
+
+CREATE INDEX api_tokens_user_idx ON api_tokens (user_id);
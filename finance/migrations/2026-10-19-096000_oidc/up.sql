@@ -0,0 +1,18 @@
+-- Single sign-on with OpenID Connect.
+--
+-- An identity is an account at an identity provider (`issuer`, the provider's URL, and
+-- `subject`, its stable id for the account there) linked to a user, who can then log in through
+-- the provider. A user can have several; an identity belongs to one user.
+
+CREATE TABLE oidc_identities
+(
+    id              SERIAL PRIMARY KEY                                  NOT NULL,
+    issuer          VARCHAR                                             NOT NULL,
+    subject         VARCHAR                                             NOT NULL,
+    created_date    TIMESTAMP                                           NOT NULL DEFAULT now(),
+    last_login_date TIMESTAMP,
+    user_id         INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL,
+    UNIQUE (issuer, subject)
+);
+
+CREATE INDEX oidc_identities_user_idx ON oidc_identities (user_id);
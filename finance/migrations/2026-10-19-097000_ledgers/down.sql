@@ -0,0 +1,33 @@
+-- Data goes back to the first owner of its ledger; ledgers without an owner lose theirs.
+ALTER TABLE calendar_tokens
+    DROP COLUMN ledger_id;
+
+DO
+$$
+    DECLARE
+        t TEXT;
+    BEGIN
+        FOREACH t IN ARRAY ARRAY ['categories', 'accounts', 'transactions', 'transfers',
+            'scheduled_transactions', 'scheduled_occurrences', 'reconciliations', 'loans', 'goals',
+            'securities', 'investment_transactions']
+            LOOP
+                EXECUTE format('ALTER TABLE %I DROP CONSTRAINT %I', t, t || '_ledger_id_fkey');
+                EXECUTE format(
+                        'DELETE FROM %I WHERE ledger_id NOT IN ' ||
+                        '(SELECT ledger_id FROM ledger_members WHERE role = ''owner'')', t);
+                EXECUTE format(
+                        'UPDATE %I SET ledger_id = (SELECT m.user_id FROM ledger_members m ' ||
+                        'WHERE m.ledger_id = %I.ledger_id AND m.role = ''owner'' ' ||
+                        'ORDER BY m.joined_date, m.user_id LIMIT 1)', t, t);
+                EXECUTE format('ALTER TABLE %I RENAME COLUMN ledger_id TO user_id', t);
+                EXECUTE format('ALTER TABLE %I ADD FOREIGN KEY (user_id) REFERENCES app_users (id) ON DELETE CASCADE', t);
+            END LOOP;
+    END
+$$;
+
+DROP TABLE ledger_audit;
+DROP TABLE ledger_invites;
+DROP TABLE ledger_members;
+DROP TABLE ledgers;
+
+DROP TYPE ledger_roles;
@@ -0,0 +1,97 @@
+-- Shared households: ledgers that several users can belong to.
+--
+-- Everything that was a user's (accounts, categories, transactions, schedules, ...) now belongs
+-- to a ledger instead, and users see the ledgers they are members of: as an `owner` (who also
+-- manages the members and invites), an `editor` or a read-only `viewer`. Every existing user gets
+-- a personal ledger, with the same id as theirs, holding their data.
+--
+-- Invites are single-use links into a ledger with a role; only the SHA-256 hash of an invite
+-- token is stored. The audit log records who made each change in a ledger, to what (`entity`, the
+-- resource the request's route is under, and `entity_id`, its id if the route names one) and the
+-- `fields` the request set.
+
+CREATE TYPE ledger_roles AS ENUM ('owner', 'editor', 'viewer');
+
+CREATE TABLE ledgers
+(
+    id           SERIAL PRIMARY KEY NOT NULL,
+    name         VARCHAR            NOT NULL,
+    created_date TIMESTAMP          NOT NULL DEFAULT now()
+);
+
+CREATE TABLE ledger_members
+(
+    ledger_id   INTEGER REFERENCES ledgers (id) ON DELETE CASCADE   NOT NULL,
+    user_id     INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL,
+    role        ledger_roles                                        NOT NULL,
+    joined_date TIMESTAMP                                           NOT NULL DEFAULT now(),
+    PRIMARY KEY (ledger_id, user_id)
+);
+
+CREATE INDEX ledger_members_user_idx ON ledger_members (user_id);
+
+CREATE TABLE ledger_invites
+(
+    id           SERIAL PRIMARY KEY                                  NOT NULL,
+    token_hash   VARCHAR UNIQUE                                      NOT NULL,
+    role         ledger_roles                                        NOT NULL,
+    created_date TIMESTAMP                                           NOT NULL DEFAULT now(),
+    expires_date TIMESTAMP                                           NOT NULL,
+    ledger_id    INTEGER REFERENCES ledgers (id) ON DELETE CASCADE   NOT NULL,
+    user_id      INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL
+);
+
+CREATE INDEX ledger_invites_ledger_idx ON ledger_invites (ledger_id);
+
+-- `user_id` is kept NULL once the user who made the change is deleted.
+CREATE TABLE ledger_audit
+(
+    id           BIGSERIAL PRIMARY KEY                               NOT NULL,
+    created_date TIMESTAMP                                           NOT NULL DEFAULT now(),
+    method       VARCHAR                                             NOT NULL,
+    path         VARCHAR                                             NOT NULL,
+    entity       VARCHAR                                             NOT NULL,
+    entity_id    INTEGER,
+    fields       VARCHAR[]                                           NOT NULL,
+    ledger_id    INTEGER REFERENCES ledgers (id) ON DELETE CASCADE   NOT NULL,
+    user_id      INTEGER REFERENCES app_users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX ledger_audit_ledger_idx ON ledger_audit (ledger_id, created_date);
+
+INSERT INTO ledgers (id, name, created_date)
+SELECT id, name, now()
+FROM app_users;
+
+SELECT setval(pg_get_serial_sequence('ledgers', 'id'), coalesce(max(id), 0) + 1, false)
+FROM ledgers;
+
+INSERT INTO ledger_members (ledger_id, user_id, role)
+SELECT id, id, 'owner'
+FROM app_users;
+
+DO
+$$
+    DECLARE
+        t TEXT;
+    BEGIN
+        FOREACH t IN ARRAY ARRAY ['categories', 'accounts', 'transactions', 'transfers',
+            'scheduled_transactions', 'scheduled_occurrences', 'reconciliations', 'loans', 'goals',
+            'securities', 'investment_transactions']
+            LOOP
+                EXECUTE format('ALTER TABLE %I RENAME COLUMN user_id TO ledger_id', t);
+                EXECUTE format('ALTER TABLE %I DROP CONSTRAINT %I', t, t || '_user_id_fkey');
+                EXECUTE format('ALTER TABLE %I ADD FOREIGN KEY (ledger_id) REFERENCES ledgers (id) ON DELETE CASCADE', t);
+            END LOOP;
+    END
+$$;
+
+-- A calendar feed shows the schedules of the ledger its token was created in.
+ALTER TABLE calendar_tokens
+    ADD COLUMN ledger_id INTEGER REFERENCES ledgers (id) ON DELETE CASCADE;
+
+UPDATE calendar_tokens
+SET ledger_id = user_id;
+
+ALTER TABLE calendar_tokens
+    ALTER COLUMN ledger_id SET NOT NULL;
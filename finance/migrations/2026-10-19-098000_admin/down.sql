@@ -0,0 +1,7 @@
+DROP TABLE settings;
+
+ALTER TABLE app_users
+    DROP COLUMN created_date,
+    DROP COLUMN is_admin,
+    DROP COLUMN disabled,
+    DROP COLUMN password_change_required;
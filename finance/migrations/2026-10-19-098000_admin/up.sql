@@ -0,0 +1,20 @@
+-- Administrators and user management.
+--
+-- Admins (`is_admin`, granted at startup to the names in `ADMIN_USERS`) can disable a user's
+-- logins (`disabled`), require them to change their password before anything else
+-- (`password_change_required`), and close registration. `settings` holds the instance-wide
+-- settings in its single row.
+
+ALTER TABLE app_users
+    ADD COLUMN created_date             TIMESTAMP NOT NULL DEFAULT now(),
+    ADD COLUMN is_admin                 BOOLEAN   NOT NULL DEFAULT false,
+    ADD COLUMN disabled                 BOOLEAN   NOT NULL DEFAULT false,
+    ADD COLUMN password_change_required BOOLEAN   NOT NULL DEFAULT false;
+
+CREATE TABLE settings
+(
+    id                BOOLEAN PRIMARY KEY NOT NULL DEFAULT true CHECK (id),
+    open_registration BOOLEAN             NOT NULL DEFAULT true
+);
+
+INSERT INTO settings DEFAULT VALUES;
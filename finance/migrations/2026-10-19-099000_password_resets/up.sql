@@ -0,0 +1,17 @@
+-- Self-service password resets.
+--
+-- Asking for a reset emails the user (at `app_users.email`) a link with a random token, which
+-- sets a new password once, until `expires_date`. Only the SHA-256 hash of a token is stored;
+-- a used token keeps its row, with `used_date` set, as a record of the reset.
+
+CREATE TABLE password_resets
+(
+    id           SERIAL PRIMARY KEY                                  NOT NULL,
+    token_hash   VARCHAR UNIQUE                                      NOT NULL,
+    created_date TIMESTAMP                                           NOT NULL DEFAULT now(),
+    expires_date TIMESTAMP                                           NOT NULL,
+    used_date    TIMESTAMP,
+    user_id      INTEGER REFERENCES app_users (id) ON DELETE CASCADE NOT NULL
+);
+
+CREATE INDEX password_resets_user_idx ON password_resets (user_id);
@@ -0,0 +1,199 @@
+//! Loan amortization: fixed monthly payments split into interest and principal.
+//!
+//! Amounts are integer cents. Each period's interest is the outstanding balance times the
+//! monthly rate (the annual rate / 12), rounded to the cent; the rest of the payment repays
+//! principal. The final payment is adjusted to clear the balance exactly.
+
+use chrono::{Datelike, NaiveDate};
+use chronoutil::RelativeDuration;
+
+use crate::models::{AmortizationRow, LoanPayoff};
+use crate::service;
+
+/// Upper bound on simulated payments (100 years), so a plan whose payments never cover the
+/// interest still terminates.
+pub const MAX_PAYMENTS: i32 = 1200;
+
+fn monthly_rate(annual_rate_bps: i32) -> f64 {
+    f64::from(annual_rate_bps) / 10_000.0 / 12.0
+}
+
+/// The fixed monthly payment that repays `principal` over `term_months` payments.
+pub fn monthly_payment(principal: i32, annual_rate_bps: i32, term_months: i32) -> i32 {
+    let rate = monthly_rate(annual_rate_bps);
+    if rate == 0.0 {
+        // Round up so the term is not exceeded; the last payment is smaller.
+        return (principal + term_months - 1) / term_months;
+    }
+
+    let payment = f64::from(principal) * rate / (1.0 - (1.0 + rate).powi(-term_months));
+    payment.round() as i32
+}
+
+/// Interest accrued over one month on `balance`.
+pub fn interest(balance: i32, annual_rate_bps: i32) -> i32 {
+    (f64::from(balance) * monthly_rate(annual_rate_bps)).round() as i32
+}
+
+/// The date of payment `index` (0-based): the first is `first`, later ones fall on
+/// `payment_day` of each following month.
+fn payment_date(first: NaiveDate, payment_day: i32, index: i32) -> NaiveDate {
+    if index == 0 {
+        return first;
+    }
+
+    let month = first.with_day(1).expect("day 1 exists") + RelativeDuration::months(index);
+    service::day_of_month(month.year(), month.month(), payment_day)
+}
+
+/// Pay `balance` down with `payment` a month, plus `extra` every month and `lump_sum` once with
+/// the first payment, starting on `first`. Stops when the balance is cleared or after
+/// `max_payments`, the last of which then pays off whatever is left.
+#[allow(clippy::too_many_arguments)]
+pub fn amortize(
+    balance: i32,
+    annual_rate_bps: i32,
+    payment: i32,
+    extra: i32,
+    lump_sum: i32,
+    first: NaiveDate,
+    payment_day: i32,
+    max_payments: i32,
+) -> Vec<AmortizationRow> {
+    let mut rows = Vec::new();
+    let mut balance = balance;
+
+    for index in 0..max_payments {
+        if balance <= 0 {
+            break;
+        }
+
+        let interest = interest(balance, annual_rate_bps);
+        let mut amount = payment + extra + if index == 0 { lump_sum } else { 0 };
+        if amount >= balance + interest || index == max_payments - 1 {
+            amount = balance + interest;
+        }
+
+        let principal = amount - interest;
+        balance -= principal;
+
+        rows.push(AmortizationRow {
+            number: index + 1,
+            date: payment_date(first, payment_day, index).and_time(Default::default()),
+            payment: amount,
+            principal,
+            interest,
+            balance,
+        });
+    }
+
+    rows
+}
+
+/// Summarise a payment plan produced by [`amortize`].
+pub fn payoff(rows: &[AmortizationRow]) -> LoanPayoff {
+    LoanPayoff {
+        payments: rows.len() as i32,
+        total_interest: rows.iter().map(|row| row.interest).sum(),
+        payoff_date: rows.last().map(|row| row.date),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    /// (payment, principal, interest, balance) of each row.
+    fn table(rows: &[AmortizationRow]) -> Vec<(i32, i32, i32, i32)> {
+        rows.iter()
+            .map(|row| (row.payment, row.principal, row.interest, row.balance))
+            .collect()
+    }
+
+    #[test]
+    fn monthly_payment_and_interest() {
+        // $10,000 at 12% a year over a year: $888.49 a month.
+        assert_eq!(monthly_payment(1_000_000, 1200, 12), 88_849);
+        assert_eq!(interest(1_000_000, 1200), 10_000);
+        assert_eq!(interest(921_151, 1200), 9_212);
+        assert_eq!(interest(1_000_000, 0), 0);
+
+        // Without interest the payment rounds up, so the term is not exceeded.
+        assert_eq!(monthly_payment(1_000, 0, 3), 334);
+    }
+
+    #[test]
+    fn amortize_follows_the_known_table() {
+        let rows = amortize(1_000_000, 1200, 88_849, 0, 0, date("2026-01-31"), 31, 12);
+
+        assert_eq!(
+            table(&rows),
+            vec![
+                (88_849, 78_849, 10_000, 921_151),
+                (88_849, 79_637, 9_212, 841_514),
+                (88_849, 80_434, 8_415, 761_080),
+                (88_849, 81_238, 7_611, 679_842),
+                (88_849, 82_051, 6_798, 597_791),
+                (88_849, 82_871, 5_978, 514_920),
+                (88_849, 83_700, 5_149, 431_220),
+                (88_849, 84_537, 4_312, 346_683),
+                (88_849, 85_382, 3_467, 261_301),
+                (88_849, 86_236, 2_613, 175_065),
+                (88_849, 87_098, 1_751, 87_967),
+                // The last payment clears the rounding remainder.
+                (88_847, 87_967, 880, 0),
+            ]
+        );
+
+        // Later payments fall on the payment day, or the last day of shorter months.
+        assert_eq!(rows[0].date.date(), date("2026-01-31"));
+        assert_eq!(rows[1].date.date(), date("2026-02-28"));
+        assert_eq!(rows[2].date.date(), date("2026-03-31"));
+        assert_eq!(rows[11].date.date(), date("2026-12-31"));
+
+        let payoff = payoff(&rows);
+        assert_eq!(payoff.payments, 12);
+        assert_eq!(payoff.total_interest, 66_186);
+        assert_eq!(payoff.payoff_date, Some(rows[11].date));
+    }
+
+    #[test]
+    fn extra_payments_shorten_the_plan() {
+        // $200 extra a month and a $1,000 lump sum with the first payment.
+        let rows = amortize(1_000_000, 1200, 88_849, 20_000, 100_000, date("2026-01-31"), 31, 12);
+
+        assert_eq!(
+            table(&rows),
+            vec![
+                (208_849, 198_849, 10_000, 801_151),
+                (108_849, 100_837, 8_012, 700_314),
+                (108_849, 101_846, 7_003, 598_468),
+                (108_849, 102_864, 5_985, 495_604),
+                (108_849, 103_893, 4_956, 391_711),
+                (108_849, 104_932, 3_917, 286_779),
+                (108_849, 105_981, 2_868, 180_798),
+                (108_849, 107_041, 1_808, 73_757),
+                (74_495, 73_757, 738, 0),
+            ]
+        );
+        assert_eq!(payoff(&rows).total_interest, 45_287);
+    }
+
+    #[test]
+    fn amortize_without_interest_or_past_the_last_payment() {
+        let rows = amortize(1_000, 0, 334, 0, 0, date("2026-01-15"), 15, 3);
+        assert_eq!(table(&rows), vec![(334, 334, 0, 666), (334, 334, 0, 332), (332, 332, 0, 0)]);
+
+        // A payment that does not cover the interest never repays the balance, so the last
+        // allowed payment pays off whatever is left.
+        let rows = amortize(1_000_000, 1200, 5_000, 0, 0, date("2026-01-15"), 15, 3);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].balance, 1_005_000);
+        assert_eq!(rows[2].balance, 0);
+        assert_eq!(rows[2].payment, 1_010_050 + 10_101);
+    }
+}
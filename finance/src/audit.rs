@@ -0,0 +1,143 @@
+//! The audit log of shared ledgers: who changed what.
+//!
+//! Every successful request that changes the active ledger (any method but `GET` and `HEAD`, on
+//! the routes that are not account or ledger management, see [`auth::route_access`]) is recorded
+//! with its method, path and user, once its handler authenticated it as an [`AuthUser`], along
+//! with what it changed (see [`entry`]). Routes that do not (logins, registration, password
+//! resets) are not. Membership and invite changes are recorded by their handlers, in the ledger
+//! they change.
+
+use std::sync::{Arc, Mutex};
+
+use axum::body::{self, Body};
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde_json::Value;
+
+use crate::auth::{self, AuthUser, RouteAccess};
+use crate::db;
+use crate::models::NewAuditEntry;
+use crate::state::AppState;
+
+/// Largest request body read to record its fields: the `Json` extractor's limit, so a larger one
+/// is turned away (413) here as it would be by its handler.
+const MAX_BODY: usize = 2 * 1024 * 1024;
+
+/// Request extension where the [`AuthUser`] extractor leaves the user of a request that may
+/// change a ledger, for [`record_changes`] to find once the handler is done.
+#[derive(Clone, Default)]
+pub struct Actor(Arc<Mutex<Option<AuthUser>>>);
+
+impl Actor {
+    pub fn set(&self, auth: &AuthUser) {
+        *self.0.lock().unwrap() = Some(auth.clone());
+    }
+
+    fn take(&self) -> Option<AuthUser> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+/// Middleware recording the changes made in ledgers, by the user the handler authenticated.
+pub async fn record_changes(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|route| route.as_str().to_string())
+        .filter(|route| {
+            !matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+                && auth::route_access(request.method(), route) != RouteAccess::Session
+        });
+    let Some(route) = route else {
+        return next.run(request).await;
+    };
+
+    let actor = Actor::default();
+    request.extensions_mut().insert(actor.clone());
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+
+    // Keep a copy of the body, for the fields it sets.
+    let (parts, request_body) = request.into_parts();
+    let Ok(bytes) = body::to_bytes(request_body, MAX_BODY).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+    let fields = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    if let Some(auth) = actor.take().filter(|_| response.status().is_success()) {
+        let new = entry(auth.ledger_id, auth.user_id, &method, &route, &path, &fields);
+        if let Err(err) = db::ledger_audit::insert(&state.pool, &new).await {
+            tracing::error!("recording {method} {path} in ledger {} failed: {err}", auth.ledger_id);
+        }
+    }
+
+    response
+}
+
+/// The audit entry of a change in the ledger by the user, with the request `method path`,
+/// matched by `route`, and its JSON `body`. The entity is the resource the route is under, with
+/// the id that follows it if the route names one: `/api/accounts/{id}/card-settings` changes the
+/// account, `POST /api/accounts` an account not known yet. The fields are the names of the body's
+/// top-level keys, sorted.
+pub fn entry(
+    ledger_id: i32,
+    user_id: i32,
+    method: &Method,
+    route: &str,
+    path: &str,
+    body: &Value,
+) -> NewAuditEntry {
+    // Both start with "", "api".
+    let mut segments = route.split('/').zip(path.split('/')).skip(2);
+    let entity = segments.next().map(|(_, entity)| entity.to_string()).unwrap_or_default();
+    let entity_id = segments
+        .next()
+        .filter(|(segment, _)| segment.starts_with('{'))
+        .and_then(|(_, id)| id.parse().ok());
+    let mut fields: Vec<String> =
+        body.as_object().map(|body| body.keys().cloned().collect()).unwrap_or_default();
+    fields.sort();
+
+    NewAuditEntry {
+        ledger_id,
+        user_id,
+        method: method.to_string(),
+        path: path.to_string(),
+        entity,
+        entity_id,
+        fields,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn entries_name_the_resource_of_the_route_and_the_fields_of_the_body() {
+        let route = "/api/scheduled-transactions/{id}/pay";
+        let body = json!({"value": 100, "description": "Rent", "date": null});
+        let new = entry(1, 2, &Method::POST, route, "/api/scheduled-transactions/7/pay", &body);
+        assert_eq!((new.entity.as_str(), new.entity_id), ("scheduled-transactions", Some(7)));
+        assert_eq!(new.fields, ["date", "description", "value"]);
+        assert_eq!(new.path, "/api/scheduled-transactions/7/pay");
+
+        // A new entity has no id yet, and a body that is not a JSON object sets no fields.
+        let new = entry(1, 2, &Method::POST, "/api/accounts", "/api/accounts", &Value::Null);
+        assert_eq!((new.entity.as_str(), new.entity_id), ("accounts", None));
+        assert!(new.fields.is_empty());
+        let route = "/api/transactions/account/{account_id}";
+        let new = entry(1, 2, &Method::POST, route, "/api/transactions/account/3", &json!([1]));
+        assert_eq!((new.entity.as_str(), new.entity_id), ("transactions", None));
+        assert!(new.fields.is_empty());
+    }
+}
@@ -1,19 +1,24 @@
-use axum::extract::FromRequestParts;
+use axum::extract::{FromRequestParts, MatchedPath};
 use axum::http::header::AUTHORIZATION;
 use axum::http::request::Parts;
+use axum::http::Method;
 use chrono::prelude::*;
 use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation};
 use serde::{Deserialize, Serialize};
 
+use crate::audit::Actor;
+use crate::db;
 use crate::error::AppError;
+use crate::models::{ApiTokenScopes, LedgerRoles};
 use crate::state::AppState;
 
-/// JWT claims. The shape (and the numeric-date serialization of `iat`/`exp`) is kept
-/// byte-for-byte compatible with the original Rocket backend so previously issued
-/// tokens remain valid under the same `JWT_SECRET`.
+/// JWT claims. The shape (and the numeric-date serialization of `iat`/`exp`) follows the
+/// original Rocket backend, plus `sid`, the session the token was issued for. Tokens without
+/// one (from before sessions) are rejected, so they cannot outlive a logout.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub user_id: i32,
+    pub sid: i32,
     #[serde(with = "jwt_numeric_date")]
     pub iat: DateTime<Utc>,
     #[serde(with = "jwt_numeric_date")]
@@ -21,16 +26,32 @@ pub struct Claims {
 }
 
 impl Claims {
-    fn new(user_id: i32, iat: DateTime<Utc>, exp: DateTime<Utc>) -> Self {
+    fn new(user_id: i32, sid: i32, iat: DateTime<Utc>, exp: DateTime<Utc>) -> Self {
         // Truncate to whole seconds (drop sub-second precision), matching the original.
         Self {
             user_id,
+            sid,
             iat: iat.with_nanosecond(0).unwrap_or(iat),
             exp: exp.with_nanosecond(0).unwrap_or(exp),
         }
     }
 }
 
+/// What a login challenge token is for; access tokens have no `stage`, so they never pass as one.
+const TOTP_STAGE: &str = "totp";
+
+/// Claims of a login challenge: the password was right, and a TOTP or recovery code is still
+/// needed. They have no `sid`, so a challenge token is never accepted as an access token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChallengeClaims {
+    pub user_id: i32,
+    pub stage: String,
+    #[serde(with = "jwt_numeric_date")]
+    pub iat: DateTime<Utc>,
+    #[serde(with = "jwt_numeric_date")]
+    pub exp: DateTime<Utc>,
+}
+
 mod jwt_numeric_date {
     use chrono::{DateTime, TimeZone, Utc};
     use serde::{self, Deserialize, Deserializer, Serializer};
@@ -52,10 +73,16 @@ mod jwt_numeric_date {
     }
 }
 
-pub fn create_jwt(user_id: i32, secret: &str, validity_days: i64) -> Result<String, AppError> {
+/// An access token for the session, valid for `validity_minutes`.
+pub fn create_jwt(
+    user_id: i32,
+    session_id: i32,
+    secret: &str,
+    validity_minutes: i64,
+) -> Result<String, AppError> {
     let iat = Utc::now();
-    let exp = iat + chrono::Duration::days(validity_days);
-    let claims = Claims::new(user_id, iat, exp);
+    let exp = iat + chrono::Duration::minutes(validity_minutes);
+    let claims = Claims::new(user_id, session_id, iat, exp);
 
     jsonwebtoken::encode(
         &Header::default(),
@@ -77,10 +104,137 @@ pub fn validate_jwt(token: &str, secret: &str) -> Result<TokenData<Claims>, AppE
     .map_err(|_| AppError::Unauthorized)
 }
 
+/// A login challenge token for the user, valid for `validity_minutes`.
+pub fn create_challenge(
+    user_id: i32,
+    secret: &str,
+    validity_minutes: i64,
+) -> Result<String, AppError> {
+    let iat = Utc::now();
+    let exp = iat + chrono::Duration::minutes(validity_minutes);
+    let claims = ChallengeClaims {
+        user_id,
+        stage: TOTP_STAGE.to_string(),
+        iat: iat.with_nanosecond(0).unwrap_or(iat),
+        exp: exp.with_nanosecond(0).unwrap_or(exp),
+    };
+
+    jsonwebtoken::encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .map_err(|e| AppError::Internal(format!("failed to encode JWT: {e}")))
+}
+
+/// The user a valid login challenge token was issued to.
+pub fn validate_challenge(token: &str, secret: &str) -> Result<i32, AppError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.validate_aud = false;
+
+    let claims = jsonwebtoken::decode::<ChallengeClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_ref()),
+        &validation,
+    )
+    .map_err(|_| AppError::Unauthorized)?
+    .claims;
+
+    if claims.stage != TOTP_STAGE {
+        return Err(AppError::Unauthorized);
+    }
+    Ok(claims.user_id)
+}
+
+/// Header that picks the ledger a request works in, by id; the user's default ledger without it.
+pub const LEDGER_HEADER: &str = "x-ledger-id";
+
 /// Authenticated user, extracted from the `Authorization: Bearer <jwt>` header.
-/// Mirrors the original Rocket `Authentication` request guard.
+/// Mirrors the original Rocket `Authentication` request guard, and also rejects the token once
+/// its session is revoked or expired, or its user disabled. While the user must change their
+/// password, a session only works on [`PASSWORD_CHANGE_ROUTES`]. A personal access token
+/// (`Bearer fin_…`) is accepted too, on the routes its scope allows (see [`route_access`]).
+///
+/// The request works in the ledger of [`LEDGER_HEADER`] (`Forbidden` if the user is not a
+/// member) or the user's default one, and a viewer of it only gets the read routes.
+#[derive(Debug, Clone)]
 pub struct AuthUser {
     pub user_id: i32,
+    /// The session of a JWT; `None` for a personal access token.
+    pub session_id: Option<i32>,
+    /// The active ledger.
+    pub ledger_id: i32,
+}
+
+impl AuthUser {
+    /// The session the request was made with; `Forbidden` for a personal access token.
+    pub fn session(&self) -> Result<i32, AppError> {
+        self.session_id.ok_or(AppError::Forbidden)
+    }
+}
+
+/// What a route needs from a personal access token, and from the user's role in the ledger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteAccess {
+    /// Account, ledger and credential management, which only a login session can do, whatever
+    /// the role in the active ledger.
+    Session,
+    Read,
+    Write,
+    /// Adding new entries, which an import-only token can do as well.
+    Import,
+}
+
+/// Route prefixes (as declared in the routers) that personal access tokens cannot use.
+const SESSION_ROUTES: &[&str] = &[
+    "/api/token",
+    "/api/logout",
+    "/api/sessions",
+    "/api/users/me",
+    "/api/api-tokens",
+    "/api/calendar-tokens",
+    "/api/ledgers",
+    "/api/invites",
+    "/api/admin",
+];
+
+/// The routes a session works on while its user must change their password.
+const PASSWORD_CHANGE_ROUTES: &[&str] = &[
+    "/api/token",
+    "/api/logout",
+    "/api/users/me",
+    "/api/users/me/password",
+];
+
+/// The `POST` routes that import new entries.
+const IMPORT_ROUTES: &[&str] = &[
+    "/api/transactions/account/{account_id}",
+    "/api/transfers/from/{origin_account}/to/{destination_account}",
+    "/api/investments/account/{account_id}",
+    "/api/securities/{id}/prices",
+    "/api/prices/import",
+];
+
+pub fn route_access(method: &Method, route: &str) -> RouteAccess {
+    if SESSION_ROUTES.iter().any(|prefix| route.starts_with(prefix)) {
+        RouteAccess::Session
+    } else if method == Method::POST && IMPORT_ROUTES.contains(&route) {
+        RouteAccess::Import
+    } else if method == Method::GET || method == Method::HEAD {
+        RouteAccess::Read
+    } else {
+        RouteAccess::Write
+    }
+}
+
+impl ApiTokenScopes {
+    fn allows(self, access: RouteAccess) -> bool {
+        match self {
+            ApiTokenScopes::ReadOnly => access == RouteAccess::Read,
+            ApiTokenScopes::ReadWrite => access != RouteAccess::Session,
+            ApiTokenScopes::ImportOnly => access == RouteAccess::Import,
+        }
+    }
 }
 
 impl FromRequestParts<AppState> for AuthUser {
@@ -90,6 +244,13 @@ impl FromRequestParts<AppState> for AuthUser {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
+        let route = parts
+            .extensions
+            .get::<MatchedPath>()
+            .map(MatchedPath::as_str)
+            .ok_or(AppError::Forbidden)?;
+        let access = route_access(&parts.method, route);
+
         let header = parts
             .headers
             .get(AUTHORIZATION)
@@ -101,10 +262,90 @@ impl FromRequestParts<AppState> for AuthUser {
             .strip_prefix("Bearer ")
             .ok_or(AppError::Unauthorized)?;
 
-        let token_data = validate_jwt(token, &state.config.jwt_secret)?;
+        let (user_id, session_id) = if token.starts_with(db::api_tokens::TOKEN_PREFIX) {
+            let api_token = db::api_tokens::use_token(&state.pool, token)
+                .await?
+                .ok_or(AppError::Unauthorized)?;
+            if !api_token.scope.allows(access) {
+                return Err(AppError::Forbidden);
+            }
+            (api_token.user_id, None)
+        } else {
+            let claims = validate_jwt(token, &state.config.jwt_secret)?.claims;
+            let password_change_required = db::sessions::touch(&state.pool, claims.sid, claims.user_id)
+                .await?
+                .ok_or(AppError::Unauthorized)?;
+            if password_change_required && !PASSWORD_CHANGE_ROUTES.contains(&route) {
+                return Err(AppError::Forbidden);
+            }
+            (claims.user_id, Some(claims.sid))
+        };
+
+        let ledger_id = match parts.headers.get(LEDGER_HEADER) {
+            Some(value) => value
+                .to_str()
+                .ok()
+                .and_then(|value| value.trim().parse().ok())
+                .ok_or(AppError::BadRequest)?,
+            None => db::ledgers::get_default(&state.pool, user_id).await?,
+        };
+        let role = db::ledgers::get_role(&state.pool, ledger_id, user_id)
+            .await?
+            .ok_or(AppError::Forbidden)?;
+        if role == LedgerRoles::Viewer && matches!(access, RouteAccess::Write | RouteAccess::Import)
+        {
+            return Err(AppError::Forbidden);
+        }
+
+        let auth = AuthUser {
+            user_id,
+            session_id,
+            ledger_id,
+        };
+        if let Some(actor) = parts.extensions.get::<Actor>() {
+            actor.set(&auth);
+        }
+        Ok(auth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_need_a_session_to_read_to_write_or_to_import() {
+        let access = |method: Method, route: &str| route_access(&method, route);
+
+        assert_eq!(access(Method::GET, "/api/users/me"), RouteAccess::Session);
+        assert_eq!(access(Method::PUT, "/api/users/me/password"), RouteAccess::Session);
+        assert_eq!(access(Method::GET, "/api/ledgers/{id}/members"), RouteAccess::Session);
+        assert_eq!(access(Method::POST, "/api/api-tokens"), RouteAccess::Session);
+        assert_eq!(access(Method::GET, "/api/admin/users"), RouteAccess::Session);
+
+        assert_eq!(access(Method::GET, "/api/accounts"), RouteAccess::Read);
+        assert_eq!(access(Method::HEAD, "/api/accounts"), RouteAccess::Read);
+        assert_eq!(access(Method::POST, "/api/accounts"), RouteAccess::Write);
+        assert_eq!(access(Method::DELETE, "/api/accounts/{id}"), RouteAccess::Write);
+
+        let import = "/api/transactions/account/{account_id}";
+        assert_eq!(access(Method::POST, import), RouteAccess::Import);
+        assert_eq!(access(Method::GET, import), RouteAccess::Read);
+        assert_eq!(access(Method::POST, "/api/prices/import"), RouteAccess::Import);
+        assert_eq!(access(Method::PATCH, "/api/transactions/{id}"), RouteAccess::Write);
+    }
+
+    #[test]
+    fn token_scopes_allow_their_routes_and_never_session_ones() {
+        use ApiTokenScopes::*;
+        use RouteAccess::*;
 
-        Ok(AuthUser {
-            user_id: token_data.claims.user_id,
-        })
+        let allowed = |scope: ApiTokenScopes| {
+            let all = [Session, Read, Write, Import];
+            all.into_iter().filter(|&access| scope.allows(access)).collect::<Vec<_>>()
+        };
+        assert_eq!(allowed(ReadOnly), [Read]);
+        assert_eq!(allowed(ReadWrite), [Read, Write, Import]);
+        assert_eq!(allowed(ImportOnly), [Import]);
     }
 }
@@ -0,0 +1,39 @@
+//! The job that keeps credit-card autopay schedules up to date.
+//!
+//! A change to a card's movements or settings syncs its autopay schedule right away (see
+//! [`service::sync_autopay`]), but a statement also closes by itself at the end of its closing
+//! day. So a background task wakes up every [`CHECK_INTERVAL`] and syncs every card with an
+//! autopay account, in every ledger.
+
+use std::time::Duration;
+
+use chrono::Local;
+
+use crate::db;
+use crate::error::AppError;
+use crate::service;
+use crate::state::AppState;
+
+/// Time between two syncs of all autopay schedules.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Start the autopay job.
+pub fn start(state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = sync_all(&state).await {
+                tracing::warn!("syncing the autopay schedules failed: {err}");
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+async fn sync_all(state: &AppState) -> Result<(), AppError> {
+    let today = Local::now().date_naive();
+    for card in db::accounts::get_all_with_autopay(&state.pool).await? {
+        service::sync_card_autopay(&state.pool, &card, today).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+//! iCalendar (RFC 5545) rendering of the scheduled-transaction feed.
+//!
+//! Every upcoming occurrence becomes an all-day `VEVENT` on its due date. The `UID` is built from
+//! the schedule and occurrence, so a calendar that refreshes the feed moves a snoozed occurrence
+//! instead of adding a second event for it.
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// Longest content line, in octets, before it is folded onto a continuation line.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// One bill (or income, or transfer) due on `date`.
+#[derive(Debug, Clone)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub date: NaiveDate,
+    pub summary: String,
+    pub description: Option<String>,
+}
+
+/// The feed as an iCalendar document, with `stamp` (UTC) as every event's `DTSTAMP`.
+pub fn render(name: &str, events: &[CalendarEvent], stamp: NaiveDateTime) -> String {
+    let stamp = stamp.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut ics = String::new();
+
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//finance//scheduled transactions//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, "METHOD:PUBLISH");
+    push_line(&mut ics, &format!("X-WR-CALNAME:{}", escape(name)));
+
+    for event in events {
+        push_line(&mut ics, "BEGIN:VEVENT");
+        push_line(&mut ics, &format!("UID:{}", escape(&event.uid)));
+        push_line(&mut ics, &format!("DTSTAMP:{stamp}"));
+        push_line(&mut ics, &format!("DTSTART;VALUE=DATE:{}", event.date.format("%Y%m%d")));
+        push_line(&mut ics, &format!("SUMMARY:{}", escape(&event.summary)));
+        if let Some(description) = &event.description {
+            push_line(&mut ics, &format!("DESCRIPTION:{}", escape(description)));
+        }
+        push_line(&mut ics, "TRANSP:TRANSPARENT");
+        push_line(&mut ics, "END:VEVENT");
+    }
+
+    push_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// An amount in cents as a decimal (`123456` is `1234.56`).
+pub fn format_amount(cents: i32) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    format!("{sign}{}.{:02}", cents / 100, cents % 100)
+}
+
+/// Escape a text value: backslashes, semicolons, commas and newlines.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Append a content line terminated by CRLF, folded so no line is longer than
+/// [`MAX_LINE_OCTETS`] (continuation lines start with a space). Lines are only split between
+/// characters, never inside a UTF-8 sequence.
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            ics.push_str("\r\n ");
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_lines_fold_at_75_octets_between_characters() {
+        let mut ics = String::new();
+        push_line(&mut ics, &"a".repeat(75));
+        assert_eq!(ics, format!("{}\r\n", "a".repeat(75)));
+
+        let mut ics = String::new();
+        push_line(&mut ics, &"a".repeat(160));
+        let lines: Vec<_> = ics.trim_end_matches("\r\n").split("\r\n").collect();
+        let continued = |count| format!(" {}", "a".repeat(count));
+        assert_eq!(lines, ["a".repeat(75), continued(74), continued(11)]);
+
+        // A two-octet character that would straddle the limit moves to the next line.
+        let mut ics = String::new();
+        push_line(&mut ics, &format!("{}é", "a".repeat(74)));
+        assert_eq!(ics, format!("{}\r\n é\r\n", "a".repeat(74)));
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= MAX_LINE_OCTETS);
+        }
+    }
+
+    #[test]
+    fn render_escapes_text_and_dates_events() {
+        let events = [CalendarEvent {
+            uid: "scheduled-7-3@finance".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 3, 31).unwrap(),
+            summary: "Rent; flat, 2nd floor: 1200.00 (Checking)".to_string(),
+            description: Some("Category: Housing\nPaid by transfer".to_string()),
+        }];
+        let stamp = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(8, 30, 0).unwrap();
+
+        let ics = render("Bills", &events, stamp);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nX-WR-CALNAME:Bills\r\n"));
+        assert!(ics.contains("\r\nUID:scheduled-7-3@finance\r\n"));
+        assert!(ics.contains("\r\nDTSTAMP:20260301T083000Z\r\n"));
+        assert!(ics.contains("\r\nDTSTART;VALUE=DATE:20260331\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:Rent\\; flat\\, 2nd floor: 1200.00 (Checking)\r\n"));
+        assert!(ics.contains("\r\nDESCRIPTION:Category: Housing\\nPaid by transfer\r\n"));
+    }
+
+    #[test]
+    fn amounts_are_formatted_as_decimals() {
+        assert_eq!(format_amount(123_456), "1234.56");
+        assert_eq!(format_amount(5), "0.05");
+        assert_eq!(format_amount(-1_050), "-10.50");
+        assert_eq!(format_amount(i32::MIN), "-21474836.48");
+    }
+}
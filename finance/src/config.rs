@@ -1,32 +1,220 @@
 use std::env;
+use std::path::PathBuf;
 use std::str::FromStr;
 
+use crate::password;
+
 /// Application configuration, loaded from environment variables (and a `.env` file if present).
 #[derive(Debug, Clone)]
 pub struct Config {
     pub database_url: String,
     pub jwt_secret: String,
-    pub jwt_validity_days: i64,
-    pub bf_rounds: i32,
+    /// Lifetime of an access token (JWT), in minutes.
+    pub access_token_minutes: i64,
+    /// Days a session stays open without being used; every token refresh extends it.
+    pub refresh_token_days: i32,
+    /// Argon2id cost of new password hashes: memory in KiB, passes and lanes. Hashes made with
+    /// other parameters (or bcrypt) are rehashed on the next login.
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    /// Fewest characters a new password may have.
+    pub password_min_length: usize,
     pub bind_addr: String,
     pub port: u16,
     pub db_pool_size: u32,
+    /// iCalendar or CSV file of bank holidays that scheduled due dates are moved off.
+    pub holidays_file: Option<PathBuf>,
+    /// AES-256 key (64 hex digits) that TOTP secrets are encrypted with; without it two-factor
+    /// authentication cannot be enrolled.
+    pub totp_encryption_key: Option<[u8; 32]>,
+    /// Issuer shown next to the account in authenticator apps.
+    pub totp_issuer: String,
+    /// Failed logins allowed per user name, and per client IP, before each further failure
+    /// locks it out for `login_lockout_seconds`, doubling up to `login_lockout_max_seconds`.
+    /// Registrations are limited per IP the same way, counted apart.
+    pub login_max_attempts: u32,
+    pub login_ip_max_attempts: u32,
+    pub login_lockout_seconds: u64,
+    pub login_lockout_max_seconds: u64,
+    /// Requests a minute allowed per session or personal access token (or per IP without a valid
+    /// one).
+    pub requests_per_minute: u32,
+    /// Take the client IP from `X-Forwarded-For`; only behind a reverse proxy that sets it.
+    pub trust_forwarded_for: bool,
+    /// OpenID Connect providers (issuer URLs) users may log in through; none turns single sign-on
+    /// off. The client is registered at each of them as `oidc_client_id` (with
+    /// `oidc_client_secret` unless it is a public client) and `oidc_redirect_url`, the page of
+    /// the frontend the providers redirect back to.
+    pub oidc_issuers: Vec<String>,
+    pub oidc_client_id: String,
+    pub oidc_client_secret: Option<String>,
+    pub oidc_redirect_url: String,
+    /// Space-separated scopes requested; `openid` is required.
+    pub oidc_scopes: String,
+    /// Create a user for an identity that is not linked to one, instead of refusing the login.
+    pub oidc_auto_provision: bool,
+    /// Names of the users made admins at startup (see [`crate::handlers::admin`]).
+    pub admin_users: Vec<String>,
+    /// SMTP relay for outgoing email; without it no email is sent.
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub smtp_tls: SmtpTls,
+    /// Sender of outgoing email, e.g. `Finance <finance@example.com>`.
+    pub mail_from: String,
+    /// Base URL the backend is reached at, for links in emails (the unsubscribe link).
+    pub public_url: String,
+    /// Local hour of the day (0-23) from which the daily digests are sent.
+    pub digest_hour: u32,
+    /// Page of the frontend that password reset emails link to, with the token as `?token=`.
+    pub password_reset_url: String,
+    /// Minutes a password reset link works for.
+    pub password_reset_minutes: i64,
+}
+
+/// How the connection to the SMTP relay is secured: not at all (a local relay or test sink),
+/// upgraded with STARTTLS, or TLS from the start (SMTPS).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    None,
+    StartTls,
+    Tls,
+}
+
+impl FromStr for SmtpTls {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Ok(SmtpTls::None),
+            "starttls" => Ok(SmtpTls::StartTls),
+            "tls" => Ok(SmtpTls::Tls),
+            _ => Err(()),
+        }
+    }
 }
 
 impl Config {
     pub fn from_env() -> anyhow::Result<Self> {
-        Ok(Self {
+        let config = Self {
             database_url: env::var("DATABASE_URL")
                 .map_err(|_| anyhow::anyhow!("DATABASE_URL must be set"))?,
             jwt_secret: env::var("JWT_SECRET")
                 .map_err(|_| anyhow::anyhow!("JWT_SECRET must be set"))?,
-            jwt_validity_days: parse_env("JWT_VALIDITY_DAYS", 30)?,
-            bf_rounds: parse_env("BF_ROUNDS", 10)?,
+            access_token_minutes: parse_env("ACCESS_TOKEN_MINUTES", 15)?,
+            refresh_token_days: parse_env("REFRESH_TOKEN_DAYS", 30)?,
+            argon2_memory_kib: parse_env("ARGON2_MEMORY_KIB", 19456)?,
+            argon2_iterations: parse_env("ARGON2_ITERATIONS", 2)?,
+            argon2_parallelism: parse_env("ARGON2_PARALLELISM", 1)?,
+            password_min_length: parse_env("PASSWORD_MIN_LENGTH", 8)?,
             bind_addr: env::var("BIND_ADDR").unwrap_or_else(|_| "127.0.0.1".to_string()),
             port: parse_env("PORT", 8000)?,
             db_pool_size: parse_env("DB_POOL_SIZE", 20)?,
-        })
+            holidays_file: optional_env("HOLIDAYS_FILE").map(PathBuf::from),
+            totp_encryption_key: optional_env("TOTP_ENCRYPTION_KEY")
+                .map(|key| parse_key(&key))
+                .transpose()?,
+            totp_issuer: env::var("TOTP_ISSUER").unwrap_or_else(|_| "Finance".to_string()),
+            login_max_attempts: parse_env("LOGIN_MAX_ATTEMPTS", 5)?,
+            login_ip_max_attempts: parse_env("LOGIN_IP_MAX_ATTEMPTS", 20)?,
+            login_lockout_seconds: parse_env("LOGIN_LOCKOUT_SECONDS", 30)?,
+            login_lockout_max_seconds: parse_env("LOGIN_LOCKOUT_MAX_SECONDS", 3600)?,
+            requests_per_minute: parse_env("REQUESTS_PER_MINUTE", 600)?,
+            trust_forwarded_for: parse_env("TRUST_FORWARDED_FOR", false)?,
+            oidc_issuers: optional_env("OIDC_ISSUERS")
+                .map(|issuers| {
+                    issuers
+                        .split(',')
+                        .map(|issuer| issuer.trim().trim_end_matches('/').to_string())
+                        .filter(|issuer| !issuer.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            oidc_client_id: env::var("OIDC_CLIENT_ID").unwrap_or_default(),
+            oidc_client_secret: optional_env("OIDC_CLIENT_SECRET"),
+            oidc_redirect_url: env::var("OIDC_REDIRECT_URL")
+                .unwrap_or_else(|_| "http://localhost:3000/oidc/callback".to_string()),
+            oidc_scopes: env::var("OIDC_SCOPES")
+                .unwrap_or_else(|_| "openid profile email".to_string()),
+            oidc_auto_provision: parse_env("OIDC_AUTO_PROVISION", false)?,
+            admin_users: optional_env("ADMIN_USERS")
+                .map(|names| {
+                    names
+                        .split(',')
+                        .map(|name| name.trim().to_string())
+                        .filter(|name| !name.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            smtp_host: optional_env("SMTP_HOST"),
+            smtp_port: parse_env("SMTP_PORT", 587)?,
+            smtp_username: optional_env("SMTP_USERNAME"),
+            smtp_password: optional_env("SMTP_PASSWORD"),
+            smtp_tls: parse_env("SMTP_TLS", SmtpTls::StartTls)?,
+            mail_from: env::var("MAIL_FROM")
+                .unwrap_or_else(|_| "Finance <finance@localhost>".to_string()),
+            public_url: env::var("PUBLIC_URL")
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_else(|_| "http://localhost:8000".to_string()),
+            digest_hour: parse_env("DIGEST_HOUR", 7)?,
+            password_reset_url: env::var("PASSWORD_RESET_URL")
+                .unwrap_or_else(|_| "http://localhost:3000/password-reset".to_string()),
+            password_reset_minutes: parse_env("PASSWORD_RESET_MINUTES", 30)?,
+        };
+
+        if config.access_token_minutes < 1 || config.refresh_token_days < 1 {
+            anyhow::bail!("ACCESS_TOKEN_MINUTES and REFRESH_TOKEN_DAYS must be at least 1");
+        }
+        if let Err(e) = password::params(&config) {
+            anyhow::bail!("invalid ARGON2_MEMORY_KIB, ARGON2_ITERATIONS or ARGON2_PARALLELISM: {e}");
+        }
+        if config.login_lockout_seconds < 1
+            || config.login_lockout_max_seconds < config.login_lockout_seconds
+        {
+            anyhow::bail!(
+                "LOGIN_LOCKOUT_SECONDS must be at least 1, and LOGIN_LOCKOUT_MAX_SECONDS at least that"
+            );
+        }
+        if config.requests_per_minute < 1 {
+            anyhow::bail!("REQUESTS_PER_MINUTE must be at least 1");
+        }
+        if !config.oidc_issuers.is_empty() {
+            if config.oidc_client_id.trim().is_empty() {
+                anyhow::bail!("OIDC_CLIENT_ID must be set with OIDC_ISSUERS");
+            }
+            if !config.oidc_scopes.split_whitespace().any(|scope| scope == "openid") {
+                anyhow::bail!("OIDC_SCOPES must include openid");
+            }
+        }
+        if config.digest_hour > 23 {
+            anyhow::bail!("DIGEST_HOUR must be an hour from 0 to 23");
+        }
+        if !(1..=24 * 60).contains(&config.password_reset_minutes) {
+            anyhow::bail!("PASSWORD_RESET_MINUTES must be from 1 to 1440 (a day)");
+        }
+        Ok(config)
+    }
+}
+
+/// A variable that is unset or blank is `None`.
+fn optional_env(key: &str) -> Option<String> {
+    env::var(key).ok().filter(|value| !value.trim().is_empty())
+}
+
+/// A 256-bit key written as 64 hex digits.
+fn parse_key(value: &str) -> anyhow::Result<[u8; 32]> {
+    let value = value.trim();
+    if value.len() != 64 || !value.bytes().all(|b| b.is_ascii_hexdigit()) {
+        anyhow::bail!("TOTP_ENCRYPTION_KEY must be 64 hex digits (e.g. `openssl rand -hex 32`)");
+    }
+
+    let mut key = [0u8; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&value[i * 2..i * 2 + 2], 16)?;
     }
+    Ok(key)
 }
 
 fn parse_env<T: FromStr>(key: &str, default: T) -> anyhow::Result<T> {
@@ -36,3 +224,29 @@ fn parse_env<T: FromStr>(key: &str, default: T) -> anyhow::Result<T> {
         Err(_) => Ok(default),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_64_hex_digits_in_either_case() {
+        let key = parse_key(&"0123456789abcdef".repeat(4)).unwrap();
+        assert_eq!(key[..8], [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]);
+        assert_eq!(key[8..], key[..24]);
+
+        let upper = format!(" {} \n", "0123456789ABCDEF".repeat(4));
+        assert_eq!(parse_key(&upper).unwrap(), key);
+    }
+
+    #[test]
+    fn other_keys_are_rejected() {
+        assert!(parse_key("").is_err());
+        assert!(parse_key(&"ab".repeat(31)).is_err());
+        assert!(parse_key(&"ab".repeat(33)).is_err());
+        assert!(parse_key(&format!("{}zz", "ab".repeat(31))).is_err());
+        // 64 bytes, but not 64 digits.
+        assert!(parse_key(&format!("+1{}", "ab".repeat(31))).is_err());
+        assert!(parse_key(&"é".repeat(32)).is_err());
+    }
+}
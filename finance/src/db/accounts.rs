@@ -1,38 +1,70 @@
-use sqlx::PgPool;
+use chrono::NaiveDateTime;
+use sqlx::{PgConnection, PgPool};
 
 use crate::error::AppError;
-use crate::models::Account;
+use crate::models::{Account, NewAccountData, PutCardSettings};
 
-const COLUMNS: &str = "id, name, user_id";
+const COLUMNS: &str = "id, name, ledger_id, account_type, opening_balance, opening_date, archived, \
+    sort_order, statement_closing_day, payment_due_day, autopay_account_id";
 
-pub async fn insert(pool: &PgPool, name: &str, user_id: i32) -> Result<Account, AppError> {
+/// Insert a new account at the end of the ledger's sort order.
+pub async fn insert(
+    pool: &PgPool,
+    new: &NewAccountData,
+    ledger_id: i32,
+) -> Result<Account, AppError> {
     let account = sqlx::query_as::<_, Account>(&format!(
-        "INSERT INTO accounts (name, user_id) VALUES ($1, $2) RETURNING {COLUMNS}"
+        "INSERT INTO accounts (name, account_type, opening_balance, opening_date, archived, sort_order, ledger_id) \
+         VALUES ($1, $2, $3, $4, $5, \
+            (SELECT COALESCE(MAX(sort_order), 0) + 1 FROM accounts WHERE ledger_id = $6), $6) \
+         RETURNING {COLUMNS}"
     ))
-    .bind(name)
-    .bind(user_id)
+    .bind(new.name.as_str())
+    .bind(new.account_type)
+    .bind(new.opening_balance)
+    .bind(new.opening_date)
+    .bind(new.archived)
+    .bind(ledger_id)
     .fetch_one(pool)
     .await?;
 
     Ok(account)
 }
 
-pub async fn get_all(pool: &PgPool, user_id: i32) -> Result<Vec<Account>, AppError> {
+/// All of the ledger's accounts, archived ones included, in display order.
+pub async fn get_all(pool: &PgPool, ledger_id: i32) -> Result<Vec<Account>, AppError> {
+    let accounts = sqlx::query_as::<_, Account>(&format!(
+        "SELECT {COLUMNS} FROM accounts WHERE ledger_id = $1 ORDER BY sort_order, id"
+    ))
+    .bind(ledger_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(accounts)
+}
+
+/// The ledger's accounts that are (or are not) archived, in display order.
+pub async fn get_all_by_archived(
+    pool: &PgPool,
+    archived: bool,
+    ledger_id: i32,
+) -> Result<Vec<Account>, AppError> {
     let accounts = sqlx::query_as::<_, Account>(&format!(
-        "SELECT {COLUMNS} FROM accounts WHERE user_id = $1 ORDER BY id"
+        "SELECT {COLUMNS} FROM accounts WHERE ledger_id = $1 AND archived = $2 ORDER BY sort_order, id"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
+    .bind(archived)
     .fetch_all(pool)
     .await?;
 
     Ok(accounts)
 }
 
-pub async fn get(pool: &PgPool, id: i32, user_id: i32) -> Result<Account, AppError> {
+pub async fn get(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Account, AppError> {
     let account = sqlx::query_as::<_, Account>(&format!(
-        "SELECT {COLUMNS} FROM accounts WHERE user_id = $1 AND id = $2"
+        "SELECT {COLUMNS} FROM accounts WHERE ledger_id = $1 AND id = $2"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -43,14 +75,21 @@ pub async fn get(pool: &PgPool, id: i32, user_id: i32) -> Result<Account, AppErr
 pub async fn update(
     pool: &PgPool,
     id: i32,
-    name: &str,
-    user_id: i32,
+    new: &NewAccountData,
+    ledger_id: i32,
 ) -> Result<Account, AppError> {
     let account = sqlx::query_as::<_, Account>(&format!(
-        "UPDATE accounts SET name = $1 WHERE user_id = $2 AND id = $3 RETURNING {COLUMNS}"
+        "UPDATE accounts SET name = $1, account_type = $2, opening_balance = $3, opening_date = $4, \
+            archived = $5, sort_order = $6 \
+         WHERE ledger_id = $7 AND id = $8 RETURNING {COLUMNS}"
     ))
-    .bind(name)
-    .bind(user_id)
+    .bind(new.name.as_str())
+    .bind(new.account_type)
+    .bind(new.opening_balance)
+    .bind(new.opening_date)
+    .bind(new.archived)
+    .bind(new.sort_order)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -58,11 +97,21 @@ pub async fn update(
     Ok(account)
 }
 
-pub async fn delete(pool: &PgPool, id: i32, user_id: i32) -> Result<Account, AppError> {
+/// Replace a credit card's statement cycle and autopay settings.
+pub async fn update_card_settings(
+    pool: &PgPool,
+    id: i32,
+    settings: &PutCardSettings,
+    ledger_id: i32,
+) -> Result<Account, AppError> {
     let account = sqlx::query_as::<_, Account>(&format!(
-        "DELETE FROM accounts WHERE user_id = $1 AND id = $2 RETURNING {COLUMNS}"
+        "UPDATE accounts SET statement_closing_day = $1, payment_due_day = $2, autopay_account_id = $3 \
+         WHERE ledger_id = $4 AND id = $5 RETURNING {COLUMNS}"
     ))
-    .bind(user_id)
+    .bind(settings.statement_closing_day)
+    .bind(settings.payment_due_day)
+    .bind(settings.autopay_account_id)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -70,27 +119,117 @@ pub async fn delete(pool: &PgPool, id: i32, user_id: i32) -> Result<Account, App
     Ok(account)
 }
 
-/// Compute an account balance entirely in SQL:
-///   + income transactions, - expense transactions, - transfers out, + transfers in.
-/// Equivalent to the original Rust-side accumulation in `utils::get_account_balance`.
-pub async fn balance(pool: &PgPool, account_id: i32, user_id: i32) -> Result<i32, AppError> {
+/// The accounts of every ledger that pay their statement automatically from another account.
+pub async fn get_all_with_autopay(pool: &PgPool) -> Result<Vec<Account>, AppError> {
+    let accounts = sqlx::query_as::<_, Account>(&format!(
+        "SELECT {COLUMNS} FROM accounts WHERE autopay_account_id IS NOT NULL \
+         ORDER BY ledger_id, id"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(accounts)
+}
+
+/// Rewrite the sort order of the given accounts to match their position in `ids`, in one
+/// database transaction. Fails with `NotFound` (and changes nothing) if any id is not one of
+/// the ledger's accounts.
+pub async fn reorder(pool: &PgPool, ids: &[i32], ledger_id: i32) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+
+    for (position, id) in ids.iter().enumerate() {
+        sqlx::query("UPDATE accounts SET sort_order = $1 WHERE ledger_id = $2 AND id = $3 RETURNING id")
+            .bind(position as i32 + 1)
+            .bind(ledger_id)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn delete(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Account, AppError> {
+    let account = sqlx::query_as::<_, Account>(&format!(
+        "DELETE FROM accounts WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(account)
+}
+
+/// Compute an account balance entirely in SQL, starting from the account's opening balance:
+///   + income transactions, - expense transactions, - transfers out, + transfers in,
+///   - investment buys, + investment sells and dividends.
+///
+/// When the account has an opening date, movements dated before it are ignored: they are
+/// assumed to be already reflected in the opening balance. Without an opening date this is
+/// equivalent to the original Rust-side accumulation in `utils::get_account_balance`.
+pub async fn balance(pool: &PgPool, account_id: i32, ledger_id: i32) -> Result<i32, AppError> {
+    balance_before(pool, account_id, None, ledger_id).await
+}
+
+/// [`balance`], on the caller's transaction.
+pub async fn balance_on(
+    conn: &mut PgConnection,
+    account_id: i32,
+    ledger_id: i32,
+) -> Result<i32, AppError> {
+    balance_before_on(conn, account_id, None, ledger_id).await
+}
+
+/// Like [`balance`], but only counting movements dated strictly before `until` (all of them
+/// when `None`): the balance as it stood at that instant, e.g. when a card statement closed.
+pub async fn balance_before(
+    pool: &PgPool,
+    account_id: i32,
+    until: Option<NaiveDateTime>,
+    ledger_id: i32,
+) -> Result<i32, AppError> {
+    balance_before_on(&mut *pool.acquire().await?, account_id, until, ledger_id).await
+}
+
+async fn balance_before_on(
+    conn: &mut PgConnection,
+    account_id: i32,
+    until: Option<NaiveDateTime>,
+    ledger_id: i32,
+) -> Result<i32, AppError> {
     let balance: i32 = sqlx::query_scalar(
         "SELECT ( \
-            COALESCE((SELECT SUM(CASE \
+            a.opening_balance \
+            + COALESCE((SELECT SUM(CASE \
                     WHEN c.categorytype = 'income' THEN t.value \
                     WHEN c.categorytype = 'expense' THEN -t.value \
                     ELSE 0 END) \
                 FROM transactions t JOIN categories c ON c.id = t.category \
-                WHERE t.account = $1 AND t.user_id = $2), 0) \
+                WHERE t.account = a.id AND t.ledger_id = a.ledger_id \
+                    AND (a.opening_date IS NULL OR t.date >= a.opening_date) \
+                    AND ($3::timestamp IS NULL OR t.date < $3)), 0) \
             - COALESCE((SELECT SUM(value) FROM transfers \
-                WHERE origin_account = $1 AND user_id = $2), 0) \
+                WHERE origin_account = a.id AND ledger_id = a.ledger_id \
+                    AND (a.opening_date IS NULL OR date >= a.opening_date) \
+                    AND ($3::timestamp IS NULL OR date < $3)), 0) \
             + COALESCE((SELECT SUM(value) FROM transfers \
-                WHERE destination_account = $1 AND user_id = $2), 0) \
-        )::int4",
+                WHERE destination_account = a.id AND ledger_id = a.ledger_id \
+                    AND (a.opening_date IS NULL OR date >= a.opening_date) \
+                    AND ($3::timestamp IS NULL OR date < $3)), 0) \
+            + COALESCE((SELECT SUM(CASE WHEN kind = 'buy' THEN -value ELSE value END) \
+                FROM investment_transactions \
+                WHERE account_id = a.id AND ledger_id = a.ledger_id \
+                    AND (a.opening_date IS NULL OR date >= a.opening_date) \
+                    AND ($3::timestamp IS NULL OR date < $3)), 0) \
+        )::int4 \
+        FROM accounts a WHERE a.id = $1 AND a.ledger_id = $2",
     )
     .bind(account_id)
-    .bind(user_id)
-    .fetch_one(pool)
+    .bind(ledger_id)
+    .bind(until)
+    .fetch_one(conn)
     .await?;
 
     Ok(balance)
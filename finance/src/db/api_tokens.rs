@@ -0,0 +1,97 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::{ApiToken, ApiTokenScopes, IssuedApiToken};
+use crate::secret;
+
+const COLUMNS: &str = "id, name, scope, created_date, expires_date, last_used_date, user_id";
+
+/// Prefix of every personal access token, which tells them apart from JWTs.
+pub const TOKEN_PREFIX: &str = "fin_";
+
+/// Create a token with a random secret, storing only its hash (see [`secret`]). The secret is
+/// returned once, in the result.
+pub async fn insert(
+    pool: &PgPool,
+    name: &str,
+    scope: ApiTokenScopes,
+    expires_date: NaiveDateTime,
+    user_id: i32,
+) -> Result<IssuedApiToken, AppError> {
+    let token = format!("{TOKEN_PREFIX}{}", secret::generate(32));
+    let (id, created_date) = sqlx::query_as(
+        "INSERT INTO api_tokens (name, token_hash, scope, expires_date, user_id) \
+         VALUES ($1, $2, $3, $4, $5) RETURNING id, created_date",
+    )
+    .bind(name)
+    .bind(secret::hash(&token))
+    .bind(scope)
+    .bind(expires_date)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(IssuedApiToken {
+        id,
+        name: name.to_string(),
+        scope,
+        token,
+        created_date,
+        expires_date,
+    })
+}
+
+pub async fn get_all(pool: &PgPool, user_id: i32) -> Result<Vec<ApiToken>, AppError> {
+    let tokens = sqlx::query_as::<_, ApiToken>(&format!(
+        "SELECT {COLUMNS} FROM api_tokens WHERE user_id = $1 ORDER BY id"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tokens)
+}
+
+/// The token with this secret, recording that it was just used. `None` for an unknown, revoked
+/// or expired secret, and while its user is disabled or must change their password.
+pub async fn use_token(pool: &PgPool, token: &str) -> Result<Option<ApiToken>, AppError> {
+    let token = sqlx::query_as::<_, ApiToken>(&format!(
+        "UPDATE api_tokens t SET last_used_date = now() \
+         WHERE token_hash = $1 AND expires_date > now() \
+         AND EXISTS (SELECT 1 FROM app_users u WHERE u.id = t.user_id \
+                     AND NOT u.disabled AND NOT u.password_change_required) \
+         RETURNING {COLUMNS}"
+    ))
+    .bind(secret::hash(token))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(token)
+}
+
+/// The id of the unexpired token with this secret, without recording a use.
+pub async fn get_id(pool: &PgPool, token: &str) -> Result<Option<i32>, AppError> {
+    let id = sqlx::query_scalar(
+        "SELECT id FROM api_tokens \
+         WHERE token_hash = $1 AND expires_date > now()",
+    )
+    .bind(secret::hash(token))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(id)
+}
+
+/// Revoke a token.
+pub async fn delete(pool: &PgPool, id: i32, user_id: i32) -> Result<ApiToken, AppError> {
+    let token = sqlx::query_as::<_, ApiToken>(&format!(
+        "DELETE FROM api_tokens WHERE user_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(user_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(token)
+}
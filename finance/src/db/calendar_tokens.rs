@@ -0,0 +1,78 @@
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::{CalendarToken, IssuedCalendarToken};
+use crate::secret;
+
+const COLUMNS: &str = "id, name, created_date, last_used_date, ledger_id, user_id";
+
+/// Create a token with a random secret, storing only its hash (see [`secret`]). The secret is
+/// returned once, in the result. The feed shows the ledger's schedules.
+pub async fn insert(
+    pool: &PgPool,
+    name: &str,
+    ledger_id: i32,
+    user_id: i32,
+) -> Result<IssuedCalendarToken, AppError> {
+    let token = secret::generate(24);
+    let (id, created_date) = sqlx::query_as(
+        "INSERT INTO calendar_tokens (name, token_hash, ledger_id, user_id) \
+         VALUES ($1, $2, $3, $4) RETURNING id, created_date",
+    )
+    .bind(name)
+    .bind(secret::hash(&token))
+    .bind(ledger_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(IssuedCalendarToken {
+        id,
+        name: name.to_string(),
+        token,
+        created_date,
+        ledger_id,
+    })
+}
+
+pub async fn get_all(pool: &PgPool, user_id: i32) -> Result<Vec<CalendarToken>, AppError> {
+    let tokens = sqlx::query_as::<_, CalendarToken>(&format!(
+        "SELECT {COLUMNS} FROM calendar_tokens WHERE user_id = $1 ORDER BY id"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tokens)
+}
+
+/// The token with this secret, recording that it was just used. `None` for an unknown (or
+/// revoked) secret, once its user has left the ledger, and while they are disabled.
+pub async fn use_token(pool: &PgPool, token: &str) -> Result<Option<CalendarToken>, AppError> {
+    let token = sqlx::query_as::<_, CalendarToken>(&format!(
+        "UPDATE calendar_tokens t SET last_used_date = now() \
+         WHERE token_hash = $1 \
+         AND EXISTS (SELECT 1 FROM ledger_members m \
+                     WHERE m.ledger_id = t.ledger_id AND m.user_id = t.user_id) \
+         AND EXISTS (SELECT 1 FROM app_users u WHERE u.id = t.user_id AND NOT u.disabled) \
+         RETURNING {COLUMNS}"
+    ))
+    .bind(secret::hash(token))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(token)
+}
+
+/// Revoke a token.
+pub async fn delete(pool: &PgPool, id: i32, user_id: i32) -> Result<CalendarToken, AppError> {
+    let token = sqlx::query_as::<_, CalendarToken>(&format!(
+        "DELETE FROM calendar_tokens WHERE user_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(user_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(token)
+}
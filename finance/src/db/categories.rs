@@ -3,31 +3,31 @@ use sqlx::PgPool;
 use crate::error::AppError;
 use crate::models::{Category, CategoryTypes};
 
-const COLUMNS: &str = "id, categorytype, name, user_id";
+const COLUMNS: &str = "id, categorytype, name, ledger_id";
 
 pub async fn insert(
     pool: &PgPool,
     categorytype: CategoryTypes,
     name: &str,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<Category, AppError> {
     let category = sqlx::query_as::<_, Category>(&format!(
-        "INSERT INTO categories (categorytype, name, user_id) VALUES ($1, $2, $3) RETURNING {COLUMNS}"
+        "INSERT INTO categories (categorytype, name, ledger_id) VALUES ($1, $2, $3) RETURNING {COLUMNS}"
     ))
     .bind(categorytype)
     .bind(name)
-    .bind(user_id)
+    .bind(ledger_id)
     .fetch_one(pool)
     .await?;
 
     Ok(category)
 }
 
-pub async fn get_all(pool: &PgPool, user_id: i32) -> Result<Vec<Category>, AppError> {
+pub async fn get_all(pool: &PgPool, ledger_id: i32) -> Result<Vec<Category>, AppError> {
     let categories = sqlx::query_as::<_, Category>(&format!(
-        "SELECT {COLUMNS} FROM categories WHERE user_id = $1 ORDER BY id"
+        "SELECT {COLUMNS} FROM categories WHERE ledger_id = $1 ORDER BY id"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
     .fetch_all(pool)
     .await?;
 
@@ -37,12 +37,12 @@ pub async fn get_all(pool: &PgPool, user_id: i32) -> Result<Vec<Category>, AppEr
 pub async fn get_all_by_type(
     pool: &PgPool,
     category_type: CategoryTypes,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<Vec<Category>, AppError> {
     let categories = sqlx::query_as::<_, Category>(&format!(
-        "SELECT {COLUMNS} FROM categories WHERE user_id = $1 AND categorytype = $2 ORDER BY id"
+        "SELECT {COLUMNS} FROM categories WHERE ledger_id = $1 AND categorytype = $2 ORDER BY id"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(category_type)
     .fetch_all(pool)
     .await?;
@@ -50,11 +50,11 @@ pub async fn get_all_by_type(
     Ok(categories)
 }
 
-pub async fn get(pool: &PgPool, id: i32, user_id: i32) -> Result<Category, AppError> {
+pub async fn get(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Category, AppError> {
     let category = sqlx::query_as::<_, Category>(&format!(
-        "SELECT {COLUMNS} FROM categories WHERE user_id = $1 AND id = $2"
+        "SELECT {COLUMNS} FROM categories WHERE ledger_id = $1 AND id = $2"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -67,14 +67,14 @@ pub async fn update(
     id: i32,
     categorytype: CategoryTypes,
     name: &str,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<Category, AppError> {
     let category = sqlx::query_as::<_, Category>(&format!(
-        "UPDATE categories SET name = $1, categorytype = $2 WHERE user_id = $3 AND id = $4 RETURNING {COLUMNS}"
+        "UPDATE categories SET name = $1, categorytype = $2 WHERE ledger_id = $3 AND id = $4 RETURNING {COLUMNS}"
     ))
     .bind(name)
     .bind(categorytype)
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -82,11 +82,11 @@ pub async fn update(
     Ok(category)
 }
 
-pub async fn delete(pool: &PgPool, id: i32, user_id: i32) -> Result<Category, AppError> {
+pub async fn delete(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Category, AppError> {
     let category = sqlx::query_as::<_, Category>(&format!(
-        "DELETE FROM categories WHERE user_id = $1 AND id = $2 RETURNING {COLUMNS}"
+        "DELETE FROM categories WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -0,0 +1,145 @@
+use sqlx::{PgConnection, PgPool};
+
+use crate::error::AppError;
+use crate::models::{Goal, GoalAccount, PostGoal, PostGoalAccount};
+
+const COLUMNS: &str =
+    "id, name, target_amount, target_date, created_date, scheduled_transaction_id, ledger_id";
+
+/// Insert a goal together with its linked accounts, in one database transaction.
+pub async fn insert(pool: &PgPool, new: &PostGoal, ledger_id: i32) -> Result<Goal, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let goal = sqlx::query_as::<_, Goal>(&format!(
+        "INSERT INTO goals (name, target_amount, target_date, ledger_id) \
+         VALUES ($1, $2, $3, $4) RETURNING {COLUMNS}"
+    ))
+    .bind(new.name.as_str())
+    .bind(new.target_amount)
+    .bind(new.target_date)
+    .bind(ledger_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    insert_accounts_on(&mut tx, goal.id, &new.accounts).await?;
+
+    tx.commit().await?;
+    Ok(goal)
+}
+
+pub async fn get_all(pool: &PgPool, ledger_id: i32) -> Result<Vec<Goal>, AppError> {
+    let goals = sqlx::query_as::<_, Goal>(&format!(
+        "SELECT {COLUMNS} FROM goals WHERE ledger_id = $1 ORDER BY target_date NULLS LAST, id"
+    ))
+    .bind(ledger_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(goals)
+}
+
+pub async fn get(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Goal, AppError> {
+    let goal = sqlx::query_as::<_, Goal>(&format!(
+        "SELECT {COLUMNS} FROM goals WHERE ledger_id = $1 AND id = $2"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(goal)
+}
+
+/// The accounts linked to a goal. The goal itself must already have been checked to belong to
+/// the ledger.
+pub async fn get_accounts(pool: &PgPool, goal_id: i32) -> Result<Vec<GoalAccount>, AppError> {
+    let accounts = sqlx::query_as::<_, GoalAccount>(
+        "SELECT goal_id, account_id, earmark FROM goal_accounts WHERE goal_id = $1 \
+         ORDER BY account_id",
+    )
+    .bind(goal_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(accounts)
+}
+
+/// Replace a goal's fields and linked accounts, in one database transaction.
+pub async fn update(
+    pool: &PgPool,
+    id: i32,
+    new: &PostGoal,
+    ledger_id: i32,
+) -> Result<Goal, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let goal = sqlx::query_as::<_, Goal>(&format!(
+        "UPDATE goals SET name = $1, target_amount = $2, target_date = $3 \
+         WHERE ledger_id = $4 AND id = $5 RETURNING {COLUMNS}"
+    ))
+    .bind(new.name.as_str())
+    .bind(new.target_amount)
+    .bind(new.target_date)
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM goal_accounts WHERE goal_id = $1")
+        .bind(goal.id)
+        .execute(&mut *tx)
+        .await?;
+    insert_accounts_on(&mut tx, goal.id, &new.accounts).await?;
+
+    tx.commit().await?;
+    Ok(goal)
+}
+
+/// Link (or unlink, with `None`) the goal's monthly contribution schedule.
+pub async fn set_scheduled(
+    pool: &PgPool,
+    id: i32,
+    scheduled_transaction_id: Option<i32>,
+    ledger_id: i32,
+) -> Result<Goal, AppError> {
+    let goal = sqlx::query_as::<_, Goal>(&format!(
+        "UPDATE goals SET scheduled_transaction_id = $1 \
+         WHERE ledger_id = $2 AND id = $3 RETURNING {COLUMNS}"
+    ))
+    .bind(scheduled_transaction_id)
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(goal)
+}
+
+pub async fn delete(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Goal, AppError> {
+    let goal = sqlx::query_as::<_, Goal>(&format!(
+        "DELETE FROM goals WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(goal)
+}
+
+async fn insert_accounts_on(
+    conn: &mut PgConnection,
+    goal_id: i32,
+    accounts: &[PostGoalAccount],
+) -> Result<(), AppError> {
+    for account in accounts {
+        sqlx::query("INSERT INTO goal_accounts (goal_id, account_id, earmark) VALUES ($1, $2, $3)")
+            .bind(goal_id)
+            .bind(account.account_id)
+            .bind(account.earmark)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
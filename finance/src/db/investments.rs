@@ -0,0 +1,122 @@
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::{InvestmentTransaction, NewInvestmentTransactionData};
+
+const COLUMNS: &str =
+    "id, account_id, security_id, kind, quantity, price, value, description, date, ledger_id";
+
+pub async fn insert(
+    pool: &PgPool,
+    new: &NewInvestmentTransactionData,
+) -> Result<InvestmentTransaction, AppError> {
+    let transaction = sqlx::query_as::<_, InvestmentTransaction>(&format!(
+        "INSERT INTO investment_transactions \
+         (account_id, security_id, kind, quantity, price, value, description, date, ledger_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING {COLUMNS}"
+    ))
+    .bind(new.account_id)
+    .bind(new.security_id)
+    .bind(new.kind)
+    .bind(new.quantity)
+    .bind(new.price)
+    .bind(new.value)
+    .bind(new.description.as_str())
+    .bind(new.date)
+    .bind(new.ledger_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(transaction)
+}
+
+/// An account's investment transactions in date order.
+pub async fn get_by_account(
+    pool: &PgPool,
+    account_id: i32,
+    ledger_id: i32,
+) -> Result<Vec<InvestmentTransaction>, AppError> {
+    let transactions = sqlx::query_as::<_, InvestmentTransaction>(&format!(
+        "SELECT {COLUMNS} FROM investment_transactions WHERE ledger_id = $1 AND account_id = $2 \
+         ORDER BY date, id"
+    ))
+    .bind(ledger_id)
+    .bind(account_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(transactions)
+}
+
+/// An account's transactions in one security, in date order.
+pub async fn get_by_account_security(
+    pool: &PgPool,
+    account_id: i32,
+    security_id: i32,
+    ledger_id: i32,
+) -> Result<Vec<InvestmentTransaction>, AppError> {
+    let transactions = sqlx::query_as::<_, InvestmentTransaction>(&format!(
+        "SELECT {COLUMNS} FROM investment_transactions \
+         WHERE ledger_id = $1 AND account_id = $2 AND security_id = $3 ORDER BY date, id"
+    ))
+    .bind(ledger_id)
+    .bind(account_id)
+    .bind(security_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(transactions)
+}
+
+pub async fn get(pool: &PgPool, id: i32, ledger_id: i32) -> Result<InvestmentTransaction, AppError> {
+    let transaction = sqlx::query_as::<_, InvestmentTransaction>(&format!(
+        "SELECT {COLUMNS} FROM investment_transactions WHERE ledger_id = $1 AND id = $2"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(transaction)
+}
+
+pub async fn update(
+    pool: &PgPool,
+    id: i32,
+    new: &NewInvestmentTransactionData,
+) -> Result<InvestmentTransaction, AppError> {
+    let transaction = sqlx::query_as::<_, InvestmentTransaction>(&format!(
+        "UPDATE investment_transactions SET security_id = $1, kind = $2, quantity = $3, \
+            price = $4, value = $5, description = $6, date = $7 \
+         WHERE ledger_id = $8 AND id = $9 RETURNING {COLUMNS}"
+    ))
+    .bind(new.security_id)
+    .bind(new.kind)
+    .bind(new.quantity)
+    .bind(new.price)
+    .bind(new.value)
+    .bind(new.description.as_str())
+    .bind(new.date)
+    .bind(new.ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(transaction)
+}
+
+pub async fn delete(
+    pool: &PgPool,
+    id: i32,
+    ledger_id: i32,
+) -> Result<InvestmentTransaction, AppError> {
+    let transaction = sqlx::query_as::<_, InvestmentTransaction>(&format!(
+        "DELETE FROM investment_transactions WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(transaction)
+}
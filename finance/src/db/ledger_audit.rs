@@ -0,0 +1,46 @@
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::{AuditEntry, NewAuditEntry};
+
+/// Record that the user changed something in the ledger.
+pub async fn insert(pool: &PgPool, new: &NewAuditEntry) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO ledger_audit (method, path, entity, entity_id, fields, ledger_id, user_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    )
+    .bind(&new.method)
+    .bind(&new.path)
+    .bind(&new.entity)
+    .bind(new.entity_id)
+    .bind(&new.fields)
+    .bind(new.ledger_id)
+    .bind(new.user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Up to `limit` of the ledger's entries before the id `before` (all by default), newest first.
+pub async fn get_page(
+    pool: &PgPool,
+    ledger_id: i32,
+    before: Option<i64>,
+    limit: i64,
+) -> Result<Vec<AuditEntry>, AppError> {
+    let entries = sqlx::query_as::<_, AuditEntry>(
+        "SELECT a.id, a.created_date, a.method, a.path, a.entity, a.entity_id, a.fields, \
+         a.user_id, u.name AS user_name \
+         FROM ledger_audit a LEFT JOIN app_users u ON u.id = a.user_id \
+         WHERE a.ledger_id = $1 AND ($2::bigint IS NULL OR a.id < $2) \
+         ORDER BY a.id DESC LIMIT $3",
+    )
+    .bind(ledger_id)
+    .bind(before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(entries)
+}
@@ -0,0 +1,93 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+
+use crate::db;
+use crate::error::AppError;
+use crate::models::{IssuedLedgerInvite, LedgerInvite, LedgerRoles};
+use crate::secret;
+
+const COLUMNS: &str = "id, role, created_date, expires_date, ledger_id, user_id";
+
+/// Create an invite with a random secret, storing only its hash (see [`secret`]). The secret
+/// is returned once, in the result.
+pub async fn insert(
+    pool: &PgPool,
+    ledger_id: i32,
+    role: LedgerRoles,
+    expires_date: NaiveDateTime,
+    user_id: i32,
+) -> Result<IssuedLedgerInvite, AppError> {
+    let token = secret::generate(24);
+    let (id, created_date) = sqlx::query_as(
+        "INSERT INTO ledger_invites (token_hash, role, expires_date, ledger_id, user_id) \
+         VALUES ($1, $2, $3, $4, $5) RETURNING id, created_date",
+    )
+    .bind(secret::hash(&token))
+    .bind(role)
+    .bind(expires_date)
+    .bind(ledger_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(IssuedLedgerInvite {
+        id,
+        role,
+        token,
+        created_date,
+        expires_date,
+        ledger_id,
+    })
+}
+
+/// The ledger's invites that are still valid.
+pub async fn get_all(pool: &PgPool, ledger_id: i32) -> Result<Vec<LedgerInvite>, AppError> {
+    let invites = sqlx::query_as::<_, LedgerInvite>(&format!(
+        "SELECT {COLUMNS} FROM ledger_invites WHERE ledger_id = $1 AND expires_date > now() \
+         ORDER BY id"
+    ))
+    .bind(ledger_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(invites)
+}
+
+/// Revoke an invite.
+pub async fn delete(pool: &PgPool, id: i32, ledger_id: i32) -> Result<LedgerInvite, AppError> {
+    let invite = sqlx::query_as::<_, LedgerInvite>(&format!(
+        "DELETE FROM ledger_invites WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(invite)
+}
+
+/// Join the ledger of the invite with this secret, using the invite up. `None` for an unknown,
+/// used or expired secret; `Conflict` (and the invite is kept) if the user already is a member.
+pub async fn accept(
+    pool: &PgPool,
+    token: &str,
+    user_id: i32,
+) -> Result<Option<LedgerInvite>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let Some(invite) = sqlx::query_as::<_, LedgerInvite>(&format!(
+        "DELETE FROM ledger_invites \
+         WHERE token_hash = $1 AND expires_date > now() \
+         RETURNING {COLUMNS}"
+    ))
+    .bind(secret::hash(token))
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(None);
+    };
+    db::ledgers::add_member(&mut tx, invite.ledger_id, user_id, invite.role).await?;
+
+    tx.commit().await?;
+    Ok(Some(invite))
+}
@@ -0,0 +1,241 @@
+use sqlx::{PgConnection, PgPool};
+
+use crate::error::AppError;
+use crate::models::{GetLedger, Ledger, LedgerMember, LedgerRoles};
+
+/// Order of a user's memberships `m` whose first is their default ledger, the one they work in
+/// when a request does not pick one (with `X-Ledger-Id`): the first they own, or else the first
+/// they joined.
+pub const DEFAULT_ORDER: &str = "m.role = 'owner' DESC, m.joined_date, m.ledger_id";
+
+/// Create a ledger with the user as its owner.
+pub async fn insert(conn: &mut PgConnection, name: &str, user_id: i32) -> Result<Ledger, AppError> {
+    let ledger = sqlx::query_as::<_, Ledger>(
+        "INSERT INTO ledgers (name) VALUES ($1) RETURNING id, name, created_date",
+    )
+    .bind(name)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query("INSERT INTO ledger_members (ledger_id, user_id, role) VALUES ($1, $2, 'owner')")
+        .bind(ledger.id)
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(ledger)
+}
+
+/// The user's default ledger (see [`DEFAULT_ORDER`]). A user who is no longer a member of any
+/// ledger gets a new personal one, named after them.
+pub async fn get_default(pool: &PgPool, user_id: i32) -> Result<i32, AppError> {
+    if let Some(ledger_id) = sqlx::query_scalar(&format!(
+        "SELECT m.ledger_id FROM ledger_members m WHERE m.user_id = $1 \
+         ORDER BY {DEFAULT_ORDER} LIMIT 1"
+    ))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    {
+        return Ok(ledger_id);
+    }
+
+    let mut tx = pool.begin().await?;
+    let name: String = sqlx::query_scalar("SELECT name FROM app_users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let ledger = insert(&mut tx, &name, user_id).await?;
+    tx.commit().await?;
+
+    Ok(ledger.id)
+}
+
+/// The user's role in the ledger; `None` if they are not a member.
+pub async fn get_role(
+    pool: &PgPool,
+    ledger_id: i32,
+    user_id: i32,
+) -> Result<Option<LedgerRoles>, AppError> {
+    let role = sqlx::query_scalar(
+        "SELECT role FROM ledger_members WHERE ledger_id = $1 AND user_id = $2",
+    )
+    .bind(ledger_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(role)
+}
+
+/// The ledgers the user is a member of, in the order they joined them.
+pub async fn get_all(pool: &PgPool, user_id: i32) -> Result<Vec<GetLedger>, AppError> {
+    let ledgers = sqlx::query_as::<_, GetLedger>(
+        "SELECT l.id, l.name, l.created_date, m.role, \
+         (SELECT count(*) FROM ledger_members o WHERE o.ledger_id = l.id) AS members \
+         FROM ledgers l JOIN ledger_members m ON m.ledger_id = l.id \
+         WHERE m.user_id = $1 ORDER BY m.joined_date, l.id",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ledgers)
+}
+
+pub async fn rename(pool: &PgPool, id: i32, name: &str) -> Result<Ledger, AppError> {
+    let ledger = sqlx::query_as::<_, Ledger>(
+        "UPDATE ledgers SET name = $1 WHERE id = $2 RETURNING id, name, created_date",
+    )
+    .bind(name)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(ledger)
+}
+
+/// Delete the ledger and, through the `ON DELETE CASCADE` foreign keys, everything in it.
+pub async fn delete(pool: &PgPool, id: i32) -> Result<Ledger, AppError> {
+    let ledger = sqlx::query_as::<_, Ledger>(
+        "DELETE FROM ledgers WHERE id = $1 RETURNING id, name, created_date",
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(ledger)
+}
+
+pub async fn get_members(pool: &PgPool, ledger_id: i32) -> Result<Vec<LedgerMember>, AppError> {
+    let members = sqlx::query_as::<_, LedgerMember>(
+        "SELECT m.user_id, u.name, m.role, m.joined_date \
+         FROM ledger_members m JOIN app_users u ON u.id = m.user_id \
+         WHERE m.ledger_id = $1 ORDER BY m.joined_date, m.user_id",
+    )
+    .bind(ledger_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(members)
+}
+
+/// Add the user to the ledger with `role`. `Conflict` if they already are a member.
+pub async fn add_member(
+    conn: &mut PgConnection,
+    ledger_id: i32,
+    user_id: i32,
+    role: LedgerRoles,
+) -> Result<(), AppError> {
+    sqlx::query("INSERT INTO ledger_members (ledger_id, user_id, role) VALUES ($1, $2, $3)")
+        .bind(ledger_id)
+        .bind(user_id)
+        .bind(role)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Change a member's role. `Conflict` if that would leave the ledger without an owner.
+pub async fn set_role(
+    pool: &PgPool,
+    ledger_id: i32,
+    user_id: i32,
+    role: LedgerRoles,
+) -> Result<LedgerMember, AppError> {
+    let mut tx = pool.begin().await?;
+    if role != LedgerRoles::Owner {
+        keep_an_owner(&mut tx, ledger_id, user_id).await?;
+    }
+
+    sqlx::query("UPDATE ledger_members SET role = $1 WHERE ledger_id = $2 AND user_id = $3")
+        .bind(role)
+        .bind(ledger_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    get_members(pool, ledger_id)
+        .await?
+        .into_iter()
+        .find(|member| member.user_id == user_id)
+        .ok_or(AppError::NotFound)
+}
+
+/// Remove a member from the ledger. `Conflict` for its last owner.
+pub async fn remove_member(pool: &PgPool, ledger_id: i32, user_id: i32) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    keep_an_owner(&mut tx, ledger_id, user_id).await?;
+
+    sqlx::query("DELETE FROM ledger_members WHERE ledger_id = $1 AND user_id = $2")
+        .bind(ledger_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Before the user's departure from the ledger's owners: `NotFound` if they are not a member,
+/// `Conflict` if they are its only owner. Locks the ledger's members until the transaction ends.
+async fn keep_an_owner(
+    conn: &mut PgConnection,
+    ledger_id: i32,
+    user_id: i32,
+) -> Result<(), AppError> {
+    let members: Vec<(i32, LedgerRoles)> = sqlx::query_as(
+        "SELECT user_id, role FROM ledger_members WHERE ledger_id = $1 FOR UPDATE",
+    )
+    .bind(ledger_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if !members.iter().any(|&(id, _)| id == user_id) {
+        return Err(AppError::NotFound);
+    }
+    let other_owners = members
+        .iter()
+        .filter(|&&(id, role)| id != user_id && role == LedgerRoles::Owner)
+        .count();
+    let was_owner = members.contains(&(user_id, LedgerRoles::Owner));
+    if was_owner && other_owners == 0 {
+        return Err(AppError::Conflict);
+    }
+
+    Ok(())
+}
+
+/// Before deleting the user: delete the ledgers nobody else is a member of, and hand those they
+/// are the only owner of to the member who joined first.
+pub async fn leave_all(conn: &mut PgConnection, user_id: i32) -> Result<(), AppError> {
+    sqlx::query(
+        "DELETE FROM ledgers l WHERE EXISTS \
+         (SELECT 1 FROM ledger_members m WHERE m.ledger_id = l.id AND m.user_id = $1) \
+         AND NOT EXISTS \
+         (SELECT 1 FROM ledger_members m WHERE m.ledger_id = l.id AND m.user_id <> $1)",
+    )
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "UPDATE ledger_members m SET role = 'owner' \
+         FROM (SELECT DISTINCT ON (o.ledger_id) o.ledger_id, o.user_id \
+               FROM ledger_members o \
+               JOIN ledger_members me ON me.ledger_id = o.ledger_id \
+                    AND me.user_id = $1 AND me.role = 'owner' \
+               WHERE o.user_id <> $1 \
+               AND NOT EXISTS (SELECT 1 FROM ledger_members x WHERE x.ledger_id = o.ledger_id \
+                               AND x.user_id <> $1 AND x.role = 'owner') \
+               ORDER BY o.ledger_id, o.joined_date, o.user_id) heir \
+         WHERE m.ledger_id = heir.ledger_id AND m.user_id = heir.user_id",
+    )
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::{Loan, NewLoanData};
+
+const COLUMNS: &str = "account_id, principal, annual_rate_bps, term_months, payment_day, \
+    start_date, payment_account_id, interest_category_id, scheduled_transaction_id, ledger_id";
+
+pub async fn insert(pool: &PgPool, new: &NewLoanData) -> Result<Loan, AppError> {
+    let loan = sqlx::query_as::<_, Loan>(&format!(
+        "INSERT INTO loans \
+         (account_id, principal, annual_rate_bps, term_months, payment_day, start_date, \
+          payment_account_id, interest_category_id, scheduled_transaction_id, ledger_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING {COLUMNS}"
+    ))
+    .bind(new.account_id)
+    .bind(new.principal)
+    .bind(new.annual_rate_bps)
+    .bind(new.term_months)
+    .bind(new.payment_day)
+    .bind(new.start_date)
+    .bind(new.payment_account_id)
+    .bind(new.interest_category_id)
+    .bind(new.scheduled_transaction_id)
+    .bind(new.ledger_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(loan)
+}
+
+pub async fn get_all(pool: &PgPool, ledger_id: i32) -> Result<Vec<Loan>, AppError> {
+    let loans = sqlx::query_as::<_, Loan>(&format!(
+        "SELECT {COLUMNS} FROM loans WHERE ledger_id = $1 ORDER BY account_id"
+    ))
+    .bind(ledger_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(loans)
+}
+
+pub async fn get(pool: &PgPool, account_id: i32, ledger_id: i32) -> Result<Loan, AppError> {
+    let loan = sqlx::query_as::<_, Loan>(&format!(
+        "SELECT {COLUMNS} FROM loans WHERE ledger_id = $1 AND account_id = $2"
+    ))
+    .bind(ledger_id)
+    .bind(account_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(loan)
+}
+
+/// The loan whose monthly payment is the given scheduled transaction, if any.
+pub async fn get_by_scheduled(
+    pool: &PgPool,
+    scheduled_transaction_id: i32,
+    ledger_id: i32,
+) -> Result<Option<Loan>, AppError> {
+    let loan = sqlx::query_as::<_, Loan>(&format!(
+        "SELECT {COLUMNS} FROM loans WHERE ledger_id = $1 AND scheduled_transaction_id = $2"
+    ))
+    .bind(ledger_id)
+    .bind(scheduled_transaction_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(loan)
+}
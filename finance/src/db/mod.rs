@@ -1,6 +1,22 @@
 pub mod accounts;
+pub mod api_tokens;
+pub mod calendar_tokens;
 pub mod categories;
+pub mod goals;
+pub mod investments;
+pub mod ledger_audit;
+pub mod ledger_invites;
+pub mod ledgers;
+pub mod loans;
+pub mod oidc_identities;
+pub mod password_resets;
+pub mod reconciliations;
+pub mod scheduled_occurrences;
 pub mod scheduled_transactions;
+pub mod securities;
+pub mod sessions;
+pub mod settings;
 pub mod transactions;
 pub mod transfers;
+pub mod two_factor;
 pub mod users;
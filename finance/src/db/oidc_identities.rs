@@ -0,0 +1,65 @@
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::OidcIdentity;
+
+const COLUMNS: &str = "id, issuer, subject, created_date, last_login_date, user_id";
+
+/// The user the identity is linked to, recording the login. `None` for an identity not linked
+/// to anyone.
+pub async fn log_in(pool: &PgPool, issuer: &str, subject: &str) -> Result<Option<i32>, AppError> {
+    let user_id = sqlx::query_scalar(
+        "UPDATE oidc_identities SET last_login_date = now() \
+         WHERE issuer = $1 AND subject = $2 RETURNING user_id",
+    )
+    .bind(issuer)
+    .bind(subject)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user_id)
+}
+
+/// Link an identity to the user. `Conflict` if it is already linked, to them or anyone else.
+pub async fn insert(
+    pool: &PgPool,
+    issuer: &str,
+    subject: &str,
+    user_id: i32,
+) -> Result<OidcIdentity, AppError> {
+    let identity = sqlx::query_as::<_, OidcIdentity>(&format!(
+        "INSERT INTO oidc_identities (issuer, subject, user_id) VALUES ($1, $2, $3) \
+         RETURNING {COLUMNS}"
+    ))
+    .bind(issuer)
+    .bind(subject)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(identity)
+}
+
+pub async fn get_all(pool: &PgPool, user_id: i32) -> Result<Vec<OidcIdentity>, AppError> {
+    let identities = sqlx::query_as::<_, OidcIdentity>(&format!(
+        "SELECT {COLUMNS} FROM oidc_identities WHERE user_id = $1 ORDER BY id"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(identities)
+}
+
+/// Unlink an identity.
+pub async fn delete(pool: &PgPool, id: i32, user_id: i32) -> Result<OidcIdentity, AppError> {
+    let identity = sqlx::query_as::<_, OidcIdentity>(&format!(
+        "DELETE FROM oidc_identities WHERE user_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(user_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(identity)
+}
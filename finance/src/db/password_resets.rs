@@ -0,0 +1,72 @@
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::secret;
+
+/// Create a reset token for the user with a random secret, valid for `validity_minutes`,
+/// storing only its hash (see [`secret`]). Tokens issued before that were
+/// not used stop working, so only the latest email's link does.
+pub async fn insert(pool: &PgPool, user_id: i32, validity_minutes: i64) -> Result<String, AppError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM password_resets WHERE user_id = $1 AND used_date IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let token = secret::generate(32);
+    sqlx::query(
+        "INSERT INTO password_resets (token_hash, expires_date, user_id) \
+         VALUES ($1, now() + make_interval(mins => $2::int), $3)",
+    )
+    .bind(secret::hash(&token))
+    .bind(validity_minutes)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(token)
+}
+
+/// Whether the user was sent a reset token in the last `minutes`.
+pub async fn sent_recently(pool: &PgPool, user_id: i32, minutes: i64) -> Result<bool, AppError> {
+    let sent = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM password_resets WHERE user_id = $1 \
+         AND created_date > now() - make_interval(mins => $2::int))",
+    )
+    .bind(user_id)
+    .bind(minutes)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(sent)
+}
+
+/// The user a token resets the password of; `None` for an unknown, used or expired token.
+pub async fn get_user(pool: &PgPool, token: &str) -> Result<Option<i32>, AppError> {
+    let user_id = sqlx::query_scalar(
+        "SELECT user_id FROM password_resets \
+         WHERE token_hash = $1 \
+         AND used_date IS NULL AND expires_date > now()",
+    )
+    .bind(secret::hash(token))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user_id)
+}
+
+/// Use a token up, returning its user; `None` if it is not valid (any more).
+pub async fn use_token(pool: &PgPool, token: &str) -> Result<Option<i32>, AppError> {
+    let user_id = sqlx::query_scalar(
+        "UPDATE password_resets SET used_date = now() \
+         WHERE token_hash = $1 \
+         AND used_date IS NULL AND expires_date > now() \
+         RETURNING user_id",
+    )
+    .bind(secret::hash(token))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user_id)
+}
@@ -0,0 +1,91 @@
+use chrono::NaiveDateTime;
+use sqlx::{PgConnection, PgPool};
+
+use crate::error::AppError;
+use crate::models::Reconciliation;
+
+const COLUMNS: &str =
+    "id, account_id, statement_date, statement_balance, created_date, finished_date, ledger_id";
+
+/// Open a reconciliation session. An account can only have one open session at a time; a
+/// second one violates the partial unique index and surfaces as `Conflict`.
+pub async fn insert(
+    pool: &PgPool,
+    account_id: i32,
+    statement_date: NaiveDateTime,
+    statement_balance: i32,
+    ledger_id: i32,
+) -> Result<Reconciliation, AppError> {
+    let reconciliation = sqlx::query_as::<_, Reconciliation>(&format!(
+        "INSERT INTO reconciliations (account_id, statement_date, statement_balance, ledger_id) \
+         VALUES ($1, $2, $3, $4) RETURNING {COLUMNS}"
+    ))
+    .bind(account_id)
+    .bind(statement_date)
+    .bind(statement_balance)
+    .bind(ledger_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(reconciliation)
+}
+
+pub async fn get_all_of_account(
+    pool: &PgPool,
+    account_id: i32,
+    ledger_id: i32,
+) -> Result<Vec<Reconciliation>, AppError> {
+    let rows = sqlx::query_as::<_, Reconciliation>(&format!(
+        "SELECT {COLUMNS} FROM reconciliations WHERE ledger_id = $1 AND account_id = $2 \
+         ORDER BY statement_date DESC"
+    ))
+    .bind(ledger_id)
+    .bind(account_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+pub async fn get(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Reconciliation, AppError> {
+    let reconciliation = sqlx::query_as::<_, Reconciliation>(&format!(
+        "SELECT {COLUMNS} FROM reconciliations WHERE ledger_id = $1 AND id = $2"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(reconciliation)
+}
+
+/// Mark a session finished, on the caller's transaction (see
+/// [`crate::db::transactions::reconcile_cleared`]).
+pub async fn finish_on(
+    conn: &mut PgConnection,
+    id: i32,
+    ledger_id: i32,
+) -> Result<Reconciliation, AppError> {
+    let reconciliation = sqlx::query_as::<_, Reconciliation>(&format!(
+        "UPDATE reconciliations SET finished_date = now() \
+         WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(reconciliation)
+}
+
+pub async fn delete(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Reconciliation, AppError> {
+    let reconciliation = sqlx::query_as::<_, Reconciliation>(&format!(
+        "DELETE FROM reconciliations WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(reconciliation)
+}
@@ -0,0 +1,100 @@
+use sqlx::{PgConnection, PgPool};
+
+use crate::error::AppError;
+use crate::models::{NewScheduledOccurrence, OccurrenceOutcomes, ScheduledOccurrence};
+
+const COLUMNS: &str = "id, scheduled_transaction_id, occurrence, due_date, outcome, value, \
+    snoozed_to, recorded_at, ledger_id";
+
+/// Record what happened to an occurrence, on the caller's transaction.
+pub async fn insert_on(
+    conn: &mut PgConnection,
+    new: &NewScheduledOccurrence,
+) -> Result<ScheduledOccurrence, AppError> {
+    let occurrence = sqlx::query_as::<_, ScheduledOccurrence>(&format!(
+        "INSERT INTO scheduled_occurrences \
+         (scheduled_transaction_id, occurrence, due_date, outcome, value, snoozed_to, ledger_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING {COLUMNS}"
+    ))
+    .bind(new.scheduled_transaction_id)
+    .bind(new.occurrence)
+    .bind(new.due_date)
+    .bind(new.outcome)
+    .bind(new.value)
+    .bind(new.snoozed_to)
+    .bind(new.ledger_id)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(occurrence)
+}
+
+/// A schedule's occurrence history, oldest first.
+pub async fn get_by_scheduled(
+    pool: &PgPool,
+    scheduled_transaction_id: i32,
+    ledger_id: i32,
+) -> Result<Vec<ScheduledOccurrence>, AppError> {
+    let occurrences = sqlx::query_as::<_, ScheduledOccurrence>(&format!(
+        "SELECT {COLUMNS} FROM scheduled_occurrences \
+         WHERE ledger_id = $1 AND scheduled_transaction_id = $2 ORDER BY recorded_at, id"
+    ))
+    .bind(ledger_id)
+    .bind(scheduled_transaction_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(occurrences)
+}
+
+/// The total of the partial payments made towards one occurrence of a schedule.
+pub async fn paid_value(
+    pool: &PgPool,
+    scheduled_transaction_id: i32,
+    occurrence: Option<i32>,
+) -> Result<i32, AppError> {
+    paid_value_on(&mut *pool.acquire().await?, scheduled_transaction_id, occurrence).await
+}
+
+/// [`paid_value`], on the caller's transaction.
+pub async fn paid_value_on(
+    conn: &mut PgConnection,
+    scheduled_transaction_id: i32,
+    occurrence: Option<i32>,
+) -> Result<i32, AppError> {
+    let paid: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(value), 0) FROM scheduled_occurrences \
+         WHERE scheduled_transaction_id = $1 AND occurrence IS NOT DISTINCT FROM $2 \
+            AND outcome = $3",
+    )
+    .bind(scheduled_transaction_id)
+    .bind(occurrence)
+    .bind(OccurrenceOutcomes::PartiallyPaid)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(paid as i32)
+}
+
+/// What was paid in total for each of a schedule's last `limit` paid occurrences, most recent
+/// first. Occurrences only partially paid so far are left out.
+pub async fn paid_totals(
+    pool: &PgPool,
+    scheduled_transaction_id: i32,
+    limit: i64,
+) -> Result<Vec<i32>, AppError> {
+    let totals: Vec<i32> = sqlx::query_scalar(
+        "SELECT SUM(value)::int4 FROM scheduled_occurrences \
+         WHERE scheduled_transaction_id = $1 AND outcome IN ($2, $3) \
+         GROUP BY occurrence HAVING bool_or(outcome = $2) \
+         ORDER BY MAX(recorded_at) DESC, MAX(id) DESC LIMIT $4",
+    )
+    .bind(scheduled_transaction_id)
+    .bind(OccurrenceOutcomes::Paid)
+    .bind(OccurrenceOutcomes::PartiallyPaid)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(totals)
+}
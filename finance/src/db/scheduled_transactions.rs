@@ -1,11 +1,12 @@
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 
 use crate::error::AppError;
-use crate::models::{NewScheduledTransaction, ScheduledTransaction};
+use crate::models::{NewScheduledTransaction, ScheduledEntry, ScheduledTransaction};
 
 const COLUMNS: &str = "id, kind, value, description, created_date, account_id, category_id, \
-    origin_account_id, destination_account_id, repeat, repeat_freq, repeat_interval, \
-    infinite_repeat, end_after_repeats, current_repeat_count, next_date, user_id";
+    origin_account_id, destination_account_id, repeat, repeat_freq, repeat_interval, rrule, \
+    business_day_adjustment, amount_estimate, infinite_repeat, end_after_repeats, current_repeat_count, next_date, \
+    finished, ledger_id, autopay_card_id";
 
 pub async fn insert(
     pool: &PgPool,
@@ -15,8 +16,10 @@ pub async fn insert(
         "INSERT INTO scheduled_transactions \
          (kind, value, description, created_date, account_id, category_id, origin_account_id, \
           destination_account_id, repeat, repeat_freq, repeat_interval, infinite_repeat, \
-          end_after_repeats, current_repeat_count, next_date, user_id) \
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16) \
+          end_after_repeats, current_repeat_count, next_date, ledger_id, autopay_card_id, rrule, \
+          business_day_adjustment, amount_estimate) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, \
+                 $19, $20) \
          RETURNING {COLUMNS}"
     ))
     .bind(new.kind)
@@ -34,21 +37,43 @@ pub async fn insert(
     .bind(new.end_after_repeats)
     .bind(new.current_repeat_count)
     .bind(new.next_date)
-    .bind(new.user_id)
+    .bind(new.ledger_id)
+    .bind(new.autopay_card_id)
+    .bind(new.rrule.as_deref())
+    .bind(new.business_day_adjustment)
+    .bind(new.amount_estimate)
     .fetch_one(pool)
     .await?;
 
     Ok(st)
 }
 
+/// The schedules still running (not finished).
 pub async fn get_all(
     pool: &PgPool,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<Vec<ScheduledTransaction>, AppError> {
     let rows = sqlx::query_as::<_, ScheduledTransaction>(&format!(
-        "SELECT {COLUMNS} FROM scheduled_transactions WHERE user_id = $1 ORDER BY created_date DESC"
+        "SELECT {COLUMNS} FROM scheduled_transactions WHERE ledger_id = $1 AND NOT finished \
+         ORDER BY created_date DESC"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// The schedules that ran to completion, kept for their history.
+pub async fn get_finished(
+    pool: &PgPool,
+    ledger_id: i32,
+) -> Result<Vec<ScheduledTransaction>, AppError> {
+    let rows = sqlx::query_as::<_, ScheduledTransaction>(&format!(
+        "SELECT {COLUMNS} FROM scheduled_transactions WHERE ledger_id = $1 AND finished \
+         ORDER BY created_date DESC"
+    ))
+    .bind(ledger_id)
     .fetch_all(pool)
     .await?;
 
@@ -58,12 +83,12 @@ pub async fn get_all(
 pub async fn get(
     pool: &PgPool,
     id: i32,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<ScheduledTransaction, AppError> {
     let st = sqlx::query_as::<_, ScheduledTransaction>(&format!(
-        "SELECT {COLUMNS} FROM scheduled_transactions WHERE user_id = $1 AND id = $2"
+        "SELECT {COLUMNS} FROM scheduled_transactions WHERE ledger_id = $1 AND id = $2"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -71,19 +96,106 @@ pub async fn get(
     Ok(st)
 }
 
-pub async fn update(
+/// The running autopay schedule managed for a credit card, if there is one.
+pub async fn get_autopay(
     pool: &PgPool,
+    card_id: i32,
+    ledger_id: i32,
+) -> Result<Option<ScheduledTransaction>, AppError> {
+    let st = sqlx::query_as::<_, ScheduledTransaction>(&format!(
+        "SELECT {COLUMNS} FROM scheduled_transactions \
+         WHERE ledger_id = $1 AND autopay_card_id = $2 AND NOT finished"
+    ))
+    .bind(ledger_id)
+    .bind(card_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(st)
+}
+
+/// Create the running autopay schedule of the card `new.autopay_card_id`, or move the one it
+/// has to the statement in `new`, in one statement so that concurrent updates cannot both
+/// create one.
+pub async fn upsert_autopay(
+    pool: &PgPool,
+    new: &NewScheduledTransaction,
+) -> Result<ScheduledTransaction, AppError> {
+    let st = sqlx::query_as::<_, ScheduledTransaction>(&format!(
+        "INSERT INTO scheduled_transactions \
+         (kind, value, description, created_date, origin_account_id, destination_account_id, \
+          repeat, next_date, ledger_id, autopay_card_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, false, $7, $8, $9) \
+         ON CONFLICT (autopay_card_id) WHERE autopay_card_id IS NOT NULL AND NOT finished \
+         DO UPDATE SET value = EXCLUDED.value, description = EXCLUDED.description, \
+            created_date = EXCLUDED.created_date, origin_account_id = EXCLUDED.origin_account_id, \
+            next_date = EXCLUDED.next_date \
+         RETURNING {COLUMNS}"
+    ))
+    .bind(new.kind)
+    .bind(new.value)
+    .bind(new.description.as_deref())
+    .bind(new.created_date)
+    .bind(new.origin_account_id)
+    .bind(new.destination_account_id)
+    .bind(new.next_date)
+    .bind(new.ledger_id)
+    .bind(new.autopay_card_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(st)
+}
+
+/// Remove the card's running autopay schedule, if it has one.
+pub async fn delete_autopay(pool: &PgPool, card_id: i32, ledger_id: i32) -> Result<(), AppError> {
+    sqlx::query(
+        "DELETE FROM scheduled_transactions \
+         WHERE ledger_id = $1 AND autopay_card_id = $2 AND NOT finished",
+    )
+    .bind(ledger_id)
+    .bind(card_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// The schedule, locked until the caller's transaction ends, so that its occurrences are paid,
+/// skipped or snoozed one request at a time.
+pub async fn lock_on(
+    conn: &mut PgConnection,
+    id: i32,
+    ledger_id: i32,
+) -> Result<ScheduledTransaction, AppError> {
+    let st = sqlx::query_as::<_, ScheduledTransaction>(&format!(
+        "SELECT {COLUMNS} FROM scheduled_transactions WHERE ledger_id = $1 AND id = $2 FOR UPDATE"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(st)
+}
+
+/// Update a scheduled transaction, on the caller's transaction. `autopay_card_id` is set on
+/// insert only and never changed here, so editing an autopay schedule keeps it linked to its
+/// card; `finished` is left as it is.
+pub async fn update_on(
+    conn: &mut PgConnection,
     id: i32,
     new: &NewScheduledTransaction,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<ScheduledTransaction, AppError> {
     let st = sqlx::query_as::<_, ScheduledTransaction>(&format!(
         "UPDATE scheduled_transactions SET \
             kind = $1, value = $2, description = $3, created_date = $4, account_id = $5, \
             category_id = $6, origin_account_id = $7, destination_account_id = $8, repeat = $9, \
             repeat_freq = $10, repeat_interval = $11, infinite_repeat = $12, end_after_repeats = $13, \
-            current_repeat_count = $14, next_date = $15 \
-         WHERE user_id = $16 AND id = $17 RETURNING {COLUMNS}"
+            current_repeat_count = $14, next_date = $15, rrule = $16, \
+            business_day_adjustment = $17, amount_estimate = $18 \
+         WHERE ledger_id = $19 AND id = $20 RETURNING {COLUMNS}"
     ))
     .bind(new.kind)
     .bind(new.value)
@@ -100,9 +212,68 @@ pub async fn update(
     .bind(new.end_after_repeats)
     .bind(new.current_repeat_count)
     .bind(new.next_date)
-    .bind(user_id)
+    .bind(new.rrule.as_deref())
+    .bind(new.business_day_adjustment)
+    .bind(new.amount_estimate)
+    .bind(ledger_id)
     .bind(id)
-    .fetch_one(pool)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(st)
+}
+
+/// The transactions and transfers booked by paying a schedule, by occurrence.
+pub async fn get_entries(
+    pool: &PgPool,
+    id: i32,
+    ledger_id: i32,
+) -> Result<Vec<ScheduledEntry>, AppError> {
+    get_entries_on(&mut *pool.acquire().await?, id, ledger_id).await
+}
+
+/// [`get_entries`], on the caller's transaction.
+pub async fn get_entries_on(
+    conn: &mut PgConnection,
+    id: i32,
+    ledger_id: i32,
+) -> Result<Vec<ScheduledEntry>, AppError> {
+    let rows = sqlx::query_as::<_, ScheduledEntry>(
+        "SELECT 'transaction'::scheduled_transacion_kinds AS kind, id, occurrence, value, \
+            description, date \
+         FROM transactions WHERE ledger_id = $1 AND scheduled_transaction_id = $2 \
+         UNION ALL \
+         SELECT 'transfer'::scheduled_transacion_kinds AS kind, id, occurrence, value, \
+            description, date \
+         FROM transfers WHERE ledger_id = $1 AND scheduled_transaction_id = $2 \
+         ORDER BY occurrence, date, id",
+    )
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Mark a schedule as finished after its last occurrence, with `current_repeat_count` moved
+/// past it and no next date, on the caller's transaction. The row is kept so its history and
+/// entries stay available.
+pub async fn finish_on(
+    conn: &mut PgConnection,
+    id: i32,
+    current_repeat_count: Option<i32>,
+    ledger_id: i32,
+) -> Result<ScheduledTransaction, AppError> {
+    let st = sqlx::query_as::<_, ScheduledTransaction>(&format!(
+        "UPDATE scheduled_transactions SET finished = true, current_repeat_count = $1, \
+            next_date = NULL \
+         WHERE ledger_id = $2 AND id = $3 RETURNING {COLUMNS}"
+    ))
+    .bind(current_repeat_count)
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(conn)
     .await?;
 
     Ok(st)
@@ -111,12 +282,12 @@ pub async fn update(
 pub async fn delete(
     pool: &PgPool,
     id: i32,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<ScheduledTransaction, AppError> {
     let st = sqlx::query_as::<_, ScheduledTransaction>(&format!(
-        "DELETE FROM scheduled_transactions WHERE user_id = $1 AND id = $2 RETURNING {COLUMNS}"
+        "DELETE FROM scheduled_transactions WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -0,0 +1,123 @@
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::{PostSecurity, Security, SecurityPrice};
+
+const COLUMNS: &str = "id, symbol, name, ledger_id";
+
+pub async fn insert(pool: &PgPool, new: &PostSecurity, ledger_id: i32) -> Result<Security, AppError> {
+    let security = sqlx::query_as::<_, Security>(&format!(
+        "INSERT INTO securities (symbol, name, ledger_id) VALUES ($1, $2, $3) RETURNING {COLUMNS}"
+    ))
+    .bind(new.symbol.as_str())
+    .bind(new.name.as_str())
+    .bind(ledger_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(security)
+}
+
+pub async fn get_all(pool: &PgPool, ledger_id: i32) -> Result<Vec<Security>, AppError> {
+    let securities = sqlx::query_as::<_, Security>(&format!(
+        "SELECT {COLUMNS} FROM securities WHERE ledger_id = $1 ORDER BY symbol"
+    ))
+    .bind(ledger_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(securities)
+}
+
+pub async fn get(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Security, AppError> {
+    let security = sqlx::query_as::<_, Security>(&format!(
+        "SELECT {COLUMNS} FROM securities WHERE ledger_id = $1 AND id = $2"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(security)
+}
+
+pub async fn update(
+    pool: &PgPool,
+    id: i32,
+    new: &PostSecurity,
+    ledger_id: i32,
+) -> Result<Security, AppError> {
+    let security = sqlx::query_as::<_, Security>(&format!(
+        "UPDATE securities SET symbol = $1, name = $2 WHERE ledger_id = $3 AND id = $4 \
+         RETURNING {COLUMNS}"
+    ))
+    .bind(new.symbol.as_str())
+    .bind(new.name.as_str())
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(security)
+}
+
+/// Delete a security, along with its transactions and prices.
+pub async fn delete(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Security, AppError> {
+    let security = sqlx::query_as::<_, Security>(&format!(
+        "DELETE FROM securities WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(security)
+}
+
+/// A security's prices, newest first. The caller checks that the security is the ledger's.
+pub async fn get_prices(pool: &PgPool, security_id: i32) -> Result<Vec<SecurityPrice>, AppError> {
+    let prices = sqlx::query_as::<_, SecurityPrice>(
+        "SELECT security_id, date, price FROM prices WHERE security_id = $1 ORDER BY date DESC",
+    )
+    .bind(security_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(prices)
+}
+
+pub async fn latest_price(
+    pool: &PgPool,
+    security_id: i32,
+) -> Result<Option<SecurityPrice>, AppError> {
+    let price = sqlx::query_as::<_, SecurityPrice>(
+        "SELECT security_id, date, price FROM prices WHERE security_id = $1 \
+         ORDER BY date DESC LIMIT 1",
+    )
+    .bind(security_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(price)
+}
+
+/// Insert prices, replacing any already recorded for the same security and date, in one
+/// database transaction. The caller checks that the securities are the ledger's.
+pub async fn upsert_prices(pool: &PgPool, prices: &[SecurityPrice]) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+
+    for price in prices {
+        sqlx::query(
+            "INSERT INTO prices (security_id, date, price) VALUES ($1, $2, $3) \
+             ON CONFLICT (security_id, date) DO UPDATE SET price = EXCLUDED.price",
+        )
+        .bind(price.security_id)
+        .bind(price.date)
+        .bind(price.price)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
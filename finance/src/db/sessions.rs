@@ -0,0 +1,195 @@
+use sqlx::{PgConnection, PgPool};
+
+use crate::error::AppError;
+use crate::models::Session;
+use crate::secret;
+
+const COLUMNS: &str = "id, device, created_date, last_seen_date, expires_date, user_id";
+
+/// What presenting a refresh token led to.
+pub enum Refresh {
+    /// The token was valid: it is now used up, replaced by `refresh_token`.
+    Rotated {
+        session: Session,
+        refresh_token: String,
+    },
+    /// The token had already been used, so it was copied: its session is now revoked.
+    Reused { session_id: i32, user_id: i32 },
+    /// An unknown token, or one of a revoked or expired session.
+    Invalid,
+}
+
+/// Open a session that expires after `validity_days` without a refresh, with its first
+/// refresh token. The user's expired and revoked sessions are cleared out at the same time.
+pub async fn insert(
+    pool: &PgPool,
+    device: Option<&str>,
+    validity_days: i32,
+    user_id: i32,
+) -> Result<(Session, String), AppError> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "DELETE FROM sessions \
+         WHERE user_id = $1 AND (expires_date <= now() OR revoked_date IS NOT NULL)",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let session = sqlx::query_as::<_, Session>(&format!(
+        "INSERT INTO sessions (device, expires_date, user_id) \
+         VALUES ($1, now() + make_interval(days => $2), $3) RETURNING {COLUMNS}"
+    ))
+    .bind(device)
+    .bind(validity_days)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let refresh_token = insert_refresh_token(&mut tx, session.id).await?;
+    tx.commit().await?;
+
+    Ok((session, refresh_token))
+}
+
+/// Issue a refresh token for the session with a random secret, storing only its hash (see
+/// [`secret`]).
+async fn insert_refresh_token(conn: &mut PgConnection, session_id: i32) -> Result<String, AppError> {
+    let token = secret::generate(32);
+    sqlx::query("INSERT INTO refresh_tokens (token_hash, session_id) VALUES ($1, $2)")
+        .bind(secret::hash(&token))
+        .bind(session_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(token)
+}
+
+/// Use up a refresh token: if it is the session's current one, replace it with a new one and
+/// extend the session by `validity_days`; if it was already used, revoke its session.
+pub async fn refresh(pool: &PgPool, token: &str, validity_days: i32) -> Result<Refresh, AppError> {
+    let token_hash = secret::hash(token);
+    let mut tx = pool.begin().await?;
+
+    let session_id: Option<i32> = sqlx::query_scalar(
+        "UPDATE refresh_tokens SET used_date = now() \
+         WHERE token_hash = $1 AND used_date IS NULL \
+         RETURNING session_id",
+    )
+    .bind(&token_hash)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(session_id) = session_id else {
+        let reused: Option<(i32, i32)> = sqlx::query_as(
+            "UPDATE sessions SET revoked_date = now() \
+             WHERE revoked_date IS NULL AND id = (\
+                 SELECT session_id FROM refresh_tokens \
+                 WHERE token_hash = $1) \
+             RETURNING id, user_id",
+        )
+        .bind(&token_hash)
+        .fetch_optional(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        return Ok(match reused {
+            Some((session_id, user_id)) => Refresh::Reused { session_id, user_id },
+            None => Refresh::Invalid,
+        });
+    };
+
+    let session = sqlx::query_as::<_, Session>(&format!(
+        "UPDATE sessions \
+         SET last_seen_date = now(), expires_date = now() + make_interval(days => $2) \
+         WHERE id = $1 AND revoked_date IS NULL AND expires_date > now() \
+         RETURNING {COLUMNS}"
+    ))
+    .bind(session_id)
+    .bind(validity_days)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(session) = session else {
+        return Ok(Refresh::Invalid);
+    };
+
+    let refresh_token = insert_refresh_token(&mut tx, session.id).await?;
+    tx.commit().await?;
+
+    Ok(Refresh::Rotated {
+        session,
+        refresh_token,
+    })
+}
+
+/// Whether the session is open (and its user not disabled), recording that it was just seen: if
+/// so, whether the user must change their password first.
+pub async fn touch(pool: &PgPool, id: i32, user_id: i32) -> Result<Option<bool>, AppError> {
+    let password_change_required = sqlx::query_scalar(
+        "UPDATE sessions s SET last_seen_date = now() FROM app_users u \
+         WHERE s.id = $1 AND s.user_id = $2 AND s.revoked_date IS NULL \
+         AND s.expires_date > now() AND u.id = s.user_id AND NOT u.disabled \
+         RETURNING u.password_change_required",
+    )
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(password_change_required)
+}
+
+/// The user's open sessions, most recently seen first.
+pub async fn get_all(pool: &PgPool, user_id: i32) -> Result<Vec<Session>, AppError> {
+    let sessions = sqlx::query_as::<_, Session>(&format!(
+        "SELECT {COLUMNS} FROM sessions \
+         WHERE user_id = $1 AND revoked_date IS NULL AND expires_date > now() \
+         ORDER BY last_seen_date DESC, id DESC"
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sessions)
+}
+
+/// Revoke an open session: its refresh token and access tokens stop working at once.
+pub async fn revoke(pool: &PgPool, id: i32, user_id: i32) -> Result<Session, AppError> {
+    let session = sqlx::query_as::<_, Session>(&format!(
+        "UPDATE sessions SET revoked_date = now() \
+         WHERE id = $1 AND user_id = $2 AND revoked_date IS NULL AND expires_date > now() \
+         RETURNING {COLUMNS}"
+    ))
+    .bind(id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(session)
+}
+
+/// Revoke all of the user's sessions, e.g. when their logins are disabled.
+pub async fn revoke_all(pool: &PgPool, user_id: i32) -> Result<(), AppError> {
+    sqlx::query("UPDATE sessions SET revoked_date = now() WHERE user_id = $1 AND revoked_date IS NULL")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Revoke all of the user's sessions except `keep`, e.g. after a password change.
+pub async fn revoke_others(pool: &PgPool, user_id: i32, keep: i32) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE sessions SET revoked_date = now() \
+         WHERE user_id = $1 AND id <> $2 AND revoked_date IS NULL",
+    )
+    .bind(user_id)
+    .bind(keep)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::models::Settings;
+
+/// The settings, from the single row of the `settings` table.
+pub async fn get(pool: &PgPool) -> Result<Settings, AppError> {
+    let settings = sqlx::query_as::<_, Settings>("SELECT open_registration FROM settings")
+        .fetch_one(pool)
+        .await?;
+
+    Ok(settings)
+}
+
+pub async fn update(pool: &PgPool, settings: &Settings) -> Result<Settings, AppError> {
+    let settings = sqlx::query_as::<_, Settings>(
+        "UPDATE settings SET open_registration = $1 RETURNING open_registration",
+    )
+    .bind(settings.open_registration)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(settings)
+}
@@ -1,10 +1,11 @@
 use chrono::NaiveDateTime;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 
 use crate::error::AppError;
-use crate::models::{CategoryTypes, NewTransactionData, Transaction};
+use crate::models::{CategoryTypes, NewTransactionData, Transaction, TransactionStatuses};
 
-const COLUMNS: &str = "id, value, description, date, account, category, user_id";
+const COLUMNS: &str = "id, value, description, date, account, category, ledger_id, status, \
+    scheduled_transaction_id, occurrence";
 
 /// A transaction joined with its category and account, used to build the
 /// `TransactionTransferJoined` response.
@@ -19,27 +20,52 @@ pub struct TxJoinRow {
     pub category_name: String,
     pub account_id: i32,
     pub account_name: String,
-    pub user_id: i32,
+    pub ledger_id: i32,
+    pub status: TransactionStatuses,
 }
 
 const JOIN_SELECT: &str = "SELECT t.id, t.value, t.description, t.date, \
     t.category AS category_id, c.categorytype AS category_type, c.name AS category_name, \
-    t.account AS account_id, a.name AS account_name, t.user_id \
+    t.account AS account_id, a.name AS account_name, t.ledger_id, t.status \
     FROM transactions t \
     JOIN categories c ON c.id = t.category \
     JOIN accounts a ON a.id = t.account";
 
 pub async fn insert(pool: &PgPool, new: &NewTransactionData) -> Result<Transaction, AppError> {
+    insert_on(&mut *pool.acquire().await?, new).await
+}
+
+/// [`insert`], on the caller's transaction.
+pub async fn insert_on(
+    conn: &mut PgConnection,
+    new: &NewTransactionData,
+) -> Result<Transaction, AppError> {
     let transaction = sqlx::query_as::<_, Transaction>(&format!(
-        "INSERT INTO transactions (value, description, date, account, category, user_id) \
-         VALUES ($1, $2, $3, $4, $5, $6) RETURNING {COLUMNS}"
+        "INSERT INTO transactions (value, description, date, account, category, ledger_id, status, \
+            scheduled_transaction_id, occurrence) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING {COLUMNS}"
     ))
     .bind(new.value)
     .bind(new.description.as_str())
     .bind(new.date)
     .bind(new.account)
     .bind(new.category)
-    .bind(new.user_id)
+    .bind(new.ledger_id)
+    .bind(new.status)
+    .bind(new.scheduled_transaction_id)
+    .bind(new.occurrence)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(transaction)
+}
+
+pub async fn get(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Transaction, AppError> {
+    let transaction = sqlx::query_as::<_, Transaction>(&format!(
+        "SELECT {COLUMNS} FROM transactions WHERE ledger_id = $1 AND id = $2"
+    ))
+    .bind(ledger_id)
+    .bind(id)
     .fetch_one(pool)
     .await?;
 
@@ -49,12 +75,12 @@ pub async fn insert(pool: &PgPool, new: &NewTransactionData) -> Result<Transacti
 pub async fn get_all_of_account_joined(
     pool: &PgPool,
     account_id: i32,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<Vec<TxJoinRow>, AppError> {
     let rows = sqlx::query_as::<_, TxJoinRow>(&format!(
-        "{JOIN_SELECT} WHERE t.user_id = $1 AND t.account = $2 ORDER BY t.date DESC"
+        "{JOIN_SELECT} WHERE t.ledger_id = $1 AND t.account = $2 ORDER BY t.date DESC"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(account_id)
     .fetch_all(pool)
     .await?;
@@ -62,11 +88,11 @@ pub async fn get_all_of_account_joined(
     Ok(rows)
 }
 
-pub async fn get_joined(pool: &PgPool, id: i32, user_id: i32) -> Result<TxJoinRow, AppError> {
+pub async fn get_joined(pool: &PgPool, id: i32, ledger_id: i32) -> Result<TxJoinRow, AppError> {
     let row = sqlx::query_as::<_, TxJoinRow>(&format!(
-        "{JOIN_SELECT} WHERE t.user_id = $1 AND t.id = $2"
+        "{JOIN_SELECT} WHERE t.ledger_id = $1 AND t.id = $2"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -78,18 +104,32 @@ pub async fn update(
     pool: &PgPool,
     id: i32,
     new: &NewTransactionData,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<Transaction, AppError> {
     let transaction = sqlx::query_as::<_, Transaction>(&format!(
-        "UPDATE transactions SET value = $1, description = $2, date = $3, account = $4, category = $5 \
-         WHERE user_id = $6 AND id = $7 RETURNING {COLUMNS}"
+        "UPDATE transactions SET value = $1, description = $2, date = $3, account = $4, category = $5, \
+            status = $6 \
+         WHERE ledger_id = $7 AND id = $8 RETURNING {COLUMNS}"
     ))
     .bind(new.value)
     .bind(new.description.as_str())
     .bind(new.date)
     .bind(new.account)
     .bind(new.category)
-    .bind(user_id)
+    .bind(new.status)
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(transaction)
+}
+
+pub async fn delete(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Transaction, AppError> {
+    let transaction = sqlx::query_as::<_, Transaction>(&format!(
+        "DELETE FROM transactions WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -97,14 +137,63 @@ pub async fn update(
     Ok(transaction)
 }
 
-pub async fn delete(pool: &PgPool, id: i32, user_id: i32) -> Result<Transaction, AppError> {
+pub async fn set_status(
+    pool: &PgPool,
+    id: i32,
+    status: TransactionStatuses,
+    ledger_id: i32,
+) -> Result<Transaction, AppError> {
     let transaction = sqlx::query_as::<_, Transaction>(&format!(
-        "DELETE FROM transactions WHERE user_id = $1 AND id = $2 RETURNING {COLUMNS}"
+        "UPDATE transactions SET status = $1 WHERE ledger_id = $2 AND id = $3 RETURNING {COLUMNS}"
     ))
-    .bind(user_id)
+    .bind(status)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
 
     Ok(transaction)
 }
+
+/// Lock every cleared transaction of an account dated up to `until` as reconciled. Runs on
+/// the caller's transaction so finishing a reconciliation is all-or-nothing.
+pub async fn reconcile_cleared(
+    conn: &mut PgConnection,
+    account_id: i32,
+    until: NaiveDateTime,
+    ledger_id: i32,
+) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        "UPDATE transactions SET status = 'reconciled' \
+         WHERE ledger_id = $1 AND account = $2 AND status = 'cleared' AND date <= $3",
+    )
+    .bind(ledger_id)
+    .bind(account_id)
+    .bind(until)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// The total of a category's transactions dated from `from` up to (not including) `to`.
+pub async fn sum_in_category(
+    pool: &PgPool,
+    category_id: i32,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    ledger_id: i32,
+) -> Result<i32, AppError> {
+    let total: i32 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(value), 0)::int4 FROM transactions \
+         WHERE ledger_id = $1 AND category = $2 AND date >= $3 AND date < $4",
+    )
+    .bind(ledger_id)
+    .bind(category_id)
+    .bind(from)
+    .bind(to)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(total)
+}
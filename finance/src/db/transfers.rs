@@ -1,14 +1,14 @@
 use chrono::NaiveDateTime;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 
 use crate::error::AppError;
-use crate::models::{NewTransferData, Transfer};
+use crate::models::{NewTransferData, TransactionStatuses, Transfer};
 
-const COLUMNS: &str =
-    "id, origin_account, destination_account, value, description, date, user_id";
+const COLUMNS: &str = "id, origin_account, destination_account, value, description, date, \
+    ledger_id, origin_status, destination_status, scheduled_transaction_id, occurrence";
 
 /// A transfer leaving an account (origin = the account being viewed), joined with the
-/// origin account name.
+/// origin account name. `status` is the origin side's.
 #[derive(sqlx::FromRow)]
 pub struct TransferFromRow {
     pub id: i32,
@@ -17,10 +17,11 @@ pub struct TransferFromRow {
     pub date: NaiveDateTime,
     pub origin_account: i32,
     pub origin_name: String,
+    pub status: TransactionStatuses,
 }
 
 /// A transfer entering an account (destination = the account being viewed), joined with
-/// both the origin and destination account names.
+/// both the origin and destination account names. `status` is the destination side's.
 #[derive(sqlx::FromRow)]
 pub struct TransferToRow {
     pub id: i32,
@@ -31,30 +32,44 @@ pub struct TransferToRow {
     pub origin_name: String,
     pub destination_account: i32,
     pub dest_name: String,
+    pub status: TransactionStatuses,
 }
 
 pub async fn insert(pool: &PgPool, new: &NewTransferData) -> Result<Transfer, AppError> {
+    insert_on(&mut *pool.acquire().await?, new).await
+}
+
+/// [`insert`], on the caller's transaction.
+pub async fn insert_on(
+    conn: &mut PgConnection,
+    new: &NewTransferData,
+) -> Result<Transfer, AppError> {
     let transfer = sqlx::query_as::<_, Transfer>(&format!(
-        "INSERT INTO transfers (origin_account, destination_account, value, description, date, user_id) \
-         VALUES ($1, $2, $3, $4, $5, $6) RETURNING {COLUMNS}"
+        "INSERT INTO transfers (origin_account, destination_account, value, description, date, ledger_id, \
+            origin_status, destination_status, scheduled_transaction_id, occurrence) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING {COLUMNS}"
     ))
     .bind(new.origin_account)
     .bind(new.destination_account)
     .bind(new.value)
     .bind(new.description.as_str())
     .bind(new.date)
-    .bind(new.user_id)
-    .fetch_one(pool)
+    .bind(new.ledger_id)
+    .bind(new.origin_status)
+    .bind(new.destination_status)
+    .bind(new.scheduled_transaction_id)
+    .bind(new.occurrence)
+    .fetch_one(conn)
     .await?;
 
     Ok(transfer)
 }
 
-pub async fn get(pool: &PgPool, id: i32, user_id: i32) -> Result<Transfer, AppError> {
+pub async fn get(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Transfer, AppError> {
     let transfer = sqlx::query_as::<_, Transfer>(&format!(
-        "SELECT {COLUMNS} FROM transfers WHERE user_id = $1 AND id = $2"
+        "SELECT {COLUMNS} FROM transfers WHERE ledger_id = $1 AND id = $2"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -65,14 +80,15 @@ pub async fn get(pool: &PgPool, id: i32, user_id: i32) -> Result<Transfer, AppEr
 pub async fn get_from_account_joined(
     pool: &PgPool,
     account_id: i32,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<Vec<TransferFromRow>, AppError> {
     let rows = sqlx::query_as::<_, TransferFromRow>(
-        "SELECT tr.id, tr.value, tr.description, tr.date, tr.origin_account, o.name AS origin_name \
+        "SELECT tr.id, tr.value, tr.description, tr.date, tr.origin_account, o.name AS origin_name, \
+            tr.origin_status AS status \
          FROM transfers tr JOIN accounts o ON o.id = tr.origin_account \
-         WHERE tr.user_id = $1 AND tr.origin_account = $2",
+         WHERE tr.ledger_id = $1 AND tr.origin_account = $2",
     )
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(account_id)
     .fetch_all(pool)
     .await?;
@@ -83,18 +99,18 @@ pub async fn get_from_account_joined(
 pub async fn get_to_account_joined(
     pool: &PgPool,
     account_id: i32,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<Vec<TransferToRow>, AppError> {
     let rows = sqlx::query_as::<_, TransferToRow>(
         "SELECT tr.id, tr.value, tr.description, tr.date, \
             tr.origin_account, o.name AS origin_name, \
-            tr.destination_account, d.name AS dest_name \
+            tr.destination_account, d.name AS dest_name, tr.destination_status AS status \
          FROM transfers tr \
          JOIN accounts o ON o.id = tr.origin_account \
          JOIN accounts d ON d.id = tr.destination_account \
-         WHERE tr.user_id = $1 AND tr.destination_account = $2",
+         WHERE tr.ledger_id = $1 AND tr.destination_account = $2",
     )
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(account_id)
     .fetch_all(pool)
     .await?;
@@ -106,19 +122,79 @@ pub async fn update(
     pool: &PgPool,
     id: i32,
     new: &NewTransferData,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<Transfer, AppError> {
     let transfer = sqlx::query_as::<_, Transfer>(&format!(
         "UPDATE transfers SET origin_account = $1, destination_account = $2, value = $3, \
-            description = $4, date = $5 \
-         WHERE user_id = $6 AND id = $7 RETURNING {COLUMNS}"
+            description = $4, date = $5, origin_status = $6, destination_status = $7 \
+         WHERE ledger_id = $8 AND id = $9 RETURNING {COLUMNS}"
     ))
     .bind(new.origin_account)
     .bind(new.destination_account)
     .bind(new.value)
     .bind(new.description.as_str())
     .bind(new.date)
-    .bind(user_id)
+    .bind(new.origin_status)
+    .bind(new.destination_status)
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(transfer)
+}
+
+/// Total of the transfers into an account dated on or after `since`, e.g. the payments made
+/// to a credit card since its statement closed.
+pub async fn sum_into_since(
+    pool: &PgPool,
+    account_id: i32,
+    since: NaiveDateTime,
+    ledger_id: i32,
+) -> Result<i32, AppError> {
+    let total: i32 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(value), 0)::int4 FROM transfers \
+         WHERE ledger_id = $1 AND destination_account = $2 AND date >= $3",
+    )
+    .bind(ledger_id)
+    .bind(account_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(total)
+}
+
+pub async fn delete(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Transfer, AppError> {
+    let transfer = sqlx::query_as::<_, Transfer>(&format!(
+        "DELETE FROM transfers WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(ledger_id)
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(transfer)
+}
+
+/// Set the status of the transfer's side in `account_id`, leaving the other side's.
+pub async fn set_status(
+    pool: &PgPool,
+    id: i32,
+    account_id: i32,
+    status: TransactionStatuses,
+    ledger_id: i32,
+) -> Result<Transfer, AppError> {
+    let transfer = sqlx::query_as::<_, Transfer>(&format!(
+        "UPDATE transfers SET \
+            origin_status = CASE WHEN origin_account = $2 THEN $1 ELSE origin_status END, \
+            destination_status = \
+                CASE WHEN destination_account = $2 THEN $1 ELSE destination_status END \
+         WHERE ledger_id = $3 AND id = $4 RETURNING {COLUMNS}"
+    ))
+    .bind(status)
+    .bind(account_id)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
@@ -126,14 +202,48 @@ pub async fn update(
     Ok(transfer)
 }
 
-pub async fn delete(pool: &PgPool, id: i32, user_id: i32) -> Result<Transfer, AppError> {
+/// Put the reconciled sides of a transfer back to cleared.
+pub async fn unlock(pool: &PgPool, id: i32, ledger_id: i32) -> Result<Transfer, AppError> {
     let transfer = sqlx::query_as::<_, Transfer>(&format!(
-        "DELETE FROM transfers WHERE user_id = $1 AND id = $2 RETURNING {COLUMNS}"
+        "UPDATE transfers SET \
+            origin_status = CASE WHEN origin_status = 'reconciled' THEN 'cleared' \
+                ELSE origin_status END, \
+            destination_status = CASE WHEN destination_status = 'reconciled' THEN 'cleared' \
+                ELSE destination_status END \
+         WHERE ledger_id = $1 AND id = $2 RETURNING {COLUMNS}"
     ))
-    .bind(user_id)
+    .bind(ledger_id)
     .bind(id)
     .fetch_one(pool)
     .await?;
 
     Ok(transfer)
 }
+
+/// Lock the cleared side in an account of every transfer dated up to `until` as reconciled.
+/// Runs on the caller's transaction so finishing a reconciliation is all-or-nothing.
+pub async fn reconcile_cleared(
+    conn: &mut PgConnection,
+    account_id: i32,
+    until: NaiveDateTime,
+    ledger_id: i32,
+) -> Result<u64, AppError> {
+    let result = sqlx::query(
+        "UPDATE transfers SET \
+            origin_status = CASE WHEN origin_account = $2 AND origin_status = 'cleared' \
+                THEN 'reconciled' ELSE origin_status END, \
+            destination_status = \
+                CASE WHEN destination_account = $2 AND destination_status = 'cleared' \
+                THEN 'reconciled' ELSE destination_status END \
+         WHERE ledger_id = $1 AND date <= $3 \
+            AND ((origin_account = $2 AND origin_status = 'cleared') \
+                OR (destination_account = $2 AND destination_status = 'cleared'))",
+    )
+    .bind(ledger_id)
+    .bind(account_id)
+    .bind(until)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
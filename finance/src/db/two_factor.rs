@@ -0,0 +1,147 @@
+use sqlx::{PgConnection, PgPool};
+
+use crate::error::AppError;
+use crate::models::{TwoFactorSecret, TwoFactorStatus};
+use crate::secret;
+
+/// Recovery codes issued at a time.
+const RECOVERY_CODES: usize = 10;
+
+pub async fn get(pool: &PgPool, user_id: i32) -> Result<Option<TwoFactorSecret>, AppError> {
+    let secret = sqlx::query_as::<_, TwoFactorSecret>(
+        "SELECT totp_secret, totp_enabled, totp_last_step FROM app_users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(secret)
+}
+
+pub async fn get_status(pool: &PgPool, user_id: i32) -> Result<TwoFactorStatus, AppError> {
+    let status = sqlx::query_as::<_, TwoFactorStatus>(
+        "SELECT totp_enabled AS enabled, \
+         (SELECT count(*) FROM recovery_codes r \
+          WHERE r.user_id = u.id AND r.used_date IS NULL) AS recovery_codes_left \
+         FROM app_users u WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(status)
+}
+
+/// Store a new, not yet confirmed, encrypted secret. `false` if two-factor authentication is
+/// already enabled, in which case the secret in use is kept.
+pub async fn set_secret(pool: &PgPool, user_id: i32, encrypted: &[u8]) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        "UPDATE app_users SET totp_secret = $1, totp_last_step = NULL \
+         WHERE id = $2 AND NOT totp_enabled",
+    )
+    .bind(encrypted)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Turn two-factor authentication on, returning the first recovery codes.
+pub async fn enable(pool: &PgPool, user_id: i32) -> Result<Vec<String>, AppError> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE app_users SET totp_enabled = true WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    let codes = insert_recovery_codes(&mut tx, user_id).await?;
+
+    tx.commit().await?;
+    Ok(codes)
+}
+
+/// Turn two-factor authentication off, forgetting the secret and the recovery codes.
+pub async fn disable(pool: &PgPool, user_id: i32) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "UPDATE app_users SET totp_secret = NULL, totp_enabled = false, totp_last_step = NULL \
+         WHERE id = $1",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Replace all of the user's recovery codes with new ones.
+pub async fn replace_recovery_codes(pool: &PgPool, user_id: i32) -> Result<Vec<String>, AppError> {
+    let mut tx = pool.begin().await?;
+    let codes = insert_recovery_codes(&mut tx, user_id).await?;
+    tx.commit().await?;
+
+    Ok(codes)
+}
+
+/// Record `step` as the last one a code was accepted for, unless a code of that step or a later
+/// one was already accepted (a replay, or a concurrent login with the same code).
+pub async fn use_step(pool: &PgPool, user_id: i32, step: i64) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        "UPDATE app_users SET totp_last_step = $1 \
+         WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1)",
+    )
+    .bind(step)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Mark an unused recovery code of the user as used. Whether there was one.
+pub async fn use_recovery_code(pool: &PgPool, user_id: i32, code: &str) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        "UPDATE recovery_codes SET used_date = now() \
+         WHERE user_id = $1 AND code_hash = $2 \
+         AND used_date IS NULL",
+    )
+    .bind(user_id)
+    .bind(secret::hash(code))
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete the user's recovery codes and issue [`RECOVERY_CODES`] new ones with random secrets,
+/// storing only their hashes (see [`secret`]).
+async fn insert_recovery_codes(
+    conn: &mut PgConnection,
+    user_id: i32,
+) -> Result<Vec<String>, AppError> {
+    sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let codes: Vec<String> = (0..RECOVERY_CODES).map(|_| secret::generate(5)).collect();
+    let hashes: Vec<String> = codes.iter().map(|code| secret::hash(code)).collect();
+
+    sqlx::query(
+        "INSERT INTO recovery_codes (code_hash, user_id) \
+         SELECT code_hash, $2 FROM unnest($1::varchar[]) AS code_hash",
+    )
+    .bind(&hashes)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(codes)
+}
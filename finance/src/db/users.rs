@@ -1,45 +1,406 @@
+use chrono::NaiveDate;
 use sqlx::PgPool;
 
+use crate::db;
 use crate::error::AppError;
-use crate::models::AppUser;
+use crate::models::{
+    AdminUser, AppUser, DigestRecipient, GetAppUser, NotificationPreferences, StorageStats,
+    TableUsage,
+};
+use crate::secret;
 
-/// Insert a new user, hashing the password in the database with pgcrypto's
-/// `crypt()` / `gen_salt('bf', rounds)` (blowfish), preserving the original scheme so
-/// that users created by the previous backend continue to authenticate.
-pub async fn insert(
-    pool: &PgPool,
-    name: &str,
-    password: &str,
-    bf_rounds: i32,
-) -> Result<AppUser, AppError> {
+/// Insert a new user with an already hashed password (see [`crate::password`]), a random
+/// digest unsubscribe token, and their personal ledger.
+pub async fn insert(pool: &PgPool, name: &str, password_hash: &str) -> Result<AppUser, AppError> {
+    let mut tx = pool.begin().await?;
     let user = sqlx::query_as::<_, AppUser>(
-        "INSERT INTO app_users (name, password) \
-         VALUES ($1, crypt($2, gen_salt('bf', $3))) \
+        "INSERT INTO app_users (name, password, unsubscribe_token) VALUES ($1, $2, $3) \
          RETURNING id, name, password",
     )
     .bind(name)
-    .bind(password)
-    .bind(bf_rounds)
-    .fetch_one(pool)
+    .bind(password_hash)
+    .bind(secret::generate(24))
+    .fetch_one(&mut *tx)
     .await?;
+    db::ledgers::insert(&mut tx, name, user.id).await?;
 
+    tx.commit().await?;
     Ok(user)
 }
 
-/// Return the user if the provided plaintext password matches the stored bcrypt hash.
-pub async fn authenticate(
-    pool: &PgPool,
-    name: &str,
-    password: &str,
-) -> Result<Option<AppUser>, AppError> {
+/// The user with this name, with their password hash.
+pub async fn get_by_name(pool: &PgPool, name: &str) -> Result<Option<AppUser>, AppError> {
     let user = sqlx::query_as::<_, AppUser>(
-        "SELECT id, name, password FROM app_users \
-         WHERE name = $1 AND password = crypt($2, password)",
+        "SELECT id, name, password FROM app_users WHERE name = $1",
     )
     .bind(name)
-    .bind(password)
     .fetch_optional(pool)
     .await?;
 
     Ok(user)
 }
+
+/// The ledger data tables, exported in this order (from every ledger the user is a member of),
+/// each sorted by its key. Prices and goal accounts are exported through the securities and goals
+/// they belong to; sessions, calendar and API tokens, recovery codes, invites and the audit log
+/// are not exported.
+const EXPORT_TABLES: &[(&str, &str)] = &[
+    ("categories", "id"),
+    ("accounts", "id"),
+    ("transactions", "id"),
+    ("transfers", "id"),
+    ("scheduled_transactions", "id"),
+    ("scheduled_occurrences", "id"),
+    ("reconciliations", "id"),
+    ("loans", "account_id"),
+    ("goals", "id"),
+    ("securities", "id"),
+    ("investment_transactions", "id"),
+];
+
+/// The ledgers of the user `$1`.
+const LEDGERS: &str = "SELECT ledger_id FROM ledger_members WHERE user_id = $1";
+
+/// The ledgers the user `$1` owns, whose data counts as theirs in [`storage_stats`].
+const OWNED_LEDGERS: &str =
+    "SELECT ledger_id FROM ledger_members WHERE user_id = $1 AND role = 'owner'";
+
+/// Columns of [`GetAppUser`].
+const COLUMNS: &str = "id, name, is_admin, password_change_required";
+
+/// Users `u` as [`AdminUser`]s.
+const ADMIN_SELECT: &str = "SELECT u.id, u.name, u.email, u.created_date, u.is_admin, u.disabled, \
+     u.password_change_required, \
+     (SELECT max(s.last_seen_date) FROM sessions s WHERE s.user_id = u.id) AS last_seen_date, \
+     (SELECT count(*) FROM ledger_members m WHERE m.user_id = u.id) AS ledgers \
+     FROM app_users u";
+
+pub async fn get(pool: &PgPool, user_id: i32) -> Result<GetAppUser, AppError> {
+    let user = sqlx::query_as::<_, GetAppUser>(&format!(
+        "SELECT {COLUMNS} FROM app_users WHERE id = $1"
+    ))
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Whether an admin disabled the user's logins.
+pub async fn is_disabled(pool: &PgPool, user_id: i32) -> Result<bool, AppError> {
+    let disabled = sqlx::query_scalar("SELECT disabled FROM app_users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(disabled)
+}
+
+pub async fn get_with_password(pool: &PgPool, user_id: i32) -> Result<AppUser, AppError> {
+    let user = sqlx::query_as::<_, AppUser>(
+        "SELECT id, name, password FROM app_users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Replace the user's password hash, which fulfils a required password change.
+pub async fn update_password(
+    pool: &PgPool,
+    user_id: i32,
+    password_hash: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE app_users SET password = $1, password_change_required = false WHERE id = $2",
+    )
+        .bind(password_hash)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Replace the user's password hash with a rehash of the same password, unless the password was
+/// changed in the meantime. Whether it was replaced.
+pub async fn replace_password(
+    pool: &PgPool,
+    user_id: i32,
+    old_hash: &str,
+    new_hash: &str,
+) -> Result<bool, AppError> {
+    let result = sqlx::query("UPDATE app_users SET password = $1 WHERE id = $2 AND password = $3")
+        .bind(new_hash)
+        .bind(user_id)
+        .bind(old_hash)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Rename the user. A name that is already taken is a `Conflict` (the column is `UNIQUE`).
+pub async fn update_name(pool: &PgPool, user_id: i32, name: &str) -> Result<GetAppUser, AppError> {
+    let user = sqlx::query_as::<_, GetAppUser>(&format!(
+        "UPDATE app_users SET name = $1 WHERE id = $2 RETURNING {COLUMNS}"
+    ))
+    .bind(name)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Delete the user and, through the `ON DELETE CASCADE` foreign keys, all of their data. Their
+/// ledgers are deleted too, unless shared with others (see [`db::ledgers::leave_all`]).
+pub async fn delete(pool: &PgPool, user_id: i32) -> Result<GetAppUser, AppError> {
+    let mut tx = pool.begin().await?;
+    db::ledgers::leave_all(&mut tx, user_id).await?;
+    let user = sqlx::query_as::<_, GetAppUser>(&format!(
+        "DELETE FROM app_users WHERE id = $1 RETURNING {COLUMNS}"
+    ))
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(user)
+}
+
+/// All of the user's data as one JSON document: the user (without the password) and one array
+/// of rows per table, with the values as stored.
+pub async fn export(pool: &PgPool, user_id: i32) -> Result<serde_json::Value, AppError> {
+    let tables: Vec<String> = EXPORT_TABLES
+        .iter()
+        .map(|(table, key)| {
+            format!(
+                "'{table}', (SELECT coalesce(json_agg(t ORDER BY t.{key}), '[]') FROM {table} t \
+                 WHERE t.ledger_id IN ({LEDGERS}))"
+            )
+        })
+        .collect();
+
+    let export: String = sqlx::query_scalar(&format!(
+        "SELECT json_build_object(\
+             'exported_date', now(), \
+             'user', (SELECT json_build_object('id', id, 'name', name, 'email', email) \
+                      FROM app_users WHERE id = $1), \
+             'ledgers', (SELECT coalesce(json_agg(t ORDER BY t.id), '[]') FROM ledgers t \
+                         WHERE t.id IN ({LEDGERS})), \
+             'oidc_identities', (SELECT coalesce(json_agg(t ORDER BY t.id), '[]') \
+                                 FROM oidc_identities t WHERE t.user_id = $1), \
+             {}, \
+             'goal_accounts', (SELECT coalesce(json_agg(t ORDER BY t.goal_id, t.account_id), '[]') \
+                               FROM goal_accounts t JOIN goals g ON g.id = t.goal_id \
+                               WHERE g.ledger_id IN ({LEDGERS})), \
+             'prices', (SELECT coalesce(json_agg(t ORDER BY t.security_id, t.date), '[]') \
+                        FROM prices t JOIN securities s ON s.id = t.security_id \
+                        WHERE s.ledger_id IN ({LEDGERS}))\
+         )::text",
+        tables.join(", ")
+    ))
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    serde_json::from_str(&export).map_err(|e| AppError::Internal(format!("invalid export: {e}")))
+}
+
+/// Every user, by id, as admins see them.
+pub async fn get_all_admin(pool: &PgPool) -> Result<Vec<AdminUser>, AppError> {
+    let users = sqlx::query_as::<_, AdminUser>(&format!("{ADMIN_SELECT} ORDER BY u.id"))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(users)
+}
+
+pub async fn get_admin(pool: &PgPool, user_id: i32) -> Result<AdminUser, AppError> {
+    let user = sqlx::query_as::<_, AdminUser>(&format!("{ADMIN_SELECT} WHERE u.id = $1"))
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(user)
+}
+
+/// Make the users with these names admins, returning the names that exist.
+pub async fn grant_admin(pool: &PgPool, names: &[String]) -> Result<Vec<String>, AppError> {
+    let granted = sqlx::query_scalar(
+        "UPDATE app_users SET is_admin = true WHERE name = ANY($1) RETURNING name",
+    )
+    .bind(names)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(granted)
+}
+
+/// Disable or enable the user's logins. A disabled user's sessions and tokens stop working, and
+/// they are sent no digests.
+pub async fn set_disabled(pool: &PgPool, user_id: i32, disabled: bool) -> Result<(), AppError> {
+    let result = sqlx::query("UPDATE app_users SET disabled = $1 WHERE id = $2")
+        .bind(disabled)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// Require the user to change their password before anything else (see [`update_password`]).
+pub async fn require_password_change(pool: &PgPool, user_id: i32) -> Result<(), AppError> {
+    let result = sqlx::query("UPDATE app_users SET password_change_required = true WHERE id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// The rows and bytes (as stored, without indexes) of the ledgers the user owns, per table of
+/// [`EXPORT_TABLES`].
+pub async fn storage_stats(pool: &PgPool, user_id: i32) -> Result<StorageStats, AppError> {
+    let ledgers: i64 = sqlx::query_scalar(&format!("SELECT count(*) FROM ({OWNED_LEDGERS}) l"))
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    let tables: Vec<String> = EXPORT_TABLES
+        .iter()
+        .map(|(table, _)| {
+            format!(
+                "SELECT '{table}' AS \"table\", count(*) AS rows, \
+                 coalesce(sum(pg_column_size(t.*)), 0)::bigint AS bytes \
+                 FROM {table} t WHERE t.ledger_id IN ({OWNED_LEDGERS})"
+            )
+        })
+        .collect();
+    let tables = sqlx::query_as::<_, TableUsage>(&tables.join(" UNION ALL "))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(StorageStats {
+        user_id,
+        ledgers,
+        rows: tables.iter().map(|table| table.rows).sum(),
+        bytes: tables.iter().map(|table| table.bytes).sum(),
+        tables,
+    })
+}
+
+pub async fn get_notification_preferences(
+    pool: &PgPool,
+    user_id: i32,
+) -> Result<NotificationPreferences, AppError> {
+    let preferences = sqlx::query_as::<_, NotificationPreferences>(
+        "SELECT email, digest_enabled, digest_lead_days FROM app_users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(preferences)
+}
+
+pub async fn update_notification_preferences(
+    pool: &PgPool,
+    preferences: &NotificationPreferences,
+    user_id: i32,
+) -> Result<NotificationPreferences, AppError> {
+    let preferences = sqlx::query_as::<_, NotificationPreferences>(
+        "UPDATE app_users SET email = $1, digest_enabled = $2, digest_lead_days = $3 \
+         WHERE id = $4 \
+         RETURNING email, digest_enabled, digest_lead_days",
+    )
+    .bind(&preferences.email)
+    .bind(preferences.digest_enabled)
+    .bind(preferences.digest_lead_days)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(preferences)
+}
+
+/// Turn off the digest of the user the unsubscribe token belongs to; `None` for an unknown token.
+pub async fn unsubscribe(pool: &PgPool, token: &str) -> Result<Option<i32>, AppError> {
+    let user_id: Option<i32> = sqlx::query_scalar(
+        "UPDATE app_users SET digest_enabled = false WHERE unsubscribe_token = $1 RETURNING id",
+    )
+    .bind(token)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user_id)
+}
+
+/// Digest recipients `u` matching `filter`, with their default ledger (users without a ledger
+/// are left out).
+fn recipients_where(filter: &str) -> String {
+    format!(
+        "SELECT u.id, u.name, u.email, u.digest_lead_days, u.unsubscribe_token, d.ledger_id \
+         FROM app_users u CROSS JOIN LATERAL (SELECT m.ledger_id FROM ledger_members m \
+         WHERE m.user_id = u.id ORDER BY {} LIMIT 1) d WHERE {filter}",
+        db::ledgers::DEFAULT_ORDER
+    )
+}
+
+/// The user as a digest recipient, if they have an email address (whether or not the digest is
+/// enabled).
+pub async fn get_digest_recipient(
+    pool: &PgPool,
+    user_id: i32,
+) -> Result<Option<DigestRecipient>, AppError> {
+    let recipient = sqlx::query_as::<_, DigestRecipient>(&recipients_where(
+        "u.id = $1 AND u.email IS NOT NULL",
+    ))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(recipient)
+}
+
+/// Users with the digest enabled who have not been sent one on `today` yet.
+pub async fn get_digest_recipients(
+    pool: &PgPool,
+    today: NaiveDate,
+) -> Result<Vec<DigestRecipient>, AppError> {
+    let recipients = sqlx::query_as::<_, DigestRecipient>(&recipients_where(
+        "u.digest_enabled AND NOT u.disabled AND u.email IS NOT NULL \
+         AND (u.last_digest_date IS NULL OR u.last_digest_date < $1) ORDER BY u.id",
+    ))
+    .bind(today)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(recipients)
+}
+
+pub async fn set_last_digest_date(
+    pool: &PgPool,
+    user_id: i32,
+    date: NaiveDate,
+) -> Result<(), AppError> {
+    sqlx::query("UPDATE app_users SET last_digest_date = $1 WHERE id = $2")
+        .bind(date)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
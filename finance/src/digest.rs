@@ -0,0 +1,187 @@
+//! The daily email digest of scheduled transactions.
+//!
+//! A background task wakes up every [`CHECK_INTERVAL`] and, once the local time is past
+//! `DIGEST_HOUR`, emails every user who enabled the digest and has not received one today. A
+//! digest covers the user's default ledger: the occurrences that are overdue or due within the
+//! user's lead time and the accounts projected to go negative (see [`service::digest`]); nothing
+//! is sent on a day with nothing to report. A user is only marked as done for the day once their
+//! email was accepted by the relay, so a failed send is retried at the next check.
+
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, Timelike};
+
+use crate::calendar::format_amount;
+use crate::db;
+use crate::error::AppError;
+use crate::mail::{Email, Mailer};
+use crate::models::{Digest, DigestRecipient};
+use crate::service;
+use crate::state::AppState;
+
+/// Time between two checks for digests to send.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Start the daily digest job.
+pub fn start(state: AppState, mailer: Arc<Mailer>) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = check(&state, &mailer, Local::now().naive_local()).await {
+                tracing::warn!("sending the email digests failed: {err}");
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Send the digests due at `now` (local time): none before `DIGEST_HOUR`, then each
+/// recipient's for the day until it was sent.
+pub async fn check(
+    state: &AppState,
+    mailer: &Mailer,
+    now: NaiveDateTime,
+) -> Result<(), AppError> {
+    if now.hour() < state.config.digest_hour {
+        return Ok(());
+    }
+
+    let today = now.date();
+    for recipient in db::users::get_digest_recipients(&state.pool, today).await? {
+        match send(state, mailer, &recipient, today).await {
+            Ok(_) => db::users::set_last_digest_date(&state.pool, recipient.id, today).await?,
+            Err(err) => tracing::warn!("sending the digest to user {} failed: {err}", recipient.id),
+        }
+    }
+    Ok(())
+}
+
+/// Build and send `recipient`'s digest for `today`. `false` if there was nothing to report, in
+/// which case no email is sent.
+pub async fn send(
+    state: &AppState,
+    mailer: &Mailer,
+    recipient: &DigestRecipient,
+    today: NaiveDate,
+) -> Result<bool, AppError> {
+    let digest = service::digest(
+        &state.pool,
+        &state.holidays,
+        recipient.ledger_id,
+        today,
+        recipient.digest_lead_days,
+    )
+    .await?;
+    if digest.due.is_empty() && digest.overdrafts.is_empty() {
+        return Ok(false);
+    }
+
+    let unsubscribe_url = format!(
+        "{}/api/unsubscribe/{}",
+        state.config.public_url, recipient.unsubscribe_token
+    );
+    mailer
+        .send(Email {
+            to: recipient.email.clone(),
+            subject: subject(&digest),
+            body: body(recipient, &digest, today, &unsubscribe_url),
+            unsubscribe_url: Some(unsubscribe_url),
+        })
+        .await?;
+
+    Ok(true)
+}
+
+/// e.g. "2 scheduled transactions due, 1 account projected to go negative".
+fn subject(digest: &Digest) -> String {
+    let plural = |count: usize, one: &str, many: &str| {
+        format!("{count} {}", if count == 1 { one } else { many })
+    };
+
+    let mut parts = Vec::new();
+    if !digest.due.is_empty() {
+        parts.push(format!(
+            "{} due",
+            plural(digest.due.len(), "scheduled transaction", "scheduled transactions")
+        ));
+    }
+    if !digest.overdrafts.is_empty() {
+        parts.push(format!(
+            "{} projected to go negative",
+            plural(digest.overdrafts.len(), "account", "accounts")
+        ));
+    }
+    parts.join(", ")
+}
+
+fn body(
+    recipient: &DigestRecipient,
+    digest: &Digest,
+    today: NaiveDate,
+    unsubscribe_url: &str,
+) -> String {
+    // Writing to a String cannot fail.
+    let mut body = String::new();
+    let _ = writeln!(body, "Hi {},", recipient.name);
+    let _ = writeln!(body);
+
+    let overdue: Vec<_> = digest.due.iter().filter(|entry| entry.overdue).collect();
+    let upcoming: Vec<_> = digest.due.iter().filter(|entry| !entry.overdue).collect();
+
+    if !overdue.is_empty() {
+        let _ = writeln!(body, "Overdue:");
+        for entry in overdue {
+            let _ = writeln!(
+                body,
+                "  {}  {}  {} ({})",
+                entry.date.format("%Y-%m-%d"),
+                format_amount(entry.value),
+                entry.label,
+                entry.accounts
+            );
+        }
+        let _ = writeln!(body);
+    }
+
+    if !upcoming.is_empty() {
+        let _ = writeln!(body, "Due:");
+        for entry in upcoming {
+            let when = match (entry.date.date() - today).num_days() {
+                0 => "today".to_string(),
+                1 => "tomorrow".to_string(),
+                _ => entry.date.format("%Y-%m-%d").to_string(),
+            };
+            let _ = writeln!(
+                body,
+                "  {when}  {}  {} ({})",
+                format_amount(entry.value),
+                entry.label,
+                entry.accounts
+            );
+        }
+        let _ = writeln!(body);
+    }
+
+    if !digest.overdrafts.is_empty() {
+        let _ = writeln!(
+            body,
+            "Projected to go negative in the next {} days:",
+            service::OVERDRAFT_DAYS
+        );
+        for overdraft in &digest.overdrafts {
+            let _ = writeln!(
+                body,
+                "  {}  from {}, down to {}",
+                overdraft.account_name,
+                overdraft.date.format("%Y-%m-%d"),
+                format_amount(overdraft.lowest_balance)
+            );
+        }
+        let _ = writeln!(body);
+    }
+
+    let _ = writeln!(body, "--");
+    let _ = writeln!(body, "To stop receiving this digest: {unsubscribe_url}");
+    body
+}
@@ -1,3 +1,4 @@
+use axum::http::header::RETRY_AFTER;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
@@ -9,12 +10,18 @@ use serde_json::json;
 pub enum AppError {
     #[error("unauthorized")]
     Unauthorized,
+    /// Authenticated, but not allowed to do this.
+    #[error("forbidden")]
+    Forbidden,
     #[error("not found")]
     NotFound,
     #[error("conflict")]
     Conflict,
     #[error("bad request")]
     BadRequest,
+    /// Rate limited; the client may retry after this many seconds.
+    #[error("too many requests")]
+    TooManyRequests(u64),
     #[error("internal server error: {0}")]
     Internal(String),
 }
@@ -23,9 +30,11 @@ impl AppError {
     fn status(&self) -> StatusCode {
         match self {
             AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden => StatusCode::FORBIDDEN,
             AppError::NotFound => StatusCode::NOT_FOUND,
             AppError::Conflict => StatusCode::CONFLICT,
             AppError::BadRequest => StatusCode::BAD_REQUEST,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
             AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -37,7 +46,11 @@ impl IntoResponse for AppError {
         if let AppError::Internal(ref message) = self {
             tracing::error!("internal error: {}", message);
         }
-        (status, Json(json!({ "error": self.to_string() }))).into_response()
+        let body = Json(json!({ "error": self.to_string() }));
+        if let AppError::TooManyRequests(seconds) = self {
+            return (status, [(RETRY_AFTER, seconds.to_string())], body).into_response();
+        }
+        (status, body).into_response()
     }
 }
 
@@ -1,21 +1,29 @@
 use axum::extract::{Path, State};
-use axum::routing::{get, post};
+use axum::routing::{get, post, put};
 use axum::{Json, Router};
+use chrono::Local;
 
 use crate::auth::AuthUser;
 use crate::db;
 use crate::error::AppError;
-use crate::models::{Account, GetAccount, PatchAccount, PostAccount};
+use crate::models::{
+    Account, AccountTypes, CardStatement, GetAccount, NewAccountData, PatchAccount, PostAccount,
+    PutAccountOrder, PutCardSettings,
+};
 use crate::service;
 use crate::state::AppState;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/api/accounts", post(post_account).get(get_accounts))
+        .route("/api/accounts/archived", get(get_archived_accounts))
+        .route("/api/accounts/order", put(put_account_order))
         .route(
             "/api/accounts/{id}",
             get(get_account).patch(patch_account).delete(delete_account),
         )
+        .route("/api/accounts/{id}/card-settings", put(put_card_settings))
+        .route("/api/accounts/{id}/statement", get(get_statement))
 }
 
 async fn post_account(
@@ -23,20 +31,45 @@ async fn post_account(
     auth: AuthUser,
     Json(body): Json<PostAccount>,
 ) -> Result<Json<GetAccount>, AppError> {
-    let account = db::accounts::insert(&state.pool, &body.name, auth.user_id).await?;
-    Ok(Json(GetAccount {
-        id: account.id,
-        name: account.name,
-        balance: 0,
-        user_id: account.user_id,
-    }))
+    let data = NewAccountData {
+        name: body.name,
+        account_type: body.account_type,
+        opening_balance: body.opening_balance,
+        opening_date: body.opening_date,
+        archived: false,
+        sort_order: 0,
+    };
+
+    let account = db::accounts::insert(&state.pool, &data, auth.ledger_id).await?;
+    let balance = db::accounts::balance(&state.pool, account.id, auth.ledger_id).await?;
+    Ok(Json(service::account_to_dto(account, balance)))
 }
 
+/// The ledger's active accounts; archived accounts are listed separately.
 async fn get_accounts(
     State(state): State<AppState>,
     auth: AuthUser,
 ) -> Result<Json<Vec<GetAccount>>, AppError> {
-    Ok(Json(service::accounts_with_balance(&state.pool, auth.user_id).await?))
+    Ok(Json(service::accounts_with_balance(&state.pool, auth.ledger_id).await?))
+}
+
+async fn get_archived_accounts(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<GetAccount>>, AppError> {
+    Ok(Json(
+        service::archived_accounts_with_balance(&state.pool, auth.ledger_id).await?,
+    ))
+}
+
+/// Reorder the ledger's accounts; returns the active accounts in their new order.
+async fn put_account_order(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<PutAccountOrder>,
+) -> Result<Json<Vec<GetAccount>>, AppError> {
+    db::accounts::reorder(&state.pool, &body.account_ids, auth.ledger_id).await?;
+    Ok(Json(service::accounts_with_balance(&state.pool, auth.ledger_id).await?))
 }
 
 async fn get_account(
@@ -44,14 +77,9 @@ async fn get_account(
     Path(id): Path<i32>,
     auth: AuthUser,
 ) -> Result<Json<GetAccount>, AppError> {
-    let account = db::accounts::get(&state.pool, id, auth.user_id).await?;
-    let balance = db::accounts::balance(&state.pool, account.id, auth.user_id).await?;
-    Ok(Json(GetAccount {
-        id: account.id,
-        name: account.name,
-        balance,
-        user_id: account.user_id,
-    }))
+    let account = db::accounts::get(&state.pool, id, auth.ledger_id).await?;
+    let balance = db::accounts::balance(&state.pool, account.id, auth.ledger_id).await?;
+    Ok(Json(service::account_to_dto(account, balance)))
 }
 
 async fn patch_account(
@@ -60,14 +88,22 @@ async fn patch_account(
     auth: AuthUser,
     Json(body): Json<PatchAccount>,
 ) -> Result<Json<GetAccount>, AppError> {
-    let account = db::accounts::update(&state.pool, id, &body.name, auth.user_id).await?;
-    let balance = db::accounts::balance(&state.pool, account.id, auth.user_id).await?;
-    Ok(Json(GetAccount {
-        id: account.id,
-        name: account.name,
-        balance,
-        user_id: account.user_id,
-    }))
+    let current = db::accounts::get(&state.pool, id, auth.ledger_id).await?;
+
+    let data = NewAccountData {
+        name: body.name,
+        account_type: body.account_type.unwrap_or(current.account_type),
+        opening_balance: body.opening_balance.unwrap_or(current.opening_balance),
+        opening_date: body.opening_date.unwrap_or(current.opening_date),
+        archived: body.archived.unwrap_or(current.archived),
+        sort_order: body.sort_order.unwrap_or(current.sort_order),
+    };
+
+    let account = db::accounts::update(&state.pool, id, &data, auth.ledger_id).await?;
+    service::sync_autopay(&state.pool, &[id], auth.ledger_id).await?;
+
+    let balance = db::accounts::balance(&state.pool, account.id, auth.ledger_id).await?;
+    Ok(Json(service::account_to_dto(account, balance)))
 }
 
 async fn delete_account(
@@ -75,5 +111,56 @@ async fn delete_account(
     Path(id): Path<i32>,
     auth: AuthUser,
 ) -> Result<Json<Account>, AppError> {
-    Ok(Json(db::accounts::delete(&state.pool, id, auth.user_id).await?))
+    Ok(Json(db::accounts::delete(&state.pool, id, auth.ledger_id).await?))
+}
+
+/// Set a credit card's statement closing day, payment due day and autopay account. 400 for
+/// non-card accounts, days outside 1-31, or autopay from the card itself; 404 if the autopay
+/// account does not exist.
+async fn put_card_settings(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+    Json(body): Json<PutCardSettings>,
+) -> Result<Json<GetAccount>, AppError> {
+    let current = db::accounts::get(&state.pool, id, auth.ledger_id).await?;
+    if current.account_type != AccountTypes::CreditCard {
+        return Err(AppError::BadRequest);
+    }
+
+    let valid_day = |day: Option<i32>| day.is_none_or(|d| (1..=31).contains(&d));
+    if !valid_day(body.statement_closing_day) || !valid_day(body.payment_due_day) {
+        return Err(AppError::BadRequest);
+    }
+
+    if let Some(autopay_id) = body.autopay_account_id {
+        if autopay_id == id {
+            return Err(AppError::BadRequest);
+        }
+        db::accounts::get(&state.pool, autopay_id, auth.ledger_id).await?;
+    }
+
+    let account = db::accounts::update_card_settings(&state.pool, id, &body, auth.ledger_id).await?;
+    service::sync_card_autopay(&state.pool, &account, Local::now().date_naive()).await?;
+
+    let balance = db::accounts::balance(&state.pool, account.id, auth.ledger_id).await?;
+    Ok(Json(service::account_to_dto(account, balance)))
+}
+
+/// The card's current statement and next payment due. 400 unless the account is a credit
+/// card with both a closing and a due day set.
+async fn get_statement(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<CardStatement>, AppError> {
+    let account = db::accounts::get(&state.pool, id, auth.ledger_id).await?;
+    if account.account_type != AccountTypes::CreditCard {
+        return Err(AppError::BadRequest);
+    }
+
+    service::card_statement(&state.pool, &account, Local::now().date_naive())
+        .await?
+        .map(Json)
+        .ok_or(AppError::BadRequest)
 }
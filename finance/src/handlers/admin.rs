@@ -0,0 +1,138 @@
+//! Administration: the users of the instance and its settings, for admins only.
+//!
+//! Admins are the users named in `ADMIN_USERS` when the backend starts (see [`Config`]). They
+//! can create users (also while registration is closed), disable and enable their logins,
+//! require a password change, and see how much each user stores.
+//!
+//! [`Config`]: crate::config::Config
+
+use axum::extract::{Path, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+
+use crate::auth::AuthUser;
+use crate::db;
+use crate::error::AppError;
+use crate::models::{AdminUser, NewAppUser, Settings, StorageStats};
+use crate::password;
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/admin/users", get(get_users).post(post_user))
+        .route("/api/admin/users/{id}/stats", get(get_stats))
+        .route("/api/admin/users/{id}/disable", post(disable_user))
+        .route("/api/admin/users/{id}/enable", post(enable_user))
+        .route("/api/admin/users/{id}/password-reset", post(reset_password))
+        .route("/api/admin/settings", get(get_settings).put(put_settings))
+}
+
+async fn get_users(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<AdminUser>>, AppError> {
+    require_admin(&state, &auth).await?;
+    Ok(Json(db::users::get_all_admin(&state.pool).await?))
+}
+
+/// Create a user, who must change the password given here when they first log in.
+async fn post_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<NewAppUser>,
+) -> Result<Json<AdminUser>, AppError> {
+    require_admin(&state, &auth).await?;
+    password::check_policy(&body.name, &body.password, &state.config)?;
+
+    let hash = password::hash(&body.password, &state.config).await?;
+    let user = db::users::insert(&state.pool, &body.name, &hash).await?;
+    db::users::require_password_change(&state.pool, user.id).await?;
+
+    tracing::info!("admin {} created user {} ({})", auth.user_id, user.id, user.name);
+    Ok(Json(db::users::get_admin(&state.pool, user.id).await?))
+}
+
+async fn get_stats(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<StorageStats>, AppError> {
+    require_admin(&state, &auth).await?;
+    db::users::get_admin(&state.pool, id).await?;
+
+    Ok(Json(db::users::storage_stats(&state.pool, id).await?))
+}
+
+/// Disable a user's logins, logging them out everywhere. Their tokens stop working until they are
+/// enabled again. `BadRequest` for the admin themselves.
+async fn disable_user(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<AdminUser>, AppError> {
+    require_admin(&state, &auth).await?;
+    if id == auth.user_id {
+        return Err(AppError::BadRequest);
+    }
+
+    db::users::set_disabled(&state.pool, id, true).await?;
+    db::sessions::revoke_all(&state.pool, id).await?;
+    tracing::info!("admin {} disabled user {id}", auth.user_id);
+    Ok(Json(db::users::get_admin(&state.pool, id).await?))
+}
+
+async fn enable_user(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<AdminUser>, AppError> {
+    require_admin(&state, &auth).await?;
+
+    db::users::set_disabled(&state.pool, id, false).await?;
+    tracing::info!("admin {} enabled user {id}", auth.user_id);
+    Ok(Json(db::users::get_admin(&state.pool, id).await?))
+}
+
+/// Log a user out everywhere and require them to change their password when they log in again;
+/// until then, their sessions only work for that and their personal access tokens not at all.
+async fn reset_password(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<AdminUser>, AppError> {
+    require_admin(&state, &auth).await?;
+
+    db::users::require_password_change(&state.pool, id).await?;
+    db::sessions::revoke_all(&state.pool, id).await?;
+    tracing::info!("admin {} required user {id} to change their password", auth.user_id);
+    Ok(Json(db::users::get_admin(&state.pool, id).await?))
+}
+
+async fn get_settings(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Settings>, AppError> {
+    require_admin(&state, &auth).await?;
+    Ok(Json(db::settings::get(&state.pool).await?))
+}
+
+/// Change the settings, e.g. close registration (`POST /api/users` and provisioning through
+/// single sign-on).
+async fn put_settings(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<Settings>,
+) -> Result<Json<Settings>, AppError> {
+    require_admin(&state, &auth).await?;
+
+    let settings = db::settings::update(&state.pool, &body).await?;
+    tracing::info!("admin {} changed the settings: {settings:?}", auth.user_id);
+    Ok(Json(settings))
+}
+
+async fn require_admin(state: &AppState, auth: &AuthUser) -> Result<(), AppError> {
+    if !db::users::get(&state.pool, auth.user_id).await?.is_admin {
+        return Err(AppError::Forbidden);
+    }
+    Ok(())
+}
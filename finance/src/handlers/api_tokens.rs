@@ -0,0 +1,58 @@
+use axum::extract::{Path, State};
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+use chrono::{Duration, Local};
+
+use crate::auth::AuthUser;
+use crate::db;
+use crate::error::AppError;
+use crate::models::{ApiToken, IssuedApiToken, PostApiToken};
+use crate::state::AppState;
+
+/// Longest a personal access token may be valid for, in days.
+const MAX_VALIDITY_DAYS: i64 = 366;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/api-tokens", get(get_api_tokens).post(post_api_token))
+        .route("/api/api-tokens/{id}", delete(delete_api_token))
+}
+
+/// Create a personal access token, expiring on `expires_date` (in the future and at most
+/// [`MAX_VALIDITY_DAYS`] away). The response is the only time the token is shown.
+async fn post_api_token(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<PostApiToken>,
+) -> Result<Json<IssuedApiToken>, AppError> {
+    let name = body.name.trim();
+    let now = Local::now().naive_local();
+    if name.is_empty()
+        || body.expires_date <= now
+        || body.expires_date > now + Duration::days(MAX_VALIDITY_DAYS)
+    {
+        return Err(AppError::BadRequest);
+    }
+
+    let token =
+        db::api_tokens::insert(&state.pool, name, body.scope, body.expires_date, auth.user_id)
+            .await?;
+    tracing::info!("user {} created API token {} ({:?})", auth.user_id, token.id, token.scope);
+    Ok(Json(token))
+}
+
+async fn get_api_tokens(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<ApiToken>>, AppError> {
+    Ok(Json(db::api_tokens::get_all(&state.pool, auth.user_id).await?))
+}
+
+/// Revoke a token; requests made with it are rejected from then on.
+async fn delete_api_token(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<ApiToken>, AppError> {
+    Ok(Json(db::api_tokens::delete(&state.pool, id, auth.user_id).await?))
+}
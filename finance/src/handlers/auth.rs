@@ -1,48 +1,196 @@
-use axum::extract::State;
-use axum::routing::{get, post};
+use axum::extract::{Path, State};
+use axum::http::header::USER_AGENT;
+use axum::http::HeaderMap;
+use axum::routing::{delete, get, post};
 use axum::{Json, Router};
 
-use crate::auth::AuthUser;
+use crate::auth::{self, AuthUser};
 use crate::db;
+use crate::db::sessions::Refresh;
 use crate::error::AppError;
-use crate::models::{InitialData, NewAppUser};
+use crate::models::{
+    GetSession, InitialData, LoginResponse, NewAppUser, PostLoginCode, PostRefreshToken, Session,
+    TokenPair,
+};
+use crate::password;
+use crate::rate_limit::ClientIp;
 use crate::service;
 use crate::state::AppState;
+use crate::totp;
+
+/// Longest `User-Agent` kept as a session's device name.
+const MAX_DEVICE_LEN: usize = 255;
+
+/// Minutes a login challenge token stays valid for entering the TOTP code.
+const CHALLENGE_MINUTES: i64 = 5;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/api/login", post(login))
+        .route("/api/login/2fa", post(login_code))
         .route("/api/token", get(validate_token))
+        .route("/api/token/refresh", post(refresh_token))
+        .route("/api/logout", post(logout))
+        .route("/api/sessions", get(get_sessions))
+        .route("/api/sessions/{id}", delete(delete_session))
         .route("/api/users", post(post_user))
 }
 
+/// Log in with the name and password. With two-factor authentication on, this only returns a
+/// challenge token, and the session is opened by `POST /api/login/2fa` with a code.
 async fn login(
     State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
     Json(body): Json<NewAppUser>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let key = format!("name:{}", body.name);
+    state.rate_limits.check_login(&key, ip)?;
+    let Some(user) =
+        password::authenticate(&state.pool, &state.config, &body.name, &body.password).await?
+    else {
+        state.rate_limits.login_failed(&key, ip);
+        return Err(AppError::Unauthorized);
+    };
+    state.rate_limits.login_succeeded(&key);
+
+    if db::two_factor::get_status(&state.pool, user.id).await?.enabled {
+        let challenge_token =
+            auth::create_challenge(user.id, &state.config.jwt_secret, CHALLENGE_MINUTES)?;
+        return Ok(Json(LoginResponse::Challenge { challenge_token }));
+    }
+
+    Ok(Json(LoginResponse::Session(
+        service::open_session(&state, user.id, device(&headers)).await?,
+    )))
+}
+
+/// Finish a login with two-factor authentication: the challenge token from `POST /api/login` and
+/// a TOTP code or an unused recovery code.
+async fn login_code(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    Json(body): Json<PostLoginCode>,
 ) -> Result<Json<InitialData>, AppError> {
-    match db::users::authenticate(&state.pool, &body.name, &body.password).await? {
-        Some(user) => Ok(Json(service::build_initial_data(&state, user.id).await?)),
-        None => Err(AppError::Unauthorized),
+    let user_id = auth::validate_challenge(&body.challenge_token, &state.config.jwt_secret)?;
+    // Wrong codes are counted per user, apart from wrong passwords for the name.
+    let key = format!("totp:{user_id}");
+    state.rate_limits.check_login(&key, ip)?;
+    if !totp::verify_user(&state.pool, &state.config, user_id, &body.code).await? {
+        state.rate_limits.login_failed(&key, ip);
+        return Err(AppError::Unauthorized);
     }
+    state.rate_limits.login_succeeded(&key);
+
+    Ok(Json(service::open_session(&state, user_id, device(&headers)).await?))
 }
 
 async fn validate_token(
     State(state): State<AppState>,
     auth: AuthUser,
 ) -> Result<Json<InitialData>, AppError> {
-    Ok(Json(service::build_initial_data(&state, auth.user_id).await?))
+    Ok(Json(service::build_initial_data(&state, auth.user_id, auth.session()?, auth.ledger_id).await?))
+}
+
+/// Trade a refresh token for a new access token and a new refresh token. Every refresh token
+/// works once: presenting one again revokes the whole session, since either the client or
+/// someone who copied the token already used it.
+async fn refresh_token(
+    State(state): State<AppState>,
+    Json(body): Json<PostRefreshToken>,
+) -> Result<Json<TokenPair>, AppError> {
+    match db::sessions::refresh(&state.pool, &body.refresh_token, state.config.refresh_token_days)
+        .await?
+    {
+        Refresh::Rotated {
+            session,
+            refresh_token,
+        } => Ok(Json(TokenPair {
+            token: auth::create_jwt(
+                session.user_id,
+                session.id,
+                &state.config.jwt_secret,
+                state.config.access_token_minutes,
+            )?,
+            refresh_token,
+        })),
+        Refresh::Reused {
+            session_id,
+            user_id,
+        } => {
+            tracing::warn!(
+                "refresh token reused: revoked session {session_id} of user {user_id}"
+            );
+            Err(AppError::Unauthorized)
+        }
+        Refresh::Invalid => Err(AppError::Unauthorized),
+    }
+}
+
+/// Revoke the session the request was made with.
+async fn logout(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Session>, AppError> {
+    Ok(Json(db::sessions::revoke(&state.pool, auth.session()?, auth.user_id).await?))
+}
+
+async fn get_sessions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<GetSession>>, AppError> {
+    let sessions = db::sessions::get_all(&state.pool, auth.user_id).await?;
+
+    Ok(Json(
+        sessions
+            .into_iter()
+            .map(|session| GetSession {
+                id: session.id,
+                device: session.device,
+                created_date: session.created_date,
+                last_seen_date: session.last_seen_date,
+                expires_date: session.expires_date,
+                current: Some(session.id) == auth.session_id,
+            })
+            .collect(),
+    ))
 }
 
+/// Log a device out.
+async fn delete_session(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Session>, AppError> {
+    Ok(Json(db::sessions::revoke(&state.pool, id, auth.user_id).await?))
+}
+
+/// Register a user. Registrations count against the client IP like failed logins, and are
+/// `Forbidden` once an admin closed them.
 async fn post_user(
     State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
     Json(body): Json<NewAppUser>,
 ) -> Result<Json<InitialData>, AppError> {
-    let user = db::users::insert(&state.pool, &body.name, &body.password, state.config.bf_rounds).await?;
+    if !db::settings::get(&state.pool).await?.open_registration {
+        return Err(AppError::Forbidden);
+    }
+    state.rate_limits.register(ip)?;
+    password::check_policy(&body.name, &body.password, &state.config)?;
+
+    let hash = password::hash(&body.password, &state.config).await?;
+    let user = db::users::insert(&state.pool, &body.name, &hash).await?;
+
+    Ok(Json(service::open_session(&state, user.id, device(&headers)).await?))
+}
 
-    // Re-authenticate with the same credentials to assemble the initial payload,
-    // mirroring the original backend.
-    match db::users::authenticate(&state.pool, &user.name, &body.password).await? {
-        Some(authed) => Ok(Json(service::build_initial_data(&state, authed.id).await?)),
-        None => Err(AppError::Unauthorized),
+/// The session's device name: the client's `User-Agent`, cut to [`MAX_DEVICE_LEN`] characters.
+pub fn device(headers: &HeaderMap) -> Option<&str> {
+    let agent = headers.get(USER_AGENT)?.to_str().ok()?.trim();
+    match agent.char_indices().nth(MAX_DEVICE_LEN) {
+        Some((end, _)) => Some(&agent[..end]),
+        None => Some(agent).filter(|agent| !agent.is_empty()),
     }
 }
@@ -0,0 +1,68 @@
+use axum::extract::{Path, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::{delete, get};
+use axum::{Json, Router};
+
+use crate::auth::AuthUser;
+use crate::db;
+use crate::error::AppError;
+use crate::models::{CalendarToken, IssuedCalendarToken, PostCalendarToken};
+use crate::service;
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/calendar-tokens",
+            get(get_calendar_tokens).post(post_calendar_token),
+        )
+        .route("/api/calendar-tokens/{id}", delete(delete_calendar_token))
+        .route("/api/calendar/{file}", get(get_calendar))
+}
+
+/// Create a feed token for the schedules of the active ledger. The response is the only time the
+/// token is shown.
+async fn post_calendar_token(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<PostCalendarToken>,
+) -> Result<Json<IssuedCalendarToken>, AppError> {
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest);
+    }
+
+    Ok(Json(db::calendar_tokens::insert(&state.pool, name, auth.ledger_id, auth.user_id).await?))
+}
+
+async fn get_calendar_tokens(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<CalendarToken>>, AppError> {
+    Ok(Json(db::calendar_tokens::get_all(&state.pool, auth.user_id).await?))
+}
+
+/// Revoke a feed token; calendars subscribed with it stop receiving updates.
+async fn delete_calendar_token(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<CalendarToken>, AppError> {
+    Ok(Json(db::calendar_tokens::delete(&state.pool, id, auth.user_id).await?))
+}
+
+/// The iCalendar feed at `/api/calendar/{token}.ics`. The token in the path authenticates the
+/// request instead of a JWT; an unknown or revoked token is not found.
+async fn get_calendar(
+    State(state): State<AppState>,
+    Path(file): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let token = file.strip_suffix(".ics").ok_or(AppError::NotFound)?;
+    let token = db::calendar_tokens::use_token(&state.pool, token)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let feed = service::calendar_feed(&state.pool, &state.holidays, token.ledger_id).await?;
+    Ok(([(CONTENT_TYPE, "text/calendar; charset=utf-8")], feed))
+}
@@ -25,7 +25,7 @@ async fn post_category(
     Json(body): Json<PostCategory>,
 ) -> Result<Json<Category>, AppError> {
     let category =
-        db::categories::insert(&state.pool, body.categorytype, &body.name, auth.user_id).await?;
+        db::categories::insert(&state.pool, body.categorytype, &body.name, auth.ledger_id).await?;
     Ok(Json(category))
 }
 
@@ -33,7 +33,7 @@ async fn get_categories(
     State(state): State<AppState>,
     auth: AuthUser,
 ) -> Result<Json<Vec<Category>>, AppError> {
-    Ok(Json(db::categories::get_all(&state.pool, auth.user_id).await?))
+    Ok(Json(db::categories::get_all(&state.pool, auth.ledger_id).await?))
 }
 
 async fn get_expense_categories(
@@ -41,7 +41,7 @@ async fn get_expense_categories(
     auth: AuthUser,
 ) -> Result<Json<Vec<Category>>, AppError> {
     Ok(Json(
-        db::categories::get_all_by_type(&state.pool, CategoryTypes::Expense, auth.user_id).await?,
+        db::categories::get_all_by_type(&state.pool, CategoryTypes::Expense, auth.ledger_id).await?,
     ))
 }
 
@@ -50,7 +50,7 @@ async fn get_income_categories(
     auth: AuthUser,
 ) -> Result<Json<Vec<Category>>, AppError> {
     Ok(Json(
-        db::categories::get_all_by_type(&state.pool, CategoryTypes::Income, auth.user_id).await?,
+        db::categories::get_all_by_type(&state.pool, CategoryTypes::Income, auth.ledger_id).await?,
     ))
 }
 
@@ -59,7 +59,7 @@ async fn get_category(
     Path(id): Path<i32>,
     auth: AuthUser,
 ) -> Result<Json<Category>, AppError> {
-    Ok(Json(db::categories::get(&state.pool, id, auth.user_id).await?))
+    Ok(Json(db::categories::get(&state.pool, id, auth.ledger_id).await?))
 }
 
 async fn patch_category(
@@ -69,7 +69,7 @@ async fn patch_category(
     Json(body): Json<PatchCategory>,
 ) -> Result<Json<Category>, AppError> {
     let category =
-        db::categories::update(&state.pool, id, body.categorytype, &body.name, auth.user_id).await?;
+        db::categories::update(&state.pool, id, body.categorytype, &body.name, auth.ledger_id).await?;
     Ok(Json(category))
 }
 
@@ -78,5 +78,5 @@ async fn delete_category(
     Path(id): Path<i32>,
     auth: AuthUser,
 ) -> Result<Json<Category>, AppError> {
-    Ok(Json(db::categories::delete(&state.pool, id, auth.user_id).await?))
+    Ok(Json(db::categories::delete(&state.pool, id, auth.ledger_id).await?))
 }
@@ -0,0 +1,119 @@
+use axum::extract::{Path, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+
+use crate::auth::AuthUser;
+use crate::db;
+use crate::error::AppError;
+use crate::models::{GetGoal, Goal, GoalProgress, PatchGoal, PostGoal, PostGoalContribution};
+use crate::service;
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/goals", post(post_goal).get(get_goals))
+        .route(
+            "/api/goals/{id}",
+            get(get_goal).patch(patch_goal).delete(delete_goal),
+        )
+        .route("/api/goals/{id}/progress", get(get_goal_progress))
+        .route(
+            "/api/goals/{id}/contribution",
+            post(post_goal_contribution).delete(delete_goal_contribution),
+        )
+}
+
+async fn post_goal(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<PostGoal>,
+) -> Result<Json<GetGoal>, AppError> {
+    service::validate_goal(&state.pool, &body, auth.ledger_id).await?;
+
+    let goal = db::goals::insert(&state.pool, &body, auth.ledger_id).await?;
+    Ok(Json(service::goal_to_dto(&state.pool, goal).await?))
+}
+
+async fn get_goals(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<GetGoal>>, AppError> {
+    let mut result = Vec::new();
+
+    for goal in db::goals::get_all(&state.pool, auth.ledger_id).await? {
+        result.push(service::goal_to_dto(&state.pool, goal).await?);
+    }
+
+    Ok(Json(result))
+}
+
+async fn get_goal(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<GetGoal>, AppError> {
+    let goal = db::goals::get(&state.pool, id, auth.ledger_id).await?;
+    Ok(Json(service::goal_to_dto(&state.pool, goal).await?))
+}
+
+/// Replace a goal, including its linked accounts. The contribution schedule is kept as is.
+async fn patch_goal(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+    Json(body): Json<PatchGoal>,
+) -> Result<Json<GetGoal>, AppError> {
+    db::goals::get(&state.pool, id, auth.ledger_id).await?;
+    service::validate_goal(&state.pool, &body, auth.ledger_id).await?;
+
+    let goal = db::goals::update(&state.pool, id, &body, auth.ledger_id).await?;
+    Ok(Json(service::goal_to_dto(&state.pool, goal).await?))
+}
+
+/// Delete a goal and its contribution schedule. The linked accounts are untouched.
+async fn delete_goal(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Goal>, AppError> {
+    let goal = db::goals::delete(&state.pool, id, auth.ledger_id).await?;
+    if let Some(scheduled_id) = goal.scheduled_transaction_id {
+        db::scheduled_transactions::delete(&state.pool, scheduled_id, auth.ledger_id).await?;
+    }
+
+    Ok(Json(goal))
+}
+
+async fn get_goal_progress(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<GoalProgress>, AppError> {
+    let goal = db::goals::get(&state.pool, id, auth.ledger_id).await?;
+    Ok(Json(service::goal_progress(&state.pool, &goal).await?))
+}
+
+/// Create or replace the goal's recurring monthly contribution transfer.
+async fn post_goal_contribution(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+    Json(body): Json<PostGoalContribution>,
+) -> Result<Json<GetGoal>, AppError> {
+    let goal = db::goals::get(&state.pool, id, auth.ledger_id).await?;
+    Ok(Json(service::set_goal_contribution(&state.pool, &goal, &body).await?))
+}
+
+async fn delete_goal_contribution(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<GetGoal>, AppError> {
+    let goal = db::goals::get(&state.pool, id, auth.ledger_id).await?;
+    if let Some(scheduled_id) = goal.scheduled_transaction_id {
+        db::scheduled_transactions::delete(&state.pool, scheduled_id, auth.ledger_id).await?;
+    }
+
+    let goal = db::goals::set_scheduled(&state.pool, id, None, auth.ledger_id).await?;
+    Ok(Json(service::goal_to_dto(&state.pool, goal).await?))
+}
@@ -0,0 +1,150 @@
+use axum::extract::{Path, Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+
+use crate::auth::AuthUser;
+use crate::db;
+use crate::error::AppError;
+use crate::models::{
+    Holdings, HoldingsQuery, InvestmentTransaction, PatchInvestmentTransaction,
+    PostInvestmentTransaction,
+};
+use crate::service;
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/investments/account/{account_id}",
+            get(get_investment_transactions).post(post_investment_transaction),
+        )
+        .route(
+            "/api/investments/account/{account_id}/holdings",
+            get(get_holdings),
+        )
+        .route(
+            "/api/investments/{id}",
+            get(get_investment_transaction)
+                .patch(patch_investment_transaction)
+                .delete(delete_investment_transaction),
+        )
+}
+
+/// Record a buy, sell or dividend. A sell for more units than the account holds at that date
+/// is a bad request.
+async fn post_investment_transaction(
+    State(state): State<AppState>,
+    Path(account_id): Path<i32>,
+    auth: AuthUser,
+    Json(body): Json<PostInvestmentTransaction>,
+) -> Result<Json<InvestmentTransaction>, AppError> {
+    let account = db::accounts::get(&state.pool, account_id, auth.ledger_id).await?;
+    let new = service::build_investment_transaction(&state.pool, &account, &body).await?;
+
+    let include = service::investment_transaction_from(None, &new);
+    if !service::investment_history_valid(
+        &state.pool,
+        account_id,
+        new.security_id,
+        None,
+        Some(include),
+        auth.ledger_id,
+    )
+    .await?
+    {
+        return Err(AppError::BadRequest);
+    }
+
+    Ok(Json(db::investments::insert(&state.pool, &new).await?))
+}
+
+async fn get_investment_transactions(
+    State(state): State<AppState>,
+    Path(account_id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Vec<InvestmentTransaction>>, AppError> {
+    db::accounts::get(&state.pool, account_id, auth.ledger_id).await?;
+    Ok(Json(
+        db::investments::get_by_account(&state.pool, account_id, auth.ledger_id).await?,
+    ))
+}
+
+async fn get_holdings(
+    State(state): State<AppState>,
+    Path(account_id): Path<i32>,
+    Query(query): Query<HoldingsQuery>,
+    auth: AuthUser,
+) -> Result<Json<Holdings>, AppError> {
+    let account = db::accounts::get(&state.pool, account_id, auth.ledger_id).await?;
+    Ok(Json(service::holdings(&state.pool, &account, query.method).await?))
+}
+
+async fn get_investment_transaction(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<InvestmentTransaction>, AppError> {
+    Ok(Json(db::investments::get(&state.pool, id, auth.ledger_id).await?))
+}
+
+/// Replace a buy, sell or dividend. Both the security it was in and the one it is moved to must
+/// keep a valid history (no selling more than held).
+async fn patch_investment_transaction(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+    Json(body): Json<PatchInvestmentTransaction>,
+) -> Result<Json<InvestmentTransaction>, AppError> {
+    let existing = db::investments::get(&state.pool, id, auth.ledger_id).await?;
+    let account = db::accounts::get(&state.pool, existing.account_id, auth.ledger_id).await?;
+    let new = service::build_investment_transaction(&state.pool, &account, &body).await?;
+
+    let include = service::investment_transaction_from(Some(id), &new);
+    let valid = service::investment_history_valid(
+        &state.pool,
+        account.id,
+        new.security_id,
+        Some(id),
+        Some(include),
+        auth.ledger_id,
+    )
+    .await?
+        && (existing.security_id == new.security_id
+            || service::investment_history_valid(
+                &state.pool,
+                account.id,
+                existing.security_id,
+                Some(id),
+                None,
+                auth.ledger_id,
+            )
+            .await?);
+    if !valid {
+        return Err(AppError::BadRequest);
+    }
+
+    Ok(Json(db::investments::update(&state.pool, id, &new).await?))
+}
+
+/// Delete a buy, sell or dividend. Deleting a buy whose units were later sold is a conflict.
+async fn delete_investment_transaction(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<InvestmentTransaction>, AppError> {
+    let existing = db::investments::get(&state.pool, id, auth.ledger_id).await?;
+    if !service::investment_history_valid(
+        &state.pool,
+        existing.account_id,
+        existing.security_id,
+        Some(id),
+        None,
+        auth.ledger_id,
+    )
+    .await?
+    {
+        return Err(AppError::Conflict);
+    }
+
+    Ok(Json(db::investments::delete(&state.pool, id, auth.ledger_id).await?))
+}
@@ -0,0 +1,266 @@
+use axum::extract::{MatchedPath, Path, Query, State};
+use axum::http::{Method, Uri};
+use axum::routing::{delete, get, post, put};
+use axum::{Json, Router};
+use chrono::{Duration, Local};
+use serde_json::{json, Value};
+
+use crate::audit;
+use crate::auth::AuthUser;
+use crate::db;
+use crate::error::AppError;
+use crate::models::{
+    AuditEntry, AuditQuery, ConfirmPassword, GetLedger, IssuedLedgerInvite, Ledger, LedgerInvite,
+    LedgerMember, LedgerRoles, PostInviteAccept, PostLedger, PostLedgerInvite, PutLedgerMember,
+};
+use crate::password;
+use crate::rate_limit::ClientIp;
+use crate::state::AppState;
+
+/// Days an invite is valid for by default, and at most.
+const INVITE_DAYS: i64 = 7;
+const MAX_INVITE_DAYS: i64 = 30;
+
+/// Audit log entries returned at a time by default, and at most.
+const AUDIT_PAGE: i64 = 50;
+const MAX_AUDIT_PAGE: i64 = 500;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/ledgers", get(get_ledgers).post(post_ledger))
+        .route("/api/ledgers/{id}", put(put_ledger).delete(delete_ledger))
+        .route("/api/ledgers/{id}/members", get(get_members))
+        .route(
+            "/api/ledgers/{id}/members/{user_id}",
+            put(put_member).delete(delete_member),
+        )
+        .route("/api/ledgers/{id}/invites", get(get_invites).post(post_invite))
+        .route("/api/ledgers/{id}/invites/{invite_id}", delete(delete_invite))
+        .route("/api/ledgers/{id}/audit", get(get_audit))
+        .route("/api/invites/accept", post(accept_invite))
+}
+
+async fn get_ledgers(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<GetLedger>>, AppError> {
+    Ok(Json(db::ledgers::get_all(&state.pool, auth.user_id).await?))
+}
+
+/// Create a shared ledger, owned by the user. Members join it through invites.
+async fn post_ledger(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<PostLedger>,
+) -> Result<Json<Ledger>, AppError> {
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest);
+    }
+
+    let mut tx = state.pool.begin().await?;
+    let ledger = db::ledgers::insert(&mut tx, name, auth.user_id).await?;
+    tx.commit().await?;
+    Ok(Json(ledger))
+}
+
+/// Rename a ledger (owners only).
+async fn put_ledger(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+    method: Method,
+    route: MatchedPath,
+    uri: Uri,
+    Json(body): Json<PostLedger>,
+) -> Result<Json<Ledger>, AppError> {
+    require_owner(&state, id, &auth).await?;
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest);
+    }
+
+    let ledger = db::ledgers::rename(&state.pool, id, name).await?;
+    record(&state, id, &auth, &method, &route, &uri, &json!(body)).await?;
+    Ok(Json(ledger))
+}
+
+/// Delete a ledger and everything in it, for all of its members, given the password again (see
+/// [`password::confirm`]; owners only). This cannot be undone.
+async fn delete_ledger(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    ClientIp(ip): ClientIp,
+    auth: AuthUser,
+    Json(body): Json<ConfirmPassword>,
+) -> Result<Json<Ledger>, AppError> {
+    require_owner(&state, id, &auth).await?;
+    password::confirm(&state, ip, auth.user_id, &body.password).await?;
+
+    let ledger = db::ledgers::delete(&state.pool, id).await?;
+    tracing::info!("user {} deleted ledger {} ({})", auth.user_id, ledger.id, ledger.name);
+    Ok(Json(ledger))
+}
+
+async fn get_members(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Vec<LedgerMember>>, AppError> {
+    require_member(&state, id, &auth).await?;
+    Ok(Json(db::ledgers::get_members(&state.pool, id).await?))
+}
+
+/// Change a member's role (owners only). `Conflict` if the ledger would be left without an
+/// owner.
+async fn put_member(
+    State(state): State<AppState>,
+    Path((id, user_id)): Path<(i32, i32)>,
+    auth: AuthUser,
+    method: Method,
+    route: MatchedPath,
+    uri: Uri,
+    Json(body): Json<PutLedgerMember>,
+) -> Result<Json<LedgerMember>, AppError> {
+    require_owner(&state, id, &auth).await?;
+
+    let member = db::ledgers::set_role(&state.pool, id, user_id, body.role).await?;
+    record(&state, id, &auth, &method, &route, &uri, &json!(body)).await?;
+    Ok(Json(member))
+}
+
+/// Remove a member (owners), or leave the ledger (anyone, with their own id). `Conflict` for the
+/// last owner, who can delete the ledger instead.
+async fn delete_member(
+    State(state): State<AppState>,
+    Path((id, user_id)): Path<(i32, i32)>,
+    auth: AuthUser,
+    method: Method,
+    route: MatchedPath,
+    uri: Uri,
+) -> Result<Json<Vec<LedgerMember>>, AppError> {
+    if user_id == auth.user_id {
+        require_member(&state, id, &auth).await?;
+    } else {
+        require_owner(&state, id, &auth).await?;
+    }
+
+    db::ledgers::remove_member(&state.pool, id, user_id).await?;
+    record(&state, id, &auth, &method, &route, &uri, &Value::Null).await?;
+    Ok(Json(db::ledgers::get_members(&state.pool, id).await?))
+}
+
+/// The ledger's invites that are still valid (owners only).
+async fn get_invites(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Vec<LedgerInvite>>, AppError> {
+    require_owner(&state, id, &auth).await?;
+    Ok(Json(db::ledger_invites::get_all(&state.pool, id).await?))
+}
+
+/// Invite someone into the ledger as an editor or a viewer (owners only): whoever accepts the
+/// token first, within [`INVITE_DAYS`] or until `expires_date` (at most [`MAX_INVITE_DAYS`]
+/// away), joins with that role. The response is the only time the token is shown.
+async fn post_invite(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+    method: Method,
+    route: MatchedPath,
+    uri: Uri,
+    Json(body): Json<PostLedgerInvite>,
+) -> Result<Json<IssuedLedgerInvite>, AppError> {
+    require_owner(&state, id, &auth).await?;
+    let now = Local::now().naive_local();
+    let expires_date = body.expires_date.unwrap_or(now + Duration::days(INVITE_DAYS));
+    if body.role == LedgerRoles::Owner
+        || expires_date <= now
+        || expires_date > now + Duration::days(MAX_INVITE_DAYS)
+    {
+        return Err(AppError::BadRequest);
+    }
+
+    let invite =
+        db::ledger_invites::insert(&state.pool, id, body.role, expires_date, auth.user_id).await?;
+    record(&state, id, &auth, &method, &route, &uri, &json!(body)).await?;
+    Ok(Json(invite))
+}
+
+/// Revoke an invite (owners only).
+async fn delete_invite(
+    State(state): State<AppState>,
+    Path((id, invite_id)): Path<(i32, i32)>,
+    auth: AuthUser,
+    method: Method,
+    route: MatchedPath,
+    uri: Uri,
+) -> Result<Json<LedgerInvite>, AppError> {
+    require_owner(&state, id, &auth).await?;
+
+    let invite = db::ledger_invites::delete(&state.pool, invite_id, id).await?;
+    record(&state, id, &auth, &method, &route, &uri, &Value::Null).await?;
+    Ok(Json(invite))
+}
+
+/// Join a ledger with an invite token, using it up. Not found for an unknown, used or expired
+/// token; `Conflict` if the user already is a member.
+async fn accept_invite(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    method: Method,
+    route: MatchedPath,
+    uri: Uri,
+    Json(body): Json<PostInviteAccept>,
+) -> Result<Json<Vec<GetLedger>>, AppError> {
+    let invite = db::ledger_invites::accept(&state.pool, body.token.trim(), auth.user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    record(&state, invite.ledger_id, &auth, &method, &route, &uri, &json!(body)).await?;
+    tracing::info!("user {} joined ledger {} as {:?}", auth.user_id, invite.ledger_id, invite.role);
+    Ok(Json(db::ledgers::get_all(&state.pool, auth.user_id).await?))
+}
+
+/// The ledger's audit log, newest first, a page at a time (see [`AuditQuery`]).
+async fn get_audit(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<Vec<AuditEntry>>, AppError> {
+    require_member(&state, id, &auth).await?;
+    let limit = query.limit.unwrap_or(AUDIT_PAGE).clamp(1, MAX_AUDIT_PAGE);
+
+    Ok(Json(db::ledger_audit::get_page(&state.pool, id, query.before, limit).await?))
+}
+
+/// The user's role in the ledger; not found if they are not a member, so ledgers of others are
+/// not revealed.
+async fn require_member(state: &AppState, id: i32, auth: &AuthUser) -> Result<LedgerRoles, AppError> {
+    db::ledgers::get_role(&state.pool, id, auth.user_id)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+async fn require_owner(state: &AppState, id: i32, auth: &AuthUser) -> Result<(), AppError> {
+    match require_member(state, id, auth).await? {
+        LedgerRoles::Owner => Ok(()),
+        _ => Err(AppError::Forbidden),
+    }
+}
+
+/// Record a change to the ledger or its membership in its audit log (see [`audit::entry`]).
+async fn record(
+    state: &AppState,
+    id: i32,
+    auth: &AuthUser,
+    method: &Method,
+    route: &MatchedPath,
+    uri: &Uri,
+    body: &Value,
+) -> Result<(), AppError> {
+    let new = audit::entry(id, auth.user_id, method, route.as_str(), uri.path(), body);
+    db::ledger_audit::insert(&state.pool, &new).await
+}
@@ -0,0 +1,70 @@
+use axum::extract::{Path, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+
+use crate::auth::AuthUser;
+use crate::db;
+use crate::error::AppError;
+use crate::models::{Amortization, GetLoan, LoanSimulation, PostLoan, PostLoanSimulation};
+use crate::service;
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/loans", post(post_loan).get(get_loans))
+        .route("/api/loans/{account_id}", get(get_loan))
+        .route("/api/loans/{account_id}/amortization", get(get_amortization))
+        .route("/api/loans/{account_id}/simulate", post(post_simulation))
+}
+
+/// Create a loan account with its terms and monthly payment schedule. Deleting the account
+/// (`DELETE /api/accounts/{id}`) removes the loan and its schedule.
+async fn post_loan(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<PostLoan>,
+) -> Result<Json<GetLoan>, AppError> {
+    Ok(Json(service::create_loan(&state.pool, body, auth.ledger_id).await?))
+}
+
+async fn get_loans(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<GetLoan>>, AppError> {
+    let mut result = Vec::new();
+
+    for loan in db::loans::get_all(&state.pool, auth.ledger_id).await? {
+        result.push(service::loan_to_dto(&state.pool, loan).await?);
+    }
+
+    Ok(Json(result))
+}
+
+async fn get_loan(
+    State(state): State<AppState>,
+    Path(account_id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<GetLoan>, AppError> {
+    let loan = db::loans::get(&state.pool, account_id, auth.ledger_id).await?;
+    Ok(Json(service::loan_to_dto(&state.pool, loan).await?))
+}
+
+async fn get_amortization(
+    State(state): State<AppState>,
+    Path(account_id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Amortization>, AppError> {
+    let loan = db::loans::get(&state.pool, account_id, auth.ledger_id).await?;
+    Ok(Json(service::loan_amortization(&loan)))
+}
+
+/// How much interest and how many months extra payments would save on the outstanding balance.
+async fn post_simulation(
+    State(state): State<AppState>,
+    Path(account_id): Path<i32>,
+    auth: AuthUser,
+    Json(body): Json<PostLoanSimulation>,
+) -> Result<Json<LoanSimulation>, AppError> {
+    let loan = db::loans::get(&state.pool, account_id, auth.ledger_id).await?;
+    Ok(Json(service::simulate_loan(&state.pool, &loan, &body).await?))
+}
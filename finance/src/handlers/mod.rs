@@ -1,6 +1,20 @@
 pub mod accounts;
+pub mod admin;
+pub mod api_tokens;
 pub mod auth;
+pub mod calendar;
 pub mod categories;
+pub mod goals;
+pub mod investments;
+pub mod ledgers;
+pub mod loans;
+pub mod oidc;
+pub mod password_resets;
+pub mod reconciliations;
+pub mod reports;
 pub mod scheduled_transactions;
+pub mod securities;
 pub mod transactions;
 pub mod transfers;
+pub mod two_factor;
+pub mod users;
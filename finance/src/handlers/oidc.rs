@@ -0,0 +1,140 @@
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::routing::{delete, post};
+use axum::{Json, Router};
+
+use crate::auth::AuthUser;
+use crate::db;
+use crate::error::AppError;
+use crate::handlers::auth::device;
+use crate::models::{
+    InitialData, OidcAuthorization, OidcIdentity, PostOidcAuthorize, PostOidcCallback,
+};
+use crate::oidc::{self, Identity, Oidc};
+use crate::password;
+use crate::rate_limit::ClientIp;
+use crate::service;
+use crate::state::AppState;
+
+/// Numbered names tried for a provisioned user whose name is taken (`name-2`, `name-3`, ...).
+const MAX_NAME_SUFFIX: u32 = 20;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/oidc/authorize", post(authorize))
+        .route("/api/oidc/callback", post(callback))
+        .route("/api/users/me/identities", post(post_identity).get(get_identities))
+        .route("/api/users/me/identities/{id}", delete(delete_identity))
+}
+
+/// Start a login (or the linking of an identity) at an identity provider. Not found when single
+/// sign-on is not configured.
+async fn authorize(
+    State(state): State<AppState>,
+    Json(body): Json<PostOidcAuthorize>,
+) -> Result<Json<OidcAuthorization>, AppError> {
+    let authorization_url =
+        oidc(&state)?.authorization_url(&state.config, body.issuer.as_deref()).await?;
+    Ok(Json(OidcAuthorization { authorization_url }))
+}
+
+/// Log in with the identity the provider redirected back with. An identity not linked to a user
+/// gets a new one with `OIDC_AUTO_PROVISION` (counted against the client IP like a
+/// registration, and only while registration is open), and is `Forbidden` otherwise. Two-factor authentication is left to the
+/// provider.
+async fn callback(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    Json(body): Json<PostOidcCallback>,
+) -> Result<Json<InitialData>, AppError> {
+    let identity = oidc(&state)?.finish(&state.config, &body.code, &body.state).await?;
+
+    let user_id = match db::oidc_identities::log_in(&state.pool, &identity.issuer, &identity.subject)
+        .await?
+    {
+        Some(user_id) => user_id,
+        None if state.config.oidc_auto_provision => {
+            if !db::settings::get(&state.pool).await?.open_registration {
+                return Err(AppError::Forbidden);
+            }
+            state.rate_limits.register(ip)?;
+            provision(&state, &identity).await?
+        }
+        None => return Err(AppError::Forbidden),
+    };
+
+    Ok(Json(service::open_session(&state, user_id, device(&headers)).await?))
+}
+
+/// Link the identity the provider redirected back with to the user, after a login started with
+/// `POST /api/oidc/authorize`. `Conflict` if it is already linked.
+async fn post_identity(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<PostOidcCallback>,
+) -> Result<Json<OidcIdentity>, AppError> {
+    let identity = oidc(&state)?.finish(&state.config, &body.code, &body.state).await?;
+
+    Ok(Json(
+        db::oidc_identities::insert(&state.pool, &identity.issuer, &identity.subject, auth.user_id)
+            .await?,
+    ))
+}
+
+async fn get_identities(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<OidcIdentity>>, AppError> {
+    Ok(Json(db::oidc_identities::get_all(&state.pool, auth.user_id).await?))
+}
+
+/// Unlink an identity.
+async fn delete_identity(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<OidcIdentity>, AppError> {
+    Ok(Json(db::oidc_identities::delete(&state.pool, id, auth.user_id).await?))
+}
+
+fn oidc(state: &AppState) -> Result<&Oidc, AppError> {
+    state.oidc.as_deref().ok_or(AppError::NotFound)
+}
+
+/// Create a user for the identity, named after it (numbered if the name is taken), with a random
+/// password nobody knows, and link the identity to them.
+async fn provision(state: &AppState, identity: &Identity) -> Result<i32, AppError> {
+    let base = identity
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or("user");
+    let hash = password::hash(&oidc::random_token(), &state.config).await?;
+
+    for suffix in 1..=MAX_NAME_SUFFIX {
+        let name = match suffix {
+            1 => base.to_string(),
+            _ => format!("{base}-{suffix}"),
+        };
+        let user = match db::users::insert(&state.pool, &name, &hash).await {
+            Ok(user) => user,
+            Err(AppError::Conflict) => continue,
+            Err(e) => return Err(e),
+        };
+
+        if let Err(e) =
+            db::oidc_identities::insert(&state.pool, &identity.issuer, &identity.subject, user.id)
+                .await
+        {
+            // Linked by a concurrent login in the meantime.
+            db::users::delete(&state.pool, user.id).await?;
+            return Err(e);
+        }
+        tracing::info!("provisioned user {} for {} at {}", user.id, identity.subject, identity.issuer);
+        return Ok(user.id);
+    }
+
+    Err(AppError::Conflict)
+}
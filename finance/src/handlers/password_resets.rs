@@ -0,0 +1,111 @@
+//! Self-service password resets: a user who forgot their password asks for a reset email, and
+//! sets a new password with the single-use token it links to.
+//!
+//! Asking always gets the same answer, right away, whether or not the name exists, has an email
+//! address or was sent a link: the email is written and sent in the background.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::post;
+use axum::{Json, Router};
+
+use crate::db;
+use crate::error::AppError;
+use crate::mail::{Email, Mailer};
+use crate::models::{GetAppUser, PostPasswordResetConfirm, PostPasswordResetRequest};
+use crate::password;
+use crate::rate_limit::ClientIp;
+use crate::state::AppState;
+
+/// Shortest time between two reset emails to the same user.
+const RESEND_MINUTES: i64 = 2;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/password-reset/request", post(request_reset))
+        .route("/api/password-reset/confirm", post(confirm_reset))
+}
+
+/// Email the user a password reset link, if they exist, have an email address, are not disabled
+/// and were not sent one in the last [`RESEND_MINUTES`]. `Accepted` in any case; counted against
+/// the client IP like a registration. Not found when no SMTP relay is configured.
+async fn request_reset(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    Json(body): Json<PostPasswordResetRequest>,
+) -> Result<StatusCode, AppError> {
+    let mailer = state.mailer.clone().ok_or(AppError::NotFound)?;
+    state.rate_limits.register(ip)?;
+
+    tokio::spawn(async move {
+        if let Err(err) = send_reset(&state, &mailer, body.name.trim()).await {
+            tracing::warn!("sending a password reset email failed: {err}");
+        }
+    });
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Set a new password with a reset token, using it up. The new password must meet the policy
+/// (see [`password::check_policy`]); every session of the user is logged out, and a required
+/// password change is fulfilled. Not found for an unknown, used or expired token.
+async fn confirm_reset(
+    State(state): State<AppState>,
+    Json(body): Json<PostPasswordResetConfirm>,
+) -> Result<Json<GetAppUser>, AppError> {
+    let token = body.token.trim();
+    let user_id = db::password_resets::get_user(&state.pool, token)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let user = db::users::get(&state.pool, user_id).await?;
+    password::check_policy(&user.name, &body.new_password, &state.config)?;
+
+    let hash = password::hash(&body.new_password, &state.config).await?;
+    if db::password_resets::use_token(&state.pool, token).await? != Some(user_id) {
+        return Err(AppError::NotFound);
+    }
+    db::users::update_password(&state.pool, user_id, &hash).await?;
+    db::sessions::revoke_all(&state.pool, user_id).await?;
+    state.rate_limits.login_succeeded(&format!("name:{}", user.name));
+
+    tracing::info!("user {user_id} reset their password");
+    Ok(Json(db::users::get(&state.pool, user_id).await?))
+}
+
+async fn send_reset(state: &AppState, mailer: &Mailer, name: &str) -> Result<(), AppError> {
+    let Some(user) = db::users::get_by_name(&state.pool, name).await? else {
+        return Ok(());
+    };
+    let Some(email) = db::users::get_notification_preferences(&state.pool, user.id).await?.email
+    else {
+        return Ok(());
+    };
+    if db::users::is_disabled(&state.pool, user.id).await? {
+        return Ok(());
+    }
+    if db::password_resets::sent_recently(&state.pool, user.id, RESEND_MINUTES).await? {
+        return Ok(());
+    }
+
+    let minutes = state.config.password_reset_minutes;
+    let token = db::password_resets::insert(&state.pool, user.id, minutes).await?;
+    let url = format!("{}?token={token}", state.config.password_reset_url);
+    mailer
+        .send(Email {
+            to: email,
+            subject: "Reset your password".to_string(),
+            body: format!(
+                "Hello {},\n\n\
+                 Someone asked to reset the password of your Finance account. To choose a new \
+                 password, open:\n\n{url}\n\n\
+                 The link works once, for the next {minutes} minutes. If you did not ask for \
+                 this, ignore this email: your password stays the same.\n",
+                user.name
+            ),
+            unsubscribe_url: None,
+        })
+        .await?;
+
+    tracing::info!("sent a password reset email to user {}", user.id);
+    Ok(())
+}
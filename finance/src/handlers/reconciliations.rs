@@ -0,0 +1,171 @@
+use axum::extract::{Path, State};
+use axum::routing::{get, patch, post};
+use axum::{Json, Router};
+
+use crate::auth::AuthUser;
+use crate::db;
+use crate::error::AppError;
+use crate::models::{
+    PatchReconciliationItem, PostReconciliation, Reconciliation, ReconciliationSummary,
+    TransactionStatuses,
+};
+use crate::service;
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/reconciliations", post(post_reconciliation))
+        .route(
+            "/api/reconciliations/account/{account_id}",
+            get(get_reconciliations),
+        )
+        .route(
+            "/api/reconciliations/{id}",
+            get(get_reconciliation).delete(delete_reconciliation),
+        )
+        .route(
+            "/api/reconciliations/{id}/items/transactions/{item_id}",
+            patch(patch_reconciliation_transaction),
+        )
+        .route(
+            "/api/reconciliations/{id}/items/transfers/{item_id}",
+            patch(patch_reconciliation_transfer),
+        )
+        .route("/api/reconciliations/{id}/finish", post(finish_reconciliation))
+}
+
+/// Start reconciling an account against a bank statement. 409 if the account already has an
+/// open session.
+async fn post_reconciliation(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<PostReconciliation>,
+) -> Result<Json<ReconciliationSummary>, AppError> {
+    db::accounts::get(&state.pool, body.account_id, auth.ledger_id).await?;
+
+    let reconciliation = db::reconciliations::insert(
+        &state.pool,
+        body.account_id,
+        body.statement_date,
+        body.statement_balance,
+        auth.ledger_id,
+    )
+    .await?;
+
+    Ok(Json(service::reconciliation_summary(&state.pool, &reconciliation).await?))
+}
+
+/// Past and open sessions of an account, newest statement first.
+async fn get_reconciliations(
+    State(state): State<AppState>,
+    Path(account_id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Vec<Reconciliation>>, AppError> {
+    db::accounts::get(&state.pool, account_id, auth.ledger_id).await?;
+
+    Ok(Json(
+        db::reconciliations::get_all_of_account(&state.pool, account_id, auth.ledger_id).await?,
+    ))
+}
+
+async fn get_reconciliation(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<ReconciliationSummary>, AppError> {
+    let reconciliation = db::reconciliations::get(&state.pool, id, auth.ledger_id).await?;
+    Ok(Json(service::reconciliation_summary(&state.pool, &reconciliation).await?))
+}
+
+/// Discard a session. Movements already reconciled by a finished session stay reconciled.
+async fn delete_reconciliation(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Reconciliation>, AppError> {
+    Ok(Json(db::reconciliations::delete(&state.pool, id, auth.ledger_id).await?))
+}
+
+/// Tick or untick one of the session's transactions. Only items listed by the open session can
+/// be changed; anything else is a 404.
+async fn patch_reconciliation_transaction(
+    State(state): State<AppState>,
+    Path((id, item_id)): Path<(i32, i32)>,
+    auth: AuthUser,
+    Json(body): Json<PatchReconciliationItem>,
+) -> Result<Json<ReconciliationSummary>, AppError> {
+    Ok(Json(tick(&state, &auth, id, false, item_id, body.cleared).await?))
+}
+
+/// Tick or untick one of the session's transfers, on the side of the session's account.
+async fn patch_reconciliation_transfer(
+    State(state): State<AppState>,
+    Path((id, item_id)): Path<(i32, i32)>,
+    auth: AuthUser,
+    Json(body): Json<PatchReconciliationItem>,
+) -> Result<Json<ReconciliationSummary>, AppError> {
+    Ok(Json(tick(&state, &auth, id, true, item_id, body.cleared).await?))
+}
+
+/// Mark an item of the open session `id` cleared or uncleared. A transaction and a transfer can
+/// have the same id (they only share a sequence by default), so the kind is part of the key.
+async fn tick(
+    state: &AppState,
+    auth: &AuthUser,
+    id: i32,
+    transfer: bool,
+    item_id: i32,
+    cleared: bool,
+) -> Result<ReconciliationSummary, AppError> {
+    let reconciliation = db::reconciliations::get(&state.pool, id, auth.ledger_id).await?;
+    if reconciliation.finished_date.is_some() {
+        return Err(AppError::Conflict);
+    }
+
+    let summary = service::reconciliation_summary(&state.pool, &reconciliation).await?;
+    if !summary
+        .items
+        .iter()
+        .any(|item| item.id == item_id && service::is_transfer(item) == transfer)
+    {
+        return Err(AppError::NotFound);
+    }
+
+    let status = if cleared {
+        TransactionStatuses::Cleared
+    } else {
+        TransactionStatuses::Uncleared
+    };
+
+    if transfer {
+        let account_id = reconciliation.account_id;
+        db::transfers::set_status(&state.pool, item_id, account_id, status, auth.ledger_id)
+            .await?;
+    } else {
+        db::transactions::set_status(&state.pool, item_id, status, auth.ledger_id).await?;
+    }
+
+    let reconciliation = db::reconciliations::get(&state.pool, id, auth.ledger_id).await?;
+    service::reconciliation_summary(&state.pool, &reconciliation).await
+}
+
+/// Finish the session. Only allowed once the cleared balance matches the statement balance
+/// (409 otherwise); the cleared items then become reconciled.
+async fn finish_reconciliation(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<ReconciliationSummary>, AppError> {
+    let reconciliation = db::reconciliations::get(&state.pool, id, auth.ledger_id).await?;
+    if reconciliation.finished_date.is_some() {
+        return Err(AppError::Conflict);
+    }
+
+    let summary = service::reconciliation_summary(&state.pool, &reconciliation).await?;
+    if summary.difference != 0 {
+        return Err(AppError::Conflict);
+    }
+
+    let finished = service::finish_reconciliation(&state.pool, &reconciliation).await?;
+    Ok(Json(service::reconciliation_summary(&state.pool, &finished).await?))
+}
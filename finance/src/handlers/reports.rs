@@ -0,0 +1,20 @@
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::models::NetWorthReport;
+use crate::service;
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/reports/net-worth", get(get_net_worth))
+}
+
+async fn get_net_worth(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<NetWorthReport>, AppError> {
+    Ok(Json(service::net_worth(&state.pool, auth.ledger_id).await?))
+}
@@ -1,18 +1,23 @@
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::routing::{get, post};
 use axum::{Json, Router};
+use chrono::NaiveDateTime;
+use sqlx::{PgConnection, PgPool};
 
 use crate::auth::AuthUser;
 use crate::db;
 use crate::error::AppError;
+use crate::holidays::Holidays;
 use crate::models::{
-    GetScheduledTransaction, NewScheduledTransaction, NewTransactionData, NewTransferData,
-    PatchScheduledTransaction, PostScheduledTransaction, PostScheduledTransactionPay,
-    ScheduledTransaction, ScheduledTransactionKinds,
+    GetScheduledTransaction, NewScheduledOccurrence, NewScheduledTransaction, NewTransactionData,
+    NewTransferData, OccurrenceOutcomes, OccurrencesQuery, PatchScheduledTransaction,
+    PostScheduledTransaction, PostScheduledTransactionPay, PostScheduledTransactionSnooze,
+    ScheduledEntry, ScheduledOccurrence, ScheduledTransaction, ScheduledTransactionKinds,
+    TransactionStatuses, UpcomingOccurrence,
 };
+use crate::recurrence::RRule;
 use crate::service;
 use crate::state::AppState;
-use sqlx::PgPool;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
@@ -20,11 +25,21 @@ pub fn routes() -> Router<AppState> {
             "/api/scheduled-transactions",
             post(post_scheduled).get(get_scheduled_list),
         )
+        .route("/api/scheduled-transactions/finished", get(get_finished_list))
+        .route("/api/scheduled-transactions/preview", post(preview_scheduled))
         .route(
             "/api/scheduled-transactions/{id}",
             get(get_scheduled).patch(patch_scheduled).delete(delete_scheduled),
         )
         .route("/api/scheduled-transactions/{id}/pay", post(pay_scheduled))
+        .route("/api/scheduled-transactions/{id}/skip", post(skip_scheduled))
+        .route("/api/scheduled-transactions/{id}/snooze", post(snooze_scheduled))
+        .route("/api/scheduled-transactions/{id}/history", get(get_scheduled_history))
+        .route("/api/scheduled-transactions/{id}/entries", get(get_scheduled_entries))
+        .route(
+            "/api/scheduled-transactions/{id}/occurrences",
+            get(get_scheduled_occurrences),
+        )
 }
 
 async fn post_scheduled(
@@ -32,7 +47,7 @@ async fn post_scheduled(
     auth: AuthUser,
     Json(body): Json<PostScheduledTransaction>,
 ) -> Result<Json<GetScheduledTransaction>, AppError> {
-    let new = build_new_scheduled(&body, &state.pool, auth.user_id)
+    let new = build_new_scheduled(&body, &state.pool, &state.holidays, auth.ledger_id)
         .await?
         .ok_or(AppError::BadRequest)?;
 
@@ -44,7 +59,43 @@ async fn get_scheduled_list(
     State(state): State<AppState>,
     auth: AuthUser,
 ) -> Result<Json<Vec<GetScheduledTransaction>>, AppError> {
-    Ok(Json(service::all_scheduled_enriched(&state.pool, auth.user_id).await?))
+    Ok(Json(service::all_scheduled_enriched(&state.pool, auth.ledger_id).await?))
+}
+
+/// The occurrences an unsaved schedule would have, validated as creating it would be.
+async fn preview_scheduled(
+    State(state): State<AppState>,
+    Query(query): Query<OccurrencesQuery>,
+    auth: AuthUser,
+    Json(body): Json<PostScheduledTransaction>,
+) -> Result<Json<Vec<UpcomingOccurrence>>, AppError> {
+    let new = build_new_scheduled(&body, &state.pool, &state.holidays, auth.ledger_id)
+        .await?
+        .ok_or(AppError::BadRequest)?;
+
+    let st = service::scheduled_transaction_from(&new);
+    Ok(Json(service::upcoming_occurrences(
+        &st,
+        &state.holidays,
+        service::estimated_value(&state.pool, &st).await?,
+        query.count.unwrap_or(service::PREVIEW_OCCURRENCES),
+        query.until,
+    )?))
+}
+
+/// Schedules that ran to completion, kept so their history and entries can be audited.
+async fn get_finished_list(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<GetScheduledTransaction>>, AppError> {
+    let finished = db::scheduled_transactions::get_finished(&state.pool, auth.ledger_id).await?;
+    let mut result = Vec::with_capacity(finished.len());
+
+    for st in &finished {
+        result.push(service::enrich_scheduled(&state.pool, st).await?);
+    }
+
+    Ok(Json(result))
 }
 
 async fn get_scheduled(
@@ -52,7 +103,7 @@ async fn get_scheduled(
     Path(id): Path<i32>,
     auth: AuthUser,
 ) -> Result<Json<GetScheduledTransaction>, AppError> {
-    let st = db::scheduled_transactions::get(&state.pool, id, auth.user_id).await?;
+    let st = db::scheduled_transactions::get(&state.pool, id, auth.ledger_id).await?;
     Ok(Json(service::enrich_scheduled(&state.pool, &st).await?))
 }
 
@@ -62,25 +113,46 @@ async fn patch_scheduled(
     auth: AuthUser,
     Json(body): Json<PatchScheduledTransaction>,
 ) -> Result<Json<GetScheduledTransaction>, AppError> {
-    // Ensure the scheduled transaction exists (404 otherwise).
-    db::scheduled_transactions::get(&state.pool, id, auth.user_id).await?;
+    let st = running(db::scheduled_transactions::get(&state.pool, id, auth.ledger_id).await?)?;
 
-    let new = build_new_scheduled(&body, &state.pool, auth.user_id)
+    let mut new = build_new_scheduled(&body, &state.pool, &state.holidays, auth.ledger_id)
         .await?
         .ok_or(AppError::BadRequest)?;
+    // Only a new recurrence starts the schedule over from its first occurrence.
+    if same_recurrence(&st, &new) {
+        new.current_repeat_count = st.current_repeat_count;
+        new.next_date = st.next_date;
+    }
 
-    let updated = db::scheduled_transactions::update(&state.pool, id, &new, auth.user_id).await?;
+    let mut tx = state.pool.begin().await?;
+    lock_due(&mut tx, &st).await?;
+    let updated =
+        db::scheduled_transactions::update_on(&mut tx, id, &new, auth.ledger_id).await?;
+    tx.commit().await?;
     Ok(Json(service::enrich_scheduled(&state.pool, &updated).await?))
 }
 
+/// Whether an edit leaves the schedule's due dates where they were.
+fn same_recurrence(st: &ScheduledTransaction, new: &NewScheduledTransaction) -> bool {
+    st.created_date == new.created_date
+        && st.repeat == new.repeat
+        && st.repeat_freq == new.repeat_freq
+        && st.repeat_interval == new.repeat_interval
+        && st.rrule == new.rrule
+        && st.business_day_adjustment == new.business_day_adjustment
+}
+
 async fn delete_scheduled(
     State(state): State<AppState>,
     Path(id): Path<i32>,
     auth: AuthUser,
 ) -> Result<Json<ScheduledTransaction>, AppError> {
-    Ok(Json(db::scheduled_transactions::delete(&state.pool, id, auth.user_id).await?))
+    Ok(Json(db::scheduled_transactions::delete(&state.pool, id, auth.ledger_id).await?))
 }
 
+/// Book the current occurrence as a transaction or transfer. With `partial`, only part of it is
+/// paid and the occurrence stays due until its payments add up to the scheduled value. The
+/// booked entries are linked to the schedule and occurrence.
 async fn pay_scheduled(
     State(state): State<AppState>,
     Path(id): Path<i32>,
@@ -88,37 +160,28 @@ async fn pay_scheduled(
     Json(body): Json<PostScheduledTransactionPay>,
 ) -> Result<Json<ScheduledTransaction>, AppError> {
     let pool = &state.pool;
-    let user_id = auth.user_id;
+    let ledger_id = auth.ledger_id;
 
-    let st = db::scheduled_transactions::get(pool, id, user_id).await?;
+    let st = running(db::scheduled_transactions::get(pool, id, ledger_id).await?)?;
+    if body.partial && body.value <= 0 {
+        return Err(AppError::BadRequest);
+    }
 
-    // 1. Materialise the scheduled item into a real transaction or transfer.
-    match st.kind {
+    // 1. Check what the payment books into.
+    let (accounts, category_id, loan) = match st.kind {
         ScheduledTransactionKinds::Transaction => {
             let (account_id, category_id) = match (body.account_id, body.category_id) {
                 (Some(a), Some(c)) => (a, c),
                 _ => return Err(AppError::BadRequest),
             };
 
-            db::accounts::get(pool, account_id, user_id)
+            db::accounts::get(pool, account_id, ledger_id)
                 .await
                 .map_err(|_| AppError::NotFound)?;
-            db::categories::get(pool, category_id, user_id)
+            db::categories::get(pool, category_id, ledger_id)
                 .await
                 .map_err(|_| AppError::NotFound)?;
-
-            db::transactions::insert(
-                pool,
-                &NewTransactionData {
-                    value: body.value,
-                    description: body.description.clone(),
-                    date: body.date,
-                    account: account_id,
-                    category: category_id,
-                    user_id,
-                },
-            )
-            .await?;
+            (vec![account_id], category_id, None)
         }
         ScheduledTransactionKinds::Transfer => {
             let (origin_id, destination_id) =
@@ -127,31 +190,239 @@ async fn pay_scheduled(
                     _ => return Err(AppError::BadRequest),
                 };
 
-            db::accounts::get(pool, origin_id, user_id)
+            db::accounts::get(pool, origin_id, ledger_id)
                 .await
                 .map_err(|_| AppError::BadRequest)?;
-            db::accounts::get(pool, destination_id, user_id)
+            db::accounts::get(pool, destination_id, ledger_id)
                 .await
                 .map_err(|_| AppError::BadRequest)?;
 
-            db::transfers::insert(
-                pool,
+            // A loan's payment is split into principal (transfer) and interest (expense).
+            let loan = db::loans::get_by_scheduled(pool, st.id, ledger_id)
+                .await?
+                .filter(|loan| loan.account_id == destination_id);
+            (vec![origin_id, destination_id], 0, loan)
+        }
+    };
+
+    // 2. Materialise the occurrence into a real transaction or transfer, record it and move the
+    // schedule on, all in one transaction that holds the schedule's lock.
+    let mut tx = pool.begin().await?;
+    let st = lock_due(&mut tx, &st).await?;
+    match (st.kind, &loan) {
+        (ScheduledTransactionKinds::Transaction, _) => {
+            db::transactions::insert_on(
+                &mut tx,
+                &NewTransactionData {
+                    value: body.value,
+                    description: body.description.clone(),
+                    date: body.date,
+                    account: accounts[0],
+                    category: category_id,
+                    ledger_id,
+                    status: TransactionStatuses::Uncleared,
+                    scheduled_transaction_id: Some(st.id),
+                    occurrence: st.current_repeat_count,
+                },
+            )
+            .await?;
+        }
+        (ScheduledTransactionKinds::Transfer, Some(loan)) => {
+            service::pay_loan_installment(
+                &mut tx,
+                loan,
+                &st,
+                accounts[0],
+                body.value,
+                &body.description,
+                body.date,
+            )
+            .await?;
+        }
+        (ScheduledTransactionKinds::Transfer, None) => {
+            db::transfers::insert_on(
+                &mut tx,
                 &NewTransferData {
-                    origin_account: origin_id,
-                    destination_account: destination_id,
+                    origin_account: accounts[0],
+                    destination_account: accounts[1],
                     value: body.value,
                     description: body.description.clone(),
                     date: body.date,
-                    user_id,
+                    ledger_id,
+                    origin_status: TransactionStatuses::Uncleared,
+                    destination_status: TransactionStatuses::Uncleared,
+                    scheduled_transaction_id: Some(st.id),
+                    occurrence: st.current_repeat_count,
                 },
             )
             .await?;
         }
     }
 
-    // 2. Either drop the schedule (one-off / finished) or advance it to the next occurrence.
+    // A partial payment leaves the occurrence due until its payments reach the scheduled
+    // value; a full one (or the payment completing it) moves the schedule on.
+    let mut outcome = OccurrenceOutcomes::Paid;
+    if body.partial {
+        let paid =
+            db::scheduled_occurrences::paid_value_on(&mut tx, id, st.current_repeat_count).await?;
+        if paid + body.value < st.value {
+            outcome = OccurrenceOutcomes::PartiallyPaid;
+        }
+    }
+    record_occurrence(&mut tx, &st, outcome, body.value, None).await?;
+
+    let st = if outcome == OccurrenceOutcomes::PartiallyPaid {
+        st
+    } else {
+        advance_scheduled(&mut tx, &state.holidays, &st).await?
+    };
+    tx.commit().await?;
+
+    // 3. A payment into or out of a credit card changes what its statement still owes.
+    service::sync_autopay(pool, &accounts, ledger_id).await?;
+    Ok(Json(st))
+}
+
+/// Move past the current occurrence without booking anything.
+async fn skip_scheduled(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<ScheduledTransaction>, AppError> {
+    let st = running(db::scheduled_transactions::get(&state.pool, id, auth.ledger_id).await?)?;
+
+    let mut tx = state.pool.begin().await?;
+    let st = lock_due(&mut tx, &st).await?;
+    record_occurrence(&mut tx, &st, OccurrenceOutcomes::Skipped, 0, None).await?;
+    let st = advance_scheduled(&mut tx, &state.holidays, &st).await?;
+
+    tx.commit().await?;
+    Ok(Json(st))
+}
+
+/// Move the current due date to a later date. The following occurrences keep their dates.
+async fn snooze_scheduled(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+    Json(body): Json<PostScheduledTransactionSnooze>,
+) -> Result<Json<ScheduledTransaction>, AppError> {
+    let st = running(db::scheduled_transactions::get(&state.pool, id, auth.ledger_id).await?)?;
+
+    let mut tx = state.pool.begin().await?;
+    let st = lock_due(&mut tx, &st).await?;
+    if body.date <= st.next_date.unwrap_or(st.created_date) {
+        return Err(AppError::BadRequest);
+    }
+    record_occurrence(&mut tx, &st, OccurrenceOutcomes::Snoozed, 0, Some(body.date)).await?;
+
+    let snoozed = service::rescheduled(&st, st.current_repeat_count, Some(body.date));
+    let st =
+        db::scheduled_transactions::update_on(&mut tx, id, &snoozed, auth.ledger_id).await?;
+
+    tx.commit().await?;
+    Ok(Json(st))
+}
+
+/// What happened to the schedule's occurrences so far, oldest first.
+async fn get_scheduled_history(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Vec<ScheduledOccurrence>>, AppError> {
+    db::scheduled_transactions::get(&state.pool, id, auth.ledger_id).await?;
+    Ok(Json(
+        db::scheduled_occurrences::get_by_scheduled(&state.pool, id, auth.ledger_id).await?,
+    ))
+}
+
+/// The transactions and transfers booked by paying the schedule, by occurrence.
+async fn get_scheduled_entries(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Vec<ScheduledEntry>>, AppError> {
+    db::scheduled_transactions::get(&state.pool, id, auth.ledger_id).await?;
+    Ok(Json(
+        db::scheduled_transactions::get_entries(&state.pool, id, auth.ledger_id).await?,
+    ))
+}
+
+/// The schedule's next occurrences, starting with the one currently due.
+async fn get_scheduled_occurrences(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(query): Query<OccurrencesQuery>,
+    auth: AuthUser,
+) -> Result<Json<Vec<UpcomingOccurrence>>, AppError> {
+    let st = db::scheduled_transactions::get(&state.pool, id, auth.ledger_id).await?;
+    Ok(Json(service::upcoming_occurrences(
+        &st,
+        &state.holidays,
+        service::estimated_value(&state.pool, &st).await?,
+        query.count.unwrap_or(service::PREVIEW_OCCURRENCES),
+        query.until,
+    )?))
+}
+
+/// A finished schedule has no occurrence left to pay, skip or snooze (`Conflict`).
+fn running(st: ScheduledTransaction) -> Result<ScheduledTransaction, AppError> {
+    if st.finished {
+        return Err(AppError::Conflict);
+    }
+    Ok(st)
+}
+
+/// Lock the schedule until the transaction ends, and check that the occurrence `st` was read at
+/// is still the one due: `Conflict` once another request paid, skipped or snoozed it meanwhile.
+async fn lock_due(
+    conn: &mut PgConnection,
+    st: &ScheduledTransaction,
+) -> Result<ScheduledTransaction, AppError> {
+    let locked = running(db::scheduled_transactions::lock_on(conn, st.id, st.ledger_id).await?)?;
+    if locked.current_repeat_count != st.current_repeat_count || locked.next_date != st.next_date
+    {
+        return Err(AppError::Conflict);
+    }
+    Ok(locked)
+}
+
+/// Add an entry for the schedule's current occurrence to its history.
+async fn record_occurrence(
+    conn: &mut PgConnection,
+    st: &ScheduledTransaction,
+    outcome: OccurrenceOutcomes,
+    value: i32,
+    snoozed_to: Option<NaiveDateTime>,
+) -> Result<(), AppError> {
+    db::scheduled_occurrences::insert_on(
+        conn,
+        &NewScheduledOccurrence {
+            scheduled_transaction_id: st.id,
+            occurrence: st.current_repeat_count,
+            due_date: st.next_date.unwrap_or(st.created_date),
+            outcome,
+            value,
+            snoozed_to,
+            ledger_id: st.ledger_id,
+        },
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Mark a schedule whose current occurrence was its last (one-off / end reached) as finished,
+/// or advance it to its next occurrence.
+async fn advance_scheduled(
+    conn: &mut PgConnection,
+    holidays: &Holidays,
+    st: &ScheduledTransaction,
+) -> Result<ScheduledTransaction, AppError> {
+    let (id, ledger_id) = (st.id, st.ledger_id);
+
     if !st.repeat {
-        return Ok(Json(db::scheduled_transactions::delete(pool, id, user_id).await?));
+        return db::scheduled_transactions::finish_on(conn, id, None, ledger_id).await;
     }
 
     let internal = |m: &str| AppError::Internal(m.to_string());
@@ -169,57 +440,37 @@ async fn pay_scheduled(
             .end_after_repeats
             .ok_or_else(|| internal("finite schedule missing end_after_repeats"))?;
         if new_repeat_count >= end_after_repeats {
-            return Ok(Json(db::scheduled_transactions::delete(pool, id, user_id).await?));
+            return db::scheduled_transactions::finish_on(
+                conn,
+                id,
+                Some(new_repeat_count),
+                ledger_id,
+            )
+            .await;
         }
     }
 
-    let repeat_freq = st
-        .repeat_freq
-        .ok_or_else(|| internal("repeating schedule missing repeat_freq"))?;
-    let repeat_interval = st
-        .repeat_interval
-        .ok_or_else(|| internal("repeating schedule missing repeat_interval"))?;
-
-    let next_date = service::calculate_next_date(
-        st.created_date,
-        st.repeat,
-        repeat_freq,
-        repeat_interval,
-        new_repeat_count,
-    );
-
-    let updated_input = NewScheduledTransaction {
-        kind: st.kind,
-        value: st.value,
-        description: st.description.clone(),
-        created_date: st.created_date,
-        account_id: st.account_id,
-        category_id: st.category_id,
-        origin_account_id: st.origin_account_id,
-        destination_account_id: st.destination_account_id,
-        repeat: st.repeat,
-        repeat_freq: st.repeat_freq,
-        repeat_interval: st.repeat_interval,
-        infinite_repeat: st.infinite_repeat,
-        end_after_repeats: st.end_after_repeats,
-        current_repeat_count: Some(new_repeat_count),
-        next_date: Some(next_date),
-        user_id,
+    // A rule with no further occurrence (past its UNTIL or COUNT) is finished too.
+    let Some(next_date) = service::scheduled_occurrence(st, new_repeat_count, holidays)? else {
+        return db::scheduled_transactions::finish_on(conn, id, Some(new_repeat_count), ledger_id)
+            .await;
     };
 
-    db::scheduled_transactions::update(pool, id, &updated_input, user_id)
+    let updated_input = service::rescheduled(st, Some(new_repeat_count), Some(next_date));
+    db::scheduled_transactions::update_on(conn, id, &updated_input, ledger_id)
         .await
-        .map(Json)
         .map_err(|_| internal("failed to update scheduled transaction"))
 }
 
 /// Validate and assemble a `NewScheduledTransaction` from a request body. Returns `Ok(None)`
 /// when the payload is invalid (missing repeat parameters, missing/unknown referenced
 /// accounts or category, or origin == destination), which the caller maps to HTTP 400.
+/// The first due date is moved off weekends and `holidays` per the body's adjustment.
 async fn build_new_scheduled(
     body: &PostScheduledTransaction,
     pool: &PgPool,
-    user_id: i32,
+    holidays: &Holidays,
+    ledger_id: i32,
 ) -> Result<Option<NewScheduledTransaction>, AppError> {
     let mut new = NewScheduledTransaction {
         kind: body.kind,
@@ -233,23 +484,38 @@ async fn build_new_scheduled(
         repeat: body.repeat,
         repeat_freq: None,
         repeat_interval: None,
+        rrule: None,
+        business_day_adjustment: body.business_day_adjustment,
+        amount_estimate: body.amount_estimate,
         infinite_repeat: None,
         end_after_repeats: None,
         current_repeat_count: None,
         next_date: Some(body.created_date),
-        user_id,
+        ledger_id,
+        autopay_card_id: None,
     };
 
     if body.repeat {
-        let Some(freq) = body.repeat_freq else {
-            return Ok(None);
-        };
-        new.repeat_freq = Some(freq);
+        if let Some(rule) = &body.rrule {
+            // The rule replaces freq/interval; its first occurrence is the first due date.
+            let Some(first) =
+                RRule::parse(rule).and_then(|rule| rule.nth(body.created_date, 0))
+            else {
+                return Ok(None);
+            };
+            new.rrule = Some(rule.trim().to_string());
+            new.next_date = Some(first);
+        } else {
+            let Some(freq) = body.repeat_freq else {
+                return Ok(None);
+            };
+            new.repeat_freq = Some(freq);
 
-        let Some(interval) = body.repeat_interval else {
-            return Ok(None);
-        };
-        new.repeat_interval = Some(interval);
+            let Some(interval) = body.repeat_interval else {
+                return Ok(None);
+            };
+            new.repeat_interval = Some(interval);
+        }
 
         let Some(infinite) = body.infinite_repeat else {
             return Ok(None);
@@ -266,14 +532,20 @@ async fn build_new_scheduled(
         }
 
         new.current_repeat_count = Some(0);
+    } else if body.rrule.is_some() {
+        return Ok(None);
     }
 
+    new.next_date = new
+        .next_date
+        .map(|date| holidays.adjust(date, body.business_day_adjustment));
+
     match body.kind {
         ScheduledTransactionKinds::Transaction => {
             let Some(account_id) = body.account_id else {
                 return Ok(None);
             };
-            let account = match db::accounts::get(pool, account_id, user_id).await {
+            let account = match db::accounts::get(pool, account_id, ledger_id).await {
                 Ok(account) => account,
                 Err(_) => return Ok(None),
             };
@@ -281,7 +553,7 @@ async fn build_new_scheduled(
             let Some(category_id) = body.category_id else {
                 return Ok(None);
             };
-            let category = match db::categories::get(pool, category_id, user_id).await {
+            let category = match db::categories::get(pool, category_id, ledger_id).await {
                 Ok(category) => category,
                 Err(_) => return Ok(None),
             };
@@ -293,7 +565,7 @@ async fn build_new_scheduled(
             let Some(origin_id) = body.origin_account_id else {
                 return Ok(None);
             };
-            let origin = match db::accounts::get(pool, origin_id, user_id).await {
+            let origin = match db::accounts::get(pool, origin_id, ledger_id).await {
                 Ok(account) => account,
                 Err(_) => return Ok(None),
             };
@@ -301,7 +573,7 @@ async fn build_new_scheduled(
             let Some(destination_id) = body.destination_account_id else {
                 return Ok(None);
             };
-            let destination = match db::accounts::get(pool, destination_id, user_id).await {
+            let destination = match db::accounts::get(pool, destination_id, ledger_id).await {
                 Ok(account) => account,
                 Err(_) => return Ok(None),
             };
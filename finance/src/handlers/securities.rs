@@ -0,0 +1,117 @@
+use axum::extract::{Path, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+
+use crate::auth::AuthUser;
+use crate::db;
+use crate::error::AppError;
+use crate::models::{
+    PatchSecurity, PostSecurity, PostSecurityPrice, PriceImport, Security, SecurityPrice,
+};
+use crate::service;
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/securities", post(post_security).get(get_securities))
+        .route(
+            "/api/securities/{id}",
+            get(get_security).patch(patch_security).delete(delete_security),
+        )
+        .route(
+            "/api/securities/{id}/prices",
+            post(post_security_price).get(get_security_prices),
+        )
+        .route("/api/prices/import", post(post_price_import))
+}
+
+/// Trim and upper-case the symbol; `BadRequest` if it is empty.
+fn normalize(mut body: PostSecurity) -> Result<PostSecurity, AppError> {
+    body.symbol = body.symbol.trim().to_uppercase();
+    if body.symbol.is_empty() {
+        return Err(AppError::BadRequest);
+    }
+    Ok(body)
+}
+
+async fn post_security(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<PostSecurity>,
+) -> Result<Json<Security>, AppError> {
+    let body = normalize(body)?;
+    Ok(Json(db::securities::insert(&state.pool, &body, auth.ledger_id).await?))
+}
+
+async fn get_securities(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<Security>>, AppError> {
+    Ok(Json(db::securities::get_all(&state.pool, auth.ledger_id).await?))
+}
+
+async fn get_security(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Security>, AppError> {
+    Ok(Json(db::securities::get(&state.pool, id, auth.ledger_id).await?))
+}
+
+async fn patch_security(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+    Json(body): Json<PatchSecurity>,
+) -> Result<Json<Security>, AppError> {
+    let body = normalize(body)?;
+    Ok(Json(db::securities::update(&state.pool, id, &body, auth.ledger_id).await?))
+}
+
+/// Delete a security along with all of its transactions and prices.
+async fn delete_security(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Security>, AppError> {
+    Ok(Json(db::securities::delete(&state.pool, id, auth.ledger_id).await?))
+}
+
+/// Record (or replace) the security's price on a date.
+async fn post_security_price(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+    Json(body): Json<PostSecurityPrice>,
+) -> Result<Json<SecurityPrice>, AppError> {
+    db::securities::get(&state.pool, id, auth.ledger_id).await?;
+    if body.price < 0 {
+        return Err(AppError::BadRequest);
+    }
+
+    let price = SecurityPrice {
+        security_id: id,
+        date: body.date,
+        price: body.price,
+    };
+    db::securities::upsert_prices(&state.pool, std::slice::from_ref(&price)).await?;
+    Ok(Json(price))
+}
+
+async fn get_security_prices(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Vec<SecurityPrice>>, AppError> {
+    db::securities::get(&state.pool, id, auth.ledger_id).await?;
+    Ok(Json(db::securities::get_prices(&state.pool, id).await?))
+}
+
+/// Import prices from a CSV request body (`symbol,date,price` rows).
+async fn post_price_import(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    body: String,
+) -> Result<Json<PriceImport>, AppError> {
+    Ok(Json(service::import_prices(&state.pool, &body, auth.ledger_id).await?))
+}
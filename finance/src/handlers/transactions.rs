@@ -6,7 +6,8 @@ use crate::auth::AuthUser;
 use crate::db;
 use crate::error::AppError;
 use crate::models::{
-    NewTransactionData, PatchTransaction, PostTransaction, Transaction, TransactionTransferJoined,
+    NewTransactionData, PatchTransaction, PostTransaction, Transaction, TransactionStatuses,
+    TransactionTransferJoined,
 };
 use crate::service;
 use crate::state::AppState;
@@ -21,6 +22,7 @@ pub fn routes() -> Router<AppState> {
             "/api/transactions/{id}",
             get(get_transaction).patch(patch_transaction).delete(delete_transaction),
         )
+        .route("/api/transactions/{id}/unlock", post(unlock_transaction))
 }
 
 async fn post_transaction(
@@ -29,9 +31,14 @@ async fn post_transaction(
     auth: AuthUser,
     Json(body): Json<PostTransaction>,
 ) -> Result<Json<Transaction>, AppError> {
-    // Both the account and category must exist and belong to the user (404 otherwise).
-    db::accounts::get(&state.pool, account_id, auth.user_id).await?;
-    db::categories::get(&state.pool, body.category, auth.user_id).await?;
+    // Only a finished reconciliation may mark a transaction reconciled.
+    if body.status == Some(TransactionStatuses::Reconciled) {
+        return Err(AppError::BadRequest);
+    }
+
+    // Both the account and category must exist and belong to the ledger (404 otherwise).
+    db::accounts::get(&state.pool, account_id, auth.ledger_id).await?;
+    db::categories::get(&state.pool, body.category, auth.ledger_id).await?;
 
     let data = NewTransactionData {
         value: body.value,
@@ -39,10 +46,15 @@ async fn post_transaction(
         date: body.date,
         account: account_id,
         category: body.category,
-        user_id: auth.user_id,
+        ledger_id: auth.ledger_id,
+        status: body.status.unwrap_or_default(),
+        scheduled_transaction_id: None,
+        occurrence: None,
     };
 
-    Ok(Json(db::transactions::insert(&state.pool, &data).await?))
+    let transaction = db::transactions::insert(&state.pool, &data).await?;
+    service::sync_autopay(&state.pool, &[account_id], auth.ledger_id).await?;
+    Ok(Json(transaction))
 }
 
 async fn get_transactions(
@@ -50,26 +62,12 @@ async fn get_transactions(
     Path(account_id): Path<i32>,
     auth: AuthUser,
 ) -> Result<Json<Vec<TransactionTransferJoined>>, AppError> {
-    // 404 if the account does not exist for this user.
-    db::accounts::get(&state.pool, account_id, auth.user_id).await?;
-
-    let mut list = Vec::new();
+    // 404 if the account does not exist in this ledger.
+    db::accounts::get(&state.pool, account_id, auth.ledger_id).await?;
 
-    for row in db::transactions::get_all_of_account_joined(&state.pool, account_id, auth.user_id).await? {
-        list.push(service::tx_join_to_dto(row));
-    }
-    for row in db::transfers::get_from_account_joined(&state.pool, account_id, auth.user_id).await? {
-        list.push(service::transfer_from_to_dto(row, auth.user_id));
-    }
-    for row in db::transfers::get_to_account_joined(&state.pool, account_id, auth.user_id).await? {
-        list.push(service::transfer_to_to_dto(row, auth.user_id));
-    }
-
-    // Sort by date ascending then reverse -> descending (stable), matching the original.
-    list.sort_by_key(|t| t.date);
-    list.reverse();
-
-    Ok(Json(list))
+    Ok(Json(
+        service::account_movements(&state.pool, account_id, auth.ledger_id).await?,
+    ))
 }
 
 async fn get_transaction(
@@ -77,18 +75,27 @@ async fn get_transaction(
     Path(id): Path<i32>,
     auth: AuthUser,
 ) -> Result<Json<TransactionTransferJoined>, AppError> {
-    let row = db::transactions::get_joined(&state.pool, id, auth.user_id).await?;
+    let row = db::transactions::get_joined(&state.pool, id, auth.ledger_id).await?;
     Ok(Json(service::tx_join_to_dto(row)))
 }
 
+/// Update a transaction. Reconciled transactions are locked (409) until unlocked.
 async fn patch_transaction(
     State(state): State<AppState>,
     Path(id): Path<i32>,
     auth: AuthUser,
     Json(body): Json<PatchTransaction>,
 ) -> Result<Json<Transaction>, AppError> {
-    db::accounts::get(&state.pool, body.account, auth.user_id).await?;
-    db::categories::get(&state.pool, body.category, auth.user_id).await?;
+    let current = db::transactions::get(&state.pool, id, auth.ledger_id).await?;
+    if current.status == TransactionStatuses::Reconciled {
+        return Err(AppError::Conflict);
+    }
+    if body.status == Some(TransactionStatuses::Reconciled) {
+        return Err(AppError::BadRequest);
+    }
+
+    db::accounts::get(&state.pool, body.account, auth.ledger_id).await?;
+    db::categories::get(&state.pool, body.category, auth.ledger_id).await?;
 
     let data = NewTransactionData {
         value: body.value,
@@ -96,16 +103,47 @@ async fn patch_transaction(
         date: body.date,
         account: body.account,
         category: body.category,
-        user_id: auth.user_id,
+        ledger_id: auth.ledger_id,
+        status: body.status.unwrap_or(current.status),
+        scheduled_transaction_id: current.scheduled_transaction_id,
+        occurrence: current.occurrence,
     };
 
-    Ok(Json(db::transactions::update(&state.pool, id, &data, auth.user_id).await?))
+    let transaction = db::transactions::update(&state.pool, id, &data, auth.ledger_id).await?;
+    service::sync_autopay(&state.pool, &[current.account, body.account], auth.ledger_id).await?;
+    Ok(Json(transaction))
 }
 
+/// Delete a transaction. Reconciled transactions are locked (409) until unlocked.
 async fn delete_transaction(
     State(state): State<AppState>,
     Path(id): Path<i32>,
     auth: AuthUser,
 ) -> Result<Json<Transaction>, AppError> {
-    Ok(Json(db::transactions::delete(&state.pool, id, auth.user_id).await?))
+    let current = db::transactions::get(&state.pool, id, auth.ledger_id).await?;
+    if current.status == TransactionStatuses::Reconciled {
+        return Err(AppError::Conflict);
+    }
+
+    let transaction = db::transactions::delete(&state.pool, id, auth.ledger_id).await?;
+    service::sync_autopay(&state.pool, &[current.account], auth.ledger_id).await?;
+    Ok(Json(transaction))
+}
+
+/// Explicitly unlock a reconciled transaction so it can be edited again. It drops back to
+/// cleared (it still matches the bank statement until it is changed).
+async fn unlock_transaction(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Transaction>, AppError> {
+    let current = db::transactions::get(&state.pool, id, auth.ledger_id).await?;
+    if current.status != TransactionStatuses::Reconciled {
+        return Ok(Json(current));
+    }
+
+    Ok(Json(
+        db::transactions::set_status(&state.pool, id, TransactionStatuses::Cleared, auth.ledger_id)
+            .await?,
+    ))
 }
@@ -5,7 +5,8 @@ use axum::{Json, Router};
 use crate::auth::AuthUser;
 use crate::db;
 use crate::error::AppError;
-use crate::models::{NewTransferData, PatchTransfer, PostTransfer, Transfer};
+use crate::models::{NewTransferData, PatchTransfer, PostTransfer, TransactionStatuses, Transfer};
+use crate::service;
 use crate::state::AppState;
 
 pub fn routes() -> Router<AppState> {
@@ -18,6 +19,7 @@ pub fn routes() -> Router<AppState> {
             "/api/transfers/{id}",
             get(get_transfer).patch(patch_transfer).delete(delete_transfer),
         )
+        .route("/api/transfers/{id}/unlock", post(unlock_transfer))
 }
 
 async fn post_transfer(
@@ -26,9 +28,15 @@ async fn post_transfer(
     auth: AuthUser,
     Json(body): Json<PostTransfer>,
 ) -> Result<Json<Transfer>, AppError> {
-    // Both endpoints of the transfer must exist for this user (404 otherwise).
-    db::accounts::get(&state.pool, origin_account, auth.user_id).await?;
-    db::accounts::get(&state.pool, destination_account, auth.user_id).await?;
+    // Only a finished reconciliation may mark a transfer reconciled.
+    let statuses = [body.origin_status, body.destination_status];
+    if statuses.contains(&Some(TransactionStatuses::Reconciled)) {
+        return Err(AppError::BadRequest);
+    }
+
+    // Both endpoints of the transfer must exist in this ledger (404 otherwise).
+    db::accounts::get(&state.pool, origin_account, auth.ledger_id).await?;
+    db::accounts::get(&state.pool, destination_account, auth.ledger_id).await?;
 
     let data = NewTransferData {
         origin_account,
@@ -36,10 +44,17 @@ async fn post_transfer(
         value: body.value,
         description: body.description,
         date: body.date,
-        user_id: auth.user_id,
+        ledger_id: auth.ledger_id,
+        origin_status: body.origin_status.unwrap_or_default(),
+        destination_status: body.destination_status.unwrap_or_default(),
+        scheduled_transaction_id: None,
+        occurrence: None,
     };
 
-    Ok(Json(db::transfers::insert(&state.pool, &data).await?))
+    let transfer = db::transfers::insert(&state.pool, &data).await?;
+    service::sync_autopay(&state.pool, &[origin_account, destination_account], auth.ledger_id)
+        .await?;
+    Ok(Json(transfer))
 }
 
 async fn get_transfer(
@@ -47,17 +62,27 @@ async fn get_transfer(
     Path(id): Path<i32>,
     auth: AuthUser,
 ) -> Result<Json<Transfer>, AppError> {
-    Ok(Json(db::transfers::get(&state.pool, id, auth.user_id).await?))
+    Ok(Json(db::transfers::get(&state.pool, id, auth.ledger_id).await?))
 }
 
+/// Update a transfer. Transfers reconciled on either side are locked (409) until unlocked.
 async fn patch_transfer(
     State(state): State<AppState>,
     Path(id): Path<i32>,
     auth: AuthUser,
     Json(body): Json<PatchTransfer>,
 ) -> Result<Json<Transfer>, AppError> {
-    db::accounts::get(&state.pool, body.origin_account, auth.user_id).await?;
-    db::accounts::get(&state.pool, body.destination_account, auth.user_id).await?;
+    let current = db::transfers::get(&state.pool, id, auth.ledger_id).await?;
+    if is_locked(&current) {
+        return Err(AppError::Conflict);
+    }
+    let statuses = [body.origin_status, body.destination_status];
+    if statuses.contains(&Some(TransactionStatuses::Reconciled)) {
+        return Err(AppError::BadRequest);
+    }
+
+    db::accounts::get(&state.pool, body.origin_account, auth.ledger_id).await?;
+    db::accounts::get(&state.pool, body.destination_account, auth.ledger_id).await?;
 
     let data = NewTransferData {
         origin_account: body.origin_account,
@@ -65,16 +90,61 @@ async fn patch_transfer(
         value: body.value,
         description: body.description,
         date: body.date,
-        user_id: auth.user_id,
+        ledger_id: auth.ledger_id,
+        origin_status: body.origin_status.unwrap_or(current.origin_status),
+        destination_status: body.destination_status.unwrap_or(current.destination_status),
+        scheduled_transaction_id: current.scheduled_transaction_id,
+        occurrence: current.occurrence,
     };
 
-    Ok(Json(db::transfers::update(&state.pool, id, &data, auth.user_id).await?))
+    let transfer = db::transfers::update(&state.pool, id, &data, auth.ledger_id).await?;
+    let accounts = [
+        current.origin_account,
+        current.destination_account,
+        transfer.origin_account,
+        transfer.destination_account,
+    ];
+    service::sync_autopay(&state.pool, &accounts, auth.ledger_id).await?;
+    Ok(Json(transfer))
 }
 
+/// Delete a transfer. Transfers reconciled on either side are locked (409) until unlocked.
 async fn delete_transfer(
     State(state): State<AppState>,
     Path(id): Path<i32>,
     auth: AuthUser,
 ) -> Result<Json<Transfer>, AppError> {
-    Ok(Json(db::transfers::delete(&state.pool, id, auth.user_id).await?))
+    let current = db::transfers::get(&state.pool, id, auth.ledger_id).await?;
+    if is_locked(&current) {
+        return Err(AppError::Conflict);
+    }
+
+    let transfer = db::transfers::delete(&state.pool, id, auth.ledger_id).await?;
+    service::sync_autopay(
+        &state.pool,
+        &[transfer.origin_account, transfer.destination_account],
+        auth.ledger_id,
+    )
+    .await?;
+    Ok(Json(transfer))
+}
+
+/// Explicitly unlock a reconciled transfer so it can be edited again (its reconciled sides back
+/// to cleared).
+async fn unlock_transfer(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    auth: AuthUser,
+) -> Result<Json<Transfer>, AppError> {
+    let current = db::transfers::get(&state.pool, id, auth.ledger_id).await?;
+    if !is_locked(&current) {
+        return Ok(Json(current));
+    }
+
+    Ok(Json(db::transfers::unlock(&state.pool, id, auth.ledger_id).await?))
+}
+
+fn is_locked(transfer: &Transfer) -> bool {
+    transfer.origin_status == TransactionStatuses::Reconciled
+        || transfer.destination_status == TransactionStatuses::Reconciled
 }
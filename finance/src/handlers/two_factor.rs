@@ -0,0 +1,107 @@
+use axum::extract::State;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+
+use crate::auth::AuthUser;
+use crate::db;
+use crate::error::AppError;
+use crate::models::{ConfirmPassword, PostTotpCode, RecoveryCodes, TotpEnrolment, TwoFactorStatus};
+use crate::password;
+use crate::rate_limit::ClientIp;
+use crate::state::AppState;
+use crate::totp;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/users/me/2fa",
+            get(get_two_factor).post(post_two_factor).delete(delete_two_factor),
+        )
+        .route("/api/users/me/2fa/confirm", post(confirm_two_factor))
+        .route("/api/users/me/2fa/recovery-codes", post(post_recovery_codes))
+}
+
+async fn get_two_factor(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<TwoFactorStatus>, AppError> {
+    Ok(Json(db::two_factor::get_status(&state.pool, auth.user_id).await?))
+}
+
+/// Start enrolling: a new secret for the authenticator app, replacing any earlier one that was
+/// not confirmed. `Conflict` if two-factor authentication is already on; not found when no
+/// `TOTP_ENCRYPTION_KEY` is configured.
+async fn post_two_factor(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<TotpEnrolment>, AppError> {
+    let key = state.config.totp_encryption_key.as_ref().ok_or(AppError::NotFound)?;
+    let user = db::users::get(&state.pool, auth.user_id).await?;
+
+    let secret = totp::generate_secret();
+    let encrypted = totp::encrypt(key, auth.user_id, &secret)?;
+    if !db::two_factor::set_secret(&state.pool, auth.user_id, &encrypted).await? {
+        return Err(AppError::Conflict);
+    }
+
+    Ok(Json(TotpEnrolment {
+        secret: totp::encode_secret(&secret),
+        otpauth_uri: totp::otpauth_uri(&state.config.totp_issuer, &user.name, &secret),
+    }))
+}
+
+/// Turn two-factor authentication on with a first code from the authenticator app (`Forbidden`
+/// if it is wrong), returning the recovery codes. Not found without a pending secret.
+async fn confirm_two_factor(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<PostTotpCode>,
+) -> Result<Json<RecoveryCodes>, AppError> {
+    let stored = db::two_factor::get(&state.pool, auth.user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if stored.totp_enabled {
+        return Err(AppError::Conflict);
+    }
+    if stored.totp_secret.is_none() {
+        return Err(AppError::NotFound);
+    }
+    if !totp::verify_user(&state.pool, &state.config, auth.user_id, &body.code).await? {
+        return Err(AppError::Forbidden);
+    }
+
+    let recovery_codes = db::two_factor::enable(&state.pool, auth.user_id).await?;
+    tracing::info!("user {} enabled two-factor authentication", auth.user_id);
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+/// Replace the recovery codes, given the password (see [`password::confirm`]). Not found while
+/// two-factor authentication is off.
+async fn post_recovery_codes(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    auth: AuthUser,
+    Json(body): Json<ConfirmPassword>,
+) -> Result<Json<RecoveryCodes>, AppError> {
+    password::confirm(&state, ip, auth.user_id, &body.password).await?;
+    if !db::two_factor::get_status(&state.pool, auth.user_id).await?.enabled {
+        return Err(AppError::NotFound);
+    }
+
+    let recovery_codes = db::two_factor::replace_recovery_codes(&state.pool, auth.user_id).await?;
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+/// Turn two-factor authentication off, given the password (see [`password::confirm`]).
+async fn delete_two_factor(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    auth: AuthUser,
+    Json(body): Json<ConfirmPassword>,
+) -> Result<Json<TwoFactorStatus>, AppError> {
+    password::confirm(&state, ip, auth.user_id, &body.password).await?;
+
+    db::two_factor::disable(&state.pool, auth.user_id).await?;
+    tracing::info!("user {} disabled two-factor authentication", auth.user_id);
+    Ok(Json(db::two_factor::get_status(&state.pool, auth.user_id).await?))
+}
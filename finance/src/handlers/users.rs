@@ -0,0 +1,150 @@
+use axum::extract::{Path, State};
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use chrono::Local;
+
+use crate::auth::AuthUser;
+use crate::db;
+use crate::digest;
+use crate::error::AppError;
+use crate::mail;
+use crate::models::{DeleteAppUser, GetAppUser, NotificationPreferences, PutPassword, PutUserName};
+use crate::password;
+use crate::rate_limit::ClientIp;
+use crate::service;
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/users/me", get(get_user).delete(delete_user))
+        .route("/api/users/me/password", put(put_password))
+        .route("/api/users/me/name", put(put_name))
+        .route("/api/users/me/export", get(get_export))
+        .route(
+            "/api/users/me/notifications",
+            get(get_notification_preferences).put(put_notification_preferences),
+        )
+        .route("/api/users/me/notifications/digest", post(post_digest))
+        .route("/api/unsubscribe/{token}", get(unsubscribe))
+}
+
+async fn get_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<GetAppUser>, AppError> {
+    Ok(Json(db::users::get(&state.pool, auth.user_id).await?))
+}
+
+/// Change the password, given the current one (see [`password::confirm`]); the new one must meet
+/// the password policy (see [`password::check_policy`]). The user's other sessions are logged out.
+async fn put_password(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    auth: AuthUser,
+    Json(body): Json<PutPassword>,
+) -> Result<Json<GetAppUser>, AppError> {
+    let user = db::users::get(&state.pool, auth.user_id).await?;
+    password::check_policy(&user.name, &body.new_password, &state.config)?;
+    password::confirm(&state, ip, auth.user_id, &body.current_password).await?;
+
+    let hash = password::hash(&body.new_password, &state.config).await?;
+    db::users::update_password(&state.pool, auth.user_id, &hash).await?;
+    db::sessions::revoke_others(&state.pool, auth.user_id, auth.session()?).await?;
+
+    Ok(Json(db::users::get(&state.pool, auth.user_id).await?))
+}
+
+/// Change the name the user logs in with; `Conflict` if another user has it.
+async fn put_name(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<PutUserName>,
+) -> Result<Json<GetAppUser>, AppError> {
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest);
+    }
+
+    Ok(Json(db::users::update_name(&state.pool, auth.user_id, name).await?))
+}
+
+/// Everything the user has stored, as one JSON document to keep before deleting the account.
+async fn get_export(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<serde_json::Value>, AppError> {
+    Ok(Json(db::users::export(&state.pool, auth.user_id).await?))
+}
+
+/// Delete the account and all of its data, given the password again (see
+/// [`password::confirm`]). This cannot be undone; `GET /api/users/me/export` first to keep a copy.
+async fn delete_user(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    auth: AuthUser,
+    Json(body): Json<DeleteAppUser>,
+) -> Result<Json<GetAppUser>, AppError> {
+    password::confirm(&state, ip, auth.user_id, &body.password).await?;
+
+    let user = db::users::delete(&state.pool, auth.user_id).await?;
+    tracing::info!("deleted user {} ({})", user.id, user.name);
+    Ok(Json(user))
+}
+
+async fn get_notification_preferences(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<NotificationPreferences>, AppError> {
+    Ok(Json(db::users::get_notification_preferences(&state.pool, auth.user_id).await?))
+}
+
+/// Set the email address and digest preferences. The digest needs a valid address, and looks
+/// ahead 0 to [`service::MAX_DIGEST_LEAD_DAYS`] days. A blank address removes it.
+async fn put_notification_preferences(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(mut body): Json<NotificationPreferences>,
+) -> Result<Json<NotificationPreferences>, AppError> {
+    body.email = body
+        .email
+        .map(|email| email.trim().to_string())
+        .filter(|email| !email.is_empty());
+
+    if body.email.as_deref().is_some_and(|email| !mail::is_valid_address(email))
+        || (body.digest_enabled && body.email.is_none())
+        || !(0..=service::MAX_DIGEST_LEAD_DAYS).contains(&body.digest_lead_days)
+    {
+        return Err(AppError::BadRequest);
+    }
+
+    Ok(Json(db::users::update_notification_preferences(&state.pool, &body, auth.user_id).await?))
+}
+
+/// Send the digest right away (whether or not the daily digest is enabled), e.g. to check the
+/// address. `true` if it was sent, `false` if there was nothing to report. Not found without an
+/// email address or when no SMTP relay is configured.
+async fn post_digest(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<bool>, AppError> {
+    let mailer = state.mailer.clone().ok_or(AppError::NotFound)?;
+    let recipient = db::users::get_digest_recipient(&state.pool, auth.user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let sent = digest::send(&state, &mailer, &recipient, Local::now().date_naive()).await?;
+    Ok(Json(sent))
+}
+
+/// The unsubscribe link of the digest emails. The token in the path identifies the user instead
+/// of a JWT, so the link works straight from the email; an unknown token is not found.
+async fn unsubscribe(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<&'static str, AppError> {
+    db::users::unsubscribe(&state.pool, &token)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    Ok("You have been unsubscribed from the daily digest.\n")
+}
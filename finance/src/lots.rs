@@ -0,0 +1,206 @@
+//! Lot tracking for investment positions.
+//!
+//! Each buy opens a lot: the units bought and what they cost. A sell consumes units from the
+//! oldest lots first; its realized gain is the proceeds minus the cost of the units sold. With
+//! FIFO that cost is the cost of the lots consumed; with average cost every lot held is first
+//! re-priced at the average cost of all units held, so the units sold cost that average.
+//! Quantities may be fractional; costs are tracked as fractional cents and rounded on output.
+
+use std::collections::VecDeque;
+
+use chrono::NaiveDateTime;
+
+use crate::models::{CostBasisMethods, InvestmentKinds, InvestmentTransaction, Lot};
+
+/// Quantities below this are treated as zero, absorbing floating-point error when a position
+/// is sold off in several parts.
+const EPSILON: f64 = 1e-9;
+
+struct OpenLot {
+    date: NaiveDateTime,
+    quantity: f64,
+    cost: f64,
+}
+
+/// The state of one security's position after replaying its history.
+pub struct Tracked {
+    pub quantity: f64,
+    pub cost_basis: i32,
+    pub realized_gain: i32,
+    pub dividends: i32,
+    pub lots: Vec<Lot>,
+}
+
+/// Re-price every lot at the average cost of all units held.
+fn pool(lots: &mut VecDeque<OpenLot>) {
+    let quantity: f64 = lots.iter().map(|lot| lot.quantity).sum();
+    let cost: f64 = lots.iter().map(|lot| lot.cost).sum();
+    if quantity <= EPSILON {
+        return;
+    }
+
+    for lot in lots.iter_mut() {
+        lot.cost = cost * lot.quantity / quantity;
+    }
+}
+
+/// Remove `quantity` units from the oldest lots, returning their cost.
+fn consume(lots: &mut VecDeque<OpenLot>, mut quantity: f64) -> f64 {
+    let mut cost = 0.0;
+
+    while quantity > EPSILON {
+        let Some(lot) = lots.front_mut() else {
+            break;
+        };
+
+        let taken = quantity.min(lot.quantity);
+        let taken_cost = lot.cost * taken / lot.quantity;
+        cost += taken_cost;
+        lot.cost -= taken_cost;
+        lot.quantity -= taken;
+        quantity -= taken;
+
+        if lot.quantity <= EPSILON {
+            lots.pop_front();
+        }
+    }
+
+    cost
+}
+
+/// Replay a security's transactions, in date order, into its open lots and gains. Returns
+/// `None` if a sell is for more units than were held at that point.
+pub fn track(trades: &[InvestmentTransaction], method: CostBasisMethods) -> Option<Tracked> {
+    let mut lots = VecDeque::new();
+    let mut realized_gain = 0.0;
+    let mut dividends = 0;
+
+    for trade in trades {
+        match trade.kind {
+            InvestmentKinds::Buy => lots.push_back(OpenLot {
+                date: trade.date,
+                quantity: trade.quantity,
+                cost: f64::from(trade.value),
+            }),
+            InvestmentKinds::Sell => {
+                let held: f64 = lots.iter().map(|lot| lot.quantity).sum();
+                if trade.quantity > held + EPSILON {
+                    return None;
+                }
+
+                if method == CostBasisMethods::Average {
+                    pool(&mut lots);
+                }
+                realized_gain += f64::from(trade.value) - consume(&mut lots, trade.quantity);
+            }
+            InvestmentKinds::Dividend => dividends += trade.value,
+        }
+    }
+
+    if method == CostBasisMethods::Average {
+        pool(&mut lots);
+    }
+
+    let quantity: f64 = lots.iter().map(|lot| lot.quantity).sum();
+    let cost: f64 = lots.iter().map(|lot| lot.cost).sum();
+
+    Some(Tracked {
+        quantity,
+        cost_basis: cost.round() as i32,
+        realized_gain: realized_gain.round() as i32,
+        dividends,
+        lots: lots
+            .into_iter()
+            .map(|lot| Lot {
+                date: lot.date,
+                quantity: lot.quantity,
+                cost: lot.cost.round() as i32,
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(
+        kind: InvestmentKinds,
+        date: &str,
+        quantity: f64,
+        value: i32,
+    ) -> InvestmentTransaction {
+        InvestmentTransaction {
+            id: 0,
+            account_id: 1,
+            security_id: 1,
+            kind,
+            quantity,
+            price: 0,
+            value,
+            description: String::new(),
+            date: NaiveDateTime::parse_from_str(&format!("{date} 00:00"), "%Y-%m-%d %H:%M")
+                .unwrap(),
+            ledger_id: 1,
+        }
+    }
+
+    /// Two buys at different prices, then a sell that takes all of the first lot and half of
+    /// the second, and a dividend.
+    fn history() -> Vec<InvestmentTransaction> {
+        vec![
+            trade(InvestmentKinds::Buy, "2026-01-05", 10.0, 10_000),
+            trade(InvestmentKinds::Buy, "2026-02-05", 10.0, 20_000),
+            trade(InvestmentKinds::Sell, "2026-03-05", 15.0, 30_000),
+            trade(InvestmentKinds::Dividend, "2026-03-20", 0.0, 500),
+        ]
+    }
+
+    #[test]
+    fn fifo_sells_the_oldest_lots_first() {
+        let tracked = track(&history(), CostBasisMethods::Fifo).unwrap();
+
+        // The 15 units sold cost 10,000 + 5/10 of 20,000.
+        assert_eq!(tracked.realized_gain, 10_000);
+        assert_eq!(tracked.quantity, 5.0);
+        assert_eq!(tracked.cost_basis, 10_000);
+        assert_eq!(tracked.dividends, 500);
+        assert_eq!(tracked.lots.len(), 1);
+        assert_eq!(tracked.lots[0].date, history()[1].date);
+        assert_eq!(tracked.lots[0].cost, 10_000);
+    }
+
+    #[test]
+    fn average_cost_prices_every_unit_alike() {
+        let tracked = track(&history(), CostBasisMethods::Average).unwrap();
+
+        // 20 units for 30,000 cost 1,500 each.
+        assert_eq!(tracked.realized_gain, 30_000 - 22_500);
+        assert_eq!(tracked.quantity, 5.0);
+        assert_eq!(tracked.cost_basis, 7_500);
+        assert_eq!(tracked.lots[0].cost, 7_500);
+    }
+
+    #[test]
+    fn selling_more_than_held_is_refused() {
+        let trades = vec![
+            trade(InvestmentKinds::Buy, "2026-01-05", 10.0, 10_000),
+            trade(InvestmentKinds::Sell, "2026-02-05", 10.5, 12_000),
+        ];
+        assert!(track(&trades, CostBasisMethods::Fifo).is_none());
+        assert!(track(&trades, CostBasisMethods::Average).is_none());
+    }
+
+    #[test]
+    fn a_position_sold_off_in_fractions_closes() {
+        let mut trades = vec![trade(InvestmentKinds::Buy, "2026-01-05", 1.0, 1_000)];
+        for _ in 0..10 {
+            trades.push(trade(InvestmentKinds::Sell, "2026-02-05", 0.1, 110));
+        }
+
+        let tracked = track(&trades, CostBasisMethods::Fifo).unwrap();
+        assert!(tracked.lots.is_empty());
+        assert_eq!(tracked.cost_basis, 0);
+        assert_eq!(tracked.realized_gain, 100);
+    }
+}
@@ -0,0 +1,112 @@
+//! Outgoing email through the SMTP relay configured with `SMTP_HOST` (see [`Config`]).
+//!
+//! For local testing, point `SMTP_HOST`/`SMTP_PORT` at an SMTP sink (e.g. the Mailpit service in
+//! Compose) with `SMTP_TLS=none`.
+
+use anyhow::Context;
+use lettre::message::header::{ContentType, Header, HeaderName, HeaderValue};
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::{Config, SmtpTls};
+use crate::error::AppError;
+
+/// An SMTP connection pool and the sender address.
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+/// A plain-text email.
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+    /// Link that unsubscribes the recipient, sent as the `List-Unsubscribe` header.
+    pub unsubscribe_url: Option<String>,
+}
+
+impl Mailer {
+    /// The mailer for the configured relay, or `None` when `SMTP_HOST` is not set. Connections
+    /// are opened when the first email is sent, so an unreachable relay is not a startup error.
+    pub fn from_config(config: &Config) -> anyhow::Result<Option<Mailer>> {
+        let Some(host) = &config.smtp_host else {
+            return Ok(None);
+        };
+
+        let builder = match config.smtp_tls {
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                .with_context(|| format!("invalid SMTP host {host}"))?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+                .with_context(|| format!("invalid SMTP host {host}"))?,
+        }
+        .port(config.smtp_port);
+
+        let builder = match (&config.smtp_username, &config.smtp_password) {
+            (Some(username), Some(password)) => {
+                builder.credentials(Credentials::new(username.clone(), password.clone()))
+            }
+            _ => builder,
+        };
+
+        let from = config
+            .mail_from
+            .parse()
+            .with_context(|| format!("MAIL_FROM is not a valid address: {}", config.mail_from))?;
+
+        Ok(Some(Mailer {
+            transport: builder.build(),
+            from,
+        }))
+    }
+
+    pub async fn send(&self, email: Email) -> Result<(), AppError> {
+        let to: Mailbox = email.to.parse().map_err(|_| AppError::BadRequest)?;
+
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(email.subject)
+            .header(ContentType::TEXT_PLAIN);
+        if let Some(url) = email.unsubscribe_url {
+            builder = builder.header(ListUnsubscribe(url));
+        }
+
+        let message = builder
+            .body(email.body)
+            .map_err(|e| AppError::Internal(format!("failed to build email: {e}")))?;
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| AppError::Internal(format!("failed to send email: {e}")))?;
+
+        Ok(())
+    }
+}
+
+/// `List-Unsubscribe` (RFC 2369), which lets mail clients offer an unsubscribe button.
+#[derive(Debug, Clone)]
+struct ListUnsubscribe(String);
+
+impl Header for ListUnsubscribe {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe")
+    }
+
+    fn parse(value: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(ListUnsubscribe(
+            value.trim().trim_start_matches('<').trim_end_matches('>').to_string(),
+        ))
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), format!("<{}>", self.0))
+    }
+}
+
+/// Whether `email` is a valid address to send to.
+pub fn is_valid_address(email: &str) -> bool {
+    email.parse::<lettre::Address>().is_ok()
+}
@@ -1,22 +1,44 @@
+mod amortization;
+mod audit;
 mod auth;
+mod autopay;
+mod calendar;
 mod config;
 mod db;
+mod digest;
 mod error;
 mod handlers;
+mod holidays;
+mod lots;
+mod mail;
 mod models;
+mod oidc;
+mod password;
+mod rate_limit;
+mod recurrence;
+mod secret;
 mod service;
 mod state;
+mod totp;
 
+#[cfg(test)]
+mod tests;
+
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use anyhow::Context;
-use axum::Router;
+use axum::{middleware, Router};
 use sqlx::postgres::PgPoolOptions;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::EnvFilter;
 
 use crate::config::Config;
+use crate::holidays::Holidays;
+use crate::mail::Mailer;
+use crate::oidc::Oidc;
+use crate::rate_limit::RateLimits;
 use crate::state::AppState;
 
 #[tokio::main]
@@ -37,24 +59,48 @@ async fn main() -> anyhow::Result<()> {
         .await
         .context("failed to connect to the database")?;
 
+    if !config.admin_users.is_empty() {
+        let granted = db::users::grant_admin(&pool, &config.admin_users).await?;
+        for name in config.admin_users.iter().filter(|name| !granted.contains(name)) {
+            tracing::warn!("ADMIN_USERS names {name}, who is not a user (yet)");
+        }
+    }
+
+    let holidays = match &config.holidays_file {
+        Some(path) => {
+            let holidays = Holidays::load(path)?;
+            tracing::info!("loaded {} holidays from {}", holidays.len(), path.display());
+            holidays
+        }
+        None => Holidays::default(),
+    };
+
+    let mailer = Mailer::from_config(&config)?.map(Arc::new);
+    if mailer.is_none() {
+        tracing::info!("SMTP_HOST is not set, email digests are disabled");
+    }
+
+    let oidc = Oidc::from_config(&config)?.map(Arc::new);
+    if oidc.is_some() {
+        tracing::info!("single sign-on with {}", config.oidc_issuers.join(", "));
+    }
+
     let bind = format!("{}:{}", config.bind_addr, config.port);
     let state = AppState {
         pool,
+        rate_limits: Arc::new(RateLimits::new(&config)),
         config: Arc::new(config),
+        holidays: Arc::new(holidays),
+        mailer,
+        oidc,
     };
 
-    let app = Router::new()
-        .merge(handlers::auth::routes())
-        .merge(handlers::categories::routes())
-        .merge(handlers::accounts::routes())
-        .merge(handlers::transactions::routes())
-        .merge(handlers::transfers::routes())
-        .merge(handlers::scheduled_transactions::routes())
-        // Permissive CORS mirrors the original `rocket_cors` default (any origin/method/header);
-        // the API authenticates via a Bearer token, not cookies, so credentials are not needed.
-        .layer(CorsLayer::permissive())
-        .layer(TraceLayer::new_for_http())
-        .with_state(state);
+    autopay::start(state.clone());
+    if let Some(mailer) = &state.mailer {
+        digest::start(state.clone(), mailer.clone());
+    }
+
+    let app = app(state);
 
     let listener = tokio::net::TcpListener::bind(&bind)
         .await
@@ -62,9 +108,42 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("finance backend listening on http://{bind}");
 
-    axum::serve(listener, app)
+    // The peer address is the client IP that rate limits are kept by (see `rate_limit`).
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .context("server error")?;
 
     Ok(())
 }
+
+/// The whole API: every route, behind the audit, rate limiting, CORS and tracing layers.
+fn app(state: AppState) -> Router {
+    Router::new()
+        .merge(handlers::auth::routes())
+        .merge(handlers::categories::routes())
+        .merge(handlers::accounts::routes())
+        .merge(handlers::transactions::routes())
+        .merge(handlers::transfers::routes())
+        .merge(handlers::scheduled_transactions::routes())
+        .merge(handlers::goals::routes())
+        .merge(handlers::loans::routes())
+        .merge(handlers::securities::routes())
+        .merge(handlers::investments::routes())
+        .merge(handlers::reconciliations::routes())
+        .merge(handlers::reports::routes())
+        .merge(handlers::calendar::routes())
+        .merge(handlers::api_tokens::routes())
+        .merge(handlers::users::routes())
+        .merge(handlers::two_factor::routes())
+        .merge(handlers::oidc::routes())
+        .merge(handlers::ledgers::routes())
+        .merge(handlers::admin::routes())
+        .merge(handlers::password_resets::routes())
+        .layer(middleware::from_fn_with_state(state.clone(), audit::record_changes))
+        .layer(middleware::from_fn_with_state(state.clone(), rate_limit::limit_requests))
+        // Permissive CORS mirrors the original `rocket_cors` default (any origin/method/header);
+        // the API authenticates via a Bearer token, not cookies, so credentials are not needed.
+        .layer(CorsLayer::permissive())
+        .layer(TraceLayer::new_for_http())
+        .with_state(state)
+}
@@ -1,5 +1,5 @@
 use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 // ---------------------------------------------------------------------------
 // Enums
@@ -37,6 +37,111 @@ pub enum RepeatFrequencies {
     Years,
 }
 
+/// How a scheduled due date that falls on a weekend or holiday is moved: not at all, to the
+/// previous or the next business day, or to the next one unless that is in the following month
+/// (then the previous one, "modified following").
+#[derive(sqlx::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[sqlx(type_name = "business_day_adjustments", rename_all = "snake_case")]
+pub enum BusinessDayAdjustments {
+    #[default]
+    None,
+    Previous,
+    Next,
+    ModifiedFollowing,
+}
+
+/// The amount a schedule's occurrences are expected to need: its fixed `value`, or an estimate
+/// from what was paid for its previous occurrences (average, median or last) or from the recent
+/// monthly spend in its category.
+#[derive(sqlx::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[sqlx(type_name = "amount_estimates", rename_all = "snake_case")]
+pub enum AmountEstimates {
+    #[default]
+    Fixed,
+    Average,
+    Median,
+    Last,
+    CategorySpend,
+}
+
+/// What happened to a due occurrence of a scheduled transaction.
+#[derive(sqlx::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[sqlx(type_name = "occurrence_outcomes", rename_all = "snake_case")]
+pub enum OccurrenceOutcomes {
+    Paid,
+    PartiallyPaid,
+    Skipped,
+    Snoozed,
+}
+
+#[derive(sqlx::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[sqlx(type_name = "account_types", rename_all = "snake_case")]
+pub enum AccountTypes {
+    #[default]
+    Checking,
+    Savings,
+    CreditCard,
+    Cash,
+    Loan,
+    Investment,
+    Asset,
+}
+
+#[derive(sqlx::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[sqlx(type_name = "transaction_statuses", rename_all = "snake_case")]
+pub enum TransactionStatuses {
+    #[default]
+    Uncleared,
+    Cleared,
+    Reconciled,
+}
+
+#[derive(sqlx::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[sqlx(type_name = "investment_kinds", rename_all = "snake_case")]
+pub enum InvestmentKinds {
+    Buy,
+    Sell,
+    Dividend,
+}
+
+/// What a personal access token may do: read (`GET`) requests only, anything but managing the
+/// account, or only importing new entries (see [`crate::auth`] for the routes of each).
+#[derive(sqlx::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[sqlx(type_name = "api_token_scopes", rename_all = "snake_case")]
+pub enum ApiTokenScopes {
+    ReadOnly,
+    ReadWrite,
+    ImportOnly,
+}
+
+/// What a member of a ledger may do: everything, including managing the members and invites
+/// (`Owner`), add and change entries (`Editor`), or only look (`Viewer`).
+#[derive(sqlx::Type, Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[sqlx(type_name = "ledger_roles", rename_all = "snake_case")]
+pub enum LedgerRoles {
+    Owner,
+    Editor,
+    Viewer,
+}
+
+/// How the cost of the units sold is taken from the lots held: the oldest lots first, or the
+/// average cost of everything held. Only used in requests; it is not stored.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+pub enum CostBasisMethods {
+    #[default]
+    Fifo,
+    Average,
+}
+
+impl AccountTypes {
+    /// Whether accounts of this type hold money owed rather than money owned. A liability's
+    /// balance is normally negative (e.g. a card carrying a 500 debt has balance -500), and
+    /// net-worth reporting shows it as a positive amount owed.
+    pub fn is_liability(self) -> bool {
+        matches!(self, AccountTypes::CreditCard | AccountTypes::Loan)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Database row models
 // ---------------------------------------------------------------------------
@@ -48,21 +153,41 @@ pub struct AppUser {
     pub password: String,
 }
 
+/// A user without their password hash. `password_change_required` is set by an admin: until the
+/// user changes their password, only that and logging out work.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct GetAppUser {
+    pub id: i32,
+    pub name: String,
+    pub is_admin: bool,
+    pub password_change_required: bool,
+}
+
 #[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
 pub struct Category {
     pub id: i32,
     pub categorytype: CategoryTypes,
     pub name: String,
-    pub user_id: i32,
+    pub ledger_id: i32,
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
 pub struct Account {
     pub id: i32,
     pub name: String,
-    pub user_id: i32,
+    pub ledger_id: i32,
+    pub account_type: AccountTypes,
+    pub opening_balance: i32,
+    pub opening_date: Option<NaiveDateTime>,
+    pub archived: bool,
+    pub sort_order: i32,
+    pub statement_closing_day: Option<i32>,
+    pub payment_due_day: Option<i32>,
+    pub autopay_account_id: Option<i32>,
 }
 
+/// A transaction. `scheduled_transaction_id` and `occurrence` are set when it was booked by paying
+/// that occurrence of a schedule.
 #[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
 pub struct Transaction {
     pub id: i32,
@@ -71,9 +196,15 @@ pub struct Transaction {
     pub date: NaiveDateTime,
     pub account: i32,
     pub category: i32,
-    pub user_id: i32,
+    pub ledger_id: i32,
+    pub status: TransactionStatuses,
+    pub scheduled_transaction_id: Option<i32>,
+    pub occurrence: Option<i32>,
 }
 
+/// A transfer. `scheduled_transaction_id` and `occurrence` are set when it was booked by paying
+/// that occurrence of a schedule. Each side has its own status, as each account is reconciled on
+/// its own.
 #[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
 pub struct Transfer {
     pub id: i32,
@@ -82,7 +213,89 @@ pub struct Transfer {
     pub value: i32,
     pub description: String,
     pub date: NaiveDateTime,
-    pub user_id: i32,
+    pub ledger_id: i32,
+    pub origin_status: TransactionStatuses,
+    pub destination_status: TransactionStatuses,
+    pub scheduled_transaction_id: Option<i32>,
+    pub occurrence: Option<i32>,
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct Reconciliation {
+    pub id: i32,
+    pub account_id: i32,
+    pub statement_date: NaiveDateTime,
+    pub statement_balance: i32,
+    pub created_date: NaiveDateTime,
+    pub finished_date: Option<NaiveDateTime>,
+    pub ledger_id: i32,
+}
+
+/// Terms of a loan account. `annual_rate_bps` is the annual interest rate in basis points
+/// (525 = 5.25%).
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct Loan {
+    pub account_id: i32,
+    pub principal: i32,
+    pub annual_rate_bps: i32,
+    pub term_months: i32,
+    pub payment_day: i32,
+    pub start_date: NaiveDateTime,
+    pub payment_account_id: i32,
+    pub interest_category_id: i32,
+    pub scheduled_transaction_id: Option<i32>,
+    pub ledger_id: i32,
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct Security {
+    pub id: i32,
+    pub symbol: String,
+    pub name: String,
+    pub ledger_id: i32,
+}
+
+/// A buy, sell or dividend in an investment account. `price` is in cents per unit and `value`
+/// is the cash amount: the cost of a buy, the proceeds of a sell or the dividend paid.
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct InvestmentTransaction {
+    pub id: i32,
+    pub account_id: i32,
+    pub security_id: i32,
+    pub kind: InvestmentKinds,
+    pub quantity: f64,
+    pub price: i32,
+    pub value: i32,
+    pub description: String,
+    pub date: NaiveDateTime,
+    pub ledger_id: i32,
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct SecurityPrice {
+    pub security_id: i32,
+    pub date: NaiveDateTime,
+    pub price: i32,
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct Goal {
+    pub id: i32,
+    pub name: String,
+    pub target_amount: i32,
+    pub target_date: Option<NaiveDateTime>,
+    pub created_date: NaiveDateTime,
+    pub scheduled_transaction_id: Option<i32>,
+    pub ledger_id: i32,
+}
+
+/// An account funding a goal. Without an `earmark` the whole balance counts towards the goal,
+/// otherwise only up to the earmarked amount.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct GoalAccount {
+    pub goal_id: i32,
+    pub account_id: i32,
+    pub earmark: Option<i32>,
 }
 
 #[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
@@ -99,13 +312,212 @@ pub struct ScheduledTransaction {
     pub repeat: bool,
     pub repeat_freq: Option<RepeatFrequencies>,
     pub repeat_interval: Option<i32>,
+    pub rrule: Option<String>,
+    pub business_day_adjustment: BusinessDayAdjustments,
+    pub amount_estimate: AmountEstimates,
     pub infinite_repeat: Option<bool>,
     pub end_after_repeats: Option<i32>,
     pub current_repeat_count: Option<i32>,
     pub next_date: Option<NaiveDateTime>,
+    pub finished: bool,
+    pub ledger_id: i32,
+    pub autopay_card_id: Option<i32>,
+}
+
+/// One entry of a schedule's occurrence history. `occurrence` is the schedule's
+/// `current_repeat_count` when it was recorded (`None` for a one-off), `value` the amount booked
+/// (0 for a skip or snooze) and `snoozed_to` the due date a snooze moved it to.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct ScheduledOccurrence {
+    pub id: i32,
+    pub scheduled_transaction_id: i32,
+    pub occurrence: Option<i32>,
+    pub due_date: NaiveDateTime,
+    pub outcome: OccurrenceOutcomes,
+    pub value: i32,
+    pub snoozed_to: Option<NaiveDateTime>,
+    pub recorded_at: NaiveDateTime,
+    pub ledger_id: i32,
+}
+
+/// A transaction or transfer booked by paying a schedule, tagged with the occurrence it paid.
+/// A loan payment books both a transfer (principal) and a transaction (interest).
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct ScheduledEntry {
+    pub kind: ScheduledTransactionKinds,
+    pub id: i32,
+    pub occurrence: Option<i32>,
+    pub value: i32,
+    pub description: String,
+    pub date: NaiveDateTime,
+}
+
+/// A secret token for the iCalendar feed. Only its hash is stored, so the token itself is not
+/// part of the row (see [`IssuedCalendarToken`]).
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct CalendarToken {
+    pub id: i32,
+    pub name: String,
+    pub created_date: NaiveDateTime,
+    pub last_used_date: Option<NaiveDateTime>,
+    pub ledger_id: i32,
+    pub user_id: i32,
+}
+
+/// A personal access token. Only its hash is stored, so the token itself is not part of the row
+/// (see [`IssuedApiToken`]).
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: i32,
+    pub name: String,
+    pub scope: ApiTokenScopes,
+    pub created_date: NaiveDateTime,
+    pub expires_date: NaiveDateTime,
+    pub last_used_date: Option<NaiveDateTime>,
+    pub user_id: i32,
+}
+
+/// A login session, one per device. `device` is the `User-Agent` it was opened with.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub id: i32,
+    pub device: Option<String>,
+    pub created_date: NaiveDateTime,
+    pub last_seen_date: NaiveDateTime,
+    pub expires_date: NaiveDateTime,
     pub user_id: i32,
 }
 
+/// A ledger: the accounts, categories and everything recorded in them, shared by its members.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct Ledger {
+    pub id: i32,
+    pub name: String,
+    pub created_date: NaiveDateTime,
+}
+
+/// A ledger the user is a member of, with their role in it.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct GetLedger {
+    pub id: i32,
+    pub name: String,
+    pub created_date: NaiveDateTime,
+    pub role: LedgerRoles,
+    pub members: i64,
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct LedgerMember {
+    pub user_id: i32,
+    pub name: String,
+    pub role: LedgerRoles,
+    pub joined_date: NaiveDateTime,
+}
+
+/// An invite into a ledger. Only its hash is stored, so the token itself is not part of the row
+/// (see [`IssuedLedgerInvite`]). `user_id` is the owner who created it.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct LedgerInvite {
+    pub id: i32,
+    pub role: LedgerRoles,
+    pub created_date: NaiveDateTime,
+    pub expires_date: NaiveDateTime,
+    pub ledger_id: i32,
+    pub user_id: i32,
+}
+
+/// A change made in a ledger: the request that made it, what it changed (see
+/// [`NewAuditEntry`]) and who sent it (`None` once that user is deleted).
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_date: NaiveDateTime,
+    pub method: String,
+    pub path: String,
+    pub entity: String,
+    pub entity_id: Option<i32>,
+    pub fields: Vec<String>,
+    pub user_id: Option<i32>,
+    pub user_name: Option<String>,
+}
+
+/// Internal (non-serialized) value object used to record a change in a ledger's audit log: the
+/// resource the request's route is under (`accounts` for `/api/accounts/{id}/card-settings`), the
+/// id the route names in it, if any, and the top-level fields of the request's JSON body (only
+/// their names, so that passwords and tokens are not logged).
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub ledger_id: i32,
+    pub user_id: i32,
+    pub method: String,
+    pub path: String,
+    pub entity: String,
+    pub entity_id: Option<i32>,
+    pub fields: Vec<String>,
+}
+
+/// An account at an OpenID Connect provider linked to a user, who can log in through it.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct OidcIdentity {
+    pub id: i32,
+    pub issuer: String,
+    pub subject: String,
+    pub created_date: NaiveDateTime,
+    pub last_login_date: Option<NaiveDateTime>,
+    pub user_id: i32,
+}
+
+/// A user as admins see them: when they were last seen (in their latest session) and how many
+/// ledgers they are a member of.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct AdminUser {
+    pub id: i32,
+    pub name: String,
+    pub email: Option<String>,
+    pub created_date: NaiveDateTime,
+    pub is_admin: bool,
+    pub disabled: bool,
+    pub password_change_required: bool,
+    pub last_seen_date: Option<NaiveDateTime>,
+    pub ledgers: i64,
+}
+
+/// The instance-wide settings admins change. Without `open_registration`, only admins create
+/// users.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct Settings {
+    pub open_registration: bool,
+}
+
+/// A user's email address and digest preferences. `digest_lead_days` is how many days ahead the
+/// daily digest lists scheduled transactions.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct NotificationPreferences {
+    pub email: Option<String>,
+    pub digest_enabled: bool,
+    pub digest_lead_days: i32,
+}
+
+/// A user due a digest today, with what is needed to write it.
+#[derive(sqlx::FromRow, Debug)]
+pub struct DigestRecipient {
+    pub id: i32,
+    pub name: String,
+    pub email: String,
+    pub digest_lead_days: i32,
+    pub unsubscribe_token: String,
+    /// The ledger the digest covers: the user's default one (see [`crate::db::ledgers::DEFAULT_ORDER`]).
+    pub ledger_id: i32,
+}
+
+/// The user's TOTP secret (encrypted, see [`crate::totp`]): pending until `totp_enabled`.
+#[derive(sqlx::FromRow, Debug)]
+pub struct TwoFactorSecret {
+    pub totp_secret: Option<Vec<u8>>,
+    pub totp_enabled: bool,
+    pub totp_last_step: Option<i64>,
+}
+
 // ---------------------------------------------------------------------------
 // Request DTOs (deserialized from JSON request bodies)
 // ---------------------------------------------------------------------------
@@ -116,6 +528,42 @@ pub struct NewAppUser {
     pub password: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PutPassword {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PutUserName {
+    pub name: String,
+}
+
+/// Deleting the account takes the password again.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteAppUser {
+    pub password: String,
+}
+
+/// Turning off two-factor authentication or issuing new recovery codes takes the password again.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmPassword {
+    pub password: String,
+}
+
+/// A TOTP code from the authenticator app, or (when logging in) a recovery code.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostTotpCode {
+    pub code: String,
+}
+
+/// The second step of a login with two-factor authentication.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostLoginCode {
+    pub challenge_token: String,
+    pub code: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PostCategory {
     pub categorytype: CategoryTypes,
@@ -124,21 +572,161 @@ pub struct PostCategory {
 
 pub type PatchCategory = PostCategory;
 
+/// New account. Everything but the name is optional so older clients that only send a name
+/// keep creating plain checking accounts.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PostAccount {
     pub name: String,
+    #[serde(default)]
+    pub account_type: AccountTypes,
+    #[serde(default)]
+    pub opening_balance: i32,
+    pub opening_date: Option<NaiveDateTime>,
+}
+
+/// Account update. Omitted fields keep their current value, so a name-only PATCH does not
+/// reset the type, opening balance or archived flag; an `opening_date` of `null` removes it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatchAccount {
+    pub name: String,
+    pub account_type: Option<AccountTypes>,
+    pub opening_balance: Option<i32>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub opening_date: Option<Option<NaiveDateTime>>,
+    pub archived: Option<bool>,
+    pub sort_order: Option<i32>,
+}
+
+/// A field of a PATCH body that can be cleared: `None` when omitted, `Some(None)` for `null`.
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// New display order for the ledger's accounts: the ids in the order they should be listed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PutAccountOrder {
+    pub account_ids: Vec<i32>,
+}
+
+/// Statement cycle and autopay settings of a credit-card account. This replaces all three
+/// settings: a `null` (or omitted) field clears it. The statement is only tracked once both
+/// days are set; `autopay_account_id` names the account the statement is paid from.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PutCardSettings {
+    pub statement_closing_day: Option<i32>,
+    pub payment_due_day: Option<i32>,
+    pub autopay_account_id: Option<i32>,
+}
+
+/// New loan. Creates a `loan` account named `name` owing `principal` from `start_date`, and a
+/// monthly scheduled transfer from `payment_account_id` on `payment_day`. The interest part of
+/// each payment is booked to `interest_category_id`, which must be an expense category.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostLoan {
+    pub name: String,
+    pub principal: i32,
+    pub annual_rate_bps: i32,
+    pub term_months: i32,
+    pub payment_day: i32,
+    pub start_date: NaiveDateTime,
+    pub payment_account_id: i32,
+    pub interest_category_id: i32,
+}
+
+/// Extra payments to simulate against a loan's outstanding balance: `extra_monthly` is added
+/// to every remaining payment and `lump_sum` is paid once with the next payment.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostLoanSimulation {
+    #[serde(default)]
+    pub extra_monthly: i32,
+    #[serde(default)]
+    pub lump_sum: i32,
 }
 
-pub type PatchAccount = PostAccount;
+/// New security. The symbol is stored trimmed and upper-cased, and is unique per ledger.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostSecurity {
+    pub symbol: String,
+    pub name: String,
+}
+
+pub type PatchSecurity = PostSecurity;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostSecurityPrice {
+    pub date: NaiveDateTime,
+    pub price: i32,
+}
+
+/// New buy, sell or dividend, or the full replacement of one (`PatchInvestmentTransaction`).
+/// A buy or sell needs a positive `quantity` and its `value` is computed as `quantity * price`;
+/// a dividend needs a positive `value` and ignores `quantity` and `price`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostInvestmentTransaction {
+    pub security_id: i32,
+    pub kind: InvestmentKinds,
+    #[serde(default)]
+    pub quantity: f64,
+    #[serde(default)]
+    pub price: i32,
+    #[serde(default)]
+    pub value: i32,
+    pub description: String,
+    pub date: NaiveDateTime,
+}
+
+pub type PatchInvestmentTransaction = PostInvestmentTransaction;
+
+/// Query string of the holdings endpoint (`?method=Average`); FIFO by default.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HoldingsQuery {
+    #[serde(default)]
+    pub method: CostBasisMethods,
+}
+
+/// New goal, or the full replacement of one (`PatchGoal`), including its linked accounts.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostGoal {
+    pub name: String,
+    pub target_amount: i32,
+    pub target_date: Option<NaiveDateTime>,
+    pub accounts: Vec<PostGoalAccount>,
+}
+
+pub type PatchGoal = PostGoal;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostGoalAccount {
+    pub account_id: i32,
+    pub earmark: Option<i32>,
+}
 
+/// Set up the goal's monthly contribution: a transfer from `origin_account_id` into one of the
+/// goal's accounts for the required monthly amount, starting on `start_date` and repeating
+/// until the target date.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostGoalContribution {
+    pub origin_account_id: i32,
+    pub destination_account_id: i32,
+    pub start_date: NaiveDateTime,
+}
+
+/// New transaction. `status` defaults to uncleared; it may be set to cleared but never to
+/// reconciled, which only a finished reconciliation can do.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PostTransaction {
     pub value: i32,
     pub description: String,
     pub date: NaiveDateTime,
     pub category: i32,
+    pub status: Option<TransactionStatuses>,
 }
 
+/// Transaction update. An omitted `status` keeps the current one.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PatchTransaction {
     pub value: i32,
@@ -146,6 +734,7 @@ pub struct PatchTransaction {
     pub date: NaiveDateTime,
     pub account: i32,
     pub category: i32,
+    pub status: Option<TransactionStatuses>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -153,6 +742,8 @@ pub struct PostTransfer {
     pub value: i32,
     pub description: String,
     pub date: NaiveDateTime,
+    pub origin_status: Option<TransactionStatuses>,
+    pub destination_status: Option<TransactionStatuses>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -162,6 +753,21 @@ pub struct PatchTransfer {
     pub value: i32,
     pub description: String,
     pub date: NaiveDateTime,
+    pub origin_status: Option<TransactionStatuses>,
+    pub destination_status: Option<TransactionStatuses>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostReconciliation {
+    pub account_id: i32,
+    pub statement_date: NaiveDateTime,
+    pub statement_balance: i32,
+}
+
+/// Tick (`cleared: true`) or untick an item in a reconciliation session.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatchReconciliationItem {
+    pub cleared: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -177,6 +783,11 @@ pub struct PostScheduledTransaction {
     pub repeat: bool,
     pub repeat_freq: Option<RepeatFrequencies>,
     pub repeat_interval: Option<i32>,
+    pub rrule: Option<String>,
+    #[serde(default)]
+    pub business_day_adjustment: BusinessDayAdjustments,
+    #[serde(default)]
+    pub amount_estimate: AmountEstimates,
     pub infinite_repeat: Option<bool>,
     pub end_after_repeats: Option<i32>,
     pub current_repeat_count: Option<i32>,
@@ -185,7 +796,48 @@ pub struct PostScheduledTransaction {
 
 pub type PatchScheduledTransaction = PostScheduledTransaction;
 
-/// Internal (non-serialized) value object used to insert/update a transaction row.
+/// Internal (non-serialized) value object used to insert/update an account row.
+#[derive(Debug, Clone)]
+pub struct NewAccountData {
+    pub name: String,
+    pub account_type: AccountTypes,
+    pub opening_balance: i32,
+    pub opening_date: Option<NaiveDateTime>,
+    pub archived: bool,
+    pub sort_order: i32,
+}
+
+/// Internal (non-serialized) value object used to insert a loan row.
+#[derive(Debug, Clone)]
+pub struct NewLoanData {
+    pub account_id: i32,
+    pub principal: i32,
+    pub annual_rate_bps: i32,
+    pub term_months: i32,
+    pub payment_day: i32,
+    pub start_date: NaiveDateTime,
+    pub payment_account_id: i32,
+    pub interest_category_id: i32,
+    pub scheduled_transaction_id: Option<i32>,
+    pub ledger_id: i32,
+}
+
+/// Internal (non-serialized) value object used to insert/update an investment transaction row.
+#[derive(Debug, Clone)]
+pub struct NewInvestmentTransactionData {
+    pub account_id: i32,
+    pub security_id: i32,
+    pub kind: InvestmentKinds,
+    pub quantity: f64,
+    pub price: i32,
+    pub value: i32,
+    pub description: String,
+    pub date: NaiveDateTime,
+    pub ledger_id: i32,
+}
+
+/// Internal (non-serialized) value object used to insert/update a transaction row. The
+/// schedule link (`scheduled_transaction_id`, `occurrence`) is set on insert only.
 #[derive(Debug, Clone)]
 pub struct NewTransactionData {
     pub value: i32,
@@ -193,10 +845,14 @@ pub struct NewTransactionData {
     pub date: NaiveDateTime,
     pub account: i32,
     pub category: i32,
-    pub user_id: i32,
+    pub ledger_id: i32,
+    pub status: TransactionStatuses,
+    pub scheduled_transaction_id: Option<i32>,
+    pub occurrence: Option<i32>,
 }
 
-/// Internal (non-serialized) value object used to insert/update a transfer row.
+/// Internal (non-serialized) value object used to insert/update a transfer row. The schedule
+/// link (`scheduled_transaction_id`, `occurrence`) is set on insert only.
 #[derive(Debug, Clone)]
 pub struct NewTransferData {
     pub origin_account: i32,
@@ -204,7 +860,11 @@ pub struct NewTransferData {
     pub value: i32,
     pub description: String,
     pub date: NaiveDateTime,
-    pub user_id: i32,
+    pub ledger_id: i32,
+    pub origin_status: TransactionStatuses,
+    pub destination_status: TransactionStatuses,
+    pub scheduled_transaction_id: Option<i32>,
+    pub occurrence: Option<i32>,
 }
 
 /// Internal (non-serialized) value object used to insert/update a scheduled
@@ -222,13 +882,19 @@ pub struct NewScheduledTransaction {
     pub repeat: bool,
     pub repeat_freq: Option<RepeatFrequencies>,
     pub repeat_interval: Option<i32>,
+    pub rrule: Option<String>,
+    pub business_day_adjustment: BusinessDayAdjustments,
+    pub amount_estimate: AmountEstimates,
     pub infinite_repeat: Option<bool>,
     pub end_after_repeats: Option<i32>,
     pub current_repeat_count: Option<i32>,
     pub next_date: Option<NaiveDateTime>,
-    pub user_id: i32,
+    pub ledger_id: i32,
+    pub autopay_card_id: Option<i32>,
 }
 
+/// Payment of a schedule's current occurrence. With `partial`, only part of it is paid: the
+/// occurrence stays due until its payments add up to the scheduled value.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PostScheduledTransactionPay {
     pub value: i32,
@@ -238,15 +904,266 @@ pub struct PostScheduledTransactionPay {
     pub account_id: Option<i32>,
     pub origin_account_id: Option<i32>,
     pub destination_account_id: Option<i32>,
+    #[serde(default)]
+    pub partial: bool,
+}
+
+/// Move the current due date of a schedule to a later `date`, without changing the following
+/// occurrences.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostScheduledTransactionSnooze {
+    pub date: NaiveDateTime,
+}
+
+/// Query string of the occurrence preview (`?count=12&until=2026-12-31T00:00:00`): at most
+/// `count` occurrences (12 by default), none due after `until`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OccurrencesQuery {
+    pub count: Option<i32>,
+    pub until: Option<NaiveDateTime>,
+}
+
+/// New calendar feed token, `name`d after where it is used (e.g. "Kitchen tablet").
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostCalendarToken {
+    pub name: String,
+}
+
+/// New personal access token, `name`d after the script that uses it. It stops working on
+/// `expires_date`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostApiToken {
+    pub name: String,
+    pub scope: ApiTokenScopes,
+    pub expires_date: NaiveDateTime,
+}
+
+/// Start of a single sign-on login at `issuer`, one of `OIDC_ISSUERS` (the first by default).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostOidcAuthorize {
+    pub issuer: Option<String>,
+}
+
+/// What the identity provider redirected back to `OIDC_REDIRECT_URL` with.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostOidcCallback {
+    pub code: String,
+    pub state: String,
+}
+
+/// New shared ledger (or a new name for one).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostLedger {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PutLedgerMember {
+    pub role: LedgerRoles,
+}
+
+/// New invite into a ledger with `role` (not `Owner`), valid until `expires_date` (a week by
+/// default).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostLedgerInvite {
+    pub role: LedgerRoles,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_date: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostInviteAccept {
+    pub token: String,
+}
+
+/// Page of the audit log: the entries before the id `before` (the latest by default), newest
+/// first.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditQuery {
+    pub before: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// Ask for a password reset email for the user with this name.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostPasswordResetRequest {
+    pub name: String,
+}
+
+/// Set a new password with the token from a password reset email.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostPasswordResetConfirm {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostRefreshToken {
+    pub refresh_token: String,
+}
+
+/// Internal (non-serialized) value object used to record an entry of a schedule's occurrence
+/// history.
+#[derive(Debug, Clone)]
+pub struct NewScheduledOccurrence {
+    pub scheduled_transaction_id: i32,
+    pub occurrence: Option<i32>,
+    pub due_date: NaiveDateTime,
+    pub outcome: OccurrenceOutcomes,
+    pub value: i32,
+    pub snoozed_to: Option<NaiveDateTime>,
+    pub ledger_id: i32,
 }
 
 // ---------------------------------------------------------------------------
 // Response DTOs (serialized to JSON response bodies)
 // ---------------------------------------------------------------------------
 
+/// A newly created calendar feed token. `token` is only returned here; the feed is at
+/// `/api/calendar/{token}.ics`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssuedCalendarToken {
+    pub id: i32,
+    pub name: String,
+    pub token: String,
+    pub created_date: NaiveDateTime,
+    pub ledger_id: i32,
+}
+
+/// A newly created personal access token. `token` is only returned here; it is sent as
+/// `Authorization: Bearer <token>`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssuedApiToken {
+    pub id: i32,
+    pub name: String,
+    pub scope: ApiTokenScopes,
+    pub token: String,
+    pub created_date: NaiveDateTime,
+    pub expires_date: NaiveDateTime,
+}
+
+/// A new invite, with its token: the only time the token is returned, since only its hash is
+/// stored.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssuedLedgerInvite {
+    pub id: i32,
+    pub role: LedgerRoles,
+    pub token: String,
+    pub created_date: NaiveDateTime,
+    pub expires_date: NaiveDateTime,
+    pub ledger_id: i32,
+}
+
+/// An occurrence of a scheduled transaction listed in a digest. `label` is the description (or
+/// category), `accounts` the account it is booked in (or origin → destination of a transfer).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DigestEntry {
+    pub scheduled_transaction_id: i32,
+    pub label: String,
+    pub accounts: String,
+    pub date: NaiveDateTime,
+    pub value: i32,
+    pub overdue: bool,
+}
+
+/// A checking, savings or cash account whose balance is projected to drop below zero once the
+/// scheduled transactions are paid: first on `date`, reaching `lowest_balance`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectedOverdraft {
+    pub account_id: i32,
+    pub account_name: String,
+    pub date: NaiveDateTime,
+    pub lowest_balance: i32,
+}
+
+/// What a user's daily digest reports.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Digest {
+    pub due: Vec<DigestEntry>,
+    pub overdrafts: Vec<ProjectedOverdraft>,
+}
+
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub recovery_codes_left: i64,
+}
+
+/// A new TOTP secret, as base32 to type in and as an `otpauth://` URI to show as a QR code. It
+/// is only used once a first code confirms it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TotpEnrolment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+/// One-time codes for logging in without the authenticator app, only returned when issued.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Where to send the browser to log in at the identity provider.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OidcAuthorization {
+    pub authorization_url: String,
+}
+
+/// The answer to a login with a valid password: the session, or, with two-factor
+/// authentication, a short-lived token to present with a code to `POST /api/login/2fa`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Session(InitialData),
+    Challenge { challenge_token: String },
+}
+
+/// A new access token and the refresh token that replaces the one that was used.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenPair {
+    pub token: String,
+    pub refresh_token: String,
+}
+
+/// The rows one table holds for a user, and their size in bytes.
+#[derive(sqlx::FromRow, Debug, Serialize, Deserialize)]
+pub struct TableUsage {
+    pub table: String,
+    pub rows: i64,
+    pub bytes: i64,
+}
+
+/// What a user stores: the data of the ledgers they own, per table and in total.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageStats {
+    pub user_id: i32,
+    pub ledgers: i64,
+    pub rows: i64,
+    pub bytes: i64,
+    pub tables: Vec<TableUsage>,
+}
+
+/// An open session, `current` if it is the one the request was made with.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetSession {
+    pub id: i32,
+    pub device: Option<String>,
+    pub created_date: NaiveDateTime,
+    pub last_seen_date: NaiveDateTime,
+    pub expires_date: NaiveDateTime,
+    pub current: bool,
+}
+
+/// `refresh_token` is only set when a session was opened (login and register).
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InitialData {
     pub token: String,
+    pub refresh_token: Option<String>,
+    /// Set by an admin: the user must change their password before anything else works.
+    pub password_change_required: bool,
+    /// The ledger the data below is from, and every ledger the user can switch to.
+    pub ledger_id: i32,
+    pub ledgers: Vec<GetLedger>,
     pub accounts: Vec<GetAccount>,
     pub categories: Vec<Category>,
     pub scheduled_transactions: Vec<GetScheduledTransaction>,
@@ -257,7 +1174,207 @@ pub struct GetAccount {
     pub id: i32,
     pub name: String,
     pub balance: i32,
-    pub user_id: i32,
+    pub ledger_id: i32,
+    pub account_type: AccountTypes,
+    pub opening_balance: i32,
+    pub opening_date: Option<NaiveDateTime>,
+    pub archived: bool,
+    pub sort_order: i32,
+    pub statement_closing_day: Option<i32>,
+    pub payment_due_day: Option<i32>,
+    pub autopay_account_id: Option<i32>,
+}
+
+/// The current statement of a credit-card account, as of today.
+///
+/// `statement_balance` is the card balance at the end of `last_closing_date` (normally
+/// negative: what was owed when the statement closed). `payments` are transfers into the card
+/// since then, and `amount_due` is what is still owed on that statement (never negative),
+/// payable by `payment_due_date`. `cycle_balance` is the card balance now, which will close
+/// into the next statement on `next_closing_date`. `next_payment_due` is `payment_due_date`
+/// while something is still due, otherwise the due date of the next statement.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CardStatement {
+    pub account_id: i32,
+    pub last_closing_date: NaiveDateTime,
+    pub next_closing_date: NaiveDateTime,
+    pub statement_balance: i32,
+    pub payments: i32,
+    pub amount_due: i32,
+    pub payment_due_date: NaiveDateTime,
+    pub next_payment_due: NaiveDateTime,
+    pub cycle_balance: i32,
+    pub autopay_account_id: Option<i32>,
+    pub autopay_scheduled_transaction_id: Option<i32>,
+}
+
+/// A loan with its account name and current state. `outstanding` is what is still owed
+/// (the negated loan account balance).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetLoan {
+    pub account_id: i32,
+    pub account_name: String,
+    pub principal: i32,
+    pub annual_rate_bps: i32,
+    pub term_months: i32,
+    pub payment_day: i32,
+    pub start_date: NaiveDateTime,
+    pub payment_account_id: i32,
+    pub interest_category_id: i32,
+    pub scheduled_transaction_id: Option<i32>,
+    pub monthly_payment: i32,
+    pub outstanding: i32,
+    pub next_payment_date: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetGoal {
+    pub id: i32,
+    pub name: String,
+    pub target_amount: i32,
+    pub target_date: Option<NaiveDateTime>,
+    pub created_date: NaiveDateTime,
+    pub scheduled_transaction_id: Option<i32>,
+    pub accounts: Vec<GoalAccount>,
+}
+
+/// How much of an account's balance counts towards a goal: the balance capped by the
+/// earmark, and never negative.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GoalAccountProgress {
+    pub account_id: i32,
+    pub account_name: String,
+    pub earmark: Option<i32>,
+    pub balance: i32,
+    pub contribution: i32,
+}
+
+/// A goal's progress as of today. `months_left` counts the monthly contributions still possible
+/// before the target date, and `required_monthly` is what each must be to reach the target
+/// (all of `remaining` when the date has come); both are `None` without a target date.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub goal_id: i32,
+    pub target_amount: i32,
+    pub target_date: Option<NaiveDateTime>,
+    pub saved: i32,
+    pub remaining: i32,
+    pub percent: i32,
+    pub months_left: Option<i32>,
+    pub required_monthly: Option<i32>,
+    pub accounts: Vec<GoalAccountProgress>,
+}
+
+/// One payment of an amortization table; `balance` is what is owed after it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AmortizationRow {
+    pub number: i32,
+    pub date: NaiveDateTime,
+    pub payment: i32,
+    pub principal: i32,
+    pub interest: i32,
+    pub balance: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Amortization {
+    pub monthly_payment: i32,
+    pub total_paid: i32,
+    pub total_interest: i32,
+    pub payments: Vec<AmortizationRow>,
+}
+
+/// How a loan's outstanding balance is paid off under one payment plan.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoanPayoff {
+    pub payments: i32,
+    pub total_interest: i32,
+    pub payoff_date: Option<NaiveDateTime>,
+}
+
+/// The regular plan against the plan with extra payments, from the current outstanding
+/// balance, and what the extra payments save.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoanSimulation {
+    pub outstanding: i32,
+    pub baseline: LoanPayoff,
+    pub with_extra: LoanPayoff,
+    pub interest_saved: i32,
+    pub months_saved: i32,
+}
+
+/// Units still held from one purchase and what they cost.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Lot {
+    pub date: NaiveDateTime,
+    pub quantity: f64,
+    pub cost: i32,
+}
+
+/// One security held in an investment account. `price` is the latest known price (from
+/// `prices`, else the last trade), and is `None` only when nothing has ever been priced.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Position {
+    pub security_id: i32,
+    pub symbol: String,
+    pub name: String,
+    pub quantity: f64,
+    pub cost_basis: i32,
+    pub price: Option<i32>,
+    pub price_date: Option<NaiveDateTime>,
+    pub market_value: i32,
+    pub unrealized_gain: i32,
+    pub realized_gain: i32,
+    pub dividends: i32,
+    pub lots: Vec<Lot>,
+}
+
+/// An investment account's cash balance and positions; `total` is the cash plus the market
+/// value of the positions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Holdings {
+    pub account_id: i32,
+    pub method: CostBasisMethods,
+    pub cash: i32,
+    pub market_value: i32,
+    pub cost_basis: i32,
+    pub unrealized_gain: i32,
+    pub realized_gain: i32,
+    pub dividends: i32,
+    pub total: i32,
+    pub positions: Vec<Position>,
+}
+
+/// Result of a price CSV import. Rows for symbols that are not one of the ledger's securities are
+/// skipped and their symbols listed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PriceImport {
+    pub imported: i32,
+    pub unknown_symbols: Vec<String>,
+}
+
+/// One account's contribution to the net-worth report. `amount` is the account's value with
+/// the sign convention of its side of the balance sheet: an asset counts its balance, a
+/// liability counts what is owed (the negated balance), so both are normally positive. An
+/// investment account also counts the `market_value` of its positions.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetWorthLine {
+    pub account_id: i32,
+    pub account_name: String,
+    pub account_type: AccountTypes,
+    pub archived: bool,
+    pub liability: bool,
+    pub balance: i32,
+    pub market_value: i32,
+    pub amount: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetWorthReport {
+    pub assets: i32,
+    pub liabilities: i32,
+    pub net_worth: i32,
+    pub accounts: Vec<NetWorthLine>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -271,11 +1388,47 @@ pub struct TransactionTransferJoined {
     pub category_name: Option<String>,
     pub account_id: i32,
     pub account_name: String,
-    pub user_id: i32,
+    pub ledger_id: i32,
     pub from_account_id: Option<i32>,
     pub from_account_name: Option<String>,
+    pub status: TransactionStatuses,
+}
+
+/// A reconciliation session with its running totals. `items` are the account's movements up
+/// to the statement date that are not yet reconciled; ticking one marks it cleared.
+/// `cleared_balance` is the account balance counting only cleared and reconciled movements up
+/// to the statement date, and `difference` is `statement_balance - cleared_balance`: the
+/// session can be finished once it reaches zero.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReconciliationSummary {
+    pub id: i32,
+    pub account_id: i32,
+    pub statement_date: NaiveDateTime,
+    pub statement_balance: i32,
+    pub created_date: NaiveDateTime,
+    pub finished_date: Option<NaiveDateTime>,
+    pub cleared_balance: i32,
+    pub difference: i32,
+    pub items: Vec<TransactionTransferJoined>,
 }
 
+/// One upcoming occurrence of a schedule (`occurrence` is `None` for a one-off). `date` is when
+/// it is due, after any move off a weekend or holiday (or a snooze), and `scheduled_date` the
+/// date the recurrence gives; `clamped` is set when that date is earlier in the month than the
+/// schedule's start because the month is shorter (the 31st becoming the 28th of February).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpcomingOccurrence {
+    pub occurrence: Option<i32>,
+    pub date: NaiveDateTime,
+    pub scheduled_date: NaiveDateTime,
+    pub value: i32,
+    pub clamped: bool,
+}
+
+/// A scheduled transaction with the names of what it references. `paid_value` is what partial
+/// payments have already paid towards the current occurrence and `estimated_value` the amount
+/// expected for it (`value` unless it has an `amount_estimate`); `finished` is set once the last
+/// occurrence has been paid or skipped.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetScheduledTransaction {
     pub id: i32,
@@ -295,9 +1448,16 @@ pub struct GetScheduledTransaction {
     pub repeat: bool,
     pub repeat_freq: Option<RepeatFrequencies>,
     pub repeat_interval: Option<i32>,
+    pub rrule: Option<String>,
+    pub business_day_adjustment: BusinessDayAdjustments,
+    pub amount_estimate: AmountEstimates,
     pub infinite_repeat: Option<bool>,
     pub end_after_repeats: Option<i32>,
     pub current_repeat_count: Option<i32>,
     pub next_date: Option<NaiveDateTime>,
-    pub user_id: i32,
+    pub finished: bool,
+    pub paid_value: i32,
+    pub estimated_value: i32,
+    pub ledger_id: i32,
+    pub autopay_card_id: Option<i32>,
 }
@@ -0,0 +1,282 @@
+//! OpenID Connect single sign-on: the authorization code flow with PKCE against the identity
+//! providers listed in `OIDC_ISSUERS` (see [`Config`]).
+//!
+//! The client asks for an authorization URL and sends the browser there; the provider redirects
+//! back to `OIDC_REDIRECT_URL` (a page of the client) with a `code` and the `state`, which the
+//! client posts to the callback. The code is exchanged for an ID token, whose signature (with the
+//! provider's published keys), issuer, audience, expiry and nonce are checked. Providers are
+//! found through their discovery document, so any compliant one works, including a local mock
+//! over plain HTTP.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::error::AppError;
+
+/// How long the user has to log in at the provider.
+const PENDING_TTL: Duration = Duration::from_secs(10 * 60);
+/// Most logins waiting for their callback at a time.
+const MAX_PENDING: usize = 10_000;
+/// Signature algorithms accepted on ID tokens; the symmetric (HS*) ones are not, since the keys
+/// come from the provider's public key set.
+const ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// The HTTP client for the providers and the logins waiting for their callback, by `state`.
+pub struct Oidc {
+    client: reqwest::Client,
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+struct Pending {
+    issuer: String,
+    verifier: String,
+    nonce: String,
+    created: Instant,
+}
+
+/// The parts of a provider's discovery document (`/.well-known/openid-configuration`) used here.
+#[derive(Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Deserialize)]
+struct IdClaims {
+    sub: String,
+    nonce: Option<String>,
+    preferred_username: Option<String>,
+    email: Option<String>,
+}
+
+/// An account at a provider that logged in.
+pub struct Identity {
+    pub issuer: String,
+    pub subject: String,
+    /// A name for a provisioned user: the `preferred_username`, or the `email`.
+    pub name: Option<String>,
+}
+
+impl Oidc {
+    /// The client for the configured providers, or `None` when `OIDC_ISSUERS` is not set.
+    pub fn from_config(config: &Config) -> anyhow::Result<Option<Oidc>> {
+        if config.oidc_issuers.is_empty() {
+            return Ok(None);
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        Ok(Some(Oidc {
+            client,
+            pending: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// Start a login at `issuer` (the first configured one by default), returning the URL to send
+    /// the browser to. `BadRequest` for an issuer that is not allowed.
+    pub async fn authorization_url(
+        &self,
+        config: &Config,
+        issuer: Option<&str>,
+    ) -> Result<String, AppError> {
+        let issuer = match issuer.map(|issuer| issuer.trim_end_matches('/')) {
+            Some(issuer) => config
+                .oidc_issuers
+                .iter()
+                .find(|allowed| allowed.as_str() == issuer)
+                .ok_or(AppError::BadRequest)?,
+            None => config.oidc_issuers.first().ok_or(AppError::NotFound)?,
+        };
+        let discovery = self.discover(issuer).await?;
+
+        let state = random_token();
+        let nonce = random_token();
+        let verifier = random_token();
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+        let mut url = reqwest::Url::parse(&discovery.authorization_endpoint)
+            .map_err(|e| AppError::Internal(format!("invalid authorization endpoint: {e}")))?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &config.oidc_client_id)
+            .append_pair("redirect_uri", &config.oidc_redirect_url)
+            .append_pair("scope", &config.oidc_scopes)
+            .append_pair("state", &state)
+            .append_pair("nonce", &nonce)
+            .append_pair("code_challenge", &challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        let now = Instant::now();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, login| now.duration_since(login.created) < PENDING_TTL);
+        if pending.len() >= MAX_PENDING {
+            return Err(AppError::TooManyRequests(PENDING_TTL.as_secs()));
+        }
+        pending.insert(
+            state,
+            Pending {
+                issuer: issuer.clone(),
+                verifier,
+                nonce,
+                created: now,
+            },
+        );
+
+        Ok(url.to_string())
+    }
+
+    /// Finish a login with the `code` and `state` the provider redirected back with, returning
+    /// the identity that logged in. `Unauthorized` for an unknown or expired `state`, a code the
+    /// provider rejects or an invalid ID token.
+    pub async fn finish(
+        &self,
+        config: &Config,
+        code: &str,
+        state: &str,
+    ) -> Result<Identity, AppError> {
+        let login = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(state)
+            .filter(|login| login.created.elapsed() < PENDING_TTL)
+            .ok_or(AppError::Unauthorized)?;
+        let discovery = self.discover(&login.issuer).await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", config.oidc_redirect_url.as_str()),
+            ("client_id", config.oidc_client_id.as_str()),
+            ("code_verifier", login.verifier.as_str()),
+        ];
+        if let Some(secret) = &config.oidc_client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+
+        let response = self
+            .client
+            .post(&discovery.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("OIDC token request failed: {e}")))?;
+        if !response.status().is_success() {
+            tracing::warn!("{} rejected an authorization code: {}", login.issuer, response.status());
+            return Err(AppError::Unauthorized);
+        }
+        let tokens: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::Internal(format!("invalid OIDC token response: {e}")))?;
+
+        let claims = self.validate(config, &discovery, &tokens.id_token).await?;
+        if claims.nonce.as_deref() != Some(login.nonce.as_str()) {
+            return Err(AppError::Unauthorized);
+        }
+
+        Ok(Identity {
+            issuer: login.issuer,
+            subject: claims.sub,
+            name: claims.preferred_username.or(claims.email),
+        })
+    }
+
+    /// The provider's discovery document, which must name the configured issuer.
+    async fn discover(&self, issuer: &str) -> Result<Discovery, AppError> {
+        let discovery: Discovery = self
+            .get_json(&format!("{issuer}/.well-known/openid-configuration"))
+            .await?;
+        if discovery.issuer.trim_end_matches('/') != issuer {
+            return Err(AppError::Internal(format!(
+                "the discovery document of {issuer} names issuer {}",
+                discovery.issuer
+            )));
+        }
+
+        Ok(discovery)
+    }
+
+    /// Check the ID token's signature against the provider's keys and its standard claims.
+    async fn validate(
+        &self,
+        config: &Config,
+        discovery: &Discovery,
+        id_token: &str,
+    ) -> Result<IdClaims, AppError> {
+        let header = jsonwebtoken::decode_header(id_token).map_err(|_| AppError::Unauthorized)?;
+        if !ALGORITHMS.contains(&header.alg) {
+            return Err(AppError::Unauthorized);
+        }
+
+        let keys: JwkSet = self.get_json(&discovery.jwks_uri).await?;
+        let jwk = match &header.kid {
+            Some(kid) => keys.find(kid),
+            None => keys.keys.first(),
+        }
+        .ok_or(AppError::Unauthorized)?;
+        let key = DecodingKey::from_jwk(jwk).map_err(|_| AppError::Unauthorized)?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[discovery.issuer.as_str()]);
+        validation.set_audience(&[config.oidc_client_id.as_str()]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        jsonwebtoken::decode::<IdClaims>(id_token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| {
+                tracing::warn!("invalid ID token from {}: {e}", discovery.issuer);
+                AppError::Unauthorized
+            })
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, AppError> {
+        self.client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| AppError::Internal(format!("OIDC request to {url} failed: {e}")))?
+            .json()
+            .await
+            .map_err(|e| AppError::Internal(format!("invalid OIDC response from {url}: {e}")))
+    }
+}
+
+/// 256 random bits, base64url-encoded: PKCE code verifiers (RFC 7636), `state`, `nonce` and the
+/// unusable passwords of provisioned users.
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
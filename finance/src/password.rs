@@ -0,0 +1,177 @@
+//! Password hashing, done in Rust so that plaintext passwords never reach the database.
+//!
+//! New passwords are hashed with Argon2id, with the cost set by `ARGON2_MEMORY_KIB`,
+//! `ARGON2_ITERATIONS` and `ARGON2_PARALLELISM` (see [`Config`]). The bcrypt hashes that
+//! pgcrypto's `crypt()` stored before are still accepted, and every hash that is not Argon2id
+//! with the current cost is replaced on the user's next successful login.
+
+use std::net::IpAddr;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::db;
+use crate::error::AppError;
+use crate::models::AppUser;
+use crate::state::AppState;
+
+/// Longest password accepted, in characters; the hash cost does not depend on it, but there is no
+/// point in hashing megabytes.
+const MAX_PASSWORD_LEN: usize = 1024;
+
+/// Whether a password matches a stored hash.
+pub enum Verification {
+    Mismatch,
+    /// It matches, and the hash is Argon2id with the configured cost.
+    Current,
+    /// It matches, but the hash is bcrypt or uses other Argon2 parameters, so it is rehashed.
+    Outdated,
+}
+
+/// The Argon2id parameters from the configuration, checked when it is loaded.
+pub fn params(config: &Config) -> Result<Params, argon2::Error> {
+    Params::new(
+        config.argon2_memory_kib,
+        config.argon2_iterations,
+        config.argon2_parallelism,
+        None,
+    )
+}
+
+/// Reject a new password (or user name) that does not meet the policy: a name that is not blank,
+/// and a password of at least `PASSWORD_MIN_LENGTH` characters that is not the user name.
+pub fn check_policy(name: &str, password: &str, config: &Config) -> Result<(), AppError> {
+    let length = password.chars().count();
+    if name.trim().is_empty()
+        || length < config.password_min_length
+        || length > MAX_PASSWORD_LEN
+        || password.trim().is_empty()
+        || password.trim().eq_ignore_ascii_case(name.trim())
+    {
+        return Err(AppError::BadRequest);
+    }
+
+    Ok(())
+}
+
+/// Hash a password with Argon2id and a random salt, as a PHC string (`$argon2id$v=19$…`).
+pub async fn hash(password: &str, config: &Config) -> Result<String, AppError> {
+    let password = password.to_string();
+    let params = params(config)
+        .map_err(|e| AppError::Internal(format!("invalid Argon2 parameters: {e}")))?;
+
+    blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| AppError::Internal(format!("failed to hash password: {e}")))
+    })
+    .await
+}
+
+/// Check a password against a stored Argon2 or bcrypt hash.
+pub async fn verify(password: &str, hash: &str, config: &Config) -> Result<Verification, AppError> {
+    let password = password.to_string();
+    let hash = hash.to_string();
+    let params = params(config)
+        .map_err(|e| AppError::Internal(format!("invalid Argon2 parameters: {e}")))?;
+
+    blocking(move || {
+        if hash.starts_with("$2") {
+            let matches = bcrypt::verify(&password, &hash)
+                .map_err(|e| AppError::Internal(format!("invalid bcrypt hash: {e}")))?;
+            return Ok(if matches { Verification::Outdated } else { Verification::Mismatch });
+        }
+
+        let parsed = PasswordHash::new(&hash)
+            .map_err(|e| AppError::Internal(format!("invalid password hash: {e}")))?;
+        if Argon2::default().verify_password(password.as_bytes(), &parsed).is_err() {
+            return Ok(Verification::Mismatch);
+        }
+
+        let current = parsed.algorithm == Algorithm::Argon2id.ident()
+            && parsed.version == Some(Version::V0x13.into())
+            && Params::try_from(&parsed).is_ok_and(|used| {
+                used.m_cost() == params.m_cost()
+                    && used.t_cost() == params.t_cost()
+                    && used.p_cost() == params.p_cost()
+            });
+        Ok(if current { Verification::Current } else { Verification::Outdated })
+    })
+    .await
+}
+
+/// Return the user if the password is theirs, moving an outdated hash to the current one. An
+/// unknown name costs a hash too, so that it takes as long as a wrong password.
+pub async fn authenticate(
+    pool: &PgPool,
+    config: &Config,
+    name: &str,
+    password: &str,
+) -> Result<Option<AppUser>, AppError> {
+    let Some(user) = db::users::get_by_name(pool, name).await? else {
+        hash(password, config).await?;
+        return Ok(None);
+    };
+
+    if check(pool, config, &user, password).await? {
+        Ok(Some(user))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Confirm a change with the user's current password, moving an outdated hash to the current
+/// one. Guessing is slowed down like logins: `TooManyRequests` while the name or the IP is
+/// locked out, and a wrong password is `Forbidden` and counts as a failed login.
+pub async fn confirm(
+    state: &AppState,
+    ip: IpAddr,
+    user_id: i32,
+    password: &str,
+) -> Result<(), AppError> {
+    let user = db::users::get_with_password(&state.pool, user_id).await?;
+    let key = format!("name:{}", user.name);
+    state.rate_limits.check_login(&key, ip)?;
+
+    if !check(&state.pool, &state.config, &user, password).await? {
+        state.rate_limits.login_failed(&key, ip);
+        return Err(AppError::Forbidden);
+    }
+    state.rate_limits.login_succeeded(&key);
+    Ok(())
+}
+
+async fn check(
+    pool: &PgPool,
+    config: &Config,
+    user: &AppUser,
+    password: &str,
+) -> Result<bool, AppError> {
+    match verify(password, &user.password, config).await? {
+        Verification::Mismatch => Ok(false),
+        Verification::Current => Ok(true),
+        Verification::Outdated => {
+            let rehashed = hash(password, config).await?;
+            if db::users::replace_password(pool, user.id, &user.password, &rehashed).await? {
+                tracing::info!("rehashed the password of user {} with Argon2id", user.id);
+            }
+            Ok(true)
+        }
+    }
+}
+
+/// Run CPU-bound hashing off the async worker threads.
+async fn blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| AppError::Internal(format!("password hashing task failed: {e}")))?
+}
@@ -0,0 +1,435 @@
+//! In-process rate limiting, kept in memory (so it is per backend process and reset by a
+//! restart).
+//!
+//! Failed logins are counted per user name and per client IP. Past `LOGIN_MAX_ATTEMPTS` (per
+//! name) or `LOGIN_IP_MAX_ATTEMPTS` (per IP) failures, every further failure locks the name or IP
+//! out for `LOGIN_LOCKOUT_SECONDS`, doubling each time up to `LOGIN_LOCKOUT_MAX_SECONDS`; a
+//! successful login clears the name's failures. Registrations and password reset requests are
+//! counted per IP with the same limits as failed logins, but apart from them. Apart from that,
+//! every client (by session or personal access token, or by IP without a valid one) may make
+//! `REQUESTS_PER_MINUTE` requests a minute. Rejected requests get 429 with `Retry-After`.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, FromRequestParts, Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::auth;
+use crate::config::Config;
+use crate::db;
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Entries kept per limiter. Past it the stale ones are cleared out, and if that is not enough
+/// the least recently used ones, down to [`MAX_ENTRIES`] * 9 / 10. Lockouts are never cleared
+/// out early: while a limiter holds nothing else, new keys are turned away.
+const MAX_ENTRIES: usize = 10_000;
+
+pub struct RateLimits {
+    names: Backoff,
+    ips: Backoff,
+    registrations: Backoff,
+    requests: Buckets,
+}
+
+impl RateLimits {
+    pub fn new(config: &Config) -> Self {
+        let base = Duration::from_secs(config.login_lockout_seconds);
+        let max = Duration::from_secs(config.login_lockout_max_seconds);
+
+        Self {
+            names: Backoff::new(config.login_max_attempts, base, max),
+            ips: Backoff::new(config.login_ip_max_attempts, base, max),
+            registrations: Backoff::new(config.login_ip_max_attempts, base, max),
+            requests: Buckets::new(config.requests_per_minute),
+        }
+    }
+
+    /// `TooManyRequests` while the account (`key`, `name:<user name>` for passwords or
+    /// `totp:<user id>` for two-factor codes) or the IP is locked out.
+    pub fn check_login(&self, key: &str, ip: IpAddr) -> Result<(), AppError> {
+        let now = Instant::now();
+        let name_wait = self.names.locked_for(key, now);
+        let ip_wait = self.ips.locked_for(&ip.to_string(), now);
+        match name_wait.max(ip_wait) {
+            Some(wait) => Err(AppError::TooManyRequests(retry_after(wait))),
+            None => Ok(()),
+        }
+    }
+
+    /// Count a failed login against the name and the IP, locking them out past their limits.
+    pub fn login_failed(&self, key: &str, ip: IpAddr) {
+        let now = Instant::now();
+        if let Some((failures, lockout)) = self.names.fail(key, now) {
+            tracing::warn!(
+                "login for {key:?} locked out for {}s after {failures} failures (last from {ip})",
+                lockout.as_secs()
+            );
+        }
+        if let Some((failures, lockout)) = self.ips.fail(&ip.to_string(), now) {
+            tracing::warn!(
+                "logins from {ip} locked out for {}s after {failures} failures",
+                lockout.as_secs()
+            );
+        }
+    }
+
+    pub fn login_succeeded(&self, key: &str) {
+        self.names.reset(key);
+    }
+
+    /// Check that the IP is not locked out, and count the registration (or password reset
+    /// request) against it.
+    pub fn register(&self, ip: IpAddr) -> Result<(), AppError> {
+        let now = Instant::now();
+        let key = ip.to_string();
+        if let Some(wait) = self.registrations.locked_for(&key, now) {
+            return Err(AppError::TooManyRequests(retry_after(wait)));
+        }
+        if let Some((registrations, lockout)) = self.registrations.fail(&key, now) {
+            tracing::warn!(
+                "registrations and resets from {ip} locked out for {}s after {registrations} attempts",
+                lockout.as_secs()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Failure counts with an exponentially growing lockout.
+struct Backoff {
+    allowed: u32,
+    base: Duration,
+    max: Duration,
+    entries: Mutex<HashMap<String, Failures>>,
+}
+
+struct Failures {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl Backoff {
+    fn new(allowed: u32, base: Duration, max: Duration) -> Self {
+        Self {
+            allowed,
+            base,
+            max,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// How much longer `key` is locked out, if it is. A new key is turned away until the first
+    /// lockout ends while there is no room for it.
+    fn locked_for(&self, key: &str, now: Instant) -> Option<Duration> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(failures) => failures.locked_for(now),
+            None => self.make_room(&mut entries, now).err(),
+        }
+    }
+
+    /// Count a failure; past the allowed failures, the number of failures and the lockout it
+    /// started. Failures are forgotten after the longest lockout without another one, and not
+    /// counted for a new key there is no room for (which [`Backoff::locked_for`] turns away).
+    fn fail(&self, key: &str, now: Instant) -> Option<(u32, Duration)> {
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.contains_key(key) {
+            self.make_room(&mut entries, now).ok()?;
+        }
+
+        let failures = entries.entry(key.to_string()).or_insert(Failures {
+            count: 0,
+            last: now,
+            locked_until: None,
+        });
+        if now.duration_since(failures.last) >= self.max {
+            failures.count = 0;
+        }
+        failures.count += 1;
+        failures.last = now;
+
+        let over = failures.count.checked_sub(self.allowed + 1)?;
+        let lockout = self.base.saturating_mul(2u32.saturating_pow(over)).min(self.max);
+        failures.locked_until = Some(now + lockout);
+        Some((failures.count, lockout))
+    }
+
+    fn reset(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+
+    /// Clear out stale entries and then the least recently used ones that are not locked out, if
+    /// the map is full; how long until the first lockout ends if that leaves no room.
+    fn make_room(
+        &self,
+        entries: &mut HashMap<String, Failures>,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        if entries.len() < MAX_ENTRIES {
+            return Ok(());
+        }
+        entries.retain(|_, failures| now.duration_since(failures.last) < self.max);
+        evict_oldest(entries, |failures| match failures.locked_for(now) {
+            Some(_) => None,
+            None => Some(failures.last),
+        });
+
+        if entries.len() < MAX_ENTRIES {
+            return Ok(());
+        }
+        let first = entries.values().filter_map(|failures| failures.locked_for(now)).min();
+        Err(first.unwrap_or_default())
+    }
+}
+
+impl Failures {
+    fn locked_for(&self, now: Instant) -> Option<Duration> {
+        self.locked_until?.checked_duration_since(now).filter(|wait| !wait.is_zero())
+    }
+}
+
+/// Token buckets of `per_minute` requests per client, refilled continuously.
+struct Buckets {
+    per_minute: u32,
+    entries: Mutex<HashMap<Client, Bucket>>,
+}
+
+/// Whom a request is counted against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Client {
+    Session(i32),
+    ApiToken(i32),
+    Ip(IpAddr),
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Buckets {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a request from the client's bucket, or how long until there is one.
+    fn take(&self, client: Client, now: Instant) -> Result<(), Duration> {
+        let capacity = f64::from(self.per_minute);
+        let per_second = capacity / 60.0;
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= MAX_ENTRIES {
+            // A bucket untouched for a minute is full again, as good as a new one.
+            entries.retain(|_, bucket| {
+                now.duration_since(bucket.updated) < Duration::from_secs(60)
+            });
+            evict_oldest(&mut entries, |bucket| Some(bucket.updated));
+        }
+
+        let bucket = entries.entry(client).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+}
+
+/// Drop the least recently used entries (by `last`, keeping those it gives `None` for) while
+/// the map is still full, down to nine tenths of [`MAX_ENTRIES`] so that this does not run again
+/// on the next request.
+fn evict_oldest<K: Clone + Eq + Hash, V>(
+    entries: &mut HashMap<K, V>,
+    last: impl Fn(&V) -> Option<Instant>,
+) {
+    if entries.len() < MAX_ENTRIES {
+        return;
+    }
+
+    let mut by_age: Vec<(Instant, K)> = entries
+        .iter()
+        .filter_map(|(key, value)| Some((last(value)?, key.clone())))
+        .collect();
+    by_age.sort_unstable_by_key(|(last, _)| *last);
+    for (_, key) in by_age.iter().take(entries.len() - MAX_ENTRIES * 9 / 10) {
+        entries.remove(key);
+    }
+}
+
+/// Whole seconds to wait, rounded up.
+fn retry_after(wait: Duration) -> u64 {
+    wait.as_secs() + u64::from(wait.subsec_nanos() > 0)
+}
+
+/// The client's IP: the connection's peer, or with `TRUST_FORWARDED_FOR` (behind a reverse proxy)
+/// the first address of `X-Forwarded-For`.
+pub struct ClientIp(pub IpAddr);
+
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if state.config.trust_forwarded_for {
+            let forwarded = parts
+                .headers
+                .get("x-forwarded-for")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(',').next())
+                .and_then(|ip| ip.trim().parse().ok());
+            if let Some(ip) = forwarded {
+                return Ok(ClientIp(ip));
+            }
+        }
+
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        Ok(ClientIp(ip))
+    }
+}
+
+/// Middleware limiting every client to `REQUESTS_PER_MINUTE` requests: by session for a valid
+/// access token, by personal access token for a known one, and by IP for requests with neither
+/// (so that made-up tokens do not get a bucket each).
+pub async fn limit_requests(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    request: Request,
+    next: Next,
+) -> Response {
+    let token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let client = match token {
+        Some(token) if token.starts_with(db::api_tokens::TOKEN_PREFIX) => {
+            db::api_tokens::get_id(&state.pool, token)
+                .await
+                .ok()
+                .flatten()
+                .map(Client::ApiToken)
+        }
+        Some(token) => auth::validate_jwt(token, &state.config.jwt_secret)
+            .ok()
+            .map(|data| Client::Session(data.claims.sid)),
+        None => None,
+    };
+
+    match state.rate_limits.requests.take(client.unwrap_or(Client::Ip(ip)), Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => AppError::TooManyRequests(retry_after(wait)).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn backoff_locks_out_past_the_allowed_failures_doubling_up_to_the_max() {
+        let backoff = Backoff::new(2, 10 * SECOND, 35 * SECOND);
+        let start = Instant::now();
+
+        assert_eq!(backoff.fail("name:alice", start), None);
+        assert_eq!(backoff.fail("name:alice", start), None);
+        assert_eq!(backoff.locked_for("name:alice", start), None);
+
+        assert_eq!(backoff.fail("name:alice", start), Some((3, 10 * SECOND)));
+        assert_eq!(backoff.locked_for("name:alice", start), Some(10 * SECOND));
+        assert_eq!(backoff.locked_for("name:alice", start + 10 * SECOND), None);
+        assert_eq!(backoff.locked_for("name:bob", start), None);
+
+        assert_eq!(backoff.fail("name:alice", start + 10 * SECOND), Some((4, 20 * SECOND)));
+        assert_eq!(backoff.fail("name:alice", start + 30 * SECOND), Some((5, 35 * SECOND)));
+
+        // Forgotten after the longest lockout without another failure, or on success.
+        assert_eq!(backoff.fail("name:alice", start + 65 * SECOND), None);
+        backoff.reset("name:alice");
+        assert_eq!(backoff.locked_for("name:alice", start + 65 * SECOND), None);
+    }
+
+    #[test]
+    fn full_backoffs_keep_their_lockouts_and_turn_new_keys_away() {
+        let backoff = Backoff::new(0, 10 * SECOND, 60 * SECOND);
+        let start = Instant::now();
+        for i in 0..MAX_ENTRIES {
+            assert!(backoff.fail(&format!("ip:{i}"), start).is_some());
+        }
+
+        assert_eq!(backoff.locked_for("ip:new", start + SECOND), Some(9 * SECOND));
+        assert_eq!(backoff.fail("ip:new", start + SECOND), None);
+        assert_eq!(backoff.locked_for("ip:0", start + SECOND), Some(9 * SECOND));
+        assert_eq!(backoff.entries.lock().unwrap().len(), MAX_ENTRIES);
+
+        // Once the lockouts end, their entries make room like any other.
+        let later = start + 10 * SECOND;
+        assert_eq!(backoff.locked_for("ip:new", later), None);
+        assert_eq!(backoff.fail("ip:new", later), Some((1, 10 * SECOND)));
+        assert_eq!(backoff.entries.lock().unwrap().len(), MAX_ENTRIES * 9 / 10 + 1);
+    }
+
+    #[test]
+    fn buckets_refill_continuously() {
+        let buckets = Buckets::new(60);
+        let client = Client::Session(1);
+        let start = Instant::now();
+
+        for _ in 0..60 {
+            buckets.take(client, start).unwrap();
+        }
+        assert_eq!(buckets.take(client, start), Err(SECOND));
+        assert!(buckets.take(Client::Session(2), start).is_ok(), "other clients are not limited");
+
+        buckets.take(client, start + SECOND).unwrap();
+        assert!(buckets.take(client, start + SECOND).is_err());
+    }
+
+    #[test]
+    fn full_maps_drop_the_least_recently_used_entries() {
+        let buckets = Buckets::new(1);
+        let start = Instant::now();
+
+        // Fresh clients, none stale: the oldest tenth is evicted to make room.
+        for i in 0..MAX_ENTRIES {
+            let ip = IpAddr::from((i as u32).to_be_bytes());
+            buckets.take(Client::Ip(ip), start + Duration::from_millis(i as u64)).unwrap();
+        }
+        let now = start + Duration::from_millis(MAX_ENTRIES as u64);
+        buckets.take(Client::Session(1), now).unwrap();
+
+        let entries = buckets.entries.lock().unwrap();
+        assert_eq!(entries.len(), MAX_ENTRIES * 9 / 10 + 1);
+        assert!(!entries.contains_key(&Client::Ip(IpAddr::from([0, 0, 0, 0]))));
+        let newest = IpAddr::from((MAX_ENTRIES as u32 - 1).to_be_bytes());
+        assert!(entries.contains_key(&Client::Ip(newest)));
+    }
+}
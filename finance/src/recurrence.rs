@@ -0,0 +1,371 @@
+//! RFC 5545 recurrence rules (`RRULE`) for scheduled transactions.
+//!
+//! Supported parts: `FREQ` (`DAILY`, `WEEKLY`, `MONTHLY`, `YEARLY`), `INTERVAL`, `BYDAY` (with
+//! ordinals such as `2TU` or `-1FR` in monthly and yearly rules), `BYMONTHDAY` (negative days
+//! count from the end of the month), `BYMONTH`, `BYSETPOS`, `UNTIL` and `COUNT`. Weeks start on
+//! Monday (`WKST=MO` is accepted, nothing else).
+//!
+//! A schedule's `created_date` is the rule's start (`DTSTART`): the occurrences are the dates
+//! matching the rule on or after it, at its time of day. As the RFC requires, dates that do not
+//! exist (the 31st of a 30-day month) are skipped rather than moved; use `BYMONTHDAY=-1` for the
+//! last day of the month.
+//!
+//! Examples: `FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1` (last business day of the month),
+//! `FREQ=MONTHLY;BYDAY=2TU` (second Tuesday), `FREQ=MONTHLY;BYMONTHDAY=15,-1` (the 15th and the
+//! last day), `FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR` (every weekday).
+
+use std::collections::VecDeque;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Weekday};
+
+/// Periods scanned in a row without an occurrence before a rule is considered exhausted, so
+/// that a rule which can never match (`BYMONTH=2;BYMONTHDAY=30`) still terminates. Large
+/// enough for a daily rule on February 29th.
+const MAX_EMPTY_PERIODS: u32 = 3000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    /// Weekdays, each with an optional ordinal within the month (or year): `2TU` is
+    /// `(Some(2), Tue)`, `-1FR` is `(Some(-1), Fri)` and `MO` is `(None, Mon)`.
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+    pub until: Option<NaiveDateTime>,
+    pub count: Option<u32>,
+}
+
+fn parse_list<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Vec<T>> {
+    value.split(',').map(|item| parse(item.trim())).collect()
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    match code.to_ascii_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// Parse a `BYDAY` item: a weekday code with an optional signed ordinal (`MO`, `2TU`, `-1FR`).
+fn parse_by_day(item: &str) -> Option<(Option<i32>, Weekday)> {
+    let split = item.len().checked_sub(2)?;
+    let (ordinal, code) = (item.get(..split)?, item.get(split..)?);
+    let weekday = parse_weekday(code)?;
+    if ordinal.is_empty() {
+        return Some((None, weekday));
+    }
+
+    let ordinal: i32 = ordinal.parse().ok()?;
+    (ordinal != 0 && ordinal.abs() <= 53).then_some((Some(ordinal), weekday))
+}
+
+/// Parse an `UNTIL` value. A date-only value includes the whole of that day.
+fn parse_until(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim_end_matches(['Z', 'z']);
+    if value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok()?.and_hms_opt(23, 59, 59);
+    }
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|first| first.pred_opt())
+        .map_or(31, |last| last.day())
+}
+
+/// The `n`th item (1-based; negative counts from the end) of a sorted list.
+fn nth_of<T: Copy>(items: &[T], n: i32) -> Option<T> {
+    let index = if n > 0 {
+        n as usize - 1
+    } else {
+        items.len().checked_sub(n.unsigned_abs() as usize)?
+    };
+    items.get(index).copied()
+}
+
+impl RRule {
+    /// Parse a rule such as `FREQ=MONTHLY;BYDAY=2TU`, with or without the `RRULE:` prefix.
+    /// `None` if it is malformed or uses a part this module does not support.
+    pub fn parse(rule: &str) -> Option<RRule> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+        let mut freq = None;
+        let mut parsed = RRule {
+            freq: Frequency::Daily,
+            interval: 1,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            until: None,
+            count: None,
+        };
+
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=')?;
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.trim().to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return None,
+                    })
+                }
+                "INTERVAL" => parsed.interval = value.trim().parse().ok().filter(|i| *i >= 1)?,
+                "BYDAY" => parsed.by_day = parse_list(value, parse_by_day)?,
+                "BYMONTHDAY" => {
+                    parsed.by_month_day = parse_list(value, |item| {
+                        item.parse().ok().filter(|d: &i32| *d != 0 && d.abs() <= 31)
+                    })?
+                }
+                "BYMONTH" => {
+                    parsed.by_month = parse_list(value, |item| {
+                        item.parse().ok().filter(|m| (1..=12).contains(m))
+                    })?
+                }
+                "BYSETPOS" => {
+                    parsed.by_set_pos = parse_list(value, |item| {
+                        item.parse().ok().filter(|p: &i32| *p != 0 && p.abs() <= 366)
+                    })?
+                }
+                "UNTIL" => parsed.until = Some(parse_until(value.trim())?),
+                "COUNT" => parsed.count = Some(value.trim().parse().ok().filter(|c| *c >= 1)?),
+                "WKST" if value.trim().eq_ignore_ascii_case("MO") => {}
+                _ => return None,
+            }
+        }
+
+        parsed.freq = freq?;
+
+        let ordinals = parsed.by_day.iter().any(|(ordinal, _)| ordinal.is_some());
+        let invalid = (parsed.until.is_some() && parsed.count.is_some())
+            || (ordinals && matches!(parsed.freq, Frequency::Daily | Frequency::Weekly))
+            || (!parsed.by_month_day.is_empty() && parsed.freq == Frequency::Weekly);
+
+        (!invalid).then_some(parsed)
+    }
+
+    /// The occurrences on or after `start`, in order.
+    pub fn occurrences(&self, start: NaiveDateTime) -> Occurrences<'_> {
+        Occurrences {
+            rule: self,
+            start,
+            period: 0,
+            pending: VecDeque::new(),
+            emitted: 0,
+            empty_periods: 0,
+            done: false,
+        }
+    }
+
+    /// Occurrence `n` (0-based) on or after `start`; `None` once the rule has ended.
+    pub fn nth(&self, start: NaiveDateTime, n: i32) -> Option<NaiveDateTime> {
+        self.occurrences(start).nth(usize::try_from(n).ok()?)
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let last = days_in_month(date.year(), date.month()) as i32;
+        (self.by_month.is_empty() || self.by_month.contains(&date.month()))
+            && (self.by_month_day.is_empty()
+                || self.by_month_day.iter().any(|&d| {
+                    let day = if d > 0 { d } else { last + d + 1 };
+                    day == date.day() as i32
+                }))
+            && (self.by_day.is_empty() || self.by_day.iter().any(|(_, w)| *w == date.weekday()))
+    }
+
+    /// The dates `BYMONTHDAY` / `BYDAY` select in a month, or `default_day` without either.
+    fn expand_month(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        let last = days_in_month(year, month);
+        let days = || (1..=last).filter_map(|day| NaiveDate::from_ymd_opt(year, month, day));
+
+        if !self.by_month_day.is_empty() {
+            days().filter(|date| self.matches_day(*date)).collect()
+        } else if !self.by_day.is_empty() {
+            self.expand_by_day(&days().collect::<Vec<_>>())
+        } else {
+            NaiveDate::from_ymd_opt(year, month, default_day)
+                .into_iter()
+                .collect()
+        }
+    }
+
+    /// The dates of `days` each `BYDAY` item selects: every matching weekday, or the one at its
+    /// ordinal.
+    fn expand_by_day(&self, days: &[NaiveDate]) -> Vec<NaiveDate> {
+        let mut result = Vec::new();
+
+        for (ordinal, weekday) in &self.by_day {
+            let matching: Vec<NaiveDate> =
+                days.iter().copied().filter(|d| d.weekday() == *weekday).collect();
+            match ordinal {
+                None => result.extend(matching),
+                Some(n) => result.extend(nth_of(&matching, *n)),
+            }
+        }
+
+        result
+    }
+
+    /// The candidate dates of period `index`: the `index * interval`th day, week, month or year
+    /// from the one containing `start`. `None` past the range of representable dates.
+    fn expand(&self, start: NaiveDate, index: u32) -> Option<Vec<NaiveDate>> {
+        let step = i64::from(index) * i64::from(self.interval);
+
+        let mut dates = match self.freq {
+            Frequency::Daily => {
+                let day = start.checked_add_signed(Duration::try_days(step)?)?;
+                if self.matches_day(day) {
+                    vec![day]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let monday = start
+                    - Duration::days(i64::from(start.weekday().num_days_from_monday()));
+                let monday = monday.checked_add_signed(Duration::try_weeks(step)?)?;
+                (0..7)
+                    .map(|offset| monday + Duration::days(offset))
+                    .filter(|day| {
+                        let weekday_ok = if self.by_day.is_empty() {
+                            day.weekday() == start.weekday()
+                        } else {
+                            self.by_day.iter().any(|(_, w)| *w == day.weekday())
+                        };
+                        weekday_ok
+                            && (self.by_month.is_empty() || self.by_month.contains(&day.month()))
+                    })
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let months = i64::from(start.year()) * 12 + i64::from(start.month0()) + step;
+                let year = i32::try_from(months.div_euclid(12)).ok()?;
+                let month = months.rem_euclid(12) as u32 + 1;
+                NaiveDate::from_ymd_opt(year, month, 1)?;
+
+                if !self.by_month.is_empty() && !self.by_month.contains(&month) {
+                    Vec::new()
+                } else {
+                    self.expand_month(year, month, start.day())
+                }
+            }
+            Frequency::Yearly => {
+                let year = i32::try_from(i64::from(start.year()) + step).ok()?;
+                NaiveDate::from_ymd_opt(year, 1, 1)?;
+
+                if !self.by_month.is_empty() {
+                    self.by_month
+                        .iter()
+                        .flat_map(|month| self.expand_month(year, *month, start.day()))
+                        .collect()
+                } else if !self.by_month_day.is_empty() {
+                    (1..=12)
+                        .flat_map(|month| self.expand_month(year, month, start.day()))
+                        .collect()
+                } else if !self.by_day.is_empty() {
+                    let days: Vec<NaiveDate> = NaiveDate::from_ymd_opt(year, 1, 1)?
+                        .iter_days()
+                        .take_while(|day| day.year() == year)
+                        .collect();
+                    self.expand_by_day(&days)
+                } else {
+                    NaiveDate::from_ymd_opt(year, start.month(), start.day())
+                        .into_iter()
+                        .collect()
+                }
+            }
+        };
+
+        dates.sort();
+        dates.dedup();
+
+        if !self.by_set_pos.is_empty() {
+            let mut selected: Vec<NaiveDate> = self
+                .by_set_pos
+                .iter()
+                .filter_map(|pos| nth_of(&dates, *pos))
+                .collect();
+            selected.sort();
+            selected.dedup();
+            dates = selected;
+        }
+
+        Some(dates)
+    }
+}
+
+/// Iterator over a rule's occurrences, produced one period at a time.
+pub struct Occurrences<'a> {
+    rule: &'a RRule,
+    start: NaiveDateTime,
+    period: u32,
+    pending: VecDeque<NaiveDate>,
+    emitted: u32,
+    empty_periods: u32,
+    done: bool,
+}
+
+impl Iterator for Occurrences<'_> {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<NaiveDateTime> {
+        loop {
+            if self.done || self.rule.count.is_some_and(|count| self.emitted >= count) {
+                return None;
+            }
+
+            if let Some(date) = self.pending.pop_front() {
+                let occurrence = date.and_time(self.start.time());
+                if self.rule.until.is_some_and(|until| occurrence > until) {
+                    self.done = true;
+                    return None;
+                }
+
+                self.emitted += 1;
+                return Some(occurrence);
+            }
+
+            if self.empty_periods >= MAX_EMPTY_PERIODS {
+                self.done = true;
+                return None;
+            }
+
+            let Some(dates) = self.rule.expand(self.start.date(), self.period) else {
+                self.done = true;
+                return None;
+            };
+            self.period += 1;
+
+            let start = self.start.date();
+            let before = self.pending.len();
+            self.pending.extend(dates.into_iter().filter(|date| *date >= start));
+            if self.pending.len() == before {
+                self.empty_periods += 1;
+            } else {
+                self.empty_periods = 0;
+            }
+        }
+    }
+}
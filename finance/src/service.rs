@@ -1,65 +1,188 @@
-use chrono::{Duration, NaiveDateTime};
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, Utc};
 use chronoutil::RelativeDuration;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 
+use crate::amortization;
 use crate::auth;
+use crate::calendar;
 use crate::db;
 use crate::db::transactions::TxJoinRow;
 use crate::db::transfers::{TransferFromRow, TransferToRow};
 use crate::error::AppError;
+use crate::holidays::Holidays;
+use crate::lots;
 use crate::models::{
-    CategoryTypes, GetAccount, GetScheduledTransaction, InitialData, RepeatFrequencies,
-    ScheduledTransaction, ScheduledTransactionKinds, TransactionTransferJoined,
+    Account, AccountTypes, Amortization, AmountEstimates, BusinessDayAdjustments, CardStatement,
+    CategoryTypes, CostBasisMethods, Digest, DigestEntry, GetAccount, GetGoal, GetLoan,
+    GetScheduledTransaction, Goal, GoalAccountProgress, GoalProgress, Holdings, InitialData,
+    InvestmentKinds, InvestmentTransaction, Loan, LoanSimulation, NetWorthLine, NetWorthReport,
+    NewAccountData, NewInvestmentTransactionData, NewLoanData, NewScheduledTransaction,
+    NewTransactionData, NewTransferData, Position, PostGoal, PostGoalContribution,
+    PostInvestmentTransaction, PostLoan, PostLoanSimulation, PriceImport, ProjectedOverdraft,
+    Reconciliation, ReconciliationSummary, RepeatFrequencies, ScheduledTransaction,
+    ScheduledTransactionKinds, SecurityPrice, TransactionStatuses, TransactionTransferJoined,
+    UpcomingOccurrence,
 };
+use crate::recurrence::RRule;
 use crate::state::AppState;
 
-/// Load all of a user's accounts, each with its computed balance.
+/// Load a ledger's active (non-archived) accounts, each with its computed balance, in the
+/// ledger's sort order.
 pub async fn accounts_with_balance(
     pool: &PgPool,
-    user_id: i32,
+    ledger_id: i32,
+) -> Result<Vec<GetAccount>, AppError> {
+    let accounts = db::accounts::get_all_by_archived(pool, false, ledger_id).await?;
+    with_balances(pool, accounts, ledger_id).await
+}
+
+/// Load a ledger's archived accounts, each with its computed balance.
+pub async fn archived_accounts_with_balance(
+    pool: &PgPool,
+    ledger_id: i32,
+) -> Result<Vec<GetAccount>, AppError> {
+    let accounts = db::accounts::get_all_by_archived(pool, true, ledger_id).await?;
+    with_balances(pool, accounts, ledger_id).await
+}
+
+async fn with_balances(
+    pool: &PgPool,
+    accounts: Vec<Account>,
+    ledger_id: i32,
 ) -> Result<Vec<GetAccount>, AppError> {
-    let accounts = db::accounts::get_all(pool, user_id).await?;
     let mut result = Vec::with_capacity(accounts.len());
 
-    for account in &accounts {
-        let balance = db::accounts::balance(pool, account.id, user_id).await?;
-        result.push(GetAccount {
-            id: account.id,
-            name: account.name.clone(),
+    for account in accounts {
+        let balance = db::accounts::balance(pool, account.id, ledger_id).await?;
+        result.push(account_to_dto(account, balance));
+    }
+
+    Ok(result)
+}
+
+/// Map an account row and its computed balance to the account response shape.
+pub fn account_to_dto(account: Account, balance: i32) -> GetAccount {
+    GetAccount {
+        id: account.id,
+        name: account.name,
+        balance,
+        ledger_id: account.ledger_id,
+        account_type: account.account_type,
+        opening_balance: account.opening_balance,
+        opening_date: account.opening_date,
+        archived: account.archived,
+        sort_order: account.sort_order,
+        statement_closing_day: account.statement_closing_day,
+        payment_due_day: account.payment_due_day,
+        autopay_account_id: account.autopay_account_id,
+    }
+}
+
+/// Build the net-worth report over all of a ledger's accounts. Archived accounts are included:
+/// archiving only hides an account from day-to-day lists, its money still counts. Investment
+/// accounts count the market value of their positions on top of their cash balance.
+pub async fn net_worth(pool: &PgPool, ledger_id: i32) -> Result<NetWorthReport, AppError> {
+    let accounts = db::accounts::get_all(pool, ledger_id).await?;
+    let mut report = NetWorthReport {
+        assets: 0,
+        liabilities: 0,
+        net_worth: 0,
+        accounts: Vec::with_capacity(accounts.len()),
+    };
+
+    for account in accounts {
+        let balance = db::accounts::balance(pool, account.id, ledger_id).await?;
+        let market_value: i32 = positions(pool, account.id, CostBasisMethods::Fifo, ledger_id)
+            .await?
+            .iter()
+            .map(|position| position.market_value)
+            .sum();
+        let liability = account.account_type.is_liability();
+        let amount = if liability {
+            -(balance + market_value)
+        } else {
+            balance + market_value
+        };
+
+        if liability {
+            report.liabilities += amount;
+        } else {
+            report.assets += amount;
+        }
+
+        report.accounts.push(NetWorthLine {
+            account_id: account.id,
+            account_name: account.name,
+            account_type: account.account_type,
+            archived: account.archived,
+            liability,
             balance,
-            user_id,
+            market_value,
+            amount,
         });
     }
 
-    Ok(result)
+    report.net_worth = report.assets - report.liabilities;
+    Ok(report)
 }
 
-/// Build the `InitialData` payload returned by login / register / token-refresh.
+/// Build the `InitialData` payload returned by login / register / token-refresh, with a new
+/// access token for the session and the data of the ledger.
 pub async fn build_initial_data(
     state: &AppState,
     user_id: i32,
+    session_id: i32,
+    ledger_id: i32,
 ) -> Result<InitialData, AppError> {
     let token = auth::create_jwt(
         user_id,
+        session_id,
         &state.config.jwt_secret,
-        state.config.jwt_validity_days,
+        state.config.access_token_minutes,
     )?;
 
     Ok(InitialData {
         token,
-        accounts: accounts_with_balance(&state.pool, user_id).await?,
-        categories: db::categories::get_all(&state.pool, user_id).await?,
-        scheduled_transactions: all_scheduled_enriched(&state.pool, user_id).await?,
+        refresh_token: None,
+        password_change_required: db::users::get(&state.pool, user_id)
+            .await?
+            .password_change_required,
+        ledger_id,
+        ledgers: db::ledgers::get_all(&state.pool, user_id).await?,
+        accounts: accounts_with_balance(&state.pool, ledger_id).await?,
+        categories: db::categories::get_all(&state.pool, ledger_id).await?,
+        scheduled_transactions: all_scheduled_enriched(&state.pool, ledger_id).await?,
+    })
+}
+
+/// Open a session for a user who just logged in (or registered) from `device`, and build their
+/// `InitialData` with its access and refresh tokens, from their default ledger. `Forbidden` if an
+/// admin disabled their logins.
+pub async fn open_session(
+    state: &AppState,
+    user_id: i32,
+    device: Option<&str>,
+) -> Result<InitialData, AppError> {
+    if db::users::is_disabled(&state.pool, user_id).await? {
+        return Err(AppError::Forbidden);
+    }
+    let (session, refresh_token) =
+        db::sessions::insert(&state.pool, device, state.config.refresh_token_days, user_id).await?;
+    let ledger_id = db::ledgers::get_default(&state.pool, user_id).await?;
+
+    Ok(InitialData {
+        refresh_token: Some(refresh_token),
+        ..build_initial_data(state, user_id, session.id, ledger_id).await?
     })
 }
 
-/// Load all of a user's scheduled transactions, enriched with account/category names,
+/// Load all of a ledger's scheduled transactions, enriched with account/category names,
 /// ordered by `created_date` descending (the order comes from the database query).
 pub async fn all_scheduled_enriched(
     pool: &PgPool,
-    user_id: i32,
+    ledger_id: i32,
 ) -> Result<Vec<GetScheduledTransaction>, AppError> {
-    let scheduled = db::scheduled_transactions::get_all(pool, user_id).await?;
+    let scheduled = db::scheduled_transactions::get_all(pool, ledger_id).await?;
     let mut result = Vec::with_capacity(scheduled.len());
 
     for st in &scheduled {
@@ -94,11 +217,19 @@ pub async fn enrich_scheduled(
         repeat: st.repeat,
         repeat_freq: st.repeat_freq,
         repeat_interval: st.repeat_interval,
+        rrule: st.rrule.clone(),
+        business_day_adjustment: st.business_day_adjustment,
+        amount_estimate: st.amount_estimate,
         infinite_repeat: st.infinite_repeat,
         end_after_repeats: st.end_after_repeats,
         current_repeat_count: st.current_repeat_count,
         next_date: st.next_date,
-        user_id: st.user_id,
+        finished: st.finished,
+        paid_value: db::scheduled_occurrences::paid_value(pool, st.id, st.current_repeat_count)
+            .await?,
+        estimated_value: estimated_value(pool, st).await?,
+        ledger_id: st.ledger_id,
+        autopay_card_id: st.autopay_card_id,
     };
 
     let missing = || AppError::Internal("scheduled transaction has an unresolved reference".into());
@@ -108,10 +239,10 @@ pub async fn enrich_scheduled(
             let account_id = st.account_id.ok_or_else(missing)?;
             let category_id = st.category_id.ok_or_else(missing)?;
 
-            let account = db::accounts::get(pool, account_id, st.user_id)
+            let account = db::accounts::get(pool, account_id, st.ledger_id)
                 .await
                 .map_err(|_| missing())?;
-            let category = db::categories::get(pool, category_id, st.user_id)
+            let category = db::categories::get(pool, category_id, st.ledger_id)
                 .await
                 .map_err(|_| missing())?;
 
@@ -125,10 +256,10 @@ pub async fn enrich_scheduled(
             let origin_id = st.origin_account_id.ok_or_else(missing)?;
             let destination_id = st.destination_account_id.ok_or_else(missing)?;
 
-            let origin = db::accounts::get(pool, origin_id, st.user_id)
+            let origin = db::accounts::get(pool, origin_id, st.ledger_id)
                 .await
                 .map_err(|_| missing())?;
-            let destination = db::accounts::get(pool, destination_id, st.user_id)
+            let destination = db::accounts::get(pool, destination_id, st.ledger_id)
                 .await
                 .map_err(|_| missing())?;
 
@@ -154,14 +285,15 @@ pub fn tx_join_to_dto(row: TxJoinRow) -> TransactionTransferJoined {
         category_name: Some(row.category_name),
         account_id: row.account_id,
         account_name: row.account_name,
-        user_id: row.user_id,
+        ledger_id: row.ledger_id,
         from_account_id: None,
         from_account_name: None,
+        status: row.status,
     }
 }
 
 /// Map a transfer leaving the viewed account to a `TransferExpense` pseudo-transaction.
-pub fn transfer_from_to_dto(row: TransferFromRow, user_id: i32) -> TransactionTransferJoined {
+pub fn transfer_from_to_dto(row: TransferFromRow, ledger_id: i32) -> TransactionTransferJoined {
     TransactionTransferJoined {
         id: row.id,
         value: row.value,
@@ -172,14 +304,15 @@ pub fn transfer_from_to_dto(row: TransferFromRow, user_id: i32) -> TransactionTr
         category_name: None,
         account_id: row.origin_account,
         account_name: row.origin_name.clone(),
-        user_id,
+        ledger_id,
         from_account_id: Some(row.origin_account),
         from_account_name: Some(row.origin_name),
+        status: row.status,
     }
 }
 
 /// Map a transfer entering the viewed account to a `TransferIncome` pseudo-transaction.
-pub fn transfer_to_to_dto(row: TransferToRow, user_id: i32) -> TransactionTransferJoined {
+pub fn transfer_to_to_dto(row: TransferToRow, ledger_id: i32) -> TransactionTransferJoined {
     TransactionTransferJoined {
         id: row.id,
         value: row.value,
@@ -190,12 +323,1322 @@ pub fn transfer_to_to_dto(row: TransferToRow, user_id: i32) -> TransactionTransf
         category_name: None,
         account_id: row.destination_account,
         account_name: row.dest_name,
-        user_id,
+        ledger_id,
         from_account_id: Some(row.origin_account),
         from_account_name: Some(row.origin_name),
+        status: row.status,
+    }
+}
+
+/// All movements of an account — its transactions plus transfers out of and into it — in the
+/// unified transaction/transfer shape, newest first.
+pub async fn account_movements(
+    pool: &PgPool,
+    account_id: i32,
+    ledger_id: i32,
+) -> Result<Vec<TransactionTransferJoined>, AppError> {
+    let mut list = Vec::new();
+
+    for row in db::transactions::get_all_of_account_joined(pool, account_id, ledger_id).await? {
+        list.push(tx_join_to_dto(row));
+    }
+    for row in db::transfers::get_from_account_joined(pool, account_id, ledger_id).await? {
+        list.push(transfer_from_to_dto(row, ledger_id));
+    }
+    for row in db::transfers::get_to_account_joined(pool, account_id, ledger_id).await? {
+        list.push(transfer_to_to_dto(row, ledger_id));
+    }
+
+    // Sort by date ascending then reverse -> descending (stable), matching the original.
+    list.sort_by_key(|t| t.date);
+    list.reverse();
+
+    Ok(list)
+}
+
+/// The effect of a movement on the viewed account's balance: income and incoming transfers
+/// add, expenses and outgoing transfers subtract.
+pub fn signed_value(movement: &TransactionTransferJoined) -> i32 {
+    match movement.category_type {
+        CategoryTypes::Income | CategoryTypes::TransferIncome => movement.value,
+        CategoryTypes::Expense | CategoryTypes::TransferExpense => -movement.value,
+    }
+}
+
+/// Whether a movement in the unified shape is one side of a transfer (rather than a
+/// transaction).
+pub fn is_transfer(movement: &TransactionTransferJoined) -> bool {
+    matches!(
+        movement.category_type,
+        CategoryTypes::TransferIncome | CategoryTypes::TransferExpense
+    )
+}
+
+/// Build a reconciliation session's summary: the not-yet-reconciled movements up to the
+/// statement date, and the cleared balance and remaining difference. The cleared balance
+/// follows the same rules as [`db::accounts::balance`] (opening balance, opening-date cutoff)
+/// but only counts cleared and reconciled movements.
+pub async fn reconciliation_summary(
+    pool: &PgPool,
+    reconciliation: &Reconciliation,
+) -> Result<ReconciliationSummary, AppError> {
+    let ledger_id = reconciliation.ledger_id;
+    let account = db::accounts::get(pool, reconciliation.account_id, ledger_id).await?;
+
+    let mut cleared_balance = account.opening_balance;
+    let mut items = Vec::new();
+
+    for movement in account_movements(pool, account.id, ledger_id).await? {
+        if movement.date > reconciliation.statement_date {
+            continue;
+        }
+        if account.opening_date.is_some_and(|opening| movement.date < opening) {
+            continue;
+        }
+
+        if movement.status != TransactionStatuses::Uncleared {
+            cleared_balance += signed_value(&movement);
+        }
+        if movement.status != TransactionStatuses::Reconciled {
+            items.push(movement);
+        }
+    }
+
+    Ok(ReconciliationSummary {
+        id: reconciliation.id,
+        account_id: reconciliation.account_id,
+        statement_date: reconciliation.statement_date,
+        statement_balance: reconciliation.statement_balance,
+        created_date: reconciliation.created_date,
+        finished_date: reconciliation.finished_date,
+        cleared_balance,
+        difference: reconciliation.statement_balance - cleared_balance,
+        items,
+    })
+}
+
+/// Finish a balanced reconciliation session: every cleared movement of the account up to the
+/// statement date becomes reconciled (and thereby locked), and the session is closed, all in
+/// one database transaction.
+pub async fn finish_reconciliation(
+    pool: &PgPool,
+    reconciliation: &Reconciliation,
+) -> Result<Reconciliation, AppError> {
+    let mut tx = pool.begin().await?;
+
+    db::transactions::reconcile_cleared(
+        &mut tx,
+        reconciliation.account_id,
+        reconciliation.statement_date,
+        reconciliation.ledger_id,
+    )
+    .await?;
+    db::transfers::reconcile_cleared(
+        &mut tx,
+        reconciliation.account_id,
+        reconciliation.statement_date,
+        reconciliation.ledger_id,
+    )
+    .await?;
+    let finished =
+        db::reconciliations::finish_on(&mut tx, reconciliation.id, reconciliation.ledger_id).await?;
+
+    tx.commit().await?;
+    Ok(finished)
+}
+
+/// `day` of the given month, or the month's last day when it is shorter (day 31 of April is
+/// April 30th).
+pub fn day_of_month(year: i32, month: u32, day: i32) -> NaiveDate {
+    let first = NaiveDate::from_ymd_opt(year, month, 1).expect("valid month");
+    let last = (first + RelativeDuration::months(1)).pred_opt().expect("valid date");
+    first.with_day((day.max(1) as u32).min(last.day())).expect("day within the month")
+}
+
+/// The first date strictly after `after` that falls on `day` of its month (see
+/// [`day_of_month`]).
+pub fn next_day_of_month(after: NaiveDate, day: i32) -> NaiveDate {
+    let this_month = day_of_month(after.year(), after.month(), day);
+    if this_month > after {
+        return this_month;
+    }
+
+    let next_month = after.with_day(1).expect("day 1 exists") + RelativeDuration::months(1);
+    day_of_month(next_month.year(), next_month.month(), day)
+}
+
+/// Compute a credit card's statement as of `today`. Returns `None` when the account has no
+/// statement cycle configured (no closing or due day).
+///
+/// A statement closes at the end of its closing day, so on the closing day itself the cycle
+/// is still open. This only reads: the autopay schedule is kept up to date by
+/// [`sync_card_autopay`].
+pub async fn card_statement(
+    pool: &PgPool,
+    card: &Account,
+    today: NaiveDate,
+) -> Result<Option<CardStatement>, AppError> {
+    let (Some(closing_day), Some(due_day)) = (card.statement_closing_day, card.payment_due_day)
+    else {
+        return Ok(None);
+    };
+
+    let this_month = day_of_month(today.year(), today.month(), closing_day);
+    let last_closing = if this_month < today {
+        this_month
+    } else {
+        let previous = today.with_day(1).expect("day 1 exists") - RelativeDuration::months(1);
+        day_of_month(previous.year(), previous.month(), closing_day)
+    };
+    let next_closing = next_day_of_month(last_closing, closing_day);
+    let payment_due = next_day_of_month(last_closing, due_day);
+
+    // Everything up to the end of the closing day belongs to the statement.
+    let cycle_start = (last_closing + Duration::days(1)).and_time(Default::default());
+
+    let statement_balance =
+        db::accounts::balance_before(pool, card.id, Some(cycle_start), card.ledger_id).await?;
+    let payments = db::transfers::sum_into_since(pool, card.id, cycle_start, card.ledger_id).await?;
+    let cycle_balance = db::accounts::balance(pool, card.id, card.ledger_id).await?;
+    let amount_due = (-statement_balance - payments).max(0);
+
+    let next_payment_due = if amount_due > 0 {
+        payment_due
+    } else {
+        next_day_of_month(next_closing, due_day)
+    };
+
+    let autopay = db::scheduled_transactions::get_autopay(pool, card.id, card.ledger_id).await?;
+    let midnight = |date: NaiveDate| date.and_time(Default::default());
+
+    Ok(Some(CardStatement {
+        account_id: card.id,
+        last_closing_date: midnight(last_closing),
+        next_closing_date: midnight(next_closing),
+        statement_balance,
+        payments,
+        amount_due,
+        payment_due_date: midnight(payment_due),
+        next_payment_due: midnight(next_payment_due),
+        cycle_balance,
+        autopay_account_id: card.autopay_account_id,
+        autopay_scheduled_transaction_id: autopay.map(|st| st.id),
+    }))
+}
+
+/// Keep a credit card's autopay schedule in sync with its statement as of `today`.
+///
+/// When the card has an autopay account and something is due, a one-off scheduled transfer for
+/// `amount_due` on the due date is created or updated; otherwise any running autopay schedule
+/// is removed. Paying that schedule records a transfer into the card, which brings `amount_due`
+/// to zero and so does not recreate it.
+pub async fn sync_card_autopay(
+    pool: &PgPool,
+    card: &Account,
+    today: NaiveDate,
+) -> Result<(), AppError> {
+    let statement = card_statement(pool, card, today)
+        .await?
+        .filter(|statement| statement.amount_due > 0);
+
+    let (Some(origin_id), Some(statement)) = (card.autopay_account_id, statement) else {
+        return db::scheduled_transactions::delete_autopay(pool, card.id, card.ledger_id).await;
+    };
+
+    let new = NewScheduledTransaction {
+        kind: ScheduledTransactionKinds::Transfer,
+        value: statement.amount_due,
+        description: Some(format!("{} statement", card.name)),
+        created_date: statement.payment_due_date,
+        account_id: None,
+        category_id: None,
+        origin_account_id: Some(origin_id),
+        destination_account_id: Some(card.id),
+        repeat: false,
+        repeat_freq: None,
+        repeat_interval: None,
+        rrule: None,
+        business_day_adjustment: BusinessDayAdjustments::None,
+        amount_estimate: AmountEstimates::Fixed,
+        infinite_repeat: None,
+        end_after_repeats: None,
+        current_repeat_count: None,
+        next_date: Some(statement.payment_due_date),
+        ledger_id: card.ledger_id,
+        autopay_card_id: Some(card.id),
+    };
+    db::scheduled_transactions::upsert_autopay(pool, &new).await?;
+
+    Ok(())
+}
+
+/// Bring the autopay schedules of the ledger's accounts `account_ids` up to date (see
+/// [`sync_card_autopay`]), after their movements or settings changed.
+pub async fn sync_autopay(
+    pool: &PgPool,
+    account_ids: &[i32],
+    ledger_id: i32,
+) -> Result<(), AppError> {
+    let mut account_ids = account_ids.to_vec();
+    account_ids.sort_unstable();
+    account_ids.dedup();
+
+    let today = Local::now().date_naive();
+    for id in account_ids {
+        let account = db::accounts::get(pool, id, ledger_id).await?;
+        sync_card_autopay(pool, &account, today).await?;
+    }
+
+    Ok(())
+}
+
+/// Create a loan: the `loan` account (owing the principal from the start date), its monthly
+/// payment schedule, and the loan terms. Returns `BadRequest` for invalid terms or a
+/// non-expense interest category, `NotFound` for an unknown payment account or category.
+pub async fn create_loan(pool: &PgPool, body: PostLoan, ledger_id: i32) -> Result<GetLoan, AppError> {
+    if body.principal <= 0
+        || body.annual_rate_bps < 0
+        || body.term_months <= 0
+        || body.term_months > amortization::MAX_PAYMENTS
+        || !(1..=31).contains(&body.payment_day)
+    {
+        return Err(AppError::BadRequest);
+    }
+
+    db::accounts::get(pool, body.payment_account_id, ledger_id).await?;
+    let category = db::categories::get(pool, body.interest_category_id, ledger_id).await?;
+    if category.categorytype != CategoryTypes::Expense {
+        return Err(AppError::BadRequest);
+    }
+
+    let account = db::accounts::insert(
+        pool,
+        &NewAccountData {
+            name: body.name.clone(),
+            account_type: AccountTypes::Loan,
+            opening_balance: -body.principal,
+            opening_date: Some(body.start_date),
+            archived: false,
+            sort_order: 0,
+        },
+        ledger_id,
+    )
+    .await?;
+
+    let first_payment = next_day_of_month(body.start_date.date(), body.payment_day)
+        .and_time(Default::default());
+    let scheduled = db::scheduled_transactions::insert(
+        pool,
+        &NewScheduledTransaction {
+            kind: ScheduledTransactionKinds::Transfer,
+            value: amortization::monthly_payment(
+                body.principal,
+                body.annual_rate_bps,
+                body.term_months,
+            ),
+            description: Some(format!("{} payment", body.name)),
+            created_date: first_payment,
+            account_id: None,
+            category_id: None,
+            origin_account_id: Some(body.payment_account_id),
+            destination_account_id: Some(account.id),
+            repeat: true,
+            repeat_freq: Some(RepeatFrequencies::Months),
+            repeat_interval: Some(1),
+            rrule: None,
+            business_day_adjustment: BusinessDayAdjustments::None,
+            amount_estimate: AmountEstimates::Fixed,
+            infinite_repeat: Some(false),
+            end_after_repeats: Some(body.term_months),
+            current_repeat_count: Some(0),
+            next_date: Some(first_payment),
+            ledger_id,
+            autopay_card_id: None,
+        },
+    )
+    .await?;
+
+    let loan = db::loans::insert(
+        pool,
+        &NewLoanData {
+            account_id: account.id,
+            principal: body.principal,
+            annual_rate_bps: body.annual_rate_bps,
+            term_months: body.term_months,
+            payment_day: body.payment_day,
+            start_date: body.start_date,
+            payment_account_id: body.payment_account_id,
+            interest_category_id: body.interest_category_id,
+            scheduled_transaction_id: Some(scheduled.id),
+            ledger_id,
+        },
+    )
+    .await?;
+
+    loan_to_dto(pool, loan).await
+}
+
+/// Add the account name, payment, outstanding balance and next payment date to a loan.
+pub async fn loan_to_dto(pool: &PgPool, loan: Loan) -> Result<GetLoan, AppError> {
+    let account = db::accounts::get(pool, loan.account_id, loan.ledger_id).await?;
+    let balance = db::accounts::balance(pool, loan.account_id, loan.ledger_id).await?;
+
+    let next_payment_date = match loan.scheduled_transaction_id {
+        Some(id) => db::scheduled_transactions::get(pool, id, loan.ledger_id).await?.next_date,
+        None => None,
+    };
+
+    Ok(GetLoan {
+        account_id: loan.account_id,
+        account_name: account.name,
+        principal: loan.principal,
+        annual_rate_bps: loan.annual_rate_bps,
+        term_months: loan.term_months,
+        payment_day: loan.payment_day,
+        start_date: loan.start_date,
+        payment_account_id: loan.payment_account_id,
+        interest_category_id: loan.interest_category_id,
+        scheduled_transaction_id: loan.scheduled_transaction_id,
+        monthly_payment: amortization::monthly_payment(
+            loan.principal,
+            loan.annual_rate_bps,
+            loan.term_months,
+        ),
+        outstanding: -balance,
+        next_payment_date,
+    })
+}
+
+/// The loan's full amortization table, from the first payment to the end of the term.
+pub fn loan_amortization(loan: &Loan) -> Amortization {
+    let monthly_payment =
+        amortization::monthly_payment(loan.principal, loan.annual_rate_bps, loan.term_months);
+    let first_payment = next_day_of_month(loan.start_date.date(), loan.payment_day);
+
+    let payments = amortization::amortize(
+        loan.principal,
+        loan.annual_rate_bps,
+        monthly_payment,
+        0,
+        0,
+        first_payment,
+        loan.payment_day,
+        loan.term_months,
+    );
+
+    Amortization {
+        monthly_payment,
+        total_paid: payments.iter().map(|row| row.payment).sum(),
+        total_interest: payments.iter().map(|row| row.interest).sum(),
+        payments,
+    }
+}
+
+/// Compare paying off the loan's outstanding balance with the regular payment against paying
+/// extra, starting from the next payment date (or the next payment day from today when the
+/// schedule has ended) and over at most the remaining scheduled payments.
+pub async fn simulate_loan(
+    pool: &PgPool,
+    loan: &Loan,
+    extra: &PostLoanSimulation,
+) -> Result<LoanSimulation, AppError> {
+    let outstanding = -db::accounts::balance(pool, loan.account_id, loan.ledger_id).await?;
+    let monthly_payment =
+        amortization::monthly_payment(loan.principal, loan.annual_rate_bps, loan.term_months);
+
+    let scheduled = match loan.scheduled_transaction_id {
+        Some(id) => Some(db::scheduled_transactions::get(pool, id, loan.ledger_id).await?),
+        None => None,
+    };
+    let first = scheduled
+        .as_ref()
+        .and_then(|st| st.next_date)
+        .map(|date| date.date())
+        .unwrap_or_else(|| next_day_of_month(Local::now().date_naive(), loan.payment_day));
+
+    // The payments left on the schedule; the last of them clears any rounding remainder.
+    let remaining = scheduled
+        .as_ref()
+        .and_then(|st| Some(st.end_after_repeats? - st.current_repeat_count?))
+        .filter(|remaining| *remaining > 0)
+        .unwrap_or(amortization::MAX_PAYMENTS);
+
+    let plan = |extra_monthly: i32, lump_sum: i32| {
+        amortization::payoff(&amortization::amortize(
+            outstanding,
+            loan.annual_rate_bps,
+            monthly_payment,
+            extra_monthly,
+            lump_sum,
+            first,
+            loan.payment_day,
+            remaining,
+        ))
+    };
+
+    let baseline = plan(0, 0);
+    let with_extra = plan(extra.extra_monthly.max(0), extra.lump_sum.max(0));
+
+    Ok(LoanSimulation {
+        outstanding,
+        interest_saved: baseline.total_interest - with_extra.total_interest,
+        months_saved: baseline.payments - with_extra.payments,
+        baseline,
+        with_extra,
+    })
+}
+
+/// Record a loan payment of `value` from `origin_id` for the current occurrence of `scheduled`,
+/// the loan's schedule, linking both parts to it. The installment owes a month of interest on
+/// what was still owed when it came due: whatever part of that earlier payments of the same
+/// installment did not cover yet is booked as an expense in the loan's interest category, and
+/// the rest is transferred to the loan account, reducing the balance. So partial payments book
+/// the month's interest once, and paying extra repays principal only. Runs on the caller's
+/// transaction, which holds the schedule's lock.
+pub async fn pay_loan_installment(
+    conn: &mut PgConnection,
+    loan: &Loan,
+    scheduled: &ScheduledTransaction,
+    origin_id: i32,
+    value: i32,
+    description: &str,
+    date: NaiveDateTime,
+) -> Result<(), AppError> {
+    let (mut principal_paid, mut interest_paid) = (0, 0);
+    let entries =
+        db::scheduled_transactions::get_entries_on(conn, scheduled.id, loan.ledger_id).await?;
+    for entry in entries {
+        if entry.occurrence != scheduled.current_repeat_count {
+            continue;
+        }
+        match entry.kind {
+            ScheduledTransactionKinds::Transfer => principal_paid += entry.value,
+            ScheduledTransactionKinds::Transaction => interest_paid += entry.value,
+        }
+    }
+
+    let outstanding =
+        -db::accounts::balance_on(conn, loan.account_id, loan.ledger_id).await? + principal_paid;
+    let interest = (amortization::interest(outstanding.max(0), loan.annual_rate_bps)
+        - interest_paid)
+        .clamp(0, value);
+    let principal = value - interest;
+
+    if principal > 0 {
+        db::transfers::insert_on(
+            conn,
+            &NewTransferData {
+                origin_account: origin_id,
+                destination_account: loan.account_id,
+                value: principal,
+                description: description.to_string(),
+                date,
+                ledger_id: loan.ledger_id,
+                origin_status: TransactionStatuses::Uncleared,
+                destination_status: TransactionStatuses::Uncleared,
+                scheduled_transaction_id: Some(scheduled.id),
+                occurrence: scheduled.current_repeat_count,
+            },
+        )
+        .await?;
+    }
+
+    if interest > 0 {
+        db::transactions::insert_on(
+            conn,
+            &NewTransactionData {
+                value: interest,
+                description: format!("{description} (interest)"),
+                date,
+                account: origin_id,
+                category: loan.interest_category_id,
+                ledger_id: loan.ledger_id,
+                status: TransactionStatuses::Uncleared,
+                scheduled_transaction_id: Some(scheduled.id),
+                occurrence: scheduled.current_repeat_count,
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Check a goal body: a positive target, non-negative earmarks, and linked accounts that exist
+/// in the ledger (`NotFound` otherwise).
+pub async fn validate_goal(pool: &PgPool, body: &PostGoal, ledger_id: i32) -> Result<(), AppError> {
+    if body.target_amount <= 0 || body.accounts.iter().any(|a| a.earmark.is_some_and(|e| e < 0)) {
+        return Err(AppError::BadRequest);
+    }
+
+    for account in &body.accounts {
+        db::accounts::get(pool, account.account_id, ledger_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Add the linked accounts to a goal row.
+pub async fn goal_to_dto(pool: &PgPool, goal: Goal) -> Result<GetGoal, AppError> {
+    Ok(GetGoal {
+        accounts: db::goals::get_accounts(pool, goal.id).await?,
+        id: goal.id,
+        name: goal.name,
+        target_amount: goal.target_amount,
+        target_date: goal.target_date,
+        created_date: goal.created_date,
+        scheduled_transaction_id: goal.scheduled_transaction_id,
+    })
+}
+
+/// The number of monthly contributions that still fit between `today` and `target`: whole
+/// months, so a target on the 10th seen on the 19th is one month fewer. Never negative.
+pub fn months_until(today: NaiveDate, target: NaiveDate) -> i32 {
+    let months = (target.year() - today.year()) * 12 + target.month() as i32
+        - today.month() as i32
+        - i32::from(target.day() < today.day());
+    months.max(0)
+}
+
+/// The monthly contribution needed to save `remaining` in `months_left` contributions,
+/// rounded up to the cent. With no months left, everything is needed now.
+pub fn required_monthly(remaining: i32, months_left: i32) -> i32 {
+    if months_left <= 0 {
+        return remaining;
+    }
+    (remaining + months_left - 1) / months_left
+}
+
+/// Compute a goal's progress from the current balances of its linked accounts.
+pub async fn goal_progress(pool: &PgPool, goal: &Goal) -> Result<GoalProgress, AppError> {
+    let mut accounts = Vec::new();
+
+    for link in db::goals::get_accounts(pool, goal.id).await? {
+        let account = db::accounts::get(pool, link.account_id, goal.ledger_id).await?;
+        let balance = db::accounts::balance(pool, link.account_id, goal.ledger_id).await?;
+        let contribution = link.earmark.map_or(balance, |earmark| balance.min(earmark)).max(0);
+
+        accounts.push(GoalAccountProgress {
+            account_id: account.id,
+            account_name: account.name,
+            earmark: link.earmark,
+            balance,
+            contribution,
+        });
+    }
+
+    let saved: i32 = accounts.iter().map(|a| a.contribution).sum();
+    let remaining = (goal.target_amount - saved).max(0);
+    let percent = (i64::from(saved) * 100 / i64::from(goal.target_amount)).min(100) as i32;
+
+    let months_left = goal
+        .target_date
+        .map(|target| months_until(Local::now().date_naive(), target.date()));
+
+    Ok(GoalProgress {
+        goal_id: goal.id,
+        target_amount: goal.target_amount,
+        target_date: goal.target_date,
+        saved,
+        remaining,
+        percent,
+        months_left,
+        required_monthly: months_left.map(|months| required_monthly(remaining, months)),
+        accounts,
+    })
+}
+
+/// Create (or replace) a goal's monthly contribution: a transfer for the required monthly
+/// amount into one of the goal's accounts, repeating once per month left. `BadRequest` when
+/// the goal has no target date, nothing remains to save, no months are left, or the
+/// destination is not one of the goal's accounts.
+pub async fn set_goal_contribution(
+    pool: &PgPool,
+    goal: &Goal,
+    body: &PostGoalContribution,
+) -> Result<GetGoal, AppError> {
+    let progress = goal_progress(pool, goal).await?;
+    let (Some(months_left), Some(monthly)) = (progress.months_left, progress.required_monthly)
+    else {
+        return Err(AppError::BadRequest);
+    };
+    if months_left <= 0
+        || monthly <= 0
+        || body.origin_account_id == body.destination_account_id
+        || !progress.accounts.iter().any(|a| a.account_id == body.destination_account_id)
+    {
+        return Err(AppError::BadRequest);
+    }
+    db::accounts::get(pool, body.origin_account_id, goal.ledger_id).await?;
+
+    let scheduled = db::scheduled_transactions::insert(
+        pool,
+        &NewScheduledTransaction {
+            kind: ScheduledTransactionKinds::Transfer,
+            value: monthly,
+            description: Some(format!("{} contribution", goal.name)),
+            created_date: body.start_date,
+            account_id: None,
+            category_id: None,
+            origin_account_id: Some(body.origin_account_id),
+            destination_account_id: Some(body.destination_account_id),
+            repeat: true,
+            repeat_freq: Some(RepeatFrequencies::Months),
+            repeat_interval: Some(1),
+            rrule: None,
+            business_day_adjustment: BusinessDayAdjustments::None,
+            amount_estimate: AmountEstimates::Fixed,
+            infinite_repeat: Some(false),
+            end_after_repeats: Some(months_left),
+            current_repeat_count: Some(0),
+            next_date: Some(body.start_date),
+            ledger_id: goal.ledger_id,
+            autopay_card_id: None,
+        },
+    )
+    .await?;
+
+    if let Some(previous) = goal.scheduled_transaction_id {
+        db::scheduled_transactions::delete(pool, previous, goal.ledger_id).await?;
+    }
+
+    let goal = db::goals::set_scheduled(pool, goal.id, Some(scheduled.id), goal.ledger_id).await?;
+    goal_to_dto(pool, goal).await
+}
+
+/// Validate an investment transaction body for `account` and assemble the row to write.
+/// `BadRequest` unless the account is an investment account and the amounts fit the kind;
+/// `NotFound` for an unknown security.
+pub async fn build_investment_transaction(
+    pool: &PgPool,
+    account: &Account,
+    body: &PostInvestmentTransaction,
+) -> Result<NewInvestmentTransactionData, AppError> {
+    if account.account_type != AccountTypes::Investment {
+        return Err(AppError::BadRequest);
+    }
+    db::securities::get(pool, body.security_id, account.ledger_id).await?;
+
+    let (quantity, price, value) = match body.kind {
+        InvestmentKinds::Buy | InvestmentKinds::Sell => {
+            if !body.quantity.is_finite() || body.quantity <= 0.0 || body.price < 0 {
+                return Err(AppError::BadRequest);
+            }
+            let value = (body.quantity * f64::from(body.price)).round();
+            if value > f64::from(i32::MAX) {
+                return Err(AppError::BadRequest);
+            }
+            (body.quantity, body.price, value as i32)
+        }
+        InvestmentKinds::Dividend => {
+            if body.value <= 0 {
+                return Err(AppError::BadRequest);
+            }
+            (0.0, 0, body.value)
+        }
+    };
+
+    Ok(NewInvestmentTransactionData {
+        account_id: account.id,
+        security_id: body.security_id,
+        kind: body.kind,
+        quantity,
+        price,
+        value,
+        description: body.description.clone(),
+        date: body.date,
+        ledger_id: account.ledger_id,
+    })
+}
+
+/// Whether an account's history in a security stays valid (never selling more units than
+/// held) once the stored transaction `exclude` is dropped and `include` is added.
+pub async fn investment_history_valid(
+    pool: &PgPool,
+    account_id: i32,
+    security_id: i32,
+    exclude: Option<i32>,
+    include: Option<InvestmentTransaction>,
+    ledger_id: i32,
+) -> Result<bool, AppError> {
+    let mut trades =
+        db::investments::get_by_account_security(pool, account_id, security_id, ledger_id).await?;
+    trades.retain(|trade| Some(trade.id) != exclude);
+    trades.extend(include);
+    trades.sort_by_key(|trade| (trade.date, trade.id));
+
+    Ok(lots::track(&trades, CostBasisMethods::Fifo).is_some())
+}
+
+/// The stored row a new or updated investment transaction would become, for
+/// [`investment_history_valid`]. A new row sorts after existing ones on the same date.
+pub fn investment_transaction_from(
+    id: Option<i32>,
+    new: &NewInvestmentTransactionData,
+) -> InvestmentTransaction {
+    InvestmentTransaction {
+        id: id.unwrap_or(i32::MAX),
+        account_id: new.account_id,
+        security_id: new.security_id,
+        kind: new.kind,
+        quantity: new.quantity,
+        price: new.price,
+        value: new.value,
+        description: new.description.clone(),
+        date: new.date,
+        ledger_id: new.ledger_id,
+    }
+}
+
+/// An account's positions, one per security it has transactions in, valued at the latest
+/// price of each security (or its last trade price in the account when none was recorded).
+pub async fn positions(
+    pool: &PgPool,
+    account_id: i32,
+    method: CostBasisMethods,
+    ledger_id: i32,
+) -> Result<Vec<Position>, AppError> {
+    let mut trades = db::investments::get_by_account(pool, account_id, ledger_id).await?;
+    trades.sort_by_key(|trade| trade.security_id);
+
+    let mut result = Vec::new();
+
+    for group in trades.chunk_by(|a, b| a.security_id == b.security_id) {
+        let security = db::securities::get(pool, group[0].security_id, ledger_id).await?;
+        let tracked = lots::track(group, method).ok_or_else(|| {
+            AppError::Internal(format!("account {account_id} sold more {} than held", security.symbol))
+        })?;
+
+        let latest = match db::securities::latest_price(pool, security.id).await? {
+            Some(price) => Some((price.price, price.date)),
+            None => group
+                .iter()
+                .rev()
+                .find(|trade| trade.kind != InvestmentKinds::Dividend)
+                .map(|trade| (trade.price, trade.date)),
+        };
+        let market_value = latest
+            .map(|(price, _)| (tracked.quantity * f64::from(price)).round() as i32)
+            .unwrap_or(0);
+
+        result.push(Position {
+            security_id: security.id,
+            symbol: security.symbol,
+            name: security.name,
+            quantity: tracked.quantity,
+            cost_basis: tracked.cost_basis,
+            price: latest.map(|(price, _)| price),
+            price_date: latest.map(|(_, date)| date),
+            market_value,
+            unrealized_gain: market_value - tracked.cost_basis,
+            realized_gain: tracked.realized_gain,
+            dividends: tracked.dividends,
+            lots: tracked.lots,
+        });
+    }
+
+    Ok(result)
+}
+
+/// An investment account's cash balance, positions and totals.
+pub async fn holdings(
+    pool: &PgPool,
+    account: &Account,
+    method: CostBasisMethods,
+) -> Result<Holdings, AppError> {
+    let cash = db::accounts::balance(pool, account.id, account.ledger_id).await?;
+    let positions = positions(pool, account.id, method, account.ledger_id).await?;
+    let market_value = positions.iter().map(|p| p.market_value).sum();
+
+    Ok(Holdings {
+        account_id: account.id,
+        method,
+        cash,
+        market_value,
+        cost_basis: positions.iter().map(|p| p.cost_basis).sum(),
+        unrealized_gain: positions.iter().map(|p| p.unrealized_gain).sum(),
+        realized_gain: positions.iter().map(|p| p.realized_gain).sum(),
+        dividends: positions.iter().map(|p| p.dividends).sum(),
+        total: cash + market_value,
+        positions,
+    })
+}
+
+/// Parse a decimal price ("123.45") into cents.
+fn parse_price(value: &str) -> Option<i32> {
+    let price: f64 = value.parse().ok()?;
+    if !price.is_finite() || price < 0.0 || price * 100.0 > f64::from(i32::MAX) {
+        return None;
+    }
+    Some((price * 100.0).round() as i32)
+}
+
+/// Import prices from CSV text with `symbol,date,price` rows (an optional header row first),
+/// dates as `YYYY-MM-DD` and decimal prices. Prices already recorded for the same security and
+/// date are replaced. `BadRequest` (and nothing imported) if any row is malformed.
+pub async fn import_prices(pool: &PgPool, csv: &str, ledger_id: i32) -> Result<PriceImport, AppError> {
+    let securities = db::securities::get_all(pool, ledger_id).await?;
+    let mut prices = Vec::new();
+    let mut unknown_symbols: Vec<String> = Vec::new();
+
+    for (index, line) in csv.lines().enumerate() {
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim().trim_matches('"')).collect();
+        if line.trim().is_empty() || (index == 0 && fields[0].eq_ignore_ascii_case("symbol")) {
+            continue;
+        }
+
+        let [symbol, date, price] = fields[..] else {
+            return Err(AppError::BadRequest);
+        };
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| AppError::BadRequest)?;
+        let price = parse_price(price).ok_or(AppError::BadRequest)?;
+        let symbol = symbol.to_uppercase();
+
+        match securities.iter().find(|s| s.symbol == symbol) {
+            Some(security) => prices.push(SecurityPrice {
+                security_id: security.id,
+                date: date.and_hms_opt(0, 0, 0).unwrap(),
+                price,
+            }),
+            None if !unknown_symbols.contains(&symbol) => unknown_symbols.push(symbol),
+            None => {}
+        }
+    }
+
+    db::securities::upsert_prices(pool, &prices).await?;
+
+    Ok(PriceImport {
+        imported: prices.len() as i32,
+        unknown_symbols,
+    })
+}
+
+/// The update that keeps a schedule as it is but moves it to another occurrence and due date.
+pub fn rescheduled(
+    st: &ScheduledTransaction,
+    current_repeat_count: Option<i32>,
+    next_date: Option<NaiveDateTime>,
+) -> NewScheduledTransaction {
+    NewScheduledTransaction {
+        kind: st.kind,
+        value: st.value,
+        description: st.description.clone(),
+        created_date: st.created_date,
+        account_id: st.account_id,
+        category_id: st.category_id,
+        origin_account_id: st.origin_account_id,
+        destination_account_id: st.destination_account_id,
+        repeat: st.repeat,
+        repeat_freq: st.repeat_freq,
+        repeat_interval: st.repeat_interval,
+        rrule: st.rrule.clone(),
+        business_day_adjustment: st.business_day_adjustment,
+        amount_estimate: st.amount_estimate,
+        infinite_repeat: st.infinite_repeat,
+        end_after_repeats: st.end_after_repeats,
+        current_repeat_count,
+        next_date,
+        ledger_id: st.ledger_id,
+        autopay_card_id: st.autopay_card_id,
     }
 }
 
+/// The due date of occurrence `repeat_count` (0-based) of a repeating schedule, moved off
+/// weekends and `holidays` as the schedule's `business_day_adjustment` says (see
+/// [`recurrence_date`]). Each occurrence is computed from the unadjusted anchor, so a moved due
+/// date never shifts the ones after it.
+pub fn scheduled_occurrence(
+    st: &ScheduledTransaction,
+    repeat_count: i32,
+    holidays: &Holidays,
+) -> Result<Option<NaiveDateTime>, AppError> {
+    Ok(recurrence_date(st, repeat_count)?
+        .map(|date| holidays.adjust(date, st.business_day_adjustment)))
+}
+
+/// The date occurrence `repeat_count` (0-based) of a repeating schedule falls on before any
+/// business-day move: from its RRULE when it has one (`None` once the rule has ended),
+/// otherwise every `repeat_interval` `repeat_freq` from its `created_date`.
+fn recurrence_date(
+    st: &ScheduledTransaction,
+    repeat_count: i32,
+) -> Result<Option<NaiveDateTime>, AppError> {
+    let internal = |m: &str| AppError::Internal(m.to_string());
+
+    if let Some(rule) = &st.rrule {
+        let rule = RRule::parse(rule).ok_or_else(|| internal("schedule has an invalid rrule"))?;
+        return Ok(rule.nth(st.created_date, repeat_count));
+    }
+
+    let repeat_freq = st
+        .repeat_freq
+        .ok_or_else(|| internal("repeating schedule missing repeat_freq"))?;
+    let repeat_interval = st
+        .repeat_interval
+        .ok_or_else(|| internal("repeating schedule missing repeat_interval"))?;
+
+    Ok(Some(calculate_next_date(
+        st.created_date,
+        st.repeat,
+        repeat_freq,
+        repeat_interval,
+        repeat_count,
+    )))
+}
+
+/// The stored row an unsaved schedule would become, for [`upcoming_occurrences`].
+pub fn scheduled_transaction_from(new: &NewScheduledTransaction) -> ScheduledTransaction {
+    ScheduledTransaction {
+        id: 0,
+        kind: new.kind,
+        value: new.value,
+        description: new.description.clone(),
+        created_date: new.created_date,
+        account_id: new.account_id,
+        category_id: new.category_id,
+        origin_account_id: new.origin_account_id,
+        destination_account_id: new.destination_account_id,
+        repeat: new.repeat,
+        repeat_freq: new.repeat_freq,
+        repeat_interval: new.repeat_interval,
+        rrule: new.rrule.clone(),
+        business_day_adjustment: new.business_day_adjustment,
+        amount_estimate: new.amount_estimate,
+        infinite_repeat: new.infinite_repeat,
+        end_after_repeats: new.end_after_repeats,
+        current_repeat_count: new.current_repeat_count,
+        next_date: new.next_date,
+        finished: false,
+        ledger_id: new.ledger_id,
+        autopay_card_id: new.autopay_card_id,
+    }
+}
+
+/// Paid occurrences an `Average`, `Median` or `Last` estimate is based on.
+pub const ESTIMATE_OCCURRENCES: i64 = 6;
+
+/// Calendar months of category spend a `CategorySpend` estimate averages.
+pub const ESTIMATE_MONTHS: i32 = 3;
+
+/// The amount the schedule's occurrences are expected to need: its `value`, or what its
+/// `amount_estimate` asks for — from the totals paid for its last [`ESTIMATE_OCCURRENCES`]
+/// occurrences, or the monthly spend in its category over the [`ESTIMATE_MONTHS`] calendar
+/// months before the one its current occurrence is due in. Falls back to `value` when there is
+/// nothing to estimate from (no paid occurrence or category spend yet, or no category).
+pub async fn estimated_value(
+    pool: &PgPool,
+    st: &ScheduledTransaction,
+) -> Result<i32, AppError> {
+    let estimate = match st.amount_estimate {
+        AmountEstimates::Fixed => None,
+        AmountEstimates::CategorySpend => match st.category_id {
+            Some(category_id) => {
+                let due = st.next_date.unwrap_or(st.created_date).date();
+                let to = due.with_day(1).expect("day 1 exists").and_time(Default::default());
+                let from = to - RelativeDuration::months(ESTIMATE_MONTHS);
+                let spent =
+                    db::transactions::sum_in_category(pool, category_id, from, to, st.ledger_id).await?;
+                Some((spent as f64 / ESTIMATE_MONTHS as f64).round() as i32).filter(|v| *v > 0)
+            }
+            None => None,
+        },
+        estimate => {
+            let totals =
+                db::scheduled_occurrences::paid_totals(pool, st.id, ESTIMATE_OCCURRENCES).await?;
+            estimate_from(estimate, &totals)
+        }
+    };
+
+    Ok(estimate.unwrap_or(st.value))
+}
+
+/// An `Average`, `Median` or `Last` estimate from paid totals (most recent first), rounded to
+/// the cent; `None` without any.
+fn estimate_from(estimate: AmountEstimates, totals: &[i32]) -> Option<i32> {
+    let last = *totals.first()?;
+    let mean = |values: &[i32]| {
+        let sum: i64 = values.iter().map(|&v| v as i64).sum();
+        (sum as f64 / values.len() as f64).round() as i32
+    };
+
+    Some(match estimate {
+        AmountEstimates::Average => mean(totals),
+        AmountEstimates::Median => {
+            let mut sorted = totals.to_vec();
+            sorted.sort_unstable();
+            let middle = sorted.len() / 2;
+            if sorted.len() % 2 == 1 {
+                sorted[middle]
+            } else {
+                mean(&sorted[middle - 1..=middle])
+            }
+        }
+        _ => last,
+    })
+}
+
+/// Default number of occurrences listed by an occurrence preview.
+pub const PREVIEW_OCCURRENCES: i32 = 12;
+
+/// Most occurrences an occurrence preview lists.
+pub const MAX_PREVIEW_OCCURRENCES: i32 = 366;
+
+/// The schedule's upcoming occurrences, starting with the one currently due, each expected to
+/// need `value`: at most `count` (1 to [`MAX_PREVIEW_OCCURRENCES`], `BadRequest` otherwise) and
+/// none due after `until`. A finished schedule has none, a one-off just its due date.
+pub fn upcoming_occurrences(
+    st: &ScheduledTransaction,
+    holidays: &Holidays,
+    value: i32,
+    count: i32,
+    until: Option<NaiveDateTime>,
+) -> Result<Vec<UpcomingOccurrence>, AppError> {
+    if !(1..=MAX_PREVIEW_OCCURRENCES).contains(&count) {
+        return Err(AppError::BadRequest);
+    }
+
+    let mut upcoming = Vec::new();
+    let Some(next_date) = st.next_date.filter(|_| !st.finished) else {
+        return Ok(upcoming);
+    };
+
+    if !st.repeat {
+        if until.is_none_or(|until| next_date <= until) {
+            upcoming.push(UpcomingOccurrence {
+                occurrence: None,
+                date: next_date,
+                scheduled_date: st.created_date,
+                value,
+                clamped: false,
+            });
+        }
+        return Ok(upcoming);
+    }
+
+    let first = st.current_repeat_count.unwrap_or(0);
+    let end = match st.infinite_repeat {
+        Some(false) => st.end_after_repeats.unwrap_or(first),
+        _ => i32::MAX,
+    };
+
+    for occurrence in (first..end).take(count as usize) {
+        let Some(scheduled_date) = recurrence_date(st, occurrence)? else {
+            break;
+        };
+        // The current occurrence keeps its stored due date, which a snooze may have moved.
+        let date = if occurrence == first {
+            next_date
+        } else {
+            holidays.adjust(scheduled_date, st.business_day_adjustment)
+        };
+        if until.is_some_and(|until| date > until) {
+            break;
+        }
+
+        upcoming.push(UpcomingOccurrence {
+            occurrence: Some(occurrence),
+            date,
+            scheduled_date,
+            value,
+            clamped: st.rrule.is_none()
+                && matches!(
+                    st.repeat_freq,
+                    Some(RepeatFrequencies::Months | RepeatFrequencies::Years)
+                )
+                && scheduled_date.day() < st.created_date.day(),
+        });
+    }
+
+    Ok(upcoming)
+}
+
+/// How far ahead, in months, the calendar feed lists occurrences.
+pub const CALENDAR_MONTHS: i32 = 12;
+
+/// The ledger's calendar feed: every occurrence of its schedules due within the next
+/// [`CALENDAR_MONTHS`] (and any that are overdue), summarised with the expected amount and the
+/// accounts involved.
+pub async fn calendar_feed(
+    pool: &PgPool,
+    holidays: &Holidays,
+    ledger_id: i32,
+) -> Result<String, AppError> {
+    let scheduled = all_scheduled_enriched(pool, ledger_id).await?;
+    let until = Local::now().naive_local() + RelativeDuration::months(CALENDAR_MONTHS);
+    let mut events = Vec::new();
+
+    for st in db::scheduled_transactions::get_all(pool, ledger_id).await? {
+        let Some(dto) = scheduled.iter().find(|dto| dto.id == st.id) else {
+            continue;
+        };
+
+        let (label, accounts) = scheduled_label(dto);
+        // An estimated amount is marked as approximate.
+        let approximately = if st.amount_estimate == AmountEstimates::Fixed { "" } else { "~" };
+
+        for upcoming in upcoming_occurrences(
+            &st,
+            holidays,
+            dto.estimated_value,
+            MAX_PREVIEW_OCCURRENCES,
+            Some(until),
+        )? {
+            let occurrence = upcoming
+                .occurrence
+                .map_or_else(|| "once".to_string(), |occurrence| occurrence.to_string());
+            events.push(calendar::CalendarEvent {
+                uid: format!("scheduled-{}-{occurrence}@finance", st.id),
+                date: upcoming.date.date(),
+                summary: format!(
+                    "{label}: {approximately}{} ({accounts})",
+                    calendar::format_amount(upcoming.value)
+                ),
+                description: dto
+                    .category_name
+                    .as_ref()
+                    .map(|category| format!("Category: {category}")),
+            });
+        }
+    }
+
+    events.sort_by_key(|event| event.date);
+    Ok(calendar::render(
+        "Scheduled transactions",
+        &events,
+        Utc::now().naive_utc(),
+    ))
+}
+
+/// How a schedule is named in reminders: its description (or category, or "Transfer"), and the
+/// account it is booked in (or origin → destination of a transfer).
+fn scheduled_label(dto: &GetScheduledTransaction) -> (String, String) {
+    let label = dto
+        .description
+        .clone()
+        .filter(|description| !description.trim().is_empty())
+        .or_else(|| dto.category_name.clone())
+        .unwrap_or_else(|| "Transfer".to_string());
+    let accounts = match dto.kind {
+        ScheduledTransactionKinds::Transaction => dto.account_name.clone().unwrap_or_default(),
+        ScheduledTransactionKinds::Transfer => format!(
+            "{} → {}",
+            dto.origin_account_name.as_deref().unwrap_or_default(),
+            dto.destination_account_name.as_deref().unwrap_or_default()
+        ),
+    };
+    (label, accounts)
+}
+
+/// How far ahead, in days, the digest projects account balances.
+pub const OVERDRAFT_DAYS: i64 = 30;
+
+/// Most days ahead the digest lists scheduled transactions.
+pub const MAX_DIGEST_LEAD_DAYS: i32 = 30;
+
+/// A ledger's daily digest as of `today`: the occurrences that are overdue or due within
+/// `lead_days`, and the checking, savings and cash accounts whose balance would drop below zero
+/// if every occurrence due in the next [`OVERDRAFT_DAYS`] (overdue ones included) were paid as
+/// expected. The current occurrence counts what is still unpaid of it.
+pub async fn digest(
+    pool: &PgPool,
+    holidays: &Holidays,
+    ledger_id: i32,
+    today: NaiveDate,
+    lead_days: i32,
+) -> Result<Digest, AppError> {
+    let scheduled = all_scheduled_enriched(pool, ledger_id).await?;
+    let end_of_day = |days: i64| (today + Duration::days(days)).and_hms_opt(23, 59, 59).unwrap();
+    let due_until = end_of_day(lead_days as i64);
+    let projection_until = end_of_day(OVERDRAFT_DAYS);
+
+    let mut due = Vec::new();
+    // (date, account, change in balance) of every occurrence within the projection.
+    let mut movements = Vec::new();
+
+    for st in db::scheduled_transactions::get_all(pool, ledger_id).await? {
+        let Some(dto) = scheduled.iter().find(|dto| dto.id == st.id) else {
+            continue;
+        };
+        let (label, accounts) = scheduled_label(dto);
+
+        let occurrences = upcoming_occurrences(
+            &st,
+            holidays,
+            dto.estimated_value,
+            MAX_PREVIEW_OCCURRENCES,
+            Some(projection_until),
+        )?;
+        for (index, upcoming) in occurrences.into_iter().enumerate() {
+            // The first one is the occurrence currently due.
+            let value = if index == 0 {
+                (upcoming.value - dto.paid_value).max(0)
+            } else {
+                upcoming.value
+            };
+
+            if upcoming.date <= due_until {
+                due.push(DigestEntry {
+                    scheduled_transaction_id: st.id,
+                    label: label.clone(),
+                    accounts: accounts.clone(),
+                    date: upcoming.date,
+                    value,
+                    overdue: upcoming.date.date() < today,
+                });
+            }
+
+            match (dto.kind, dto.category_type) {
+                (ScheduledTransactionKinds::Transaction, Some(CategoryTypes::Income)) => {
+                    movements.push((upcoming.date, dto.account_id, value));
+                }
+                (ScheduledTransactionKinds::Transaction, _) => {
+                    movements.push((upcoming.date, dto.account_id, -value));
+                }
+                (ScheduledTransactionKinds::Transfer, _) => {
+                    movements.push((upcoming.date, dto.origin_account_id, -value));
+                    movements.push((upcoming.date, dto.destination_account_id, value));
+                }
+            }
+        }
+    }
+
+    due.sort_by_key(|entry| (entry.date, entry.scheduled_transaction_id));
+    movements.sort_by_key(|(date, _, _)| *date);
+
+    let mut overdrafts = Vec::new();
+    for account in accounts_with_balance(pool, ledger_id).await? {
+        if !matches!(
+            account.account_type,
+            AccountTypes::Checking | AccountTypes::Savings | AccountTypes::Cash
+        ) {
+            continue;
+        }
+
+        let mut balance = account.balance;
+        let mut overdraft: Option<ProjectedOverdraft> = None;
+        for (date, _, change) in movements.iter().filter(|(_, id, _)| *id == Some(account.id)) {
+            balance += change;
+            if balance >= 0 {
+                continue;
+            }
+            match &mut overdraft {
+                Some(overdraft) => overdraft.lowest_balance = overdraft.lowest_balance.min(balance),
+                None => {
+                    overdraft = Some(ProjectedOverdraft {
+                        account_id: account.id,
+                        account_name: account.name.clone(),
+                        date: *date,
+                        lowest_balance: balance,
+                    })
+                }
+            }
+        }
+        overdrafts.extend(overdraft);
+    }
+
+    Ok(Digest { due, overdrafts })
+}
+
 /// Compute the next due date for a repeating scheduled transaction.
 pub fn calculate_next_date(
     initial_date: NaiveDateTime,
@@ -3,10 +3,21 @@ use std::sync::Arc;
 use sqlx::PgPool;
 
 use crate::config::Config;
+use crate::holidays::Holidays;
+use crate::mail::Mailer;
+use crate::oidc::Oidc;
+use crate::rate_limit::RateLimits;
 
 /// Shared application state injected into every handler via `axum::extract::State`.
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
     pub config: Arc<Config>,
+    /// Holiday calendar for business-day adjustment; empty (weekends only) if none is configured.
+    pub holidays: Arc<Holidays>,
+    /// Outgoing email; `None` if no SMTP relay is configured.
+    pub mailer: Option<Arc<Mailer>>,
+    pub rate_limits: Arc<RateLimits>,
+    /// Single sign-on; `None` if no OpenID Connect provider is configured.
+    pub oidc: Option<Arc<Oidc>>,
 }
@@ -0,0 +1,256 @@
+//! Time-based one-time passwords (RFC 6238) for two-factor authentication: HMAC-SHA1, 6 digits,
+//! 30-second steps, as every authenticator app expects by default.
+//!
+//! Secrets are stored encrypted with AES-256-GCM under `TOTP_ENCRYPTION_KEY` (see [`Config`]),
+//! bound to the user they belong to, so a secret copied to another row does not decrypt.
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, OsRng, Payload};
+use aes_gcm::{AeadCore, Aes256Gcm, Key, KeyInit, Nonce};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::db;
+use crate::error::AppError;
+
+/// Seconds per time step.
+pub const STEP_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+/// Length of a new secret, in bytes (160 bits, as RFC 4226 recommends).
+const SECRET_LEN: usize = 20;
+/// Steps before and after the current one whose codes are accepted, for clock drift.
+const SKEW: i64 = 1;
+const NONCE_LEN: usize = 12;
+
+/// A new random secret.
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_LEN];
+    OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+/// The secret as authenticator apps take it: unpadded RFC 4648 base32.
+pub fn encode_secret(secret: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut encoded = String::with_capacity(secret.len().div_ceil(5) * 8);
+    for chunk in secret.chunks(5) {
+        let mut buffer = [0u8; 5];
+        buffer[..chunk.len()].copy_from_slice(chunk);
+        let bits = buffer.iter().fold(0u64, |bits, &byte| bits << 8 | u64::from(byte));
+        let chars = (chunk.len() * 8).div_ceil(5);
+        for i in 0..chars {
+            encoded.push(ALPHABET[(bits >> (35 - i * 5) & 0x1f) as usize] as char);
+        }
+    }
+    encoded
+}
+
+/// The `otpauth://` URI (the Key Uri Format of Google Authenticator) that authenticator apps
+/// scan as a QR code.
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &[u8]) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECONDS}",
+        percent_encode(issuer),
+        percent_encode(account),
+        encode_secret(secret),
+        percent_encode(issuer),
+    )
+}
+
+/// The code for a time step (RFC 4226 HOTP of the step counter).
+fn code(secret: &[u8], step: i64) -> u32 {
+    let mut mac =
+        <Hmac<Sha1> as Mac>::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset],
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]) & 0x7fff_ffff;
+    binary % 10u32.pow(DIGITS)
+}
+
+/// The time step of `code` if it is valid at `now` (a Unix time) and newer than `last_step`,
+/// the step of the last code accepted, so that every code is used once.
+pub fn verify(secret: &[u8], code: &str, now: i64, last_step: Option<i64>) -> Option<i64> {
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+
+    let current = now.div_euclid(STEP_SECONDS);
+    (current - SKEW..=current + SKEW)
+        .filter(|&step| last_step.is_none_or(|last| step > last))
+        .find(|&step| self::code(secret, step) == code)
+}
+
+/// Encrypt a secret for `user_id`, as the nonce followed by the ciphertext.
+pub fn encrypt(key: &[u8; 32], user_id: i32, secret: &[u8]) -> Result<Vec<u8>, AppError> {
+    let cipher = Aes256Gcm::new(&Key::<Aes256Gcm>::from(*key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: secret,
+                aad: &user_id.to_be_bytes(),
+            },
+        )
+        .map_err(|_| AppError::Internal("failed to encrypt TOTP secret".to_string()))?;
+
+    Ok([&nonce[..], &ciphertext].concat())
+}
+
+/// Decrypt a secret stored by [`encrypt`] for `user_id`.
+pub fn decrypt(key: &[u8; 32], user_id: i32, stored: &[u8]) -> Result<Vec<u8>, AppError> {
+    let invalid = || AppError::Internal("invalid encrypted TOTP secret".to_string());
+    let (nonce, ciphertext) = stored.split_at_checked(NONCE_LEN).ok_or_else(invalid)?;
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().map_err(|_| invalid())?;
+
+    Aes256Gcm::new(&Key::<Aes256Gcm>::from(*key))
+        .decrypt(
+            &Nonce::from(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: &user_id.to_be_bytes(),
+            },
+        )
+        .map_err(|_| AppError::Internal("failed to decrypt TOTP secret (wrong key?)".to_string()))
+}
+
+/// Whether `code` is a valid TOTP code or an unused recovery code of the user, using it up either
+/// way. Codes may be typed with spaces or dashes.
+pub async fn verify_user(
+    pool: &PgPool,
+    config: &Config,
+    user_id: i32,
+    code: &str,
+) -> Result<bool, AppError> {
+    let code: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    if code.len() != DIGITS as usize {
+        return db::two_factor::use_recovery_code(pool, user_id, &code).await;
+    }
+
+    let key = config
+        .totp_encryption_key
+        .as_ref()
+        .ok_or_else(|| AppError::Internal("TOTP_ENCRYPTION_KEY is not set".to_string()))?;
+    let Some(stored) = db::two_factor::get(pool, user_id).await? else {
+        return Ok(false);
+    };
+    let Some(encrypted) = stored.totp_secret else {
+        return Ok(false);
+    };
+
+    let secret = decrypt(key, user_id, &encrypted)?;
+    match verify(&secret, &code, chrono::Utc::now().timestamp(), stored.totp_last_step) {
+        Some(step) => db::two_factor::use_step(pool, user_id, step).await,
+        None => Ok(false),
+    }
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters.
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The SHA-1 secret of the RFC 6238 test vectors.
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn codes_match_the_rfc_6238_vectors() {
+        // The RFC lists 8-digit codes; ours are their last 6 digits.
+        for (time, expected) in [
+            (59, 94287082),
+            (1111111109, 7081804),
+            (1111111111, 14050471),
+            (1234567890, 89005924),
+            (2000000000, 69279037),
+            (20000000000, 65353130),
+        ] {
+            assert_eq!(code(RFC_SECRET, time / STEP_SECONDS), expected % 1_000_000, "at {time}");
+        }
+    }
+
+    #[test]
+    fn secrets_encode_as_the_rfc_4648_base32_vectors_unpadded() {
+        for (data, expected) in [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ] {
+            assert_eq!(encode_secret(data.as_bytes()), expected, "{data:?}");
+        }
+        assert_eq!(encode_secret(RFC_SECRET), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+    }
+
+    #[test]
+    fn codes_are_accepted_one_step_either_side() {
+        let now = 1111111111;
+        let step = now / STEP_SECONDS;
+        let code_at = |step: i64| format!("{:06}", code(RFC_SECRET, step));
+
+        assert_eq!(verify(RFC_SECRET, &code_at(step), now, None), Some(step));
+        assert_eq!(verify(RFC_SECRET, &code_at(step - 1), now, None), Some(step - 1));
+        assert_eq!(verify(RFC_SECRET, &code_at(step + 1), now, None), Some(step + 1));
+        assert_eq!(verify(RFC_SECRET, &code_at(step - 2), now, None), None);
+        assert_eq!(verify(RFC_SECRET, &code_at(step + 2), now, None), None);
+
+        assert_eq!(verify(RFC_SECRET, "050471", now, None), Some(step));
+        assert_eq!(verify(RFC_SECRET, "50471", now, None), None, "codes have 6 digits");
+        assert_eq!(verify(RFC_SECRET, "+50471", now, None), None);
+    }
+
+    #[test]
+    fn codes_are_not_accepted_again() {
+        let now = 1111111111;
+        let step = now / STEP_SECONDS;
+        let code_at = |step: i64| format!("{:06}", code(RFC_SECRET, step));
+
+        // Neither the code last used nor an older one, even within the skew window.
+        assert_eq!(verify(RFC_SECRET, &code_at(step), now, Some(step)), None);
+        assert_eq!(verify(RFC_SECRET, &code_at(step - 1), now, Some(step)), None);
+        assert_eq!(verify(RFC_SECRET, &code_at(step + 1), now, Some(step)), Some(step + 1));
+        assert_eq!(verify(RFC_SECRET, &code_at(step), now, Some(step - 1)), Some(step));
+    }
+
+    #[test]
+    fn secrets_only_decrypt_for_their_user() {
+        let key = [7u8; 32];
+        let secret = generate_secret();
+        let stored = encrypt(&key, 1, &secret).unwrap();
+
+        assert_eq!(decrypt(&key, 1, &stored).unwrap(), secret);
+        assert!(decrypt(&key, 2, &stored).is_err());
+        assert!(decrypt(&[8u8; 32], 1, &stored).is_err());
+        assert!(decrypt(&key, 1, &stored[..NONCE_LEN]).is_err());
+    }
+}
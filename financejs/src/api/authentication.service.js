@@ -1,40 +1,67 @@
 import finance from './finance';
 
-const getWithAuth = async (url) => {
-    try {
-        return await finance.get(url, authenticationService.getAuthHeader());
-    } catch (e) {
-        if (e.response.status === 401) logout();
-        throw(e);
+// Access tokens are short-lived: a request rejected with 401 is retried once with a fresh one.
+// Concurrent requests share a single refresh, since a refresh token only works once.
+let refreshing = null;
+
+const refresh = () => {
+    if (refreshing == null) {
+        refreshing = refreshTokens().finally(() => refreshing = null);
+    }
+    return refreshing;
+};
+
+const refreshTokens = async () => {
+    const refreshToken = localStorage.getItem('refreshToken');
+    if (refreshToken == null) {
+        return false;
     }
-}
 
-const postWithAuth = async (url, body) => {
     try {
-        return await finance.post(url, body, authenticationService.getAuthHeader());
-    } catch (e) {
-        if (e.response.status === 401) logout();
-        throw(e);
+        const result = await finance.post('/token/refresh', {refresh_token: refreshToken});
+        storeTokens(result.data);
+        return true;
+    } catch {
+        clearTokens();
+        return false;
     }
-}
+};
 
-const patchWithAuth = async (url, body) => {
+const withAuth = async (request) => {
     try {
-        return await finance.patch(url, body, authenticationService.getAuthHeader());
+        return await request(authenticationService.getAuthHeader());
     } catch (e) {
-        if (e.response.status === 401) logout();
-        throw(e);
+        if (e.response?.status !== 401) throw(e);
+        if (!await refresh()) throw(e);
     }
-}
 
-const deleteWithAuth = async (url) => {
     try {
-        return await finance.delete(url, authenticationService.getAuthHeader());
+        return await request(authenticationService.getAuthHeader());
     } catch (e) {
-        if (e.response.status === 401) logout();
+        if (e.response?.status === 401) clearTokens();
         throw(e);
     }
-}
+};
+
+const getWithAuth = (url) => withAuth(config => finance.get(url, config));
+
+const postWithAuth = (url, body) => withAuth(config => finance.post(url, body, config));
+
+const patchWithAuth = (url, body) => withAuth(config => finance.patch(url, body, config));
+
+const deleteWithAuth = (url) => withAuth(config => finance.delete(url, config));
+
+const storeTokens = (data) => {
+    localStorage.setItem('token', data.token);
+    if (data.refresh_token) {
+        localStorage.setItem('refreshToken', data.refresh_token);
+    }
+};
+
+const clearTokens = () => {
+    localStorage.removeItem('token');
+    localStorage.removeItem('refreshToken');
+};
 
 const newUser = async (name, password) => {
     try {
@@ -43,15 +70,18 @@ const newUser = async (name, password) => {
             password
         });
 
-        localStorage.setItem('token', result.data.token);
+        storeTokens(result.data);
 
         return result.data;
     } catch (e) {
-        logout();
+        clearTokens();
         throw(e);
     }
 };
 
+// With two-factor authentication the password only gets a challenge token, returned as
+// `{challenge_token}`: the login is finished by `loginCode` with a code from the authenticator
+// app (or a recovery code).
 const login = async (name, password) => {
     try {
         const result = await finance.post('/login', {
@@ -59,17 +89,45 @@ const login = async (name, password) => {
             password
         });
 
-        localStorage.setItem('token', result.data.token);
+        if (result.data.challenge_token) {
+            clearTokens();
+        } else {
+            storeTokens(result.data);
+        }
 
         return result.data;
     } catch (e) {
-        logout();
+        clearTokens();
         throw(e);
     }
 };
 
-const logout = () => {
-    localStorage.removeItem('token');
+const loginCode = async (challengeToken, code) => {
+    try {
+        const result = await finance.post('/login/2fa', {
+            challenge_token: challengeToken,
+            code
+        });
+
+        storeTokens(result.data);
+
+        return result.data;
+    } catch (e) {
+        clearTokens();
+        throw(e);
+    }
+};
+
+// Ends the session on the server too, so its tokens stop working.
+const logout = async () => {
+    if (localStorage.getItem('token') != null) {
+        try {
+            await postWithAuth('/logout', null);
+        } catch {
+            // Already expired or revoked.
+        }
+    }
+    clearTokens();
 };
 
 const validateToken = async () => {
@@ -94,6 +152,7 @@ const getAuthHeader = () => {
 
 export const authenticationService = {
     login,
+    loginCode,
     logout,
     newUser,
     getWithAuth,
@@ -13,9 +13,9 @@ const Settings = () => {
 
     const navigate = useNavigate();
 
-    const onLogout = () => {
+    const onLogout = async () => {
         setLoadingModalOpen(true);
-        authenticationService.logout();
+        await authenticationService.logout();
         setLoadingModalOpen(false);
         navigate('/');
     };
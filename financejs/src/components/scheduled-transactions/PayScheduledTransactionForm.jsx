@@ -42,7 +42,7 @@ const PayScheduledTransactionForm = (props) => {
 
                 setCategories(allCategories.filter(category => category.categorytype === scheduledTransaction.category_type));
 
-                await formik.setFieldValue('value', currency(scheduledTransaction.value, {fromCents: true}));
+                await formik.setFieldValue('value', currency(scheduledTransaction.estimated_value, {fromCents: true}));
                 await formik.setFieldValue('description', scheduledTransaction.description);
                 await formik.setFieldValue('accountId', scheduledTransaction.account_id);
                 await formik.setFieldValue('categoryType', scheduledTransaction.category_type);
@@ -76,7 +76,7 @@ const ScheduledTransactionCard = (props) => {
                             variant='h6'
                             sx={getValueSx(scheduledTransaction.category_type)}
                         >
-                            {moneyFormat(scheduledTransaction.value)}
+                            {moneyFormat(scheduledTransaction.estimated_value)}
                         </Typography>
                     </Badge>
                 }
@@ -1,4 +1,4 @@
-import React, {useContext, useEffect} from 'react';
+import React, {useContext, useEffect, useState} from 'react';
 import {Button, Container, IconButton} from "@mui/material";
 import * as yup from "yup";
 import {useFormik} from "formik";
@@ -23,13 +23,29 @@ const validationSchema = yup.object({
         .required('Password is required'),
 });
 
+const codeValidationSchema = yup.object({
+    code: yup
+        .string('Enter the code from your authenticator app')
+        .required('Code is required'),
+});
+
 const Login = () => {
     const toggleLoadingModalOpen = useContext(LoadingModalContext);
     const {showMessageModal} = useContext(MessageModalContext);
 
+    // Set once the password was right for a user with two-factor authentication.
+    const [challengeToken, setChallengeToken] = useState(null);
+
     const navigate = useNavigate();
     const dispatch = useDispatch();
 
+    const onLoggedIn = (login) => {
+        dispatch({type: 'setAccounts', payload: login.accounts});
+        dispatch({type: 'setCategories', payload: login.categories});
+        dispatch({type: 'setScheduledTransactions', payload: login.scheduled_transactions});
+        navigate('/accounts');
+    };
+
     const formik = useFormik({
         initialValues: {
             userName: '',
@@ -42,11 +58,14 @@ const Login = () => {
             try {
                 toggleLoadingModalOpen();
                 const login = await authenticationService.login(userName, password);
-                dispatch({type: 'setAccounts', payload: login.accounts});
-                dispatch({type: 'setCategories', payload: login.categories});
-                dispatch({type: 'setScheduledTransactions', payload: login.scheduled_transactions});
                 toggleLoadingModalOpen();
-                navigate('/accounts');
+
+                if (login.challenge_token) {
+                    resetForm();
+                    setChallengeToken(login.challenge_token);
+                } else {
+                    onLoggedIn(login);
+                }
             } catch (e) {
                 resetForm();
 
@@ -54,6 +73,8 @@ const Login = () => {
 
                 if (e.response && e.response.status === 401) {
                     showMessageModal('Login failed', 'Wrong user name or password, please try again.');
+                } else if (e.response && e.response.status === 429) {
+                    showMessageModal('Login failed', 'Too many failed logins, please try again later.');
                 } else {
                     showMessageModal('Error', 'An error occurred while processing your request, please try again.');
                 }
@@ -61,6 +82,38 @@ const Login = () => {
         },
     });
 
+    const codeFormik = useFormik({
+        initialValues: {
+            code: '',
+        },
+        validationSchema: codeValidationSchema,
+        onSubmit: async (values, {resetForm}) => {
+            try {
+                toggleLoadingModalOpen();
+                const login = await authenticationService.loginCode(challengeToken, values.code.trim());
+                toggleLoadingModalOpen();
+                onLoggedIn(login);
+            } catch (e) {
+                resetForm();
+
+                toggleLoadingModalOpen();
+
+                if (e.response && e.response.status === 401) {
+                    showMessageModal('Login failed', 'Wrong code, or the login took too long, please try again.');
+                } else if (e.response && e.response.status === 429) {
+                    showMessageModal('Login failed', 'Too many wrong codes, please try again later.');
+                } else {
+                    showMessageModal('Error', 'An error occurred while processing your request, please try again.');
+                }
+            }
+        },
+    });
+
+    const onCancelCode = () => {
+        codeFormik.resetForm();
+        setChallengeToken(null);
+    };
+
     const onNewUser = () => {
         navigate('/users/new');
     };
@@ -72,10 +125,7 @@ const Login = () => {
             const token = await authenticationService.validateToken();
 
             if (token && token.data) {
-                dispatch({type: 'setAccounts', payload: token.data.accounts});
-                dispatch({type: 'setCategories', payload: token.data.categories});
-                dispatch({type: 'setScheduledTransactions', payload: token.data.scheduled_transactions});
-                navigate('/accounts');
+                onLoggedIn(token.data);
             }
 
             toggleLoadingModalOpen();
@@ -93,46 +143,83 @@ const Login = () => {
                 </Toolbar>
             </AppBar>
             <Container maxWidth='sm' sx={{p: 3}}>
-                <form onSubmit={formik.handleSubmit}>
-                    <TextField
-                        autoCapitalize='none'
-                        fullWidth
-                        id='userName'
-                        name='userName'
-                        label='User Name'
-                        variant='outlined'
-                        autoComplete='off'
-                        sx={{mb: 3}}
-                        value={formik.values.userName}
-                        onChange={formik.handleChange}
-                        error={formik.touched.userName && Boolean(formik.errors.userName)}
-                        helperText={formik.touched.userName && formik.errors.userName}
-                    />
-                    <TextField
-                        fullWidth
-                        id='password'
-                        name='password'
-                        label='Password'
-                        variant='outlined'
-                        type='password'
-                        sx={{mb: 3}}
-                        value={formik.values.password}
-                        onChange={formik.handleChange}
-                        error={formik.touched.password && Boolean(formik.errors.password)}
-                        helperText={formik.touched.password && formik.errors.password}
-                    />
-                    <Button
-                        type='submit'
-                        fullWidth
-                        variant='contained'
-                        color='primary'
-                        startIcon={<VpnKeyIcon/>}
-                        size='large'
-                        sx={{mb: 3}}
-                    >
-                        Login
-                    </Button>
-                </form>
+                {challengeToken ? (
+                    <form onSubmit={codeFormik.handleSubmit}>
+                        <Typography sx={{mb: 3}}>
+                            Enter the code from your authenticator app, or one of your recovery codes.
+                        </Typography>
+                        <TextField
+                            autoCapitalize='none'
+                            autoFocus
+                            fullWidth
+                            id='code'
+                            name='code'
+                            label='Code'
+                            variant='outlined'
+                            autoComplete='one-time-code'
+                            sx={{mb: 3}}
+                            value={codeFormik.values.code}
+                            onChange={codeFormik.handleChange}
+                            error={codeFormik.touched.code && Boolean(codeFormik.errors.code)}
+                            helperText={codeFormik.touched.code && codeFormik.errors.code}
+                        />
+                        <Button
+                            type='submit'
+                            fullWidth
+                            variant='contained'
+                            color='primary'
+                            startIcon={<VpnKeyIcon/>}
+                            size='large'
+                            sx={{mb: 3}}
+                        >
+                            Verify
+                        </Button>
+                        <Button fullWidth size='large' onClick={onCancelCode}>
+                            Cancel
+                        </Button>
+                    </form>
+                ) : (
+                    <form onSubmit={formik.handleSubmit}>
+                        <TextField
+                            autoCapitalize='none'
+                            fullWidth
+                            id='userName'
+                            name='userName'
+                            label='User Name'
+                            variant='outlined'
+                            autoComplete='off'
+                            sx={{mb: 3}}
+                            value={formik.values.userName}
+                            onChange={formik.handleChange}
+                            error={formik.touched.userName && Boolean(formik.errors.userName)}
+                            helperText={formik.touched.userName && formik.errors.userName}
+                        />
+                        <TextField
+                            fullWidth
+                            id='password'
+                            name='password'
+                            label='Password'
+                            variant='outlined'
+                            type='password'
+                            sx={{mb: 3}}
+                            value={formik.values.password}
+                            onChange={formik.handleChange}
+                            error={formik.touched.password && Boolean(formik.errors.password)}
+                            helperText={formik.touched.password && formik.errors.password}
+                        />
+                        <Button
+                            type='submit'
+                            fullWidth
+                            variant='contained'
+                            color='primary'
+                            startIcon={<VpnKeyIcon/>}
+                            size='large'
+                            sx={{mb: 3}}
+                        >
+                            Login
+                        </Button>
+                    </form>
+                )}
             </Container>
         </>
     );